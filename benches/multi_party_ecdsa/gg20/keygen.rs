@@ -0,0 +1,139 @@
+use criterion::criterion_main;
+
+mod bench {
+    use criterion::{criterion_group, Criterion};
+    use curv::cryptographic_primitives::proofs::sigma_dlog::DLogProof;
+    use curv::elliptic::curves::{secp256_k1::Secp256k1, Point, Scalar};
+    use multi_party_ecdsa::protocols::multi_party_ecdsa::gg_2018::VerifiableSS;
+    use multi_party_ecdsa::protocols::multi_party_ecdsa::gg_2020::party_i::*;
+    use sha2::Sha256;
+
+    const T: u16 = 5;
+    const N: u16 = 8;
+
+    // Run with and without `--features rayon` to compare serial and parallel verification
+    pub fn bench_keygen_verification(c: &mut Criterion) {
+        let params = Parameters {
+            threshold: T,
+            share_count: N,
+        };
+        // Parties share the same Paillier setup to keep benchmark setup fast, it doesn't affect
+        // verification cost
+        let setup = PaillierSetup::generate();
+        let party_keys_vec = (1..=usize::from(N))
+            .map(|i| Keys::create_from_paillier_setup(i, &setup))
+            .collect::<Vec<Keys>>();
+        let (bc1_vec, decom_vec): (Vec<_>, Vec<_>) = party_keys_vec
+            .iter()
+            .map(|key| key.phase1_broadcast_with_paillier_setup(&setup))
+            .unzip();
+        let y_vec = decom_vec
+            .iter()
+            .map(|decom| decom.y_i.clone())
+            .collect::<Vec<Point<Secp256k1>>>();
+
+        let (vss_scheme_vec, secret_shares_vec): (Vec<_>, Vec<_>) = party_keys_vec
+            .iter()
+            .map(|key| {
+                let (vss, shares, _) = key
+                    .phase1_verify_com_phase3_verify_correct_key_verify_dlog_phase2_distribute(
+                        &params, &decom_vec, &bc1_vec,
+                    )
+                    .unwrap();
+                (vss, shares)
+            })
+            .unzip();
+        let dlog_proofs_vec = party_keys_vec
+            .iter()
+            .enumerate()
+            .map(|(i, key)| {
+                let party_shares = secret_shares_vec
+                    .iter()
+                    .map(|shares| shares[i].clone())
+                    .collect::<Vec<Scalar<Secp256k1>>>();
+                key.phase2_verify_vss_construct_keypair_phase3_pok_dlog(
+                    &params,
+                    &y_vec,
+                    &party_shares,
+                    &vss_scheme_vec,
+                    i + 1,
+                )
+                .unwrap()
+                .1
+            })
+            .collect::<Vec<_>>();
+
+        c.bench_function("gg20 keygen round 2 verification t=5 n=8", |b| {
+            b.iter(|| {
+                party_keys_vec[0]
+                    .phase1_verify_com_phase3_verify_correct_key_verify_dlog_phase2_distribute(
+                        &params, &decom_vec, &bc1_vec,
+                    )
+                    .unwrap()
+            })
+        });
+        c.bench_function("gg20 keygen round 4 verification t=5 n=8", |b| {
+            b.iter(|| {
+                Keys::verify_dlog_proofs_check_against_vss(
+                    &params,
+                    &dlog_proofs_vec,
+                    &y_vec,
+                    &vss_scheme_vec,
+                )
+                .unwrap()
+            })
+        });
+    }
+
+    pub fn bench_dlog_batch_verification(c: &mut Criterion) {
+        const N: u16 = 16;
+        let params = Parameters {
+            threshold: T,
+            share_count: N,
+        };
+        // Only DLog proofs are verified in this benchmark, so VSS schemes are dealt directly
+        // instead of running keygen rounds
+        let u_vec = (0..N)
+            .map(|_| Scalar::<Secp256k1>::random())
+            .collect::<Vec<_>>();
+        let y_vec = u_vec
+            .iter()
+            .map(|u| Point::generator() * u)
+            .collect::<Vec<Point<Secp256k1>>>();
+        let (vss_scheme_vec, shares_vec): (Vec<_>, Vec<_>) =
+            u_vec.iter().map(|u| VerifiableSS::share(T, N, u)).unzip();
+        let dlog_proofs_vec = (0..usize::from(N))
+            .map(|i| {
+                let x_i = shares_vec
+                    .iter()
+                    .fold(Scalar::<Secp256k1>::zero(), |acc, shares| acc + &shares[i]);
+                DLogProof::<Secp256k1, Sha256>::prove(&x_i)
+            })
+            .collect::<Vec<_>>();
+
+        c.bench_function("gg20 keygen round 4 serial dlog verification n=16", |b| {
+            b.iter(|| {
+                Keys::verify_dlog_proofs_check_against_vss(
+                    &params,
+                    &dlog_proofs_vec,
+                    &y_vec,
+                    &vss_scheme_vec,
+                )
+                .unwrap()
+            })
+        });
+        c.bench_function("gg20 keygen round 4 batch dlog verification n=16", |b| {
+            b.iter(|| {
+                Keys::verify_dlog_proofs_batch(&params, &dlog_proofs_vec, &y_vec, &vss_scheme_vec)
+                    .unwrap()
+            })
+        });
+    }
+
+    criterion_group! {
+    name = keygen;
+    config = Criterion::default().sample_size(10);
+    targets = self::bench_keygen_verification, self::bench_dlog_batch_verification}
+}
+
+criterion_main!(bench::keygen);
@@ -0,0 +1,113 @@
+use criterion::criterion_main;
+
+mod bench {
+    use criterion::{criterion_group, BatchSize, BenchmarkId, Criterion};
+    use multi_party_ecdsa::protocols::multi_party_ecdsa::gg_2020::party_i::Keys;
+    use multi_party_ecdsa::protocols::multi_party_ecdsa::gg_2020::state_machine::keygen::{
+        Keygen, ProtocolMessage,
+    };
+    use round_based::{Msg, StateMachine};
+
+    const T: u16 = 1;
+    const PARTIES: [u16; 3] = [3, 7, 16];
+
+    /// Keys and messages of honest keygen, replayed to bring party 1 to the round being measured
+    ///
+    /// Party 1 is replayed with the same keys and VSS seed as in recorded keygen, so messages of
+    /// other parties recorded once are valid input of every replay.
+    struct Recording {
+        n: u16,
+        keys: Vec<Keys>,
+        msgs: Vec<Msg<ProtocolMessage>>,
+    }
+
+    fn party(i: u16, n: u16, keys: &[Keys]) -> Keygen {
+        let mut party = Keygen::with_keys(i, T, n, keys[usize::from(i - 1)].clone()).unwrap();
+        let mut seed = [0u8; 32];
+        seed[..2].copy_from_slice(&i.to_be_bytes());
+        party.use_vss_seed(seed);
+        party
+    }
+
+    fn is_addressed_to(msg: &Msg<ProtocolMessage>, i: u16) -> bool {
+        msg.sender != i && msg.receiver.map_or(true, |j| j == i)
+    }
+
+    impl Recording {
+        fn new(n: u16) -> Self {
+            let keys: Vec<Keys> = (1..=usize::from(n)).map(Keys::create).collect();
+            let mut parties: Vec<Keygen> = (1..=n).map(|i| party(i, n, &keys)).collect();
+
+            let mut msgs: Vec<Msg<ProtocolMessage>> = vec![];
+            while !parties.iter().all(|p| p.is_finished()) {
+                let delivered = msgs.len();
+                for party in &mut parties {
+                    if party.wants_to_proceed() {
+                        party.proceed().unwrap();
+                    }
+                    msgs.extend(party.message_queue().drain(..));
+                }
+                for msg in &msgs[delivered..] {
+                    for party in &mut parties {
+                        if is_addressed_to(msg, party.party_ind()) {
+                            party.handle_incoming(msg.clone()).unwrap();
+                        }
+                    }
+                }
+            }
+            Self { n, keys, msgs }
+        }
+
+        /// Party 1 that received every message and is ready to proceed the `round`
+        fn party_ready_to_proceed(&self, round: u16) -> Keygen {
+            let mut party = party(1, self.n, &self.keys);
+            for msg in self.msgs.iter().filter(|msg| is_addressed_to(msg, 1)) {
+                party.handle_incoming(msg.clone()).unwrap();
+            }
+            while party.current_round() < round {
+                party.proceed().unwrap();
+                party.message_queue().clear();
+            }
+            assert!(party.wants_to_proceed());
+            party
+        }
+    }
+
+    pub fn bench_keygen_rounds(c: &mut Criterion) {
+        let mut group = c.benchmark_group("gg20 keygen rounds");
+        for &n in PARTIES.iter() {
+            group.bench_with_input(BenchmarkId::new("round 0", n), &n, |b, &n| {
+                b.iter_batched(
+                    || Keygen::new(1, T, n).unwrap(),
+                    |mut party| party.proceed().unwrap(),
+                    BatchSize::PerIteration,
+                )
+            });
+
+            let recording = Recording::new(n);
+            // Round 2 verifies decommitments, deals VSS and encrypts shares. Round 4 verifies
+            // DLog proofs of all parties
+            for &round in [2, 4].iter() {
+                group.bench_with_input(
+                    BenchmarkId::new(format!("round {}", round), n),
+                    &recording,
+                    |b, recording| {
+                        b.iter_batched(
+                            || recording.party_ready_to_proceed(round),
+                            |mut party| party.proceed().unwrap(),
+                            BatchSize::PerIteration,
+                        )
+                    },
+                );
+            }
+        }
+        group.finish();
+    }
+
+    criterion_group! {
+    name = keygen_rounds;
+    config = Criterion::default().sample_size(10);
+    targets = self::bench_keygen_rounds}
+}
+
+criterion_main!(bench::keygen_rounds);
@@ -19,6 +19,8 @@
 #![allow(clippy::type_complexity)]
 
 pub mod protocols;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
 pub mod utilities;
 pub use protocols::multi_party_ecdsa::gg_2020::state_machine::traits::MessageRoundID;
 use std::fmt;
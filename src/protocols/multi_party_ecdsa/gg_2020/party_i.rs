@@ -23,6 +23,7 @@ use centipede::juggling::segmentation::Msegmentation;
 use curv::arithmetic::traits::*;
 use curv::cryptographic_primitives::commitments::hash_commitment::HashCommitment;
 use curv::cryptographic_primitives::commitments::traits::Commitment;
+use curv::cryptographic_primitives::hashing::{Digest, DigestExt};
 use curv::cryptographic_primitives::proofs::sigma_correct_homomorphic_elgamal_enc::*;
 use curv::cryptographic_primitives::proofs::sigma_dlog::DLogProof;
 use curv::elliptic::curves::{secp256_k1::Secp256k1, Curve, Point, Scalar};
@@ -32,7 +33,8 @@ use sha2::Sha256;
 use crate::protocols::multi_party_ecdsa::gg_2018::VerifiableSS;
 use crate::Error::{self, InvalidSig, Phase5BadSum, Phase6Error};
 use paillier::{
-    Decrypt, DecryptionKey, EncryptionKey, KeyGeneration, Paillier, RawCiphertext, RawPlaintext,
+    Decrypt, DecryptionKey, EncryptionKey, KeyGeneration, Keypair, Paillier, RawCiphertext,
+    RawPlaintext,
 };
 
 use serde::{Deserialize, Serialize};
@@ -43,11 +45,15 @@ use crate::protocols::multi_party_ecdsa::gg_2020::ErrorType;
 use crate::utilities::zk_pdl_with_slack::{PDLwSlackProof, PDLwSlackStatement, PDLwSlackWitness};
 use curv::cryptographic_primitives::proofs::sigma_valid_pedersen::PedersenProof;
 
+use hmac::{Hmac, Mac, NewMac};
 use std::convert::TryInto;
+use zeroize::{Zeroize, ZeroizeOnDrop};
 
 const SECURITY: usize = 256;
 const PAILLIER_MIN_BIT_LENGTH: usize = 2047;
 const PAILLIER_MAX_BIT_LENGTH: usize = 2048;
+/// Size (in bits) of Paillier modulus and `N_tilde` generated by [Keys::create]
+pub const DEFAULT_PAILLIER_BITS: usize = PAILLIER_MAX_BIT_LENGTH;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Parameters {
@@ -55,6 +61,13 @@ pub struct Parameters {
     pub share_count: u16, //n
 }
 
+impl Parameters {
+    /// Checks that parties controlled by adversary (up to `t`) are a minority out of `n`
+    pub fn honest_majority_ok(t: u16, n: u16) -> bool {
+        2 * u32::from(t) < u32::from(n)
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct Keys<E: Curve = Secp256k1> {
     pub u_i: Scalar<E>,
@@ -69,13 +82,92 @@ pub struct Keys<E: Curve = Secp256k1> {
     pub xhi_inv: BigInt,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+/// Wipes secret scalar `u_i`, Paillier primes and DLog witnesses `xhi`, `xhi_inv`
+impl<E: Curve> Zeroize for Keys<E> {
+    fn zeroize(&mut self) {
+        self.u_i = Scalar::zero();
+        self.dk.p.zeroize();
+        self.dk.q.zeroize();
+        self.xhi.zeroize();
+        self.xhi_inv.zeroize();
+    }
+}
+
+impl<E: Curve> Drop for Keys<E> {
+    fn drop(&mut self) {
+        self.zeroize()
+    }
+}
+
+impl<E: Curve> ZeroizeOnDrop for Keys<E> {}
+
+/// Secret part of [Keys]: secret scalar `u_i`, Paillier decryption key and DLog witnesses
+///
+/// Can be sealed (e.g. by HSM) independently of [public part](PublicMaterial). Use
+/// [Keys::from_parts] to put them back together.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct SecretMaterial {
+    pub u_i: Scalar<Secp256k1>,
+    pub dk: DecryptionKey,
+    pub xhi: BigInt,
+    pub xhi_inv: BigInt,
+}
+
+/// Wipes secret scalar `u_i`, Paillier primes and DLog witnesses `xhi`, `xhi_inv`
+impl Zeroize for SecretMaterial {
+    fn zeroize(&mut self) {
+        self.u_i = Scalar::zero();
+        self.dk.p.zeroize();
+        self.dk.q.zeroize();
+        self.xhi.zeroize();
+        self.xhi_inv.zeroize();
+    }
+}
+
+impl Drop for SecretMaterial {
+    fn drop(&mut self) {
+        self.zeroize()
+    }
+}
+
+impl ZeroizeOnDrop for SecretMaterial {}
+
+/// Public part of [Keys]
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PublicMaterial {
+    pub y_i: Point<Secp256k1>,
+    pub ek: EncryptionKey,
+    pub party_index: usize,
+    pub N_tilde: BigInt,
+    pub h1: BigInt,
+    pub h2: BigInt,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
 pub struct PartyPrivate {
     u_i: Scalar<Secp256k1>,
     x_i: Scalar<Secp256k1>,
     dk: DecryptionKey,
 }
 
+/// Wipes secret scalars `u_i`, `x_i` and Paillier primes
+impl Zeroize for PartyPrivate {
+    fn zeroize(&mut self) {
+        self.u_i = Scalar::zero();
+        self.x_i = Scalar::zero();
+        self.dk.p.zeroize();
+        self.dk.q.zeroize();
+    }
+}
+
+impl Drop for PartyPrivate {
+    fn drop(&mut self) {
+        self.zeroize()
+    }
+}
+
+impl ZeroizeOnDrop for PartyPrivate {}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct KeyGenBroadcastMessage1 {
     pub e: EncryptionKey,
@@ -98,6 +190,21 @@ pub struct SharedKeys<E: Curve = Secp256k1> {
     pub x_i: Scalar<E>,
 }
 
+/// Wipes secret share `x_i`
+impl<E: Curve> Zeroize for SharedKeys<E> {
+    fn zeroize(&mut self) {
+        self.x_i = Scalar::zero();
+    }
+}
+
+impl<E: Curve> Drop for SharedKeys<E> {
+    fn drop(&mut self) {
+        self.zeroize()
+    }
+}
+
+impl<E: Curve> ZeroizeOnDrop for SharedKeys<E> {}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct SignKeys {
     pub w_i: Scalar<Secp256k1>,
@@ -134,15 +241,240 @@ pub struct SignatureRecid {
     pub recid: u8,
 }
 
+/// Runs independent per-party `check` for parties `0..n`, returns parties that failed it
+///
+/// Checks are run in parallel with `rayon` feature.
+fn failed_checks<F>(n: usize, check: F) -> Vec<usize>
+where
+    F: Fn(usize) -> bool + Sync + Send,
+{
+    #[cfg(feature = "rayon")]
+    let passed: Vec<bool> = {
+        use rayon::prelude::*;
+        (0..n).into_par_iter().map(check).collect()
+    };
+    #[cfg(not(feature = "rayon"))]
+    let passed: Vec<bool> = (0..n).map(check).collect();
+    (0..n).filter(|&i| !passed[i]).collect()
+}
+
+/// Order of the field secp256k1 is defined over
+const SECP256K1_FIELD_PRIME: &str =
+    "fffffffffffffffffffffffffffffffffffffffffffffffffffffffefffffc2f";
+
+impl SignatureRecid {
+    /// Brings signature to low-S form required by BIP-62
+    ///
+    /// If `s > q/2`, replaces `s` with `q - s` and flips parity bit of `recid`, so the signature
+    /// still [recovers](Self::recover_public_key) the same public key. Signatures already in low-S
+    /// form are returned untouched. Signatures output by signing protocol are always normalized.
+    pub fn normalize_s(self) -> Self {
+        let s_bn = self.s.to_bigint();
+        let s_tag_bn = Scalar::<Secp256k1>::group_order() - &s_bn;
+        if s_bn > s_tag_bn {
+            SignatureRecid {
+                r: self.r,
+                s: Scalar::<Secp256k1>::from(&s_tag_bn),
+                recid: self.recid ^ 1,
+            }
+        } else {
+            self
+        }
+    }
+
+    /// Recovers public key from signature of `message`, the same way Ethereum's `ecrecover` does
+    ///
+    /// `recid` bit 0 is parity of `R.y`, bit 1 is set if `R.x` exceeded curve order. Returns
+    /// `None` if signature is malformed.
+    pub fn recover_public_key(&self, message: &BigInt) -> Option<Point<Secp256k1>> {
+        if self.recid > 3 {
+            return None;
+        }
+        let p = BigInt::from_hex(SECP256K1_FIELD_PRIME).expect("valid hex");
+        let mut x = self.r.to_bigint();
+        if self.recid & 2 != 0 {
+            x = x + Scalar::<Secp256k1>::group_order();
+            if x >= p {
+                return None;
+            }
+        }
+        // p = 3 mod 4, so square root of a is a^((p+1)/4)
+        let y_squared = BigInt::mod_add(
+            &BigInt::mod_pow(&x, &BigInt::from(3), &p),
+            &BigInt::from(7),
+            &p,
+        );
+        let mut y = BigInt::mod_pow(&y_squared, &((&p + BigInt::one()) / BigInt::from(4)), &p);
+        if y.test_bit(0) != (self.recid & 1 == 1) {
+            y = &p - &y;
+        }
+        let R = Point::<Secp256k1>::from_coords(&x, &y).ok()?;
+
+        let r_inv = self.r.invert()?;
+        let m = Scalar::<Secp256k1>::from(message);
+        let public_key = (R * &self.s - Point::generator() * &m) * &r_inv;
+        if public_key.is_zero() {
+            None
+        } else {
+            Some(public_key)
+        }
+    }
+
+    /// Encodes `(r, s)` as 64 bytes: big-endian `r` followed by big-endian `s`
+    ///
+    /// `recid` is not part of the encoding.
+    pub fn to_compact(&self) -> [u8; 64] {
+        let mut bytes = [0u8; 64];
+        bytes[..32].copy_from_slice(&scalar_to_be_bytes(&self.r));
+        bytes[32..].copy_from_slice(&scalar_to_be_bytes(&self.s));
+        bytes
+    }
+
+    /// Parses signature produced by [to_compact](Self::to_compact), `recid` is taken as given
+    ///
+    /// Returns [Error::InvalidSig] if `r` or `s` is zero or not less than curve order.
+    pub fn from_compact(bytes: &[u8; 64], recid: u8) -> Result<Self, Error> {
+        Ok(SignatureRecid {
+            r: scalar_from_be_bytes(&bytes[..32])?,
+            s: scalar_from_be_bytes(&bytes[32..])?,
+            recid,
+        })
+    }
+
+    /// Encodes `(r, s)` as DER `SEQUENCE { INTEGER r, INTEGER s }`, as Bitcoin does
+    ///
+    /// Integers are encoded in minimal number of bytes, with leading zero byte if the most
+    /// significant bit is set (otherwise the integer would be negative). `recid` is not part of
+    /// the encoding.
+    pub fn to_der(&self) -> Vec<u8> {
+        let r = der_integer(&scalar_to_be_bytes(&self.r));
+        let s = der_integer(&scalar_to_be_bytes(&self.s));
+        let mut der = Vec::with_capacity(2 + r.len() + s.len());
+        der.push(0x30);
+        der.push((r.len() + s.len()) as u8);
+        der.extend_from_slice(&r);
+        der.extend_from_slice(&s);
+        der
+    }
+
+    /// Parses strict DER signature produced by [to_der](Self::to_der), `recid` is taken as given
+    ///
+    /// Returns [Error::InvalidSig] on any non-canonical encoding: wrong lengths, trailing bytes,
+    /// negative integers or integers padded with unnecessary zero bytes. Also rejects `r` or `s`
+    /// that are zero or not less than curve order.
+    pub fn from_der(der: &[u8], recid: u8) -> Result<Self, Error> {
+        match der {
+            [0x30, len, body @ ..] if usize::from(*len) == body.len() => {
+                let (r, rest) = parse_der_integer(body)?;
+                let (s, rest) = parse_der_integer(rest)?;
+                if !rest.is_empty() {
+                    return Err(InvalidSig);
+                }
+                Ok(SignatureRecid {
+                    r: scalar_from_be_bytes(r)?,
+                    s: scalar_from_be_bytes(s)?,
+                    recid,
+                })
+            }
+            _ => Err(InvalidSig),
+        }
+    }
+}
+
+/// Big-endian encoding of a scalar, left-padded with zeroes to 32 bytes
+fn scalar_to_be_bytes(x: &Scalar<Secp256k1>) -> [u8; 32] {
+    let bytes = x.to_bigint().to_bytes();
+    let mut padded = [0u8; 32];
+    padded[32 - bytes.len()..].copy_from_slice(&bytes);
+    padded
+}
+
+/// Parses big-endian scalar in range `[1; q)`
+fn scalar_from_be_bytes(bytes: &[u8]) -> Result<Scalar<Secp256k1>, Error> {
+    let x = BigInt::from_bytes(bytes);
+    if x == BigInt::zero() || &x >= Scalar::<Secp256k1>::group_order() {
+        return Err(InvalidSig);
+    }
+    Ok(Scalar::from_bigint(&x))
+}
+
+/// DER `INTEGER` holding non-negative big-endian number `bytes`
+fn der_integer(bytes: &[u8]) -> Vec<u8> {
+    let first_nonzero = bytes
+        .iter()
+        .position(|b| *b != 0)
+        .unwrap_or(bytes.len() - 1);
+    let value = &bytes[first_nonzero..];
+    let pad = value[0] & 0x80 != 0;
+    let mut der = Vec::with_capacity(3 + value.len());
+    der.push(0x02);
+    der.push((value.len() + usize::from(pad)) as u8);
+    if pad {
+        der.push(0);
+    }
+    der.extend_from_slice(value);
+    der
+}
+
+/// Parses DER `INTEGER` at the start of `der`, returns its minimal big-endian value and the rest
+/// of input
+fn parse_der_integer(der: &[u8]) -> Result<(&[u8], &[u8]), Error> {
+    let (len, rest) = match der {
+        [0x02, len, rest @ ..] if (1..=33).contains(len) && usize::from(*len) <= rest.len() => {
+            (usize::from(*len), rest)
+        }
+        _ => return Err(InvalidSig),
+    };
+    let (value, rest) = rest.split_at(len);
+    match value {
+        // Negative integer
+        [first, ..] if first & 0x80 != 0 => Err(InvalidSig),
+        // Zero byte is allowed only to keep the next byte from being read as a sign
+        [0, second, ..] if second & 0x80 != 0 => Ok((&value[1..], rest)),
+        [0, _, ..] => Err(InvalidSig),
+        _ if len == 33 => Err(InvalidSig),
+        _ => Ok((value, rest)),
+    }
+}
+
+/// Recommended minimal size (in bits) of Paillier modulus for given curve
+///
+/// Paillier modulus must provide at least the same security level as the curve does. All the
+/// curves supported by `curv` target 128 bits of security which corresponds to 2048 bits modulus.
+/// Unknown curves get a conservative 3072 bits recommendation.
+pub fn recommended_paillier_bits_for_curve(curve: &str) -> usize {
+    match curve {
+        "secp256k1" | "secp256r1" | "ed25519" | "ristretto" | "bls12_381_1" | "bls12_381_2" => 2048,
+        _ => 3072,
+    }
+}
+
 pub fn generate_h1_h2_N_tilde() -> (BigInt, BigInt, BigInt, BigInt, BigInt) {
     // note, should be safe primes:
     // let (ek_tilde, dk_tilde) = Paillier::keypair_safe_primes().keys();;
     let (ek_tilde, dk_tilde) = Paillier::keypair().keys();
+    generate_h1_h2_N_tilde_from(&ek_tilde, &dk_tilde)
+}
+
+/// Derives `(N_tilde, h1, h2, xhi, xhi_inv)` from already generated modulus `N_tilde = p*q`
+pub fn generate_h1_h2_N_tilde_from(
+    ek_tilde: &EncryptionKey,
+    dk_tilde: &DecryptionKey,
+) -> (BigInt, BigInt, BigInt, BigInt, BigInt) {
+    h1_h2_N_tilde_with(ek_tilde, dk_tilde, BigInt::sample_below)
+}
+
+/// Same as [generate_h1_h2_N_tilde_from], but takes randomness from `sample_below`
+fn h1_h2_N_tilde_with(
+    ek_tilde: &EncryptionKey,
+    dk_tilde: &DecryptionKey,
+    mut sample_below: impl FnMut(&BigInt) -> BigInt,
+) -> (BigInt, BigInt, BigInt, BigInt, BigInt) {
     let one = BigInt::one();
     let phi = (&dk_tilde.p - &one) * (&dk_tilde.q - &one);
-    let h1 = BigInt::sample_below(&ek_tilde.n);
+    let h1 = sample_below(&ek_tilde.n);
     let (mut xhi, mut xhi_inv) = loop {
-        let xhi_ = BigInt::sample_below(&phi);
+        let xhi_ = sample_below(&phi);
         match BigInt::mod_inv(&xhi_, &phi) {
             Some(inv) => break (xhi_, inv),
             None => continue,
@@ -152,15 +484,395 @@ pub fn generate_h1_h2_N_tilde() -> (BigInt, BigInt, BigInt, BigInt, BigInt) {
     xhi = BigInt::sub(&phi, &xhi);
     xhi_inv = BigInt::sub(&phi, &xhi_inv);
 
-    (ek_tilde.n, h1, h2, xhi, xhi_inv)
+    (ek_tilde.n.clone(), h1, h2, xhi, xhi_inv)
+}
+
+/// Number of repetitions of proof of correct Paillier key made by
+/// [PaillierSetup::generate_insecure_dev_fast]
+#[cfg(feature = "dev_fast_proofs")]
+pub const DEV_FAST_CORRECT_KEY_PROOF_REPETITIONS: usize = 2;
+
+/// Verifies proof of correct Paillier key `ek`
+///
+/// With `dev_fast_proofs` feature, insecure proofs with
+/// [DEV_FAST_CORRECT_KEY_PROOF_REPETITIONS] repetitions are accepted as well.
+fn verify_correct_key_proof(proof: &NiCorrectKeyProof, ek: &EncryptionKey) -> bool {
+    #[cfg(feature = "dev_fast_proofs")]
+    {
+        let sigma_vec = dev_fast_sigma_vec(proof);
+        if sigma_vec.len() == DEV_FAST_CORRECT_KEY_PROOF_REPETITIONS {
+            return (0..).zip(&sigma_vec).all(|(i, sigma)| {
+                BigInt::mod_pow(sigma, &ek.n, &ek.n) == dev_fast_correct_key_challenge(&ek.n, i)
+            });
+        }
+    }
+    proof.verify(ek, zk_paillier::zkproofs::SALT_STRING).is_ok()
+}
+
+/// Proves that `dk` is a valid Paillier key by extracting `N`-th roots of
+/// [DEV_FAST_CORRECT_KEY_PROOF_REPETITIONS] challenges
+#[cfg(feature = "dev_fast_proofs")]
+fn prove_dev_fast_correct_key(dk: &DecryptionKey) -> NiCorrectKeyProof {
+    let one = BigInt::one();
+    let n = &dk.p * &dk.q;
+    let phi = (&dk.p - &one) * (&dk.q - &one);
+    let n_inv = BigInt::mod_inv(&n, &phi).expect("Paillier modulus is coprime to phi(N)");
+    let sigma_vec: Vec<_> = (0..DEV_FAST_CORRECT_KEY_PROOF_REPETITIONS as u64)
+        .map(|i| BigInt::mod_pow(&dev_fast_correct_key_challenge(&n, i), &n_inv, &n))
+        .collect();
+    // Fields of `NiCorrectKeyProof` are private, proof is built from its serialized form
+    serde_json::from_value(serde_json::json!({ "sigma_vec": sigma_vec }))
+        .expect("sigma_vec is a valid serialized proof")
+}
+
+/// Extracts `N`-th roots carried by `proof`
+#[cfg(feature = "dev_fast_proofs")]
+fn dev_fast_sigma_vec(proof: &NiCorrectKeyProof) -> Vec<BigInt> {
+    let mut proof = serde_json::to_value(proof).expect("serialization of proof doesn't fail");
+    serde_json::from_value(proof["sigma_vec"].take()).unwrap_or_default()
+}
+
+#[cfg(feature = "dev_fast_proofs")]
+fn dev_fast_correct_key_challenge(n: &BigInt, i: u64) -> BigInt {
+    Sha256::new()
+        .chain(b"gg20-dev-fast-correct-key-proof")
+        .chain_bigint(n)
+        .chain(i.to_be_bytes())
+        .result_bigint()
+        % n
+}
+
+impl KeyGenBroadcastMessage1 {
+    /// Public-only checks of party's first keygen messages
+    ///
+    /// Same checks parties do in phase 1: decommitment opens the commitment, Paillier key is
+    /// correct and of proper size, and `h1`, `h2` are correctly generated. Requires no secret
+    /// material, so it can be done by a third party (e.g. an auditor).
+    pub fn verify_with_decommit(&self, decom: &KeyGenDecommitMessage1) -> bool {
+        let decom_valid = HashCommitment::<Sha256>::create_commitment_with_user_defined_randomness(
+            &BigInt::from_bytes(&decom.y_i.to_bytes(true)),
+            &decom.blind_factor,
+        ) == self.com;
+        let size_valid = |n: &BigInt| {
+            (PAILLIER_MIN_BIT_LENGTH..=PAILLIER_MAX_BIT_LENGTH).contains(&n.bit_length())
+        };
+
+        decom_valid
+            && size_valid(&self.e.n)
+            && size_valid(&self.dlog_statement.N)
+            && self.verify_proofs()
+    }
+
+    /// Verifies proofs of correctness of Paillier key and `(N_tilde, h1, h2)`
+    ///
+    /// Unlike [verify_with_decommit](Self::verify_with_decommit), doesn't check the commitment
+    /// and key sizes, so it can be used to validate the message alone.
+    pub fn verify_proofs(&self) -> bool {
+        let dlog_statement_base_h2 = DLogStatement {
+            N: self.dlog_statement.N.clone(),
+            g: self.dlog_statement.ni.clone(),
+            ni: self.dlog_statement.g.clone(),
+        };
+        verify_correct_key_proof(&self.correct_key_proof, &self.e)
+            && self
+                .composite_dlog_proof_base_h1
+                .verify(&self.dlog_statement)
+                .is_ok()
+            && self
+                .composite_dlog_proof_base_h2
+                .verify(&dlog_statement_base_h2)
+                .is_ok()
+    }
+}
+
+/// Paillier key and `(N_tilde, h1, h2)` along with proofs of their correctness
+///
+/// It's the expensive part of keygen that doesn't depend on party's ECDSA secret, so it can be
+/// [generated](Self::generate) ahead of time, e.g. while server is idle, and later supplied to
+/// keygen (see [Keys::create_from_paillier_setup]). Contains secrets, and must be used for one
+/// keygen only.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PaillierSetup {
+    pub dk: DecryptionKey,
+    pub ek: EncryptionKey,
+    pub N_tilde: BigInt,
+    pub h1: BigInt,
+    pub h2: BigInt,
+    pub xhi: BigInt,
+    pub xhi_inv: BigInt,
+    pub correct_key_proof: NiCorrectKeyProof,
+    pub composite_dlog_proof_base_h1: CompositeDLogProof,
+    pub composite_dlog_proof_base_h2: CompositeDLogProof,
+}
+
+impl PaillierSetup {
+    /// Generates Paillier key, `(N_tilde, h1, h2)`, and proves their correctness
+    pub fn generate() -> Self {
+        Self::from_keys(&Keys::create(0))
+    }
+
+    /// Same as [generate](Self::generate), but makes proof of correct Paillier key with
+    /// [DEV_FAST_CORRECT_KEY_PROOF_REPETITIONS] repetitions only
+    ///
+    /// **Insecure**: reduced number of repetitions lowers soundness of the proof. Such proof is
+    /// accepted only by parties built with `dev_fast_proofs` feature.
+    #[cfg(feature = "dev_fast_proofs")]
+    pub fn generate_insecure_dev_fast() -> Self {
+        log::warn!("MP-ECDSA : generating INSECURE Paillier setup (dev_fast_proofs feature is on)");
+        let keys = Keys::create(0);
+        Self::with_correct_key_proof(&keys, prove_dev_fast_correct_key(&keys.dk))
+    }
+
+    /// Proves correctness of Paillier key and `h1`, `h2` of existing `keys`
+    fn from_keys(keys: &Keys) -> Self {
+        Self::with_correct_key_proof(keys, NiCorrectKeyProof::proof(&keys.dk, None))
+    }
+
+    /// Proves correctness of `h1`, `h2` of `keys`, taking proof of correct Paillier key as is
+    fn with_correct_key_proof(keys: &Keys, correct_key_proof: NiCorrectKeyProof) -> Self {
+        let dlog_statement_base_h1 = DLogStatement {
+            N: keys.N_tilde.clone(),
+            g: keys.h1.clone(),
+            ni: keys.h2.clone(),
+        };
+        let dlog_statement_base_h2 = DLogStatement {
+            N: keys.N_tilde.clone(),
+            g: keys.h2.clone(),
+            ni: keys.h1.clone(),
+        };
+
+        Self {
+            correct_key_proof,
+            composite_dlog_proof_base_h1: CompositeDLogProof::prove(
+                &dlog_statement_base_h1,
+                &keys.xhi,
+            ),
+            composite_dlog_proof_base_h2: CompositeDLogProof::prove(
+                &dlog_statement_base_h2,
+                &keys.xhi_inv,
+            ),
+            dk: keys.dk.clone(),
+            ek: keys.ek.clone(),
+            N_tilde: keys.N_tilde.clone(),
+            h1: keys.h1.clone(),
+            h2: keys.h2.clone(),
+            xhi: keys.xhi.clone(),
+            xhi_inv: keys.xhi_inv.clone(),
+        }
+    }
+}
+
+/// Pool of pre-generated safe primes
+///
+/// Safe primes search is the dominant cost of keygen. Servers provisioning many keys can fill the
+/// pool in background, and then create keys via [Keys::create_from_prime_pool] which draws
+/// primes from the pool instead of generating them inline.
+#[derive(Clone, Debug, Default)]
+pub struct SafePrimePool {
+    primes: Vec<BigInt>,
+}
+
+impl SafePrimePool {
+    /// Number of primes [Keys::create_from_prime_pool] draws from the pool
+    pub const PRIMES_PER_KEYS: usize = 4;
+
+    /// Constructs an empty pool
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Constructs a pool from given primes
+    ///
+    /// Primes must be distinct safe primes of the same size as Paillier's ones (1024 bits). This
+    /// is not checked.
+    pub fn from_primes(primes: Vec<BigInt>) -> Self {
+        Self { primes }
+    }
+
+    /// Generates safe primes until pool contains at least `count` of them
+    pub fn fill(&mut self, count: usize) {
+        while self.primes.len() < count {
+            let (_ek, dk) = Paillier::keypair_safe_primes().keys();
+            self.primes.push(dk.p);
+            self.primes.push(dk.q);
+        }
+    }
+
+    /// Number of primes in the pool
+    pub fn len(&self) -> usize {
+        self.primes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.primes.is_empty()
+    }
+
+    /// Takes `PRIMES_PER_KEYS` primes from the pool, or returns `None` leaving the pool intact
+    /// if there's not enough of them
+    fn take_for_keys(&mut self) -> Option<Vec<BigInt>> {
+        let len = self.primes.len();
+        if len < Self::PRIMES_PER_KEYS {
+            return None;
+        }
+        Some(self.primes.split_off(len - Self::PRIMES_PER_KEYS))
+    }
+}
+
+/// Deterministic stream of random numbers derived from seed, see [Keys::create_from_seed]
+struct SeedStream {
+    mac: Hmac<Sha256>,
+    index: u64,
+    counter: u64,
+}
+
+impl SeedStream {
+    fn new(seed: &[u8; 32], index: usize) -> Self {
+        Self {
+            mac: Hmac::new_varkey(seed).expect("HMAC takes key of any size"),
+            index: index as u64,
+            counter: 0,
+        }
+    }
+}
+
+/// Source of random bits party keys are sampled from, see [Keys::create_from_seed] and
+/// [Keys::create_with_random_bytes]
+trait RandomBits {
+    /// Takes `bits` random bits
+    fn sample_bits(&mut self, bits: usize) -> BigInt;
+
+    /// Takes random number in range `[0; n)` via rejection sampling
+    fn sample_below(&mut self, n: &BigInt) -> BigInt {
+        loop {
+            let x = self.sample_bits(n.bit_length());
+            if &x < n {
+                return x;
+            }
+        }
+    }
+
+    /// Takes random prime of exactly `bits` bits having two most significant bits set, so
+    /// product of two such primes has exactly `2 * bits` bits
+    fn sample_prime(&mut self, bits: usize) -> BigInt {
+        loop {
+            let mut candidate = self.sample_bits(bits);
+            candidate.set_bit(bits - 1, true);
+            candidate.set_bit(bits - 2, true);
+            candidate.set_bit(0, true);
+            if candidate.is_probable_prime(64) {
+                return candidate;
+            }
+        }
+    }
+}
+
+impl RandomBits for SeedStream {
+    fn sample_bits(&mut self, bits: usize) -> BigInt {
+        let mut bytes = Vec::with_capacity((bits + 7) / 8 + 32);
+        while bytes.len() * 8 < bits {
+            let mut mac = self.mac.clone();
+            mac.update(b"multi-party-ecdsa/keys-from-seed/v1");
+            mac.update(&self.index.to_be_bytes());
+            mac.update(&self.counter.to_be_bytes());
+            bytes.extend_from_slice(&mac.finalize().into_bytes());
+            self.counter += 1;
+        }
+        let excess_bits = (bytes.len() * 8 - bits) as u32;
+        BigInt::from_bytes(&bytes) / BigInt::from(2).pow(excess_bits)
+    }
+}
+
+/// Random bits taken from caller-supplied source of random bytes
+struct RandomBytes<F>(F);
+
+impl<F: FnMut(&mut [u8])> RandomBits for RandomBytes<F> {
+    fn sample_bits(&mut self, bits: usize) -> BigInt {
+        let mut bytes = vec![0u8; (bits + 7) / 8];
+        (self.0)(&mut bytes);
+        let excess_bits = (bytes.len() * 8 - bits) as u32;
+        let x = BigInt::from_bytes(&bytes) / BigInt::from(2).pow(excess_bits);
+        bytes.zeroize();
+        x
+    }
 }
 
 impl Keys {
     pub fn create(index: usize) -> Self {
+        Self::create_with_modulus_bits(index, DEFAULT_PAILLIER_BITS)
+    }
+
+    /// Same as [Keys::create], but generates Paillier modulus and `N_tilde` of `bits` size
+    ///
+    /// All parties of keygen must use the same size, see
+    /// [phase1_verify_com_phase3_verify_correct_key_verify_dlog_phase2_distribute_with_paillier_bits](Self::phase1_verify_com_phase3_verify_correct_key_verify_dlog_phase2_distribute_with_paillier_bits).
+    pub fn create_with_modulus_bits(index: usize, bits: usize) -> Self {
         let u = Scalar::<Secp256k1>::random();
         let y = Point::generator() * &u;
-        let (ek, dk) = Paillier::keypair().keys();
-        let (N_tilde, h1, h2, xhi, xhi_inv) = generate_h1_h2_N_tilde();
+        let (ek, dk) = Paillier::keypair_with_modulus_size(bits).keys();
+        let (ek_tilde, dk_tilde) = Paillier::keypair_with_modulus_size(bits).keys();
+        let (N_tilde, h1, h2, xhi, xhi_inv) = generate_h1_h2_N_tilde_from(&ek_tilde, &dk_tilde);
+
+        Self {
+            u_i: u,
+            y_i: y,
+            dk,
+            ek,
+            party_index: index,
+            N_tilde,
+            h1,
+            h2,
+            xhi,
+            xhi_inv,
+        }
+    }
+
+    /// Deterministically derives party keys from `seed`
+    ///
+    /// Secret `u_i`, Paillier primes, `N_tilde` primes and `h1`, `h2` are drawn from HMAC-SHA256
+    /// keyed by `seed` in counter mode, separately for every `index`. The same seed and index
+    /// always give the same keys, so the whole party can be restored from the seed. The seed
+    /// must be kept as secret as the keys themselves, and must not be reused across keygens:
+    /// keys of two keygens derived from the same seed are identical.
+    ///
+    /// Deriving is as slow as generating fresh keys with [Keys::create], as it searches for
+    /// primes of the same size.
+    pub fn create_from_seed(index: usize, seed: &[u8; 32]) -> Self {
+        Self::create_from_random_bits(index, DEFAULT_PAILLIER_BITS, SeedStream::new(seed, index))
+    }
+
+    /// Same as [Keys::create_with_modulus_bits], but takes all the randomness from `fill_bytes`
+    ///
+    /// Secret `u_i`, Paillier primes, `N_tilde` primes and `h1`, `h2` are sampled from bytes
+    /// written by `fill_bytes`, e.g. by a hardware RNG. The source must be cryptographically
+    /// secure: keys are only as secret as the bytes it produces.
+    pub fn create_with_random_bytes(
+        index: usize,
+        bits: usize,
+        fill_bytes: impl FnMut(&mut [u8]),
+    ) -> Self {
+        Self::create_from_random_bits(index, bits, RandomBytes(fill_bytes))
+    }
+
+    fn create_from_random_bits(index: usize, bits: usize, mut stream: impl RandomBits) -> Self {
+        let u = Scalar::<Secp256k1>::from_bigint(
+            &stream.sample_below(Scalar::<Secp256k1>::group_order()),
+        );
+        let y = Point::generator() * &u;
+        let prime_bits = bits / 2;
+        let keypair = |stream: &mut dyn RandomBits| {
+            let p = stream.sample_prime(prime_bits);
+            let q = loop {
+                let q = stream.sample_prime(prime_bits);
+                if q != p {
+                    break q;
+                }
+            };
+            Keypair { p, q }.keys()
+        };
+        let (ek, dk) = keypair(&mut stream);
+        let (ek_tilde, dk_tilde) = keypair(&mut stream);
+        let (N_tilde, h1, h2, xhi, xhi_inv) =
+            h1_h2_N_tilde_with(&ek_tilde, &dk_tilde, |n| stream.sample_below(n));
 
         Self {
             u_i: u,
@@ -197,6 +909,111 @@ impl Keys {
             xhi_inv,
         }
     }
+    /// Same as [Keys::create_safe_prime], but draws primes from the `pool` instead of generating
+    /// them
+    ///
+    /// Returns `None` if pool has less than [SafePrimePool::PRIMES_PER_KEYS] primes.
+    pub fn create_from_prime_pool(index: usize, pool: &mut SafePrimePool) -> Option<Self> {
+        let mut primes = pool.take_for_keys()?;
+        let (ek, dk) = Keypair {
+            p: primes.pop()?,
+            q: primes.pop()?,
+        }
+        .keys();
+        let (ek_tilde, dk_tilde) = Keypair {
+            p: primes.pop()?,
+            q: primes.pop()?,
+        }
+        .keys();
+
+        // Product of two `bits/2`-bit primes may be one bit shorter than `bits`
+        let recommended_bits = recommended_paillier_bits_for_curve(Secp256k1::CURVE_NAME);
+        if ek.n.bit_length() + 1 < recommended_bits {
+            log::warn!(
+                "Paillier modulus from prime pool is {} bits, at least {} bits is recommended",
+                ek.n.bit_length(),
+                recommended_bits
+            );
+        }
+
+        let u = Scalar::<Secp256k1>::random();
+        let y = Point::generator() * &u;
+        let (N_tilde, h1, h2, xhi, xhi_inv) = generate_h1_h2_N_tilde_from(&ek_tilde, &dk_tilde);
+
+        Some(Self {
+            u_i: u,
+            y_i: y,
+            dk,
+            ek,
+            party_index: index,
+            N_tilde,
+            h1,
+            h2,
+            xhi,
+            xhi_inv,
+        })
+    }
+
+    /// Creates party keys with a fresh secret share and Paillier key from precomputed `setup`
+    pub fn create_from_paillier_setup(index: usize, setup: &PaillierSetup) -> Self {
+        let u = Scalar::<Secp256k1>::random();
+        let y = Point::generator() * &u;
+        Self {
+            u_i: u,
+            y_i: y,
+            dk: setup.dk.clone(),
+            ek: setup.ek.clone(),
+            party_index: index,
+            N_tilde: setup.N_tilde.clone(),
+            h1: setup.h1.clone(),
+            h2: setup.h2.clone(),
+            xhi: setup.xhi.clone(),
+            xhi_inv: setup.xhi_inv.clone(),
+        }
+    }
+
+    /// Assembles party keys from ECDSA secret `u_i` and Paillier key generated elsewhere, e.g. by
+    /// secure hardware
+    ///
+    /// Only `N_tilde`, `h1`, `h2` are generated, of the same size as Paillier modulus. Returns
+    /// keys along with [PaillierSetup] carrying proofs recomputed over the supplied Paillier key,
+    /// to be used with [phase1_broadcast_with_paillier_setup](Self::phase1_broadcast_with_paillier_setup).
+    /// Returns [Error::InvalidKey] if decryption key doesn't match encryption key, or if the proof
+    /// of correct Paillier key doesn't verify.
+    pub fn from_components(
+        index: usize,
+        u_i: Scalar<Secp256k1>,
+        paillier: (EncryptionKey, DecryptionKey),
+    ) -> Result<(Self, PaillierSetup), Error> {
+        let (ek, dk) = paillier;
+        if &dk.p * &dk.q != ek.n || ek.nn != &ek.n * &ek.n {
+            return Err(Error::InvalidKey);
+        }
+        // Product of two `bits/2`-bit primes may be one bit shorter than `bits`
+        let bits = (ek.n.bit_length() + 1) / 2 * 2;
+        let (ek_tilde, dk_tilde) = Paillier::keypair_with_modulus_size(bits).keys();
+        let (N_tilde, h1, h2, xhi, xhi_inv) = generate_h1_h2_N_tilde_from(&ek_tilde, &dk_tilde);
+
+        let keys = Self {
+            y_i: Point::generator() * &u_i,
+            u_i,
+            dk,
+            ek,
+            party_index: index,
+            N_tilde,
+            h1,
+            h2,
+            xhi,
+            xhi_inv,
+        };
+        let setup = PaillierSetup::from_keys(&keys);
+        setup
+            .correct_key_proof
+            .verify(&keys.ek, zk_paillier::zkproofs::SALT_STRING)
+            .map_err(|_| Error::InvalidKey)?;
+        Ok((keys, setup))
+    }
+
     pub fn create_from(u: Scalar<Secp256k1>, index: usize) -> Self {
         let y = Point::generator() * &u;
         let (ek, dk) = Paillier::keypair().keys();
@@ -216,39 +1033,76 @@ impl Keys {
         }
     }
 
+    /// Secret part of the keys
+    pub fn secret_material(&self) -> SecretMaterial {
+        SecretMaterial {
+            u_i: self.u_i.clone(),
+            dk: self.dk.clone(),
+            xhi: self.xhi.clone(),
+            xhi_inv: self.xhi_inv.clone(),
+        }
+    }
+
+    /// Public part of the keys
+    pub fn public_material(&self) -> PublicMaterial {
+        PublicMaterial {
+            y_i: self.y_i.clone(),
+            ek: self.ek.clone(),
+            party_index: self.party_index,
+            N_tilde: self.N_tilde.clone(),
+            h1: self.h1.clone(),
+            h2: self.h2.clone(),
+        }
+    }
+
+    /// Reconstructs keys from [secret](Self::secret_material) and [public](Self::public_material) parts
+    ///
+    /// `secret` is wiped once the keys are constructed.
+    pub fn from_parts(secret: SecretMaterial, public: PublicMaterial) -> Self {
+        Self {
+            u_i: secret.u_i.clone(),
+            y_i: public.y_i,
+            dk: secret.dk.clone(),
+            ek: public.ek,
+            party_index: public.party_index,
+            N_tilde: public.N_tilde,
+            h1: public.h1,
+            h2: public.h2,
+            xhi: secret.xhi.clone(),
+            xhi_inv: secret.xhi_inv.clone(),
+        }
+    }
+
     pub fn phase1_broadcast_phase3_proof_of_correct_key_proof_of_correct_h1h2(
         &self,
     ) -> (KeyGenBroadcastMessage1, KeyGenDecommitMessage1) {
-        let blind_factor = BigInt::sample(SECURITY);
-        let correct_key_proof = NiCorrectKeyProof::proof(&self.dk, None);
-
-        let dlog_statement_base_h1 = DLogStatement {
-            N: self.N_tilde.clone(),
-            g: self.h1.clone(),
-            ni: self.h2.clone(),
-        };
-        let dlog_statement_base_h2 = DLogStatement {
-            N: self.N_tilde.clone(),
-            g: self.h2.clone(),
-            ni: self.h1.clone(),
-        };
-
-        let composite_dlog_proof_base_h1 =
-            CompositeDLogProof::prove(&dlog_statement_base_h1, &self.xhi);
-        let composite_dlog_proof_base_h2 =
-            CompositeDLogProof::prove(&dlog_statement_base_h2, &self.xhi_inv);
+        self.phase1_broadcast_with_paillier_setup(&PaillierSetup::from_keys(self))
+    }
 
+    /// Produces phase 1 messages taking proofs of correct Paillier key and `h1`, `h2` from
+    /// precomputed `setup`
+    ///
+    /// `setup` must be the one keys were [created from](Self::create_from_paillier_setup).
+    pub fn phase1_broadcast_with_paillier_setup(
+        &self,
+        setup: &PaillierSetup,
+    ) -> (KeyGenBroadcastMessage1, KeyGenDecommitMessage1) {
+        let blind_factor = BigInt::sample(SECURITY);
         let com = HashCommitment::<Sha256>::create_commitment_with_user_defined_randomness(
             &BigInt::from_bytes(self.y_i.to_bytes(true).as_ref()),
             &blind_factor,
         );
         let bcm1 = KeyGenBroadcastMessage1 {
             e: self.ek.clone(),
-            dlog_statement: dlog_statement_base_h1,
+            dlog_statement: DLogStatement {
+                N: self.N_tilde.clone(),
+                g: self.h1.clone(),
+                ni: self.h2.clone(),
+            },
             com,
-            correct_key_proof,
-            composite_dlog_proof_base_h1,
-            composite_dlog_proof_base_h2,
+            correct_key_proof: setup.correct_key_proof.clone(),
+            composite_dlog_proof_base_h1: setup.composite_dlog_proof_base_h1.clone(),
+            composite_dlog_proof_base_h2: setup.composite_dlog_proof_base_h2.clone(),
         };
         let decom1 = KeyGenDecommitMessage1 {
             blind_factor,
@@ -263,18 +1117,35 @@ impl Keys {
         decom_vec: &[KeyGenDecommitMessage1],
         bc1_vec: &[KeyGenBroadcastMessage1],
     ) -> Result<(VerifiableSS<Secp256k1>, Vec<Scalar<Secp256k1>>, usize), ErrorType> {
-        log::info!("MP-ECDSA : Round 2 : params {:?}", params);
-        log::info!("MP-ECDSA : Round 2 : decom_vec {:?}", decom_vec);
-        log::info!("MP-ECDSA : Round 2 : bc1_vec {:?}", bc1_vec);
+        self.phase1_verify_com_phase3_verify_correct_key_verify_dlog_phase2_distribute_with_paillier_bits(
+            params,
+            decom_vec,
+            bc1_vec,
+            DEFAULT_PAILLIER_BITS,
+        )
+    }
+
+    /// Same as [phase1_verify_com_phase3_verify_correct_key_verify_dlog_phase2_distribute](Self::phase1_verify_com_phase3_verify_correct_key_verify_dlog_phase2_distribute),
+    /// but expects parties' Paillier moduli and `N_tilde` to be of `paillier_bits` size
+    pub fn phase1_verify_com_phase3_verify_correct_key_verify_dlog_phase2_distribute_with_paillier_bits(
+        &self,
+        params: &Parameters,
+        decom_vec: &[KeyGenDecommitMessage1],
+        bc1_vec: &[KeyGenBroadcastMessage1],
+        paillier_bits: usize,
+    ) -> Result<(VerifiableSS<Secp256k1>, Vec<Scalar<Secp256k1>>, usize), ErrorType> {
+        let (min_bit_length, max_bit_length) = (paillier_bits - 1, paillier_bits);
+        log::trace!("MP-ECDSA : Round 2 : params {:?}", params);
+        log::trace!("MP-ECDSA : Round 2 : decom_vec {:?}", decom_vec);
+        log::trace!("MP-ECDSA : Round 2 : bc1_vec {:?}", bc1_vec);
 
-        let mut bad_actors_vec = Vec::new();
         // test length:
-        log::info!(
+        log::trace!(
             "MP-ECDSA : Round 2 : decom_vec length ({:?}, ({:?}))",
             decom_vec.len(),
             usize::from(params.share_count)
         );
-        log::info!(
+        log::trace!(
             "MP-ECDSA : Round 2 : bc1_vec length ({:?}, ({:?}))",
             bc1_vec.len(),
             usize::from(params.share_count)
@@ -282,89 +1153,85 @@ impl Keys {
         assert_eq!(decom_vec.len(), usize::from(params.share_count));
         assert_eq!(bc1_vec.len(), usize::from(params.share_count));
         // test paillier correct key, h1,h2 correct generation and test decommitments
-        let correct_key_correct_decom_all = (0..bc1_vec.len())
-            .map(|i| {
-                let dlog_statement_base_h2 = DLogStatement {
-                    N: bc1_vec[i].dlog_statement.N.clone(),
-                    g: bc1_vec[i].dlog_statement.ni.clone(),
-                    ni: bc1_vec[i].dlog_statement.g.clone(),
-                };
-                let test_res_1 =
-                    HashCommitment::<Sha256>::create_commitment_with_user_defined_randomness(
-                        &BigInt::from_bytes(&decom_vec[i].y_i.to_bytes(true)),
-                        &decom_vec[i].blind_factor,
-                    ) == bc1_vec[i].com;
-                log::info!("MP-ECDSA : Round 2 : test_res_1 {:?}", test_res_1);
-
-                if !test_res_1 {
-                    // Do a dumb test to search if the right decommit exists
-                    for j in 0..bc1_vec.len() {
-                        if i != j {
-                            let test_res_1_1 =
-                                HashCommitment::<Sha256>::create_commitment_with_user_defined_randomness(
-                                    &BigInt::from_bytes(&decom_vec[j].y_i.to_bytes(true)),
-                                    &decom_vec[j].blind_factor,
-                                ) == bc1_vec[i].com;
-                            if test_res_1_1 {
-                                log::info!("MP-ECDSA : Round 2 RETEST : test_res_1_1 {:?}", test_res_1_1);
-                                log::info!("MP-ECDSA : Round 2 RETEST : i {:?}", i);
-                                log::info!("MP-ECDSA : Round 2 RETEST : j {:?}", j);
-                                log::info!("MP-ECDSA : Round 2 RETEST : decom_vec[i] {:?}", decom_vec[i]);
-                                log::info!("MP-ECDSA : Round 2 RETEST : decom_vec[j] {:?}", decom_vec[j]);
-                                log::info!("MP-ECDSA : Round 2 RETEST : bc1_vec[i] {:?}", bc1_vec[i]);
-                                log::info!("MP-ECDSA : Round 2 RETEST : bc1_vec[j] {:?}", bc1_vec[j]);
-                            }
+        let bad_actors_vec = failed_checks(bc1_vec.len(), |i| {
+            let dlog_statement_base_h2 = DLogStatement {
+                N: bc1_vec[i].dlog_statement.N.clone(),
+                g: bc1_vec[i].dlog_statement.ni.clone(),
+                ni: bc1_vec[i].dlog_statement.g.clone(),
+            };
+            let test_res_1 =
+                HashCommitment::<Sha256>::create_commitment_with_user_defined_randomness(
+                    &BigInt::from_bytes(&decom_vec[i].y_i.to_bytes(true)),
+                    &decom_vec[i].blind_factor,
+                ) == bc1_vec[i].com;
+            log::debug!("MP-ECDSA : Round 2 : test_res_1 {:?}", test_res_1);
+
+            if !test_res_1 {
+                // Do a dumb test to search if the right decommit exists
+                for j in 0..bc1_vec.len() {
+                    if i != j {
+                        let test_res_1_1 =
+                            HashCommitment::<Sha256>::create_commitment_with_user_defined_randomness(
+                                &BigInt::from_bytes(&decom_vec[j].y_i.to_bytes(true)),
+                                &decom_vec[j].blind_factor,
+                            ) == bc1_vec[i].com;
+                        if test_res_1_1 {
+                            log::trace!(
+                                "MP-ECDSA : Round 2 RETEST : test_res_1_1 {:?}",
+                                test_res_1_1
+                            );
+                            log::trace!("MP-ECDSA : Round 2 RETEST : i {:?}", i);
+                            log::trace!("MP-ECDSA : Round 2 RETEST : j {:?}", j);
+                            log::trace!(
+                                "MP-ECDSA : Round 2 RETEST : decom_vec[i] {:?}",
+                                decom_vec[i]
+                            );
+                            log::trace!(
+                                "MP-ECDSA : Round 2 RETEST : decom_vec[j] {:?}",
+                                decom_vec[j]
+                            );
+                            log::trace!("MP-ECDSA : Round 2 RETEST : bc1_vec[i] {:?}", bc1_vec[i]);
+                            log::trace!("MP-ECDSA : Round 2 RETEST : bc1_vec[j] {:?}", bc1_vec[j]);
                         }
                     }
                 }
+            }
 
-                let test_res_2 = bc1_vec[i]
-                    .correct_key_proof
-                    .verify(&bc1_vec[i].e, zk_paillier::zkproofs::SALT_STRING)
-                    .is_ok();
-                log::info!("MP-ECDSA : Round 2 : test_res_2 {:?}", test_res_2);
-
-                let test_res_3 = bc1_vec[i].e.n.bit_length() >= PAILLIER_MIN_BIT_LENGTH;
-                log::info!("MP-ECDSA : Round 2 : test_res_3 {:?}", test_res_3);
-
-                let test_res_4 = bc1_vec[i].e.n.bit_length() <= PAILLIER_MAX_BIT_LENGTH;
-                log::info!("MP-ECDSA : Round 2 : test_res_4 {:?}", test_res_4);
-
-                let test_res_5 =
-                    bc1_vec[i].dlog_statement.N.bit_length() >= PAILLIER_MIN_BIT_LENGTH;
-                log::info!("MP-ECDSA : Round 2 : test_res_5 {:?}", test_res_5);
-
-                let test_res_6 =
-                    bc1_vec[i].dlog_statement.N.bit_length() <= PAILLIER_MAX_BIT_LENGTH;
-                log::info!("MP-ECDSA : Round 2 : test_res_6 {:?}", test_res_6);
-                let test_res_7 = bc1_vec[i]
-                    .composite_dlog_proof_base_h1
-                    .verify(&bc1_vec[i].dlog_statement)
-                    .is_ok();
-                log::info!("MP-ECDSA : Round 2 : test_res_7 {:?}", test_res_7);
-                let test_res_8 = bc1_vec[i]
-                    .composite_dlog_proof_base_h2
-                    .verify(&dlog_statement_base_h2)
-                    .is_ok();
-                log::info!("MP-ECDSA : Round 2 : test_res_8 {:?}", test_res_8);
-
-                let test_res = test_res_1
-                    && test_res_2
-                    && test_res_3
-                    && test_res_4
-                    && test_res_5
-                    && test_res_6
-                    && test_res_7
-                    && test_res_8;
-
-                if !test_res {
-                    bad_actors_vec.push(i);
-                    false
-                } else {
-                    true
-                }
-            })
-            .all(|x| x);
+            let test_res_2 = verify_correct_key_proof(&bc1_vec[i].correct_key_proof, &bc1_vec[i].e);
+            log::debug!("MP-ECDSA : Round 2 : test_res_2 {:?}", test_res_2);
+
+            let test_res_3 = bc1_vec[i].e.n.bit_length() >= min_bit_length;
+            log::debug!("MP-ECDSA : Round 2 : test_res_3 {:?}", test_res_3);
+
+            let test_res_4 = bc1_vec[i].e.n.bit_length() <= max_bit_length;
+            log::debug!("MP-ECDSA : Round 2 : test_res_4 {:?}", test_res_4);
+
+            let test_res_5 = bc1_vec[i].dlog_statement.N.bit_length() >= min_bit_length;
+            log::debug!("MP-ECDSA : Round 2 : test_res_5 {:?}", test_res_5);
+
+            let test_res_6 = bc1_vec[i].dlog_statement.N.bit_length() <= max_bit_length;
+            log::debug!("MP-ECDSA : Round 2 : test_res_6 {:?}", test_res_6);
+            let test_res_7 = bc1_vec[i]
+                .composite_dlog_proof_base_h1
+                .verify(&bc1_vec[i].dlog_statement)
+                .is_ok();
+            log::debug!("MP-ECDSA : Round 2 : test_res_7 {:?}", test_res_7);
+            let test_res_8 = bc1_vec[i]
+                .composite_dlog_proof_base_h2
+                .verify(&dlog_statement_base_h2)
+                .is_ok();
+            log::debug!("MP-ECDSA : Round 2 : test_res_8 {:?}", test_res_8);
+
+            test_res_1
+                && test_res_2
+                && test_res_3
+                && test_res_4
+                && test_res_5
+                && test_res_6
+                && test_res_7
+                && test_res_8
+        });
+        let correct_key_correct_decom_all = bad_actors_vec.is_empty();
 
         let err_type = ErrorType {
             error_type: "invalid key".to_string(),
@@ -389,25 +1256,36 @@ impl Keys {
         vss_scheme_vec: &[VerifiableSS<Secp256k1>],
         index: usize,
     ) -> Result<(SharedKeys<Secp256k1>, DLogProof<Secp256k1, Sha256>), ErrorType> {
-        let mut bad_actors_vec = Vec::new();
+        self.phase2_verify_vss_construct_keypair_phase3_pok_dlog_with_hash(
+            params,
+            y_vec,
+            secret_shares_vec,
+            vss_scheme_vec,
+            index,
+        )
+    }
+
+    /// Same as [phase2_verify_vss_construct_keypair_phase3_pok_dlog](Self::phase2_verify_vss_construct_keypair_phase3_pok_dlog),
+    /// but DLog proof uses hash function `H` for Fiat-Shamir transform
+    pub fn phase2_verify_vss_construct_keypair_phase3_pok_dlog_with_hash<H: Digest + Clone>(
+        &self,
+        params: &Parameters,
+        y_vec: &[Point<Secp256k1>],
+        secret_shares_vec: &[Scalar<Secp256k1>],
+        vss_scheme_vec: &[VerifiableSS<Secp256k1>],
+        index: usize,
+    ) -> Result<(SharedKeys<Secp256k1>, DLogProof<Secp256k1, H>), ErrorType> {
         assert_eq!(y_vec.len(), usize::from(params.share_count));
         assert_eq!(secret_shares_vec.len(), usize::from(params.share_count));
         assert_eq!(vss_scheme_vec.len(), usize::from(params.share_count));
 
-        let correct_ss_verify = (0..y_vec.len())
-            .map(|i| {
-                let res = vss_scheme_vec[i]
-                    .validate_share(&secret_shares_vec[i], index.try_into().unwrap())
-                    .is_ok()
-                    && vss_scheme_vec[i].commitments[0] == y_vec[i];
-                if !res {
-                    bad_actors_vec.push(i);
-                    false
-                } else {
-                    true
-                }
-            })
-            .all(|x| x);
+        let bad_actors_vec = failed_checks(y_vec.len(), |i| {
+            vss_scheme_vec[i]
+                .validate_share(&secret_shares_vec[i], index.try_into().unwrap())
+                .is_ok()
+                && vss_scheme_vec[i].commitments[0] == y_vec[i]
+        });
+        let correct_ss_verify = bad_actors_vec.is_empty();
 
         let err_type = ErrorType {
             error_type: "invalid vss".to_string(),
@@ -466,28 +1344,21 @@ impl Keys {
         comm * &li
     }
 
-    pub fn verify_dlog_proofs_check_against_vss(
+    pub fn verify_dlog_proofs_check_against_vss<H: Digest + Clone>(
         params: &Parameters,
-        dlog_proofs_vec: &[DLogProof<Secp256k1, Sha256>],
+        dlog_proofs_vec: &[DLogProof<Secp256k1, H>],
         y_vec: &[Point<Secp256k1>],
         vss_vec: &[VerifiableSS<Secp256k1>],
     ) -> Result<(), ErrorType> {
-        let mut bad_actors_vec = Vec::new();
         assert_eq!(y_vec.len(), usize::from(params.share_count));
         assert_eq!(dlog_proofs_vec.len(), usize::from(params.share_count));
         let xi_commitments = Keys::get_commitments_to_xi(vss_vec);
-        let xi_dlog_verify = (0..y_vec.len())
-            .map(|i| {
-                let ver_res = DLogProof::verify(&dlog_proofs_vec[i]).is_ok();
-                let verify_against_vss = xi_commitments[i] == dlog_proofs_vec[i].pk;
-                if !ver_res || !verify_against_vss {
-                    bad_actors_vec.push(i);
-                    false
-                } else {
-                    true
-                }
-            })
-            .all(|x| x);
+        let bad_actors_vec = failed_checks(y_vec.len(), |i| {
+            let ver_res = DLogProof::verify(&dlog_proofs_vec[i]).is_ok();
+            let verify_against_vss = xi_commitments[i] == dlog_proofs_vec[i].pk;
+            ver_res && verify_against_vss
+        });
+        let xi_dlog_verify = bad_actors_vec.is_empty();
 
         let err_type = ErrorType {
             error_type: "bad dlog proof".to_string(),
@@ -501,14 +1372,61 @@ impl Keys {
             Err(err_type)
         }
     }
+
+    /// Same as [verify_dlog_proofs_check_against_vss](Self::verify_dlog_proofs_check_against_vss),
+    /// but verifies all DLog proofs at once
+    ///
+    /// Proofs are verified as a random linear combination `sum(ρ_i * R_i) = G * sum(ρ_i * s_i) +
+    /// sum(ρ_i * e_i * pk_i)`, which is substantially faster for large `n`. If the combined check
+    /// fails, falls back to verifying proofs one by one to find out which parties to blame.
+    pub fn verify_dlog_proofs_batch<H: Digest + Clone>(
+        params: &Parameters,
+        dlog_proofs_vec: &[DLogProof<Secp256k1, H>],
+        y_vec: &[Point<Secp256k1>],
+        vss_vec: &[VerifiableSS<Secp256k1>],
+    ) -> Result<(), ErrorType> {
+        assert_eq!(y_vec.len(), usize::from(params.share_count));
+        assert_eq!(dlog_proofs_vec.len(), usize::from(params.share_count));
+        let xi_commitments = Keys::get_commitments_to_xi(vss_vec);
+        let commitments_match = xi_commitments
+            .iter()
+            .zip(dlog_proofs_vec)
+            .all(|(xi_commitment, proof)| *xi_commitment == proof.pk);
+
+        if commitments_match && batch_verify_dlog_proofs(dlog_proofs_vec) {
+            Ok(())
+        } else {
+            Keys::verify_dlog_proofs_check_against_vss(params, dlog_proofs_vec, y_vec, vss_vec)
+        }
+    }
+}
+
+/// Checks random linear combination of Schnorr verification equations `R = G * s + pk * e`
+fn batch_verify_dlog_proofs<H: Digest + Clone>(proofs: &[DLogProof<Secp256k1, H>]) -> bool {
+    let generator = Point::<Secp256k1>::generator().to_point();
+    let mut lhs = Point::<Secp256k1>::zero();
+    let mut rhs = Point::<Secp256k1>::zero();
+    let mut response_sum = Scalar::<Secp256k1>::zero();
+    for proof in proofs {
+        let challenge: Scalar<Secp256k1> = H::new()
+            .chain_point(&proof.pk_t_rand_commitment)
+            .chain_point(&generator)
+            .chain_point(&proof.pk)
+            .result_scalar();
+        let rho = Scalar::<Secp256k1>::random();
+        lhs = lhs + &proof.pk_t_rand_commitment * &rho;
+        rhs = rhs + &proof.pk * (&rho * &challenge);
+        response_sum = response_sum + &rho * &proof.challenge_response;
+    }
+    lhs == rhs + Point::generator() * response_sum
 }
 
 impl PartyPrivate {
     pub fn set_private(key: Keys, shared_key: SharedKeys<Secp256k1>) -> Self {
         Self {
-            u_i: key.u_i,
-            x_i: shared_key.x_i,
-            dk: key.dk,
+            u_i: key.u_i.clone(),
+            x_i: shared_key.x_i.clone(),
+            dk: key.dk.clone(),
         }
     }
 
@@ -620,6 +1538,15 @@ impl SignKeys {
             index.try_into().unwrap(),
             s.as_slice(),
         );
+        Self::create_with_lagrange_coefficient(private_x_i, &li)
+    }
+
+    /// Same as [create](Self::create), but takes Lagrange coefficient `li` of the party for
+    /// signing committee instead of interpolating it
+    pub fn create_with_lagrange_coefficient(
+        private_x_i: &Scalar<Secp256k1>,
+        li: &Scalar<Secp256k1>,
+    ) -> Self {
         let w_i = li * private_x_i;
         let g = Point::generator();
         let g_w_i = g * &w_i;
@@ -939,36 +1866,25 @@ impl LocalSignature {
     }
 
     pub fn output_signature(&self, s_vec: &[Scalar<Secp256k1>]) -> Result<SignatureRecid, Error> {
-        let mut s = s_vec.iter().fold(self.s_i.clone(), |acc, x| acc + x);
-        let s_bn = s.to_bigint();
+        let s = s_vec.iter().fold(self.s_i.clone(), |acc, x| acc + x);
 
-        let r = Scalar::<Secp256k1>::from(
-            &self
-                .R
-                .x_coord()
-                .unwrap()
-                .mod_floor(Scalar::<Secp256k1>::group_order()),
-        );
-        let ry: BigInt = self
-            .R
-            .y_coord()
-            .unwrap()
-            .mod_floor(Scalar::<Secp256k1>::group_order());
+        let rx: BigInt = self.R.x_coord().unwrap();
+        let ry: BigInt = self.R.y_coord().unwrap();
+        let r = Scalar::<Secp256k1>::from(&rx.mod_floor(Scalar::<Secp256k1>::group_order()));
 
         /*
          Calculate recovery id - it is not possible to compute the public key out of the signature
          itself. Recovery id is used to enable extracting the public key uniquely.
          1. id = R.y & 1
-         2. if (s > curve.q / 2) id = id ^ 1
+         2. if (R.x >= curve.q) id = id | 2
+         3. if (s > curve.q / 2) id = id ^ 1, see SignatureRecid::normalize_s
         */
         let is_ry_odd = ry.test_bit(0);
         let mut recid = if is_ry_odd { 1 } else { 0 };
-        let s_tag_bn = Scalar::<Secp256k1>::group_order() - &s_bn;
-        if s_bn > s_tag_bn {
-            s = Scalar::<Secp256k1>::from(&s_tag_bn);
-            recid ^= 1;
+        if &rx >= Scalar::<Secp256k1>::group_order() {
+            recid |= 2;
         }
-        let sig = SignatureRecid { r, s, recid };
+        let sig = SignatureRecid { r, s, recid }.normalize_s();
         let ver = verify(&sig, &self.y, &self.m).is_ok();
         if ver {
             Ok(sig)
@@ -0,0 +1,89 @@
+//! Applying buffered messages to a round's message store at once
+
+use round_based::containers::MessageStore;
+use round_based::Msg;
+use thiserror::Error;
+
+/// Extension of [MessageStore] pushing many messages at once
+///
+/// Useful when catching up after reconnect: messages buffered by relay while party was offline
+/// are applied in one call.
+pub trait PushBatch: MessageStore {
+    /// Pushes `msgs` in order, stopping at the first message rejected by the store
+    ///
+    /// Messages preceding the rejected one stay applied, messages following it are not pushed.
+    /// [BatchError::index] tells position of the rejected message, so remaining messages are
+    /// `&msgs[index + 1..]`, if caller wants to retry them.
+    fn push_batch(&mut self, msgs: Vec<Msg<Self::M>>) -> Result<(), BatchError<Self::Err>>;
+}
+
+impl<S: MessageStore> PushBatch for S {
+    fn push_batch(&mut self, msgs: Vec<Msg<Self::M>>) -> Result<(), BatchError<Self::Err>> {
+        for (index, msg) in msgs.into_iter().enumerate() {
+            let sender = msg.sender;
+            self.push_msg(msg).map_err(|source| BatchError {
+                index,
+                sender,
+                source,
+            })?;
+        }
+        Ok(())
+    }
+}
+
+/// Message of a batch rejected by the store, see [PushBatch::push_batch]
+#[derive(Debug, Error)]
+#[error("message {index} of the batch (sent by party {sender}) is rejected: {source}")]
+pub struct BatchError<E: std::error::Error + 'static> {
+    /// Position of rejected message in the batch
+    pub index: usize,
+    /// Sender of rejected message
+    pub sender: u16,
+    /// Reason of rejection reported by the store
+    #[source]
+    pub source: E,
+}
+
+#[cfg(test)]
+mod test {
+    use round_based::containers::{BroadcastMsgsStore, StoreErr};
+
+    use super::*;
+
+    fn broadcast(sender: u16, body: u16) -> Msg<u16> {
+        Msg {
+            round: 1,
+            sender,
+            receiver: None,
+            body,
+        }
+    }
+
+    #[test]
+    fn batch_stops_at_first_rejected_message_keeping_previous_ones() {
+        let mut store = BroadcastMsgsStore::new(1, 4);
+        store.push_batch(vec![]).unwrap();
+
+        let err = store
+            .push_batch(vec![
+                broadcast(2, 20),
+                broadcast(3, 30),
+                broadcast(2, 21),
+                broadcast(4, 40),
+            ])
+            .unwrap_err();
+        assert_eq!((err.index, err.sender), (2, 2));
+        assert!(matches!(err.source, StoreErr::MsgOverwrite));
+
+        // Messages of parties 2 and 3 are applied, message of party 4 is not
+        assert!(store.wants_more());
+        let err = store
+            .push_batch(vec![broadcast(5, 50), broadcast(4, 40)])
+            .unwrap_err();
+        assert_eq!((err.index, err.sender), (0, 5));
+        store.push_batch(vec![broadcast(4, 40)]).unwrap();
+        assert!(!store.wants_more());
+        let msgs: Vec<_> = store.finish().unwrap().into_vec();
+        assert_eq!(msgs, [20, 30, 40]);
+    }
+}
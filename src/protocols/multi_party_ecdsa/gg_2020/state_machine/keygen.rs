@@ -2,43 +2,174 @@
 
 use std::fmt;
 use std::mem::replace;
-use std::time::Duration;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
 
 use curv::cryptographic_primitives::proofs::sigma_dlog::DLogProof;
-use curv::elliptic::curves::secp256_k1::Secp256k1;
+use curv::elliptic::curves::{secp256_k1::Secp256k1, Point};
 use round_based::containers::{
     push::{Push, PushExt},
     *,
 };
 use round_based::{IsCritical, Msg, StateMachine};
-use serde::{Deserialize, Serialize};
-use sha2::Sha256;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use sha2::{Digest, Sha256};
 use thiserror::Error;
 
 use crate::protocols::multi_party_ecdsa::gg_2018::VerifiableSS;
 use crate::protocols::multi_party_ecdsa::gg_2020;
+use crate::protocols::multi_party_ecdsa::gg_2020::party_i::{
+    recommended_paillier_bits_for_curve, Keys, PaillierSetup, SafePrimePool, DEFAULT_PAILLIER_BITS,
+};
+
+use super::session::{self, SessionTag};
 
+mod audit;
+mod audit_log;
+#[cfg(feature = "ed25519")]
+mod authenticated;
+mod bandwidth;
+mod bounded;
+mod builder;
+mod capability;
+mod checkpoint;
+mod cold_wallet;
+mod compact;
+mod compressed;
+mod dedup;
+mod derivation;
+#[cfg(feature = "tokio")]
+mod driver;
+#[cfg(feature = "ed25519")]
+mod emergency;
+mod envelope;
+mod equality;
+#[cfg(feature = "eth")]
+mod ethereum;
+mod indexing;
+mod key_set;
+mod legacy;
+mod manifest;
+mod paillier_context;
+mod paillier_rotation;
+mod parse;
+mod reconstruct;
+mod recovery;
+mod report;
+mod rng;
+#[cfg(feature = "metrics")]
+mod round_metrics;
 mod rounds;
+mod safe_words;
+mod share_assignment;
+mod share_cipher;
+mod share_proof;
+mod size;
+mod timed;
+mod transcript;
+mod versioned;
+mod weighted;
+#[cfg(feature = "cbor")]
+mod wire;
 
+pub use audit::{
+    audit_paillier_strength, validate_agreement, verify_audit_data, AgreementError, AuditError,
+    AuditView, PublicAuditData,
+};
+use audit_log::AuditRecorder;
+pub use audit_log::{AuditLogEntry, AuditLogError, RoundAuditLog};
+#[cfg(feature = "ed25519")]
+pub use authenticated::{AuthenticatedKeygen, SignedMessage};
+pub use bandwidth::{estimate_keygen_bandwidth, BandwidthEstimate, RoundBandwidth, WireFormat};
+use bounded::default_message_size_limit;
+pub use bounded::{DecodeError, DecodeLimits};
+pub use builder::{KeygenBuilder, KeygenBuilderError};
+pub use capability::{CapabilityDescriptor, QuorumRule};
+pub use checkpoint::StateError;
+pub use cold_wallet::{ColdWalletBlob, ColdWalletError};
+pub use compact::{CompactKeyError, CompactLocalKey, GroupContext};
+pub use compressed::CompressedLocalKey;
+use dedup::{Received, ReceivedDigests};
+#[cfg(feature = "tokio")]
+pub use driver::{drive_keygen, run_keygen, DriverError};
+#[cfg(feature = "ed25519")]
+pub use emergency::{EmergencyError, OverrideProof};
+pub use envelope::{EnvelopeError, KeygenEnvelope, KeygenMessageType};
+pub use equality::{verify_same_secret, EqualityProof, ProofError};
+pub use indexing::Indexing;
+pub use key_set::{validate_key_set, validate_signing_subset, KeySetError, SignerPublicKey};
+pub use legacy::{Gg18KeyShare, LegacyKeyError};
+pub use manifest::{CryptoOperation, OperationCount, RoundOperations};
+pub use paillier_context::CombinedPaillierContext;
+pub use paillier_rotation::{PaillierRotation, PaillierRotationError};
+pub use parse::{parse_keygen_message, KeygenMessage, ParseError};
 use private::InternalError;
-pub use rounds::{LocalKey, ProceedError};
-use rounds::{Round0, Round1, Round2, Round3, Round4};
+pub use reconstruct::ReconstructError;
+pub use recovery::RecoveryParty;
+pub use rng::RngProvider;
+pub(crate) use rounds::{
+    add_vss, check_distinct_dlog_statements, check_distinct_paillier_keys, check_own_slot,
+    collect_broadcasts, parties_sharing_modulus,
+};
+pub use rounds::{
+    validate_dlog_statement, validate_encrypted_share, verify_vss_inclusion, CiphertextError,
+    ConsistencyError, ContributionError, DLogStatementError, LocalKey, PaillierMismatch,
+    ProceedError, PublicKeyConfirmation, Round3Message, SessionExpiry, TransportMode,
+};
+use rounds::{Round0, Round1, Round2, Round3, Round4, Round5};
+pub use share_assignment::{verify_share_assignment, ShareAssignmentProof};
+pub use share_cipher::{PaillierShareCipher, ShareCipher};
+pub use share_proof::{
+    encrypt_share_with_proof, verify_share_encryption, ShareEncryptionProof, ShareProofError,
+};
+pub use size::SizeBreakdown;
+pub use timed::TimedKeygen;
+#[cfg(feature = "rayon")]
+pub use transcript::verify_transcripts_parallel;
+pub use transcript::{
+    verify_party_transcript, verify_transcript, KeygenTranscript, RecordedTranscript,
+    TranscriptError,
+};
+pub use versioned::VersionedKeyError;
+pub use weighted::WeightedKeygen;
+#[cfg(feature = "cbor")]
+pub use wire::CborError;
 
 /// Keygen protocol state machine
 ///
 /// Successfully completed keygen protocol produces [LocalKey] that can be used in further
 /// [signing](super::sign) protocol.
-pub struct Keygen {
-    round: R,
+pub struct Keygen<H: Digest + Clone = Sha256> {
+    round: R<H>,
 
-    msgs1: Option<Store<BroadcastMsgs<gg_2020::party_i::KeyGenBroadcastMessage1>>>,
+    msgs1: Option<
+        Store<
+            BroadcastMsgs<(
+                gg_2020::party_i::KeyGenBroadcastMessage1,
+                Vec<Point<Secp256k1>>,
+            )>,
+        >,
+    >,
     msgs2: Option<Store<BroadcastMsgs<gg_2020::party_i::KeyGenDecommitMessage1>>>,
-    msgs3: Option<Store<P2PMsgs<(VerifiableSS<Secp256k1>, Vec<u8>)>>>,
-    msgs4: Option<Store<BroadcastMsgs<DLogProof<Secp256k1, Sha256>>>>,
+    msgs3: Option<Store<P2PMsgs<Round3Message>>>,
+    msgs4: Option<Store<BroadcastMsgs<DLogProof<Secp256k1, H>>>>,
+    msgs5: Option<Store<BroadcastMsgs<PublicKeyConfirmation>>>,
 
-    msgs_queue: Vec<Msg<ProtocolMessage>>,
+    msgs_queue: Vec<Msg<ProtocolMessage<H>>>,
+    received: ReceivedDigests,
+    message_size_limit: u64,
+    warnings: Vec<KeygenWarning>,
+    audit_log: Option<AuditRecorder>,
+    transcript: Option<KeygenTranscript<H>>,
+    on_round_complete: Option<Box<dyn Fn(u16, bool) + Send>>,
+    confirm_public_key: bool,
+    session_id: Option<[u8; 32]>,
+    indexing: Indexing,
+    #[cfg(feature = "testing")]
+    vss_seed: Option<[u8; 32]>,
 
     party_i: u16,
+    party_t: u16,
     party_n: u16,
 }
 
@@ -54,25 +185,393 @@ impl Keygen {
     /// * `t` is not in range `[1; n-1]`, returns [Error::InvalidThreshold]
     /// * `i` is not in range `[1; n]`, returns [Error::InvalidPartyIndex]
     pub fn new(i: u16, t: u16, n: u16) -> Result<Self> {
-        if n < 2 {
-            return Err(Error::TooFewParties);
+        Self::with_dlog_hash(i, t, n)
+    }
+
+    /// Constructs a party `i` (either 1 or 2) of 2-of-2 keygen, e.g. user device and co-signing
+    /// server
+    ///
+    /// Runs the same rounds as [Keygen::new] with `t = 1` and `n = 2`, and produces an ordinary
+    /// [LocalKey] accepted by [OfflineStage](super::sign::OfflineStage) as is. Signing recognizes
+    /// such key by [LocalKey::is_two_party]: its committee is always both parties, so signing
+    /// takes their Lagrange coefficients from constants instead of interpolating them.
+    ///
+    /// ## Performance
+    /// Keygen costs exactly as much as `Keygen::new(i, 1, 2)`, it's dominated by generation of
+    /// Paillier keys and proofs of their correctness. Skipping interpolation saves a few field
+    /// inversions per signing, which is negligible next to MtA range proofs of the offline stage,
+    /// so 2-party signing is as fast as signing of any other 2-party committee.
+    ///
+    /// Returns [Error::InvalidPartyIndex] if `i` is neither 1 nor 2.
+    pub fn two_party(i: u16) -> Result<Self> {
+        Self::new(i, 1, 2)
+    }
+
+    /// Constructs a party of keygen protocol that generates its keys in insecure, dev-only mode
+    ///
+    /// Proof of correct Paillier key is made with
+    /// [DEV_FAST_CORRECT_KEY_PROOF_REPETITIONS](gg_2020::party_i::DEV_FAST_CORRECT_KEY_PROOF_REPETITIONS)
+    /// repetitions instead of the ones fixed by `zk_paillier`, which makes making and verifying it
+    /// faster, but breaks soundness of the proof. **Never use it in production.** Available only
+    /// with non-default `dev_fast_proofs` feature, the constructor doesn't exist without it.
+    /// Parties built without the feature reject such proof, so all parties of the keygen must be
+    /// built with it. `N_tilde` is still generated independently of Paillier key.
+    #[cfg(feature = "dev_fast_proofs")]
+    pub fn new_dev_fast_proofs(i: u16, t: u16, n: u16) -> Result<Self> {
+        log::warn!("MP-ECDSA : keygen runs with dev_fast_proofs, produced key is INSECURE");
+        let mut state = Self::new(i, t, n)?;
+        if let R::Round0(round) = &mut state.round {
+            round.dev_fast_proofs = true;
+        }
+        Ok(state)
+    }
+
+    /// Constructs a party of keygen protocol that binds parties to their long-term identities
+    ///
+    /// `party_identities` are identity public keys ordered by party index, every party must
+    /// provide the same list. Parties check that their lists match, and resulting [LocalKey]
+    /// records the identities (see [LocalKey::identity_of]).
+    ///
+    /// Returns the same errors as [Keygen::new], and [Error::InvalidPartyIdentities] if number of
+    /// identities doesn't match `n`.
+    pub fn with_party_identities(
+        i: u16,
+        t: u16,
+        n: u16,
+        party_identities: Vec<Point<Secp256k1>>,
+    ) -> Result<Self> {
+        if party_identities.len() != usize::from(n) {
+            return Err(Error::InvalidPartyIdentities);
+        }
+        let mut state = Self::new(i, t, n)?;
+        if let R::Round0(round) = &mut state.round {
+            round.party_identities = party_identities;
+        }
+        Ok(state)
+    }
+
+    /// Constructs a party of keygen protocol that sends encrypted backups of all the shares to
+    /// the `recovery_party`
+    ///
+    /// Recovery party can later reconstruct share of any party, see [LocalKey::recover_share].
+    /// Every party must provide the same recovery party.
+    ///
+    /// **Recovery party is a single point of compromise**: whoever gets recovery decryption key
+    /// along with `recovery_backups` of the recovery party's [LocalKey] can reconstruct every
+    /// share, and therefore the whole signing key, without any other party. Decryption key must
+    /// be kept offline, separately from the recovery party's key.
+    ///
+    /// Backups are not verified at keygen (besides being well-formed ciphertexts), as only the
+    /// decryption key holder can decrypt them. A malicious dealer can send backups that don't
+    /// match the shares it dealt, and keygen still succeeds: recovery of shares dealt by it
+    /// silently yields wrong shares, so recovered share must be checked against party's public
+    /// share.
+    ///
+    /// Returns the same errors as [Keygen::new], and [Error::InvalidRecoveryParty] if recovery
+    /// party index is not in range `[1; n]`.
+    pub fn with_recovery_party(
+        i: u16,
+        t: u16,
+        n: u16,
+        recovery_party: RecoveryParty,
+    ) -> Result<Self> {
+        if recovery_party.index == 0 || recovery_party.index > n {
+            return Err(Error::InvalidRecoveryParty);
+        }
+        let mut state = Self::new(i, t, n)?;
+        if let R::Round0(round) = &mut state.round {
+            round.recovery_party = Some(recovery_party);
+        }
+        Ok(state)
+    }
+
+    /// Constructs a party of keygen protocol that refuses to proceed once session is older than
+    /// `max_age`
+    ///
+    /// Age is checked at the start of every round, so abandoned ceremonies can't be resumed after
+    /// the policy window. Expired keygen fails with [ProceedError::SessionExpired].
+    pub fn with_session_expiry(
+        i: u16,
+        t: u16,
+        n: u16,
+        created_at: SystemTime,
+        max_age: Duration,
+    ) -> Result<Self> {
+        let mut state = Self::new(i, t, n)?;
+        if let R::Round0(round) = &mut state.round {
+            round.session_expiry = Some(SessionExpiry {
+                created_at,
+                max_age,
+            });
+        }
+        Ok(state)
+    }
+
+    /// Constructs a party of keygen protocol that uses precomputed Paillier key and its proofs
+    ///
+    /// [PaillierSetup] is the most expensive part of keygen, generating it in advance makes keygen
+    /// itself considerably faster. Every setup must be used in one keygen only.
+    pub fn with_paillier_setup(i: u16, t: u16, n: u16, setup: PaillierSetup) -> Result<Self> {
+        let mut state = Self::new(i, t, n)?;
+        if let R::Round0(round) = &mut state.round {
+            round.paillier_setup = Some(setup);
+        }
+        Ok(state)
+    }
+
+    /// Constructs a party of keygen protocol that generates Paillier modulus and `N_tilde` of
+    /// `paillier_bits` size
+    ///
+    /// Default size is 2048 bits. Every party must use the same size, otherwise keygen fails with
+    /// [ProceedError::PaillierKeySizeMismatch]. Returns the same errors as [Keygen::new], and
+    /// [Error::InvalidPaillierBits] if size is odd or less than recommended for secp256k1.
+    pub fn with_paillier_bits(i: u16, t: u16, n: u16, paillier_bits: usize) -> Result<Self> {
+        validate_parameters(i, t, n)?;
+        let min_bits = recommended_paillier_bits_for_curve("secp256k1");
+        if paillier_bits < min_bits || paillier_bits % 2 != 0 {
+            return Err(Error::InvalidPaillierBits {
+                bits: paillier_bits,
+                min_bits,
+            });
+        }
+        let mut state = Self::new(i, t, n)?;
+        if let R::Round0(round) = &mut state.round {
+            round.paillier_bits = paillier_bits;
+        }
+        state.message_size_limit = default_message_size_limit(paillier_bits, n);
+        Ok(state)
+    }
+
+    /// Constructs a party of keygen protocol that encrypts shares sent to other parties with
+    /// `share_cipher`
+    ///
+    /// By default, shares are encrypted with [PaillierShareCipher]. Every party must use
+    /// compatible cipher, otherwise keygen fails at round 3.
+    pub fn with_share_cipher(
+        i: u16,
+        t: u16,
+        n: u16,
+        share_cipher: Arc<dyn ShareCipher>,
+    ) -> Result<Self> {
+        let mut state = Self::new(i, t, n)?;
+        if let R::Round0(round) = &mut state.round {
+            round.share_cipher = share_cipher;
+        }
+        Ok(state)
+    }
+
+    /// Constructs a party of keygen protocol that samples its secrets from `rng_provider`
+    ///
+    /// Secret `u_i`, Paillier and `N_tilde` primes, `h1`, `h2` and coefficients of VSS polynomial
+    /// are drawn from the provider instead of OS RNG. Nonces of zero-knowledge proofs (including
+    /// DLog proof of round 3), commitment blinding and Paillier encryption randomness are still
+    /// sampled by `curv`, `zk-paillier` and `paillier` from OS RNG, as these libraries don't take
+    /// external randomness. Provider is not part of [saved state](Self::save_state), keygen saved
+    /// before round 3 must be resumed with
+    /// [load_state_with_rng_provider](Self::load_state_with_rng_provider).
+    pub fn with_rng_provider(
+        i: u16,
+        t: u16,
+        n: u16,
+        rng_provider: Arc<dyn RngProvider>,
+    ) -> Result<Self> {
+        let mut state = Self::new(i, t, n)?;
+        if let R::Round0(round) = &mut state.round {
+            round.rng_provider = Some(rng_provider);
+        }
+        Ok(state)
+    }
+
+    /// Constructs a party of keygen protocol that delivers shares to other parties as specified
+    /// by `mode`
+    ///
+    /// With [TransportMode::ExternalSecureChannel] shares are sent in clear without Paillier
+    /// encryption, so p2p messages of round 3 **must** be delivered over authenticated and
+    /// encrypted channels. Every party must use the same mode, otherwise keygen fails at round 3
+    /// with [ProceedError::TransportModeMismatch]. Paillier keys are generated in either mode,
+    /// as they're needed for signing.
+    pub fn with_transport_mode(i: u16, t: u16, n: u16, mode: TransportMode) -> Result<Self> {
+        let mut state = Self::new(i, t, n)?;
+        if let R::Round0(round) = &mut state.round {
+            round.transport_mode = mode;
+        }
+        Ok(state)
+    }
+
+    /// Constructs a party of keygen protocol that proves correct encryption of every share it
+    /// sends at round 3, and requires the same proofs from other parties
+    ///
+    /// Each share is sent along with [ShareEncryptionProof] made for receiver's DLog statement,
+    /// so anyone can check that the ciphertext encrypts the share committed in VSS scheme of the
+    /// sender without decrypting it (see [verify_share_encryption]), e.g. to resolve complaint
+    /// of the receiver. Party fails at round 3 with [ProceedError::InvalidShareEncryptionProof]
+    /// if a share comes without a valid proof, so every party must enable the proofs. Shares are
+    /// encrypted with [PaillierShareCipher], proofs are not sent with
+    /// [TransportMode::ExternalSecureChannel].
+    pub fn with_share_encryption_proofs(i: u16, t: u16, n: u16) -> Result<Self> {
+        let mut state = Self::new(i, t, n)?;
+        if let R::Round0(round) = &mut state.round {
+            round.share_encryption_proofs = true;
+        }
+        Ok(state)
+    }
+
+    /// Constructs a party of keygen protocol that uses pre-generated party `keys` instead of
+    /// generating fresh ones
+    ///
+    /// Keys must be created for party `i`, otherwise keygen fails at round 0 with
+    /// [ProceedError::KeysPartyMismatch]. Every party must use Paillier keys of the same size.
+    pub fn with_keys(i: u16, t: u16, n: u16, keys: Keys) -> Result<Self> {
+        let mut state = Self::new(i, t, n)?;
+        if let R::Round0(round) = &mut state.round {
+            round.pool_keys = Some(keys);
+        }
+        Ok(state)
+    }
+
+    /// Constructs a party of keygen protocol that draws safe primes for its Paillier key and
+    /// `N_tilde` from the `pool`
+    ///
+    /// Takes [SafePrimePool::PRIMES_PER_KEYS] primes from the pool. Returns the same errors as
+    /// [Keygen::new], and [Error::PrimePoolExhausted] if pool doesn't have enough primes.
+    pub fn with_prime_pool(i: u16, t: u16, n: u16, pool: &mut SafePrimePool) -> Result<Self> {
+        let mut state = Self::new(i, t, n)?;
+        let round = Round0::with_prime_pool(i, t, n, pool).ok_or(Error::PrimePoolExhausted)?;
+        state.round = R::Round0(round);
+        Ok(state)
+    }
+
+    /// Constructs a party of keygen protocol bound to keygen session `session_id`
+    ///
+    /// Every sent message is tagged with hash of session id, round and sender, and received
+    /// messages with a different tag are rejected with [Error::SessionMismatch]. It prevents
+    /// messages of one keygen from being fed into another one, when many keygens run concurrently
+    /// over the same channel. Session id is chosen by orchestrator, it must be unique and the same
+    /// for every party of the keygen.
+    pub fn with_session_id(i: u16, t: u16, n: u16, session_id: [u8; 32]) -> Result<Self> {
+        let mut state = Self::new(i, t, n)?;
+        state.set_session_id(session_id);
+        Ok(state)
+    }
+
+    fn set_session_id(&mut self, session_id: [u8; 32]) {
+        self.session_id = Some(session_id);
+        // Round 0 messages are already sent, they need to be tagged too
+        for msg in &mut self.msgs_queue {
+            let sender = self.indexing.to_internal(msg.sender);
+            msg.body.1 = Some(session_tag(&session_id, msg.round, sender));
+        }
+    }
+
+    /// Switches numbering of parties in messages, see [KeygenBuilder::indexing]
+    fn set_indexing(&mut self, indexing: Indexing) {
+        for msg in &mut self.msgs_queue {
+            msg.sender = self.indexing.to_internal(msg.sender);
+            msg.receiver = msg.receiver.map(|j| self.indexing.to_internal(j));
         }
-        if t == 0 || t >= n {
-            return Err(Error::InvalidThreshold);
+        indexing.outgoing(&mut self.msgs_queue);
+        self.indexing = indexing;
+    }
+
+    /// Constructs a party of keygen protocol that keeps [tamper-evident log](RoundAuditLog) of
+    /// its rounds
+    ///
+    /// Every entry of the log is authenticated with `audit_key`, the same key is needed to
+    /// [verify](RoundAuditLog::verify) the log later. Log is available via [Keygen::audit_log],
+    /// its [head](RoundAuditLog::head) should be stored separately from the log.
+    pub fn with_audit_log(i: u16, t: u16, n: u16, audit_key: [u8; 32]) -> Result<Self> {
+        let mut state = Self::new(i, t, n)?;
+        state.audit_log = Some(AuditRecorder::new(audit_key));
+        Ok(state)
+    }
+
+    /// Constructs a party of keygen protocol that records every message it sends and receives
+    ///
+    /// Transcript is available via [Keygen::transcript], it can be kept for audits and checked
+    /// against keygen output later via [verify_party_transcript].
+    pub fn with_transcript(i: u16, t: u16, n: u16) -> Result<Self> {
+        let mut state = Self::new(i, t, n)?;
+        state.transcript = Some(KeygenTranscript::new(i, t, n));
+        Ok(state)
+    }
+
+    /// Constructs a party of keygen protocol that runs additional confirmation round
+    ///
+    /// At round 5, every party broadcasts hash of the public key it derived, and keygen fails with
+    /// [ProceedError::PublicKeyMismatch] if hashes don't match. It catches parties ending up with
+    /// inconsistent keys at keygen time rather than at first signing. Every party must enable the
+    /// confirmation.
+    pub fn with_public_key_confirmation(i: u16, t: u16, n: u16) -> Result<Self> {
+        let mut state = Self::new(i, t, n)?;
+        state.confirm_public_key = true;
+        Ok(state)
+    }
+
+    /// Ordering constraints between keygen messages
+    ///
+    /// Every edge `(a, b)` states that messages `b` can only be sent once all messages `a` are
+    /// received. It's static metadata, the same for any keygen. Optional [confirmation
+    /// round](Keygen::with_public_key_confirmation) is not listed.
+    pub fn message_dependencies() -> Vec<(RoundMsg, RoundMsg)> {
+        let msgs = [
+            RoundMsg::broadcast(1),
+            RoundMsg::broadcast(2),
+            RoundMsg::p2p(3),
+            RoundMsg::broadcast(4),
+        ];
+        msgs.windows(2).map(|w| (w[0], w[1])).collect()
+    }
+}
+
+impl<H: Digest + Clone> Keygen<H> {
+    /// Constructs a party of keygen protocol which DLog proofs use hash function `H`
+    ///
+    /// DLog proofs of parties' secret shares (round 4) are made non-interactive via Fiat-Shamir
+    /// transform, which uses SHA-256 in keygen constructed by [Keygen::new]. Every party must use
+    /// the same `H`, otherwise proofs of other parties don't verify and keygen fails with
+    /// [ProceedError::Round4VerifyDLogProof]. Returns the same errors as [Keygen::new].
+    pub fn with_dlog_hash(i: u16, t: u16, n: u16) -> Result<Self> {
+        validate_parameters(i, t, n)?;
+        let mut warnings = vec![];
+        if !gg_2020::party_i::Parameters::honest_majority_ok(t, n) {
+            log::warn!(
+                "MP-ECDSA : keygen t={} n={} doesn't have honest majority",
+                t,
+                n
+            );
+            warnings.push(KeygenWarning::NoHonestMajority);
         }
-        if i == 0 || i > n {
-            return Err(Error::InvalidPartyIndex);
+        if t + 1 == n {
+            log::debug!(
+                "MP-ECDSA : keygen t={} n={} requires every party to sign",
+                t,
+                n
+            );
+            warnings.push(KeygenWarning::FullConsensus);
         }
         let mut state = Self {
-            round: R::Round0(Round0 { party_i: i, t, n }),
+            round: R::Round0(Round0::new(i, t, n)),
 
             msgs1: Some(Round1::expects_messages(i, n)),
             msgs2: Some(Round2::expects_messages(i, n)),
             msgs3: Some(Round3::expects_messages(i, n)),
             msgs4: Some(Round4::expects_messages(i, n)),
+            msgs5: Some(Round5::expects_messages(i, n)),
 
             msgs_queue: vec![],
+            received: ReceivedDigests::default(),
+            message_size_limit: default_message_size_limit(DEFAULT_PAILLIER_BITS, n),
+            warnings,
+            audit_log: None,
+            transcript: None,
+            on_round_complete: None,
+            confirm_public_key: false,
+            session_id: None,
+            indexing: Indexing::OneBased,
+            #[cfg(feature = "testing")]
+            vss_seed: None,
             party_i: i,
+            party_t: t,
             party_n: n,
         };
 
@@ -80,22 +579,111 @@ impl Keygen {
         Ok(state)
     }
 
+    /// Log of proceeded rounds, if keygen was constructed via [Keygen::with_audit_log]
+    pub fn audit_log(&self) -> Option<&RoundAuditLog> {
+        self.audit_log.as_ref().map(|a| a.log())
+    }
+
+    /// Messages sent and received so far, if keygen was constructed via [Keygen::with_transcript]
+    pub fn transcript(&self) -> Option<&KeygenTranscript<H>> {
+        self.transcript.as_ref()
+    }
+
+    /// Registers a callback fired every time a round is proceeded
+    ///
+    /// `callback(round, expensive)` is called with index of proceeded round (`0` to `4`, or `5`
+    /// with [public key confirmation](Keygen::with_public_key_confirmation)) and
+    /// whether the round was expensive to compute, right after the round completed successfully.
+    /// It's not called if round fails. Replaces previously registered callback.
+    pub fn on_round_complete<F>(&mut self, callback: F)
+    where
+        F: Fn(u16, bool) + Send + 'static,
+    {
+        self.on_round_complete = Some(Box::new(callback));
+    }
+
+    /// Makes party sample its VSS polynomial from rng seeded with `seed`
+    ///
+    /// Together with [Keygen::with_keys] it makes keygen reproducible: runs with the same keys and
+    /// seeds produce the same shares, which is useful for fault injection tests. **Insecure**:
+    /// anyone who knows the seed learns party's secret. Available only with `testing` feature.
+    #[cfg(feature = "testing")]
+    pub fn use_vss_seed(&mut self, seed: [u8; 32]) {
+        self.vss_seed = Some(seed);
+    }
+
+    /// Abandons keygen, wiping secrets of the round it's currently at
+    ///
+    /// Every round wipes its secrets on drop, so it's the same as dropping keygen, but makes
+    /// giving up on the session (e.g. after a fault of another party is detected) explicit.
+    pub fn abort(self) {
+        drop(self)
+    }
+
+    /// Non-fatal issues with keygen parameters found at construction
+    pub fn warnings(&self) -> &[KeygenWarning] {
+        &self.warnings
+    }
+
+    /// Messages the party is currently waiting for
+    ///
+    /// Returns `None` if party doesn't wait for any messages, i.e. at round 0 which only sends
+    /// messages, or once keygen is finished. Together with [current_round](StateMachine::current_round)
+    /// and [total_rounds](StateMachine::total_rounds) it allows monitoring running keygen.
+    pub fn awaited_messages(&self) -> Option<RoundMsg> {
+        match &self.round {
+            R::Round1(_) => Some(RoundMsg::broadcast(1)),
+            R::Round2(_) => Some(RoundMsg::broadcast(2)),
+            R::Round3(_) => Some(RoundMsg::p2p(3)),
+            R::Round4(_) => Some(RoundMsg::broadcast(4)),
+            R::Round5(_) => Some(RoundMsg::broadcast(5)),
+            R::Round0(_) | R::Final(_) | R::Gone => None,
+        }
+    }
+
     fn gmap_queue<'a, T, F>(&'a mut self, mut f: F) -> impl Push<Msg<T>> + 'a
     where
-        F: FnMut(T) -> M + 'a,
+        F: FnMut(T) -> M<H> + 'a,
     {
-        (&mut self.msgs_queue).gmap(move |m: Msg<T>| m.map_body(|m| ProtocolMessage(f(m))))
+        let session_id = self.session_id;
+        (&mut self.msgs_queue).gmap(move |m: Msg<T>| {
+            let tag = session_id.map(|id| session_tag(&id, m.round, m.sender));
+            m.map_body(|m| ProtocolMessage(f(m), tag))
+        })
     }
 
     /// Proceeds round state if it received enough messages and if it's cheap to compute or
     /// `may_block == true`
     fn proceed_round(&mut self, may_block: bool) -> Result<()> {
+        let current_round = self.current_round();
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!(
+            "keygen_round",
+            party_i = self.party_i,
+            round = current_round,
+            t = self.party_t,
+            n = self.party_n
+        )
+        .entered();
+        #[cfg(feature = "metrics")]
+        let started = std::time::Instant::now();
+        let queue_len = self.msgs_queue.len();
+        let is_expensive = match &self.round {
+            R::Round0(round) => round.is_expensive(),
+            R::Round1(round) => round.is_expensive(),
+            R::Round2(round) => round.is_expensive(),
+            R::Round3(round) => round.is_expensive(),
+            R::Round4(round) => round.is_expensive(),
+            R::Round5(round) => round.is_expensive(),
+            R::Final(_) | R::Gone => false,
+        };
         let store1_wants_more = self.msgs1.as_ref().map(|s| s.wants_more()).unwrap_or(false);
         let store2_wants_more = self.msgs2.as_ref().map(|s| s.wants_more()).unwrap_or(false);
         let store3_wants_more = self.msgs3.as_ref().map(|s| s.wants_more()).unwrap_or(false);
         let store4_wants_more = self.msgs4.as_ref().map(|s| s.wants_more()).unwrap_or(false);
+        let store5_wants_more = self.msgs5.as_ref().map(|s| s.wants_more()).unwrap_or(false);
 
-        let next_state: R;
+        let next_state: R<H>;
         let try_again: bool = match replace(&mut self.round, R::Gone) {
             R::Round0(round) if !round.is_expensive() || may_block => {
                 next_state = round
@@ -128,10 +716,18 @@ impl Keygen {
                 let msgs = store
                     .finish()
                     .map_err(InternalError::RetrieveRoundMessages)?;
-                next_state = round
-                    .proceed(msgs, self.gmap_queue(M::Round3))
-                    .map(R::Round3)
-                    .map_err(Error::ProceedRound)?;
+                #[cfg(feature = "testing")]
+                let result = match self.vss_seed {
+                    Some(seed) => round.proceed_with_rng(
+                        msgs,
+                        self.gmap_queue(M::Round3),
+                        &mut <rand::rngs::StdRng as rand::SeedableRng>::from_seed(seed),
+                    ),
+                    None => round.proceed(msgs, self.gmap_queue(M::Round3)),
+                };
+                #[cfg(not(feature = "testing"))]
+                let result = round.proceed(msgs, self.gmap_queue(M::Round3));
+                next_state = result.map(R::Round3).map_err(Error::ProceedRound)?;
                 true
             }
             s @ R::Round2(_) => {
@@ -144,7 +740,7 @@ impl Keygen {
                     .finish()
                     .map_err(InternalError::RetrieveRoundMessages)?;
                 next_state = round
-                    .proceed(msgs, self.gmap_queue(M::Round4))
+                    .proceed_with_hash(msgs, self.gmap_queue(M::Round4))
                     .map(R::Round4)
                     .map_err(Error::ProceedRound)?;
                 true
@@ -155,6 +751,23 @@ impl Keygen {
             }
             R::Round4(round) if !store4_wants_more && (!round.is_expensive() || may_block) => {
                 let store = self.msgs4.take().ok_or(InternalError::StoreGone)?;
+                let msgs = store
+                    .finish()
+                    .map_err(InternalError::RetrieveRoundMessages)?;
+                let local_key = round.proceed(msgs).map_err(Error::ProceedRound)?;
+                next_state = if self.confirm_public_key {
+                    R::Round5(Round5::new(local_key, self.gmap_queue(M::Round5)))
+                } else {
+                    R::Final(local_key)
+                };
+                true
+            }
+            s @ R::Round4(_) => {
+                next_state = s;
+                false
+            }
+            R::Round5(round) if !store5_wants_more && (!round.is_expensive() || may_block) => {
+                let store = self.msgs5.take().ok_or(InternalError::StoreGone)?;
                 let msgs = store
                     .finish()
                     .map_err(InternalError::RetrieveRoundMessages)?;
@@ -164,7 +777,7 @@ impl Keygen {
                     .map_err(Error::ProceedRound)?;
                 true
             }
-            s @ R::Round4(_) => {
+            s @ R::Round5(_) => {
                 next_state = s;
                 false
             }
@@ -176,6 +789,23 @@ impl Keygen {
 
         self.round = next_state;
         if try_again {
+            if let Some(audit_log) = &mut self.audit_log {
+                audit_log.proceeded(current_round, &self.msgs_queue[queue_len..]);
+            }
+            if let Some(transcript) = &mut self.transcript {
+                transcript.sent(&self.msgs_queue[queue_len..]);
+            }
+            #[cfg(feature = "metrics")]
+            round_metrics::round_proceeded(
+                current_round,
+                is_expensive,
+                started.elapsed(),
+                &self.msgs_queue[queue_len..],
+            );
+            if let Some(callback) = &self.on_round_complete {
+                callback(current_round, is_expensive);
+            }
+            self.indexing.outgoing(&mut self.msgs_queue[queue_len..]);
             self.proceed_round(may_block)
         } else {
             Ok(())
@@ -183,16 +813,36 @@ impl Keygen {
     }
 }
 
-impl StateMachine for Keygen {
-    type MessageBody = ProtocolMessage;
+impl<H: Digest + Clone> StateMachine for Keygen<H> {
+    type MessageBody = ProtocolMessage<H>;
     type Err = Error;
     type Output = LocalKey<Secp256k1>;
 
     fn handle_incoming(&mut self, msg: Msg<Self::MessageBody>) -> Result<()> {
+        let msg = self.indexing.incoming(msg);
         let current_round = self.current_round();
+        let audited_msg = self.audit_log.as_ref().map(|_| msg.clone());
+        let transcript_msg = self.transcript.as_ref().map(|_| msg.clone());
+
+        let msg_round = crate::MessageRoundID::round_id(&msg.body);
+        let expected_tag = self
+            .session_id
+            .map(|id| session_tag(&id, msg_round, msg.sender));
+        if msg.body.1 != expected_tag {
+            return Err(Error::SessionMismatch {
+                sender: msg.sender,
+                msg_round,
+            });
+        }
+        // Resend of already accepted message is a no-op
+        let digest = match self.received.check(&msg)? {
+            Received::New { digest } => digest,
+            Received::Resend => return Ok(()),
+        };
+        let sender = msg.sender;
 
         match msg.body {
-            ProtocolMessage(M::Round1(m)) => {
+            ProtocolMessage(M::Round1(m), _) => {
                 let store = self
                     .msgs1
                     .as_mut()
@@ -208,9 +858,8 @@ impl StateMachine for Keygen {
                         body: m,
                     })
                     .map_err(Error::HandleMessage)?;
-                self.proceed_round(false)
             }
-            ProtocolMessage(M::Round2(m)) => {
+            ProtocolMessage(M::Round2(m), _) => {
                 let store = self
                     .msgs2
                     .as_mut()
@@ -220,15 +869,14 @@ impl StateMachine for Keygen {
                     })?;
                 store
                     .push_msg(Msg {
-                        round:current_round,
+                        round: current_round,
                         sender: msg.sender,
                         receiver: msg.receiver,
                         body: m,
                     })
                     .map_err(Error::HandleMessage)?;
-                self.proceed_round(false)
             }
-            ProtocolMessage(M::Round3(m)) => {
+            ProtocolMessage(M::Round3(m), _) => {
                 let store = self
                     .msgs3
                     .as_mut()
@@ -244,9 +892,8 @@ impl StateMachine for Keygen {
                         body: m,
                     })
                     .map_err(Error::HandleMessage)?;
-                self.proceed_round(false)
             }
-            ProtocolMessage(M::Round4(m)) => {
+            ProtocolMessage(M::Round4(m), _) => {
                 let store = self
                     .msgs4
                     .as_mut()
@@ -262,9 +909,36 @@ impl StateMachine for Keygen {
                         body: m,
                     })
                     .map_err(Error::HandleMessage)?;
-                self.proceed_round(false)
+            }
+            ProtocolMessage(M::Round5(m), _) => {
+                let store = self
+                    .msgs5
+                    .as_mut()
+                    .ok_or(Error::ReceivedOutOfOrderMessage {
+                        current_round,
+                        msg_round: 5,
+                    })?;
+                store
+                    .push_msg(Msg {
+                        round: current_round,
+                        sender: msg.sender,
+                        receiver: msg.receiver,
+                        body: m,
+                    })
+                    .map_err(Error::HandleMessage)?;
             }
         }
+        self.received.record(msg_round, sender, digest);
+
+        if let (Some(audit_log), Some(msg)) = (&mut self.audit_log, audited_msg) {
+            audit_log.received(&msg);
+        }
+        if let (Some(transcript), Some(msg)) = (&mut self.transcript, transcript_msg) {
+            transcript.received(msg);
+        }
+        #[cfg(feature = "metrics")]
+        round_metrics::message_received(msg_round);
+        self.proceed_round(false)
     }
 
     fn message_queue(&mut self) -> &mut Vec<Msg<Self::MessageBody>> {
@@ -276,6 +950,7 @@ impl StateMachine for Keygen {
         let store2_wants_more = self.msgs2.as_ref().map(|s| s.wants_more()).unwrap_or(false);
         let store3_wants_more = self.msgs3.as_ref().map(|s| s.wants_more()).unwrap_or(false);
         let store4_wants_more = self.msgs4.as_ref().map(|s| s.wants_more()).unwrap_or(false);
+        let store5_wants_more = self.msgs5.as_ref().map(|s| s.wants_more()).unwrap_or(false);
 
         match &self.round {
             R::Round0(_) => true,
@@ -283,6 +958,7 @@ impl StateMachine for Keygen {
             R::Round2(_) => !store2_wants_more,
             R::Round3(_) => !store3_wants_more,
             R::Round4(_) => !store4_wants_more,
+            R::Round5(_) => !store5_wants_more,
             R::Final(_) | R::Gone => false,
         }
     }
@@ -323,16 +999,22 @@ impl StateMachine for Keygen {
             R::Round2(_) => 2,
             R::Round3(_) => 3,
             R::Round4(_) => 4,
+            R::Round5(_) => 5,
+            R::Final(_) | R::Gone if self.confirm_public_key => 6,
             R::Final(_) | R::Gone => 5,
         }
     }
 
     fn total_rounds(&self) -> Option<u16> {
-        Some(4)
+        if self.confirm_public_key {
+            Some(5)
+        } else {
+            Some(4)
+        }
     }
 
     fn party_ind(&self) -> u16 {
-        self.party_i
+        self.indexing.to_external(self.party_i)
     }
 
     fn parties(&self) -> u16 {
@@ -340,13 +1022,14 @@ impl StateMachine for Keygen {
     }
 }
 
-impl super::traits::RoundBlame for Keygen {
+impl<H: Digest + Clone> super::traits::RoundBlame for Keygen<H> {
     /// Returns number of unwilling parties and a vector of their party indexes.
     fn round_blame(&self) -> (u16, Vec<u16>) {
         let store1_blame = self.msgs1.as_ref().map(|s| s.blame()).unwrap_or_default();
         let store2_blame = self.msgs2.as_ref().map(|s| s.blame()).unwrap_or_default();
         let store3_blame = self.msgs3.as_ref().map(|s| s.blame()).unwrap_or_default();
         let store4_blame = self.msgs4.as_ref().map(|s| s.blame()).unwrap_or_default();
+        let store5_blame = self.msgs5.as_ref().map(|s| s.blame()).unwrap_or_default();
 
         let default = (0, vec![]);
         match &self.round {
@@ -355,12 +1038,13 @@ impl super::traits::RoundBlame for Keygen {
             R::Round2(_) => store2_blame,
             R::Round3(_) => store3_blame,
             R::Round4(_) => store4_blame,
+            R::Round5(_) => store5_blame,
             R::Final(_) | R::Gone => default,
         }
     }
 }
 
-impl fmt::Debug for Keygen {
+impl<H: Digest + Clone> fmt::Debug for Keygen<H> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let current_round = match &self.round {
             R::Round0(_) => "0",
@@ -368,6 +1052,7 @@ impl fmt::Debug for Keygen {
             R::Round2(_) => "2",
             R::Round3(_) => "3",
             R::Round4(_) => "4",
+            R::Round5(_) => "5",
             R::Final(_) => "[Final]",
             R::Gone => "[Gone]",
         };
@@ -387,14 +1072,19 @@ impl fmt::Debug for Keygen {
             Some(msgs) => format!("[{}/{}]", msgs.messages_received(), msgs.messages_total()),
             None => "[None]".into(),
         };
+        let msgs5 = match self.msgs5.as_ref() {
+            Some(msgs) => format!("[{}/{}]", msgs.messages_received(), msgs.messages_total()),
+            None => "[None]".into(),
+        };
         write!(
             f,
-            "{{Keygen at round={} msgs1={} msgs2={} msgs3={} msgs4={} queue=[len={}]}}",
+            "{{Keygen at round={} msgs1={} msgs2={} msgs3={} msgs4={} msgs5={} queue=[len={}]}}",
             current_round,
             msgs1,
             msgs2,
             msgs3,
             msgs4,
+            msgs5,
             self.msgs_queue.len()
         )
     }
@@ -402,39 +1092,107 @@ impl fmt::Debug for Keygen {
 
 // Rounds
 
-enum R {
+enum R<H: Digest + Clone> {
     Round0(Round0),
     Round1(Round1),
     Round2(Round2),
     Round3(Round3),
-    Round4(Round4),
+    Round4(Round4<H>),
+    Round5(Round5),
     Final(LocalKey<Secp256k1>),
     Gone,
 }
 
+/// Keygen configuration that is allowed but likely undesired, see [Keygen::warnings]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeygenWarning {
+    /// `t >= n/2`: adversary controlling `t` parties is not a minority, whereas GG20 assumes
+    /// honest majority
+    NoHonestMajority,
+    /// `t + 1 = n`: every party must participate in signing, so losing any share makes the key
+    /// unusable
+    FullConsensus,
+}
+
 // Messages
 
+/// Messages of specific keygen round, see [Keygen::message_dependencies]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RoundMsg {
+    pub round: u16,
+    pub kind: MsgKind,
+}
+
+impl RoundMsg {
+    fn broadcast(round: u16) -> Self {
+        Self {
+            round,
+            kind: MsgKind::Broadcast,
+        }
+    }
+
+    fn p2p(round: u16) -> Self {
+        Self {
+            round,
+            kind: MsgKind::P2P,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MsgKind {
+    Broadcast,
+    P2P,
+}
+
 /// Protocol message which parties send on wire
 ///
 /// Hides actual messages structure so it could be changed without breaking semver policy.
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ProtocolMessage(M);
+/// Message of keygen without [session id](Keygen::with_session_id) is serialized the same as by
+/// versions which didn't support session ids.
+#[derive(Debug, Clone)]
+pub struct ProtocolMessage<H: Digest + Clone = Sha256>(M<H>, Option<SessionTag>);
+
+impl<H: Digest + Clone> Serialize for ProtocolMessage<H> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        session::serialize_tagged("ProtocolMessage", &self.0, &self.1, serializer)
+    }
+}
+
+impl<'de, H: Digest + Clone> Deserialize<'de> for ProtocolMessage<H> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let (body, tag) = session::deserialize_tagged(deserializer)?;
+        Ok(ProtocolMessage(body, tag))
+    }
+}
+
+fn session_tag(session_id: &[u8; 32], round: u16, sender: u16) -> SessionTag {
+    session::session_tag(b"gg20-keygen-session-tag", session_id, round, sender)
+}
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
-enum M {
-    Round1(gg_2020::party_i::KeyGenBroadcastMessage1),
+#[serde(bound = "")]
+enum M<H: Digest + Clone> {
+    Round1(
+        (
+            gg_2020::party_i::KeyGenBroadcastMessage1,
+            Vec<Point<Secp256k1>>,
+        ),
+    ),
     Round2(gg_2020::party_i::KeyGenDecommitMessage1),
-    Round3((VerifiableSS<Secp256k1>, Vec<u8>)),
-    Round4(DLogProof<Secp256k1, Sha256>),
+    Round3(Round3Message),
+    Round4(DLogProof<Secp256k1, H>),
+    Round5(PublicKeyConfirmation),
 }
 
-impl crate::MessageRoundID for ProtocolMessage {
+impl<H: Digest + Clone> crate::MessageRoundID for ProtocolMessage<H> {
     fn round_id(&self) -> u16 {
         match self.0 {
             M::Round1(_) => 1,
             M::Round2(_) => 2,
             M::Round3(_) => 3,
             M::Round4(_) => 4,
+            M::Round5(_) => 5,
         }
     }
 }
@@ -443,6 +1201,20 @@ impl crate::MessageRoundID for ProtocolMessage {
 
 type Result<T> = std::result::Result<T, Error>;
 
+/// Validates parameters shared by every [Keygen] constructor and [KeygenBuilder]
+fn validate_parameters(i: u16, t: u16, n: u16) -> Result<()> {
+    if n < 2 {
+        return Err(Error::TooFewParties);
+    }
+    if t == 0 || t >= n {
+        return Err(Error::InvalidThreshold);
+    }
+    if i == 0 || i > n {
+        return Err(Error::InvalidPartyIndex);
+    }
+    Ok(())
+}
+
 /// Error type of keygen protocol
 #[derive(Debug, Error)]
 #[non_exhaustive]
@@ -460,6 +1232,18 @@ pub enum Error {
     /// Party index `i` is not in range `[1; n]`
     #[error("party index is not in range [1; n]")]
     InvalidPartyIndex,
+    /// Number of party identities doesn't match number of parties `n`
+    #[error("number of party identities doesn't match number of parties")]
+    InvalidPartyIdentities,
+    /// [Prime pool](SafePrimePool) doesn't have enough primes to create party keys
+    #[error("prime pool doesn't have enough primes")]
+    PrimePoolExhausted,
+    /// Recovery party index is not in range `[1; n]`
+    #[error("recovery party index is not in range [1; n]")]
+    InvalidRecoveryParty,
+    /// Size of Paillier modulus is odd or less than `min_bits`
+    #[error("paillier modulus size {bits} must be even and at least {min_bits} bits")]
+    InvalidPaillierBits { bits: usize, min_bits: usize },
 
     /// Received message didn't pass pre-validation
     #[error("received message didn't pass pre-validation: {0}")]
@@ -472,6 +1256,32 @@ pub enum Error {
     /// [Keygen::pick_output] called twice
     #[error("pick_output called twice")]
     DoublePickOutput,
+    /// Round didn't receive messages of `missing` parties before [deadline](TimedKeygen)
+    #[error("round {round} timed out waiting for messages of parties {missing:?}")]
+    RoundTimeout { round: u16, missing: Vec<u16> },
+    /// Received message is not bound to this [keygen session](Keygen::with_session_id)
+    #[error("message of party {sender} at round {msg_round} belongs to different keygen session")]
+    SessionMismatch { sender: u16, msg_round: u16 },
+    /// Party sent a message different from the one it already sent at this round
+    ///
+    /// Resend of the same message is ignored, so this error indicates misbehaving sender rather
+    /// than an unreliable transport.
+    #[error("party {sender} sent conflicting messages at round {msg_round}")]
+    ConflictingMessage { sender: u16, msg_round: u16 },
+    /// Message of party `sender` exceeds [size limit](Keygen::set_message_size_limit)
+    #[error("message of party {sender} has {size} bytes, at most {max_bytes} are allowed")]
+    MessageTooLarge {
+        sender: u16,
+        size: usize,
+        max_bytes: u64,
+    },
+    /// Message of party `sender` can't be decoded or claims to be sent by another party
+    #[error("party {sender} sent malformed message")]
+    MalformedMessage { sender: u16 },
+    /// Message claiming to be sent by party `sender` isn't signed by its identity key, see
+    /// `AuthenticatedKeygen` (available with `ed25519` feature)
+    #[error("message of party {sender} at round {msg_round} is not signed by its identity key")]
+    UnauthenticatedMessage { sender: u16, msg_round: u16 },
 
     /// Some internal assertions were failed, which is a bug
     #[doc(hidden)]
@@ -489,11 +1299,21 @@ impl IsCritical for Error {
                 e,
                 StoreErr::MsgOverwrite | StoreErr::NotForMe | StoreErr::WantsMoreMessages
             ),
-            Error::ReceivedOutOfOrderMessage { .. } => false,
+            Error::ReceivedOutOfOrderMessage { .. }
+            | Error::SessionMismatch { .. }
+            | Error::ConflictingMessage { .. }
+            | Error::MessageTooLarge { .. }
+            | Error::MalformedMessage { .. }
+            | Error::UnauthenticatedMessage { .. } => false,
+            Error::RoundTimeout { .. } => true,
             Error::DoublePickOutput
             | Error::TooFewParties
             | Error::InvalidThreshold
             | Error::InvalidPartyIndex
+            | Error::InvalidPartyIdentities
+            | Error::PrimePoolExhausted
+            | Error::InvalidRecoveryParty
+            | Error::InvalidPaillierBits { .. }
             | Error::InternalError(_) => true,
         }
     }
@@ -519,6 +1339,7 @@ mod private {
 
 #[cfg(test)]
 pub mod test {
+    use curv::BigInt;
     use round_based::dev::Simulation;
 
     use super::*;
@@ -539,6 +1360,748 @@ pub mod test {
         keys
     }
 
+    fn deliver_broadcast<T: Clone>(i: u16, n: u16, msgs: &[Msg<T>]) -> BroadcastMsgs<T> {
+        let mut store = BroadcastMsgsStore::new(i, n);
+        for msg in msgs.iter().filter(|m| m.sender != i) {
+            store.push_msg(msg.clone()).unwrap();
+        }
+        store.finish().unwrap()
+    }
+
+    fn deliver_p2p<T: Clone>(i: u16, n: u16, msgs: &[Msg<T>]) -> P2PMsgs<T> {
+        let mut store = P2PMsgsStore::new(i, n);
+        for msg in msgs.iter().filter(|m| m.receiver == Some(i)) {
+            store.push_msg(msg.clone()).unwrap();
+        }
+        store.finish().unwrap()
+    }
+
+    /// Runs rounds 0-1 for every party manually, returns parties at Round2 along with round 2
+    /// messages they sent
+    fn run_until_round2(
+        t: u16,
+        n: u16,
+    ) -> (
+        Vec<Round2>,
+        Vec<Msg<gg_2020::party_i::KeyGenDecommitMessage1>>,
+    ) {
+        let mut msgs1 = vec![];
+        let parties: Vec<_> = (1..=n)
+            .map(|i| Round0::new(i, t, n).proceed(&mut msgs1).unwrap())
+            .collect();
+        let mut msgs2 = vec![];
+        let parties: Vec<_> = parties
+            .into_iter()
+            .zip(1..)
+            .map(|(p, i)| {
+                p.proceed(deliver_broadcast(i, n, &msgs1), &mut msgs2)
+                    .unwrap()
+            })
+            .collect();
+        (parties, msgs2)
+    }
+
+    /// Runs rounds 0-2 for every party manually, returns parties at Round3 along with round 3
+    /// messages they sent
+    fn run_until_round3(t: u16, n: u16) -> (Vec<Round3>, Vec<Msg<Round3Message>>) {
+        let (parties, msgs2) = run_until_round2(t, n);
+        let mut msgs3 = vec![];
+        let parties: Vec<_> = parties
+            .into_iter()
+            .zip(1..)
+            .map(|(p, i)| {
+                p.proceed(deliver_broadcast(i, n, &msgs2), &mut msgs3)
+                    .unwrap()
+            })
+            .collect();
+        (parties, msgs3)
+    }
+
+    /// Runs rounds 0-3 for every party manually, returns parties at Round4 along with round 4
+    /// messages they sent
+    fn run_until_round4(t: u16, n: u16) -> (Vec<Round4>, Vec<Msg<DLogProof<Secp256k1, Sha256>>>) {
+        let (parties, msgs3) = run_until_round3(t, n);
+        let mut msgs4 = vec![];
+        let parties: Vec<_> = parties
+            .into_iter()
+            .zip(1..)
+            .map(|(p, i)| p.proceed(deliver_p2p(i, n, &msgs3), &mut msgs4).unwrap())
+            .collect();
+        (parties, msgs4)
+    }
+
+    #[test]
+    fn round4_proceed_iter_matches_proceed() {
+        let n = 5;
+        let (parties, msgs4) = run_until_round4(2, n);
+        for (party, i) in parties.into_iter().zip(1..) {
+            let expected = party
+                .clone()
+                .proceed(deliver_broadcast(i, n, &msgs4))
+                .unwrap();
+            let actual = party
+                .proceed_iter(
+                    msgs4
+                        .iter()
+                        .filter(|m| m.sender != i)
+                        .map(|m| (m.sender, m.body.clone())),
+                )
+                .unwrap();
+            assert_eq!(
+                serde_json::to_string(&expected).unwrap(),
+                serde_json::to_string(&actual).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn round4_proceed_iter_reports_unexpected_senders() {
+        let n = 4;
+        let (mut parties, msgs4) = run_until_round4(1, n);
+        let party = parties.remove(0);
+        let proof_of = |j: u16| msgs4.iter().find(|m| m.sender == j).unwrap().body.clone();
+
+        let input = vec![
+            (2, proof_of(2)),
+            (0, proof_of(3)),
+            (3, proof_of(3)),
+            (3, proof_of(3)),
+        ];
+        let err = party.proceed_iter(input).map(|_| ()).unwrap_err();
+        match &err {
+            ProceedError::UnexpectedSenders {
+                round: 4,
+                unexpected,
+                missing,
+            } => {
+                assert_eq!(unexpected, &[0, 3]);
+                assert_eq!(missing, &[4]);
+            }
+            err => panic!("unexpected error: {:?}", err),
+        }
+        assert_eq!(err.blame(), Some(vec![0, 3, 4]));
+    }
+
+    #[test]
+    fn round4_reports_verification_progress() {
+        use std::cell::RefCell;
+
+        let n = 4;
+        let (mut parties, mut msgs4) = run_until_round4(1, n);
+        let party = parties.remove(0);
+
+        let reports = RefCell::new(vec![]);
+        let key = party
+            .clone()
+            .proceed_with_progress(deliver_broadcast(1, n, &msgs4), |j, valid| {
+                reports.borrow_mut().push((j, valid))
+            });
+        assert!(key.is_ok());
+        assert_eq!(reports.take(), [(1, true), (2, true), (3, true), (4, true)]);
+
+        // party 3 sends proof of party 2 share
+        msgs4[2].body = msgs4[1].body.clone();
+        let result = party.proceed_with_progress(deliver_broadcast(1, n, &msgs4), |j, valid| {
+            reports.borrow_mut().push((j, valid))
+        });
+        match result {
+            Err(ProceedError::Round4VerifyDLogProof(err)) => assert_eq!(err.bad_actors, [2]),
+            _ => panic!("tampered dlog proof must be rejected"),
+        }
+        assert_eq!(
+            reports.take(),
+            [(1, true), (2, true), (3, false), (4, true)]
+        );
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn keygen_with_fixed_keys_and_vss_seeds_is_reproducible() {
+        let (t, n) = (1, 2);
+        let keys: Vec<_> = (1..=n)
+            .map(|i| Keys::create_from_paillier_setup(usize::from(i), &PaillierSetup::generate()))
+            .collect();
+        let run = || {
+            let mut simulation = Simulation::new();
+            for i in 1..=n {
+                let mut party =
+                    Keygen::with_keys(i, t, n, keys[usize::from(i - 1)].clone()).unwrap();
+                party.use_vss_seed([i as u8; 32]);
+                simulation.add_party(party);
+            }
+            simulation.run().unwrap()
+        };
+
+        let (first, second) = (run(), run());
+        for (a, b) in first.iter().zip(&second) {
+            assert_eq!(a.keys_linear.x_i, b.keys_linear.x_i);
+            assert_eq!(a.vss_scheme.commitments, b.vss_scheme.commitments);
+        }
+        assert_eq!(first[0].public_key(), second[0].public_key());
+    }
+
+    /// Hash-based counter mode stream, the same seed always gives the same bytes
+    struct CounterRng {
+        seed: u8,
+        counter: std::sync::Mutex<u64>,
+    }
+
+    impl RngProvider for CounterRng {
+        fn fill_bytes(&self, dest: &mut [u8]) {
+            let mut counter = self.counter.lock().unwrap();
+            for chunk in dest.chunks_mut(32) {
+                let block = Sha256::new()
+                    .chain(&[self.seed])
+                    .chain(&counter.to_be_bytes())
+                    .finalize();
+                chunk.copy_from_slice(&block[..chunk.len()]);
+                *counter += 1;
+            }
+        }
+    }
+
+    #[test]
+    fn keygen_with_rng_provider_takes_secrets_from_it() {
+        let (t, n) = (1, 2);
+        let run = || {
+            let mut simulation = Simulation::new();
+            for i in 1..=n {
+                let rng = Arc::new(CounterRng {
+                    seed: i as u8,
+                    counter: Default::default(),
+                });
+                simulation.add_party(Keygen::with_rng_provider(i, t, n, rng).unwrap());
+            }
+            simulation.run().unwrap()
+        };
+
+        let (first, second) = (run(), run());
+        for (a, b) in first.iter().zip(&second) {
+            assert_eq!(a.keys_linear.x_i, b.keys_linear.x_i);
+            assert_eq!(a.paillier_dk.p, b.paillier_dk.p);
+            assert_eq!(a.vss_scheme.commitments, b.vss_scheme.commitments);
+        }
+        assert_eq!(first[0].public_key(), second[0].public_key());
+        assert_ne!(first[0].paillier_dk.p, first[1].paillier_dk.p);
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn round2_snapshot_proceeds_with_different_inputs() {
+        let n = 3;
+        let (mut parties, msgs2) = run_until_round2(1, n);
+        let snapshot = parties.remove(0);
+
+        let mut tampered = msgs2.clone();
+        tampered[1].body.blind_factor = BigInt::from(1);
+        let mut msgs3 = vec![];
+        match snapshot
+            .clone_state()
+            .proceed(deliver_broadcast(1, n, &tampered), &mut msgs3)
+        {
+            Err(ProceedError::Round2VerifyCommitments(err)) => assert_eq!(err.bad_actors, [1]),
+            _ => panic!("tampered decommitment must be rejected"),
+        }
+        assert!(msgs3.is_empty());
+
+        for _ in 0..2 {
+            let mut msgs3 = vec![];
+            let round3 = snapshot
+                .clone_state()
+                .proceed(deliver_broadcast(1, n, &msgs2), &mut msgs3);
+            assert!(round3.is_ok());
+            assert_eq!(msgs3.len(), usize::from(n) - 1);
+            // Every party receives the same VSS scheme
+            assert_eq!(
+                msgs3[0].body.vss_scheme.commitments,
+                msgs3[1].body.vss_scheme.commitments
+            );
+        }
+    }
+
+    #[test]
+    fn round2_error_blames_party() {
+        let n = 3;
+        let (mut parties, mut msgs2) = run_until_round2(1, n);
+        msgs2[1].body.blind_factor = BigInt::from(1);
+        let mut msgs3 = vec![];
+        let err = match parties
+            .remove(0)
+            .proceed(deliver_broadcast(1, n, &msgs2), &mut msgs3)
+        {
+            Err(err) => err,
+            Ok(_) => panic!("tampered decommitment must be rejected"),
+        };
+        assert_eq!(err.blame(), Some(vec![2]));
+        assert_eq!(ProceedError::SessionExpired.blame(), None);
+    }
+
+    #[test]
+    fn keygen_warns_about_no_honest_majority() {
+        let keygen = Keygen::new(1, 2, 4).unwrap();
+        assert_eq!(keygen.warnings(), [KeygenWarning::NoHonestMajority]);
+
+        let keygen = Keygen::new(1, 1, 4).unwrap();
+        assert!(keygen.warnings().is_empty());
+
+        let keygen = Keygen::new(1, 3, 4).unwrap();
+        assert_eq!(
+            keygen.warnings(),
+            [
+                KeygenWarning::NoHonestMajority,
+                KeygenWarning::FullConsensus
+            ]
+        );
+    }
+
+    #[test]
+    fn round3_is_independent_of_p2p_delivery_order() {
+        use crate::testing::shuffle_delivery;
+
+        let n = 5;
+        let (mut parties, msgs3) = run_until_round3(2, n);
+        let party = parties.remove(0);
+        // Round 4 messages of other parties don't depend on what party 1 received
+        let msgs4: Vec<_> = parties
+            .into_iter()
+            .zip(2..)
+            .flat_map(|(p, i)| {
+                let mut msgs4 = vec![];
+                p.proceed(deliver_p2p(i, n, &msgs3), &mut msgs4).unwrap();
+                msgs4
+            })
+            .collect();
+
+        let finish = |round3: Round3, input| {
+            let mut own_msgs4 = vec![];
+            round3
+                .proceed(input, &mut own_msgs4)
+                .unwrap()
+                .proceed(deliver_broadcast(1, n, &msgs4))
+                .unwrap()
+        };
+        let expected =
+            serde_json::to_vec(&finish(party.clone(), deliver_p2p(1, n, &msgs3))).unwrap();
+        for seed in 1..=4 {
+            let key = finish(party.clone(), shuffle_delivery(1, n, &msgs3, seed));
+            assert_eq!(serde_json::to_vec(&key).unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn vss_scheme_for_different_curve_is_rejected() {
+        let n = 3;
+        let (mut parties, mut msgs3) = run_until_round3(1, n);
+        for msg in msgs3.iter_mut().filter(|m| m.sender == 2) {
+            msg.body.curve = "ed25519".to_string();
+        }
+        let party = parties.remove(0);
+        let mut msgs4: Vec<Msg<DLogProof<Secp256k1, Sha256>>> = vec![];
+        let result = party.proceed(deliver_p2p(1, n, &msgs3), &mut msgs4);
+        assert!(matches!(
+            result,
+            Err(ProceedError::CurveMismatch { sender: 2 })
+        ));
+    }
+
+    #[test]
+    fn swapped_encrypted_shares_are_rejected() {
+        let n = 3;
+        let (mut parties, mut msgs3) = run_until_round3(1, n);
+        let to_1 = msgs3
+            .iter()
+            .position(|m| m.sender == 2 && m.receiver == Some(1))
+            .unwrap();
+        let to_3 = msgs3
+            .iter()
+            .position(|m| m.sender == 2 && m.receiver == Some(3))
+            .unwrap();
+        let share_to_3 = msgs3[to_3].body.encrypted_share.clone();
+        msgs3[to_1].body.encrypted_share = share_to_3;
+
+        let party = parties.remove(0);
+        let mut msgs4: Vec<Msg<DLogProof<Secp256k1, Sha256>>> = vec![];
+        let result = party.proceed(deliver_p2p(1, n, &msgs3), &mut msgs4);
+        assert!(matches!(
+            result,
+            Err(ProceedError::ShareBindingMismatch { sender: 2 })
+        ));
+    }
+
+    #[test]
+    fn vss_inconsistent_with_decommitment_is_rejected() {
+        use curv::elliptic::curves::Scalar;
+
+        let n = 3;
+        let (mut parties, mut msgs3) = run_until_round3(1, n);
+        let to_1 = msgs3
+            .iter()
+            .position(|m| m.sender == 2 && m.receiver == Some(1))
+            .unwrap();
+        // Party 2 deals shares of a secret different from the one it committed to
+        let (other_vss, _) = VerifiableSS::share(1, n, &Scalar::<Secp256k1>::random());
+        msgs3[to_1].body.vss_scheme = other_vss;
+
+        let party = parties.remove(0);
+        let mut msgs4: Vec<Msg<DLogProof<Secp256k1, Sha256>>> = vec![];
+        let result = party.proceed(deliver_p2p(1, n, &msgs3), &mut msgs4);
+        match result {
+            Err(err @ ProceedError::VssEquivocation { sender: 2 }) => {
+                assert_eq!(err.blame(), Some(vec![2]))
+            }
+            result => panic!("unexpected result: {:?}", result.map(|_| ())),
+        }
+        assert!(msgs4.is_empty());
+    }
+
+    #[test]
+    fn encrypted_share_out_of_range_is_rejected() {
+        use curv::arithmetic::traits::*;
+        use paillier::{KeyGeneration, Paillier};
+
+        let (ek, _dk) = Paillier::keypair().keys();
+        let valid = (&ek.nn - BigInt::one()).to_bytes();
+        assert!(validate_encrypted_share(&valid, &ek).is_ok());
+        let too_large = ek.nn.to_bytes();
+        assert!(matches!(
+            validate_encrypted_share(&too_large, &ek),
+            Err(CiphertextError::OutOfRange)
+        ));
+        for trivial in [BigInt::zero(), BigInt::one()] {
+            assert!(matches!(
+                validate_encrypted_share(&trivial.to_bytes(), &ek),
+                Err(CiphertextError::Trivial)
+            ));
+        }
+        assert!(matches!(
+            validate_encrypted_share(&ek.n.to_bytes(), &ek),
+            Err(CiphertextError::NotCoprime)
+        ));
+    }
+
+    #[test]
+    fn dlog_statement_with_h1_sharing_factor_is_rejected() {
+        use paillier::{KeyGeneration, Paillier};
+        use zk_paillier::zkproofs::DLogStatement;
+
+        let (ek, dk) = Paillier::keypair().keys();
+        let (n_tilde, h1, h2, _, _) = gg_2020::party_i::generate_h1_h2_N_tilde_from(&ek, &dk);
+        let valid = DLogStatement {
+            N: n_tilde.clone(),
+            g: h1,
+            ni: h2.clone(),
+        };
+        assert!(validate_dlog_statement(&valid).is_ok());
+
+        let invalid = DLogStatement {
+            N: n_tilde.clone(),
+            g: dk.p,
+            ni: h2.clone(),
+        };
+        assert!(matches!(
+            validate_dlog_statement(&invalid),
+            Err(DLogStatementError::NotCoprime)
+        ));
+
+        let same_generators = DLogStatement {
+            N: n_tilde,
+            g: h2.clone(),
+            ni: h2,
+        };
+        assert!(matches!(
+            validate_dlog_statement(&same_generators),
+            Err(DLogStatementError::SameGenerators)
+        ));
+    }
+
+    #[test]
+    fn simulate_keygen_with_party_identities() {
+        use curv::elliptic::curves::Scalar;
+
+        let n = 3;
+        let identities: Vec<_> = (0..n)
+            .map(|_| Point::generator() * Scalar::<Secp256k1>::random())
+            .collect();
+
+        let mut simulation = Simulation::new();
+        for i in 1..=n {
+            simulation
+                .add_party(Keygen::with_party_identities(i, 1, n, identities.clone()).unwrap());
+        }
+        let keys = simulation.run().unwrap();
+
+        for key in &keys {
+            assert_eq!(key.party_identities, identities);
+            for (j, identity) in (1..).zip(&identities) {
+                assert_eq!(key.identity_of(j), Some(identity));
+            }
+            assert_eq!(key.identity_of(n + 1), None);
+        }
+        assert!(matches!(
+            Keygen::with_party_identities(1, 1, n, identities[1..].to_vec()),
+            Err(Error::InvalidPartyIdentities)
+        ));
+    }
+
+    #[test]
+    fn simulate_keygen_with_prime_pool() {
+        let mut pool = SafePrimePool::new();
+        pool.fill(SafePrimePool::PRIMES_PER_KEYS);
+
+        let mut simulation = Simulation::new();
+        simulation.add_party(Keygen::with_prime_pool(1, 1, 2, &mut pool).unwrap());
+        simulation.add_party(Keygen::new(2, 1, 2).unwrap());
+        assert!(pool.is_empty());
+        assert!(matches!(
+            Keygen::with_prime_pool(1, 1, 2, &mut pool),
+            Err(Error::PrimePoolExhausted)
+        ));
+
+        let keys = simulation.run().unwrap();
+        assert_eq!(keys[0].public_key(), keys[1].public_key());
+    }
+
+    #[test]
+    fn expired_session_refuses_to_proceed() {
+        let hour = Duration::from_secs(60 * 60);
+        let created_at = SystemTime::now() - 2 * hour;
+
+        let mut keygen = Keygen::with_session_expiry(1, 1, 2, created_at, hour).unwrap();
+        assert!(matches!(
+            keygen.proceed(),
+            Err(Error::ProceedRound(ProceedError::SessionExpired))
+        ));
+
+        let expiry = SessionExpiry {
+            created_at,
+            max_age: hour,
+        };
+        assert!(expiry.is_expired_at(SystemTime::now()));
+        assert!(!expiry.is_expired_at(created_at + hour));
+    }
+
+    #[test]
+    fn simulate_keygen_with_paillier_setup() {
+        let setups: Vec<_> = (0..3).map(|_| PaillierSetup::generate()).collect();
+
+        let mut simulation = Simulation::new();
+        for (i, setup) in (1..).zip(&setups) {
+            simulation.add_party(Keygen::with_paillier_setup(i, 1, 3, setup.clone()).unwrap());
+        }
+        let keys = simulation.run().unwrap();
+
+        for (key, setup) in keys.iter().zip(&setups) {
+            key.verify_keys_linear_consistency().unwrap();
+            assert_eq!(key.paillier_dk.p, setup.dk.p);
+            assert_eq!(key.paillier_key_vec[usize::from(key.i - 1)].n, setup.ek.n);
+        }
+    }
+
+    #[test]
+    fn simulate_keygen_with_public_key_confirmation() {
+        let mut simulation = Simulation::new();
+        for i in 1..=3 {
+            let party = Keygen::with_public_key_confirmation(i, 1, 3).unwrap();
+            assert_eq!(party.total_rounds(), Some(5));
+            simulation.add_party(party);
+        }
+        let keys = simulation.run().unwrap();
+
+        assert!(keys.iter().all(|key| key.y_sum_s == keys[0].y_sum_s));
+    }
+
+    #[test]
+    fn simulate_keygen_over_external_secure_channel() {
+        let mut simulation = Simulation::new();
+        for i in 1..=3 {
+            simulation.add_party(
+                Keygen::with_transport_mode(i, 1, 3, TransportMode::ExternalSecureChannel).unwrap(),
+            );
+        }
+        let keys = simulation.run().unwrap();
+        for key in &keys {
+            key.verify_keys_linear_consistency().unwrap();
+            assert_eq!(key.y_sum_s, keys[0].y_sum_s);
+        }
+
+        let mut simulation = Simulation::new();
+        simulation.add_party(Keygen::new(1, 1, 2).unwrap());
+        simulation.add_party(
+            Keygen::with_transport_mode(2, 1, 2, TransportMode::ExternalSecureChannel).unwrap(),
+        );
+        assert!(matches!(
+            simulation.run(),
+            Err(Error::ProceedRound(
+                ProceedError::TransportModeMismatch { .. }
+            ))
+        ));
+    }
+
+    #[test]
+    fn simulate_keygen_with_share_encryption_proofs() {
+        let n = 3;
+        let mut simulation = Simulation::new();
+        for i in 1..=n {
+            simulation.add_party(Keygen::with_share_encryption_proofs(i, 1, n).unwrap());
+        }
+        let keys = simulation.run().unwrap();
+        for key in &keys {
+            key.verify_keys_linear_consistency().unwrap();
+            assert_eq!(key.y_sum_s, keys[0].y_sum_s);
+        }
+
+        let mut simulation = Simulation::new();
+        simulation.add_party(Keygen::with_share_encryption_proofs(1, 1, 2).unwrap());
+        simulation.add_party(Keygen::new(2, 1, 2).unwrap());
+        match simulation.run() {
+            Err(Error::ProceedRound(
+                err @ ProceedError::InvalidShareEncryptionProof { sender: 2 },
+            )) => {
+                assert_eq!(err.blame(), Some(vec![2]))
+            }
+            result => panic!("unexpected result: {:?}", result.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn simulate_keygen_with_keccak_dlog_proofs() {
+        use sha3::Keccak256;
+
+        let mut simulation = Simulation::new();
+        for i in 1..=3 {
+            simulation.add_party(Keygen::<Keccak256>::with_dlog_hash(i, 1, 3).unwrap());
+        }
+        let keys = simulation.run().unwrap();
+        for key in &keys {
+            key.verify_keys_linear_consistency().unwrap();
+            assert_eq!(key.y_sum_s, keys[0].y_sum_s);
+        }
+        use crate::protocols::multi_party_ecdsa::gg_2020::state_machine::sign::test::{
+            simulate_offline_stage, simulate_signing,
+        };
+        simulate_signing(simulate_offline_stage(keys, &[1, 3]), b"keccak dlog proofs");
+    }
+
+    #[test]
+    fn dlog_hash_mismatch_fails_round4() {
+        use sha3::Keccak256;
+
+        // Messages of both parties are serialized the same way regardless of `H`
+        fn relay<A: Digest + Clone, B: Digest + Clone>(
+            from: &mut impl StateMachine<MessageBody = ProtocolMessage<A>>,
+        ) -> Vec<Msg<ProtocolMessage<B>>> {
+            from.message_queue()
+                .drain(..)
+                .map(|msg| {
+                    msg.map_body(|body| {
+                        serde_json::from_value(serde_json::to_value(body).unwrap()).unwrap()
+                    })
+                })
+                .collect()
+        }
+
+        let mut sha256 = Keygen::new(1, 1, 2).unwrap();
+        let mut keccak = Keygen::<Keccak256>::with_dlog_hash(2, 1, 2).unwrap();
+        let err = loop {
+            if sha256.wants_to_proceed() {
+                if let Err(err) = sha256.proceed() {
+                    break err;
+                }
+            }
+            if keccak.wants_to_proceed() {
+                keccak.proceed().unwrap();
+            }
+            for msg in relay(&mut sha256) {
+                keccak.handle_incoming(msg).unwrap();
+            }
+            for msg in relay(&mut keccak) {
+                sha256.handle_incoming(msg).unwrap();
+            }
+        };
+        assert!(matches!(
+            err,
+            Error::ProceedRound(ProceedError::Round4VerifyDLogProof(_))
+        ));
+    }
+
+    #[test]
+    fn message_of_different_session_is_rejected() {
+        let mut party_a1 = Keygen::with_session_id(1, 1, 2, [1; 32]).unwrap();
+        let mut party_b1 = Keygen::with_session_id(1, 1, 2, [2; 32]).unwrap();
+        let mut party_b2 = Keygen::with_session_id(2, 1, 2, [2; 32]).unwrap();
+        party_a1.proceed().unwrap();
+        party_b1.proceed().unwrap();
+        let msg_a1 = party_a1.message_queue().pop().unwrap();
+        let msg_b1 = party_b1.message_queue().pop().unwrap();
+
+        match party_b2.handle_incoming(msg_a1) {
+            Err(err @ Error::SessionMismatch { .. }) => {
+                assert!(!err.is_critical());
+                assert!(matches!(
+                    err,
+                    Error::SessionMismatch {
+                        sender: 1,
+                        msg_round: 1
+                    }
+                ));
+            }
+            result => panic!("unexpected result: {:?}", result),
+        }
+        party_b2.handle_incoming(msg_b1).unwrap();
+    }
+
+    #[test]
+    fn message_without_session_id_keeps_wire_format() {
+        let mut party = Keygen::new(1, 1, 2).unwrap();
+        party.proceed().unwrap();
+        let msg = party.message_queue().pop().unwrap().body;
+        assert!(msg.1.is_none());
+        let json = serde_json::to_value(&msg).unwrap();
+        assert_eq!(json, serde_json::to_value(&msg.0).unwrap());
+        let decoded: ProtocolMessage = serde_json::from_value(json).unwrap();
+        assert!(decoded.1.is_none());
+
+        let mut party = Keygen::with_session_id(1, 1, 2, [1; 32]).unwrap();
+        party.proceed().unwrap();
+        let msg = party.message_queue().pop().unwrap().body;
+        let decoded: ProtocolMessage =
+            serde_json::from_slice(&serde_json::to_vec(&msg).unwrap()).unwrap();
+        assert_eq!(decoded.1, msg.1);
+        assert!(decoded.1.is_some());
+    }
+
+    #[test]
+    fn message_dependencies_form_linear_chain() {
+        let deps = Keygen::message_dependencies();
+        let total_rounds = Keygen::new(1, 1, 2).unwrap().total_rounds().unwrap();
+
+        // Round 0 doesn't receive messages, every next round waits for the previous one
+        assert_eq!(deps.len(), usize::from(total_rounds) - 1);
+        assert_eq!(deps[0].0, RoundMsg::broadcast(1));
+        for (a, b) in &deps {
+            assert_eq!(a.round + 1, b.round);
+        }
+        for w in deps.windows(2) {
+            assert_eq!(w[0].1, w[1].0);
+        }
+        assert_eq!(deps.last().unwrap().1.round, total_rounds);
+        assert_eq!(deps[1].1.kind, MsgKind::P2P);
+    }
+
+    #[test]
+    fn awaited_messages_follow_current_round() {
+        let mut keygen = Keygen::new(1, 1, 2).unwrap();
+        assert_eq!(keygen.current_round(), 0);
+        assert_eq!(keygen.awaited_messages(), None);
+
+        keygen.proceed().unwrap();
+        assert_eq!(keygen.current_round(), 1);
+        assert_eq!(keygen.awaited_messages(), Some(RoundMsg::broadcast(1)));
+        assert!(Keygen::message_dependencies()
+            .iter()
+            .any(|(a, _)| Some(*a) == keygen.awaited_messages()));
+    }
+
     #[test]
     fn simulate_keygen_t1_n2() {
         simulate_keygen(1, 2);
@@ -553,4 +2116,242 @@ pub mod test {
     fn simulate_keygen_t2_n3() {
         simulate_keygen(2, 3);
     }
+
+    #[test]
+    fn keys_linear_consistency() {
+        let keys = simulate_keygen(1, 3);
+        for key in &keys {
+            key.verify_keys_linear_consistency().unwrap();
+        }
+
+        let mut mutated = keys[0].clone();
+        mutated.keys_linear.x_i = keys[1].keys_linear.x_i.clone();
+        assert!(matches!(
+            mutated.verify_keys_linear_consistency(),
+            Err(ConsistencyError::PublicShareMismatch)
+        ));
+
+        let mut mutated = keys[0].clone();
+        mutated.keys_linear.y = keys[0].pk_vec[0].clone();
+        assert!(matches!(
+            mutated.verify_keys_linear_consistency(),
+            Err(ConsistencyError::PublicKeyMismatch)
+        ));
+    }
+
+    #[test]
+    fn contribution_sum_matches_public_key() {
+        let keys = simulate_keygen(1, 3);
+        keys[0].verify_contribution_sum().unwrap();
+
+        let mut tampered = keys[0].clone();
+        tampered.vss_scheme_vec[1].commitments[0] = keys[0].pk_vec[0].clone();
+        assert!(matches!(
+            tampered.verify_contribution_sum(),
+            Err(ContributionError::SumMismatch)
+        ));
+
+        let mut legacy = keys[0].clone();
+        legacy.vss_scheme_vec.clear();
+        assert!(matches!(
+            legacy.verify_contribution_sum(),
+            Err(ContributionError::MissingVssSchemes)
+        ));
+    }
+
+    #[test]
+    fn paillier_keypair_mismatch_is_detected() {
+        let keys = simulate_keygen(1, 2);
+        keys[0].verify_paillier_keypair().unwrap();
+
+        let mut swapped = keys[0].clone();
+        swapped.paillier_dk = keys[1].paillier_dk.clone();
+        assert!(swapped.verify_paillier_keypair().is_err());
+    }
+
+    #[test]
+    fn partial_sig_origin() {
+        let keys = simulate_keygen(1, 3);
+        let pk_2 =
+            gg_2020::party_i::Keys::get_commitments_to_xi(&keys[0].vss_scheme_vec)[1].clone();
+        assert!(keys[0].verify_partial_sig_origin(2, &pk_2));
+        assert!(!keys[0].verify_partial_sig_origin(3, &pk_2));
+        assert!(!keys[0].verify_partial_sig_origin(0, &pk_2));
+        assert!(!keys[0].verify_partial_sig_origin(4, &pk_2));
+    }
+
+    #[test]
+    fn aggregate_commitments_start_with_public_key() {
+        let keys = simulate_keygen(2, 4);
+        for key in &keys {
+            let commitments = key.aggregate_commitments();
+            assert_eq!(commitments.len(), 3);
+            assert_eq!(commitments[0], key.public_key());
+        }
+    }
+
+    #[test]
+    fn vss_schemes_are_included_under_common_root() {
+        let keys = simulate_keygen(1, 3);
+        let root = keys[0].vss_commitment_root();
+        for key in &keys {
+            assert_eq!(key.vss_commitment_root(), root);
+            let proof = key.vss_inclusion_proof().unwrap();
+            assert!(verify_vss_inclusion(&root, &key.vss_scheme, &proof));
+        }
+
+        let proof = keys[0].vss_inclusion_proof().unwrap();
+        assert!(!verify_vss_inclusion(&root, &keys[1].vss_scheme, &proof));
+    }
+
+    #[cfg(feature = "dev_fast_proofs")]
+    #[test]
+    fn simulate_keygen_with_dev_fast_proofs() {
+        let mut simulation = Simulation::new();
+        for i in 1..=3 {
+            simulation.add_party(Keygen::new_dev_fast_proofs(i, 1, 3).unwrap());
+        }
+        let keys = simulation.run().unwrap();
+
+        for key in &keys {
+            let i = usize::from(key.i - 1);
+            assert_ne!(key.h1_h2_n_tilde_vec[i].N, key.paillier_key_vec[i].n);
+        }
+    }
+
+    #[cfg(feature = "dev_fast_proofs")]
+    #[test]
+    fn dev_fast_correct_key_proof_has_reduced_repetitions() {
+        use crate::protocols::multi_party_ecdsa::gg_2020::party_i::{
+            Keys, PaillierSetup, DEV_FAST_CORRECT_KEY_PROOF_REPETITIONS,
+        };
+
+        let setup = PaillierSetup::generate_insecure_dev_fast();
+        let keys = Keys::create_from_paillier_setup(1, &setup);
+        let (bc1, _) = keys.phase1_broadcast_with_paillier_setup(&setup);
+        assert!(bc1.verify_proofs());
+        let proof = serde_json::to_value(&bc1.correct_key_proof).unwrap();
+        assert_eq!(
+            proof["sigma_vec"].as_array().unwrap().len(),
+            DEV_FAST_CORRECT_KEY_PROOF_REPETITIONS
+        );
+
+        // Proof doesn't verify against another Paillier key
+        let other = PaillierSetup::generate_insecure_dev_fast();
+        let (mut bc1, _) = keys.phase1_broadcast_with_paillier_setup(&setup);
+        bc1.correct_key_proof = other.correct_key_proof;
+        assert!(!bc1.verify_proofs());
+    }
+
+    #[cfg(not(feature = "dev_fast_proofs"))]
+    #[test]
+    fn keygen_without_dev_fast_proofs_feature_uses_independent_n_tilde() {
+        // `Keygen::new_dev_fast_proofs` is not compiled, the only keygen available generates
+        // `N_tilde` independent of Paillier key
+        for key in &simulate_keygen(1, 3) {
+            let i = usize::from(key.i - 1);
+            assert_ne!(key.h1_h2_n_tilde_vec[i].N, key.paillier_key_vec[i].n);
+        }
+    }
+
+    #[test]
+    fn on_round_complete_reports_every_round() {
+        use std::sync::{Arc, Mutex};
+
+        let (t, n) = (1, 2);
+        let completed = Arc::new(Mutex::new(vec![]));
+        let mut simulation = Simulation::new();
+        for i in 1..=n {
+            let mut party = Keygen::new(i, t, n).unwrap();
+            if i == 1 {
+                let completed = completed.clone();
+                party.on_round_complete(move |round, expensive| {
+                    completed.lock().unwrap().push((round, expensive))
+                });
+            }
+            simulation.add_party(party);
+        }
+        simulation.run().unwrap();
+
+        assert_eq!(
+            *completed.lock().unwrap(),
+            vec![(0, true), (1, false), (2, true), (3, true), (4, true)]
+        );
+    }
+
+    #[test]
+    fn local_key_is_zeroized() {
+        use curv::elliptic::curves::Scalar;
+        use zeroize::Zeroize;
+
+        let mut key = simulate_keygen(1, 2).remove(0);
+        key.zeroize();
+        assert_eq!(key.paillier_dk.p, BigInt::from(0));
+        assert_eq!(key.paillier_dk.q, BigInt::from(0));
+        assert_eq!(key.keys_linear.x_i, Scalar::<Secp256k1>::zero());
+    }
+
+    #[test]
+    fn simulate_keygen_with_3072_bits_paillier() {
+        use curv::arithmetic::traits::*;
+
+        let (t, n) = (1, 2);
+        let mut simulation = Simulation::new();
+        for i in 1..=n {
+            simulation.add_party(Keygen::with_paillier_bits(i, t, n, 3072).unwrap());
+        }
+        let keys = simulation.run().unwrap();
+        for key in &keys {
+            assert!(key
+                .paillier_key_vec
+                .iter()
+                .all(|ek| ek.n.bit_length() >= 3071));
+        }
+    }
+
+    #[test]
+    fn paillier_key_of_unexpected_size_is_rejected() {
+        let (t, n) = (1, 2);
+        let mut msgs1 = vec![];
+        let party = Round0::new(1, t, n).proceed(&mut msgs1).unwrap();
+        let mut small = Round0::new(2, t, n);
+        small.paillier_bits = 1024;
+        small.proceed(&mut msgs1).unwrap();
+
+        let mut msgs2 = vec![];
+        let result = party.proceed(deliver_broadcast(1, n, &msgs1), &mut msgs2);
+        assert!(matches!(
+            result,
+            Err(ProceedError::PaillierKeySizeMismatch { party: 2, .. })
+        ));
+    }
+
+    #[test]
+    fn n_tilde_of_unexpected_size_is_rejected() {
+        use paillier::{KeyGeneration, Paillier};
+
+        let (t, n) = (1, 2);
+        let mut msgs1 = vec![];
+        let party = Round0::new(1, t, n).proceed(&mut msgs1).unwrap();
+        // Regular Paillier key, but N_tilde of half the size
+        let mut keys = Keys::create(2);
+        let (ek_tilde, dk_tilde) = Paillier::keypair_with_modulus_size(1024).keys();
+        let (n_tilde, h1, h2, xhi, xhi_inv) =
+            gg_2020::party_i::generate_h1_h2_N_tilde_from(&ek_tilde, &dk_tilde);
+        keys.N_tilde = n_tilde;
+        keys.h1 = h1;
+        keys.h2 = h2;
+        keys.xhi = xhi;
+        keys.xhi_inv = xhi_inv;
+        Round0::new(2, t, n)
+            .proceed_with_keys(keys, &mut msgs1)
+            .unwrap();
+
+        let mut msgs2 = vec![];
+        let result = party.proceed(deliver_broadcast(1, n, &msgs1), &mut msgs2);
+        assert!(matches!(
+            result,
+            Err(ProceedError::DLogStatementSizeMismatch { party: 2, bits }) if bits <= 1024
+        ));
+    }
 }
@@ -0,0 +1,313 @@
+//! Public-only view of keygen results for auditors
+
+use curv::arithmetic::traits::*;
+use curv::elliptic::curves::{secp256_k1::Secp256k1, Point};
+use paillier::EncryptionKey;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use zk_paillier::zkproofs::DLogStatement;
+
+use crate::protocols::multi_party_ecdsa::gg_2018::VerifiableSS;
+use crate::protocols::multi_party_ecdsa::gg_2020::party_i::Keys;
+
+use super::{validate_dlog_statement, LocalKey};
+
+/// Public outputs of keygen, sufficient to re-verify its consistency
+///
+/// Contains no secret material, so it can be handed to an auditor. Obtained via
+/// [LocalKey::audit_view], checked via [AuditView::verify].
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct AuditView {
+    pub pk_vec: Vec<Point<Secp256k1>>,
+    pub vss_scheme_vec: Vec<VerifiableSS<Secp256k1>>,
+    pub paillier_key_vec: Vec<EncryptionKey>,
+    pub h1_h2_n_tilde_vec: Vec<DLogStatement>,
+    pub y_sum_s: Point<Secp256k1>,
+    pub t: u16,
+    pub n: u16,
+}
+
+impl LocalKey<Secp256k1> {
+    /// Public data needed to re-verify the keygen, see [AuditView]
+    pub fn audit_view(&self) -> AuditView {
+        AuditView {
+            pk_vec: self.pk_vec.clone(),
+            vss_scheme_vec: self.vss_scheme_vec.clone(),
+            paillier_key_vec: self.paillier_key_vec.clone(),
+            h1_h2_n_tilde_vec: self.h1_h2_n_tilde_vec.clone(),
+            y_sum_s: self.y_sum_s.clone(),
+            t: self.t,
+            n: self.n,
+        }
+    }
+
+    /// Size (in bits) of party's own Paillier modulus
+    ///
+    /// Taken from party's entry in `paillier_key_vec`, i.e. from the key other parties encrypt to
+    /// the party. Returns 0 if the key has no such entry (party index is out of range).
+    pub fn paillier_modulus_bits(&self) -> usize {
+        usize::from(self.i)
+            .checked_sub(1)
+            .and_then(|i| self.paillier_key_vec.get(i))
+            .map_or(0, |ek| ek.n.bit_length())
+    }
+}
+
+impl AuditView {
+    /// Checks that public outputs of keygen are consistent with each other
+    ///
+    /// Every party must have a VSS scheme with parameters `t` and `n`, public shares `pk_vec`
+    /// must be the ones committed by VSS schemes, public key must be the sum of VSS constant
+    /// terms, and every DLog statement must be well-formed.
+    pub fn verify(&self) -> Result<(), AuditError> {
+        let n = usize::from(self.n);
+        if self.pk_vec.len() != n
+            || self.vss_scheme_vec.len() != n
+            || self.paillier_key_vec.len() != n
+            || self.h1_h2_n_tilde_vec.len() != n
+        {
+            return Err(AuditError::WrongLength);
+        }
+
+        for (party, vss) in (1..).zip(&self.vss_scheme_vec) {
+            if vss.parameters.threshold != self.t
+                || vss.parameters.share_count != self.n
+                || vss.commitments.len() != usize::from(self.t) + 1
+            {
+                return Err(AuditError::InvalidVss { party });
+            }
+        }
+
+        let committed_shares = Keys::get_commitments_to_xi(&self.vss_scheme_vec);
+        if let Some(party) = (1..)
+            .zip(committed_shares.iter().zip(&self.pk_vec))
+            .find(|(_, (committed, pk))| committed != pk)
+            .map(|(party, _)| party)
+        {
+            return Err(AuditError::PublicShareMismatch { party });
+        }
+
+        let public_key: Point<Secp256k1> = self
+            .vss_scheme_vec
+            .iter()
+            .map(|vss| vss.commitments[0].clone())
+            .sum();
+        if public_key != self.y_sum_s {
+            return Err(AuditError::PublicKeyMismatch);
+        }
+
+        for (party, statement) in (1..).zip(&self.h1_h2_n_tilde_vec) {
+            validate_dlog_statement(statement)
+                .map_err(|_| AuditError::InvalidDLogStatement { party })?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Public data of a single party's [LocalKey], sufficient to check that the key is internally
+/// consistent
+///
+/// Contains no decryption keys or shares. Obtained via [LocalKey::public_audit_data], checked via
+/// [verify_audit_data].
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PublicAuditData {
+    /// Index of the party
+    pub i: u16,
+    /// Public key party's linear share `keys_linear` is for
+    pub keys_linear_y: Point<Secp256k1>,
+    /// Party's own VSS scheme
+    pub vss_scheme: VerifiableSS<Secp256k1>,
+    /// Public outputs of keygen seen by the party
+    pub view: AuditView,
+}
+
+impl LocalKey<Secp256k1> {
+    /// Public data needed to check consistency of this key, see [PublicAuditData]
+    pub fn public_audit_data(&self) -> PublicAuditData {
+        PublicAuditData {
+            i: self.i,
+            keys_linear_y: self.keys_linear.y.clone(),
+            vss_scheme: self.vss_scheme.clone(),
+            view: self.audit_view(),
+        }
+    }
+}
+
+/// Checks that party's key is internally consistent
+///
+/// On top of [AuditView::verify], checks that linear share `keys_linear` is for the public key
+/// `y_sum_s`, and that party's own VSS scheme is the one committing to its public share among
+/// `vss_scheme_vec`.
+pub fn verify_audit_data(data: &PublicAuditData) -> Result<(), AuditError> {
+    data.view.verify()?;
+    if data.keys_linear_y != data.view.y_sum_s {
+        return Err(AuditError::KeysLinearMismatch);
+    }
+    let own_vss = usize::from(data.i)
+        .checked_sub(1)
+        .and_then(|i| data.view.vss_scheme_vec.get(i))
+        .ok_or(AuditError::InvalidPartyIndex { party: data.i })?;
+    if own_vss.commitments != data.vss_scheme.commitments {
+        return Err(AuditError::OwnVssMismatch);
+    }
+    Ok(())
+}
+
+/// Checks that all the `keys` agree on public outputs of keygen
+///
+/// Every honest party must end up with identical `paillier_key_vec`, `h1_h2_n_tilde_vec`,
+/// `pk_vec` and `y_sum_s`. Values are compared in serialized form, so they must be
+/// byte-identical. Returns [AgreementError::Mismatch] pointing to the first key disagreeing with
+/// `keys[0]`.
+pub fn validate_agreement(keys: &[LocalKey<Secp256k1>]) -> Result<(), AgreementError> {
+    let public_outputs = |key: &LocalKey<Secp256k1>| {
+        vec![
+            ("paillier_key_vec", to_json(&key.paillier_key_vec)),
+            ("h1_h2_n_tilde_vec", to_json(&key.h1_h2_n_tilde_vec)),
+            ("pk_vec", to_json(&key.pk_vec)),
+            ("y_sum_s", to_json(&key.y_sum_s)),
+        ]
+    };
+
+    let (first, rest) = keys.split_first().ok_or(AgreementError::NoKeys)?;
+    let expected_outputs = public_outputs(first);
+    for key in rest {
+        for ((field, expected), (_, actual)) in expected_outputs.iter().zip(public_outputs(key)) {
+            if *expected != actual {
+                return Err(AgreementError::Mismatch {
+                    party: key.i,
+                    field: *field,
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Reports [Paillier modulus size](LocalKey::paillier_modulus_bits) of every party owning one
+/// of the `keys`, as `(party index, bits)` pairs
+///
+/// Allows flagging parties whose Paillier key is weaker than policy requires (e.g. smaller than
+/// 2048 bits recommended for secp256k1), so they can be re-provisioned.
+pub fn audit_paillier_strength(keys: &[LocalKey<Secp256k1>]) -> Vec<(u16, usize)> {
+    keys.iter()
+        .map(|key| (key.i, key.paillier_modulus_bits()))
+        .collect()
+}
+
+fn to_json<T: Serialize>(value: &T) -> Vec<u8> {
+    serde_json::to_vec(value).expect("serialization of public data never fails")
+}
+
+#[derive(Debug, Error)]
+pub enum AgreementError {
+    #[error("no keys given")]
+    NoKeys,
+    #[error("party {party} disagrees on {field}")]
+    Mismatch { party: u16, field: &'static str },
+}
+
+#[derive(Debug, Error)]
+pub enum AuditError {
+    #[error("public data doesn't cover exactly n parties")]
+    WrongLength,
+    #[error("party {party} has invalid vss scheme")]
+    InvalidVss { party: u16 },
+    #[error("public share of party {party} doesn't match vss commitments")]
+    PublicShareMismatch { party: u16 },
+    #[error("public key doesn't match vss commitments")]
+    PublicKeyMismatch,
+    #[error("party {party} has invalid dlog statement")]
+    InvalidDLogStatement { party: u16 },
+    #[error("keys_linear.y doesn't match public key")]
+    KeysLinearMismatch,
+    #[error("party index {party} is not in range [1; n]")]
+    InvalidPartyIndex { party: u16 },
+    #[error("party's own vss scheme doesn't match its entry in vss_scheme_vec")]
+    OwnVssMismatch,
+}
+
+#[cfg(test)]
+mod test {
+    use curv::arithmetic::traits::*;
+    use curv::BigInt;
+
+    use super::*;
+    use crate::protocols::multi_party_ecdsa::gg_2020::state_machine::keygen::test::simulate_keygen;
+
+    #[test]
+    fn audit_view_verifies_without_secrets() {
+        let keys = simulate_keygen(1, 3);
+        let view = keys[0].audit_view();
+        assert!(view.verify().is_ok());
+
+        let serialized = serde_json::to_string(&view).unwrap();
+        let x_i = keys[0].keys_linear.x_i.to_bigint();
+        assert!(!serialized.contains(&x_i.to_hex()));
+        assert!(!serialized.contains(&keys[0].paillier_dk.p.to_hex()));
+
+        let mut tampered = view;
+        tampered.pk_vec.swap(0, 1);
+        assert!(matches!(
+            tampered.verify(),
+            Err(AuditError::PublicShareMismatch { party: 1 })
+        ));
+    }
+
+    #[test]
+    fn public_audit_data_verifies_party_key() {
+        let keys = simulate_keygen(1, 3);
+        let data = keys[1].public_audit_data();
+        verify_audit_data(&data).unwrap();
+
+        let serialized = serde_json::to_string(&data).unwrap();
+        assert!(!serialized.contains(&keys[1].keys_linear.x_i.to_bigint().to_hex()));
+        assert!(!serialized.contains(&keys[1].paillier_dk.q.to_hex()));
+
+        let mut tampered = data.clone();
+        tampered.vss_scheme = keys[0].vss_scheme.clone();
+        assert!(matches!(
+            verify_audit_data(&tampered),
+            Err(AuditError::OwnVssMismatch)
+        ));
+        let mut tampered = data;
+        tampered.keys_linear_y = keys[1].pk_vec[1].clone();
+        assert!(matches!(
+            verify_audit_data(&tampered),
+            Err(AuditError::KeysLinearMismatch)
+        ));
+    }
+
+    #[test]
+    fn parties_agree_on_public_outputs() {
+        let mut keys = simulate_keygen(1, 3);
+        validate_agreement(&keys).unwrap();
+
+        keys[2].paillier_key_vec[0] = keys[2].paillier_key_vec[1].clone();
+        assert!(matches!(
+            validate_agreement(&keys),
+            Err(AgreementError::Mismatch {
+                party: 3,
+                field: "paillier_key_vec"
+            })
+        ));
+    }
+
+    #[test]
+    fn paillier_strength_is_reported_per_party() {
+        let mut keys = simulate_keygen(1, 3);
+        let expected_bits = keys[1].paillier_dk.p.bit_length() + keys[1].paillier_dk.q.bit_length();
+        assert!((expected_bits - 1..=expected_bits).contains(&keys[1].paillier_modulus_bits()));
+        assert!(audit_paillier_strength(&keys)
+            .iter()
+            .zip(1..)
+            .all(|(&(party, bits), i)| party == i && bits >= 2047));
+
+        // Party's own entry is reported, not entries of other parties
+        keys[2].paillier_key_vec[2].n = BigInt::from(2).pow(1024);
+        let report = audit_paillier_strength(&keys);
+        assert_eq!(report[2], (3, 1025));
+        assert!(report[0].1 >= 2047);
+    }
+}
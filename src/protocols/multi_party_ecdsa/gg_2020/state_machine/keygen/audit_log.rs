@@ -0,0 +1,228 @@
+//! Tamper-evident log of keygen rounds
+
+use hmac::{Hmac, Mac, NewMac};
+use round_based::Msg;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+use crate::MessageRoundID;
+
+use super::ProtocolMessage;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Hash-chained log of keygen rounds, see [Keygen::with_audit_log](super::Keygen::with_audit_log)
+///
+/// Every proceeded round appends an [entry](AuditLogEntry) with digests of messages the round
+/// received and sent. Entry is authenticated by a MAC which also covers MAC of the previous entry,
+/// so modifying, removing or reordering entries breaks the chain. Chain alone doesn't reveal
+/// removal of trailing entries, so callers need to store the [head](Self::head) of the log
+/// separately and pass it to [verify](Self::verify). Log doesn't contain any secrets.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct RoundAuditLog {
+    entries: Vec<AuditLogEntry>,
+}
+
+/// Entry of [RoundAuditLog]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct AuditLogEntry {
+    /// Index of proceeded round
+    pub round: u16,
+    /// SHA-256 digests of messages received by the round, ordered by sender
+    pub inputs: Vec<[u8; 32]>,
+    /// SHA-256 digests of messages sent by the round
+    pub outputs: Vec<[u8; 32]>,
+    /// MAC of the previous entry (zeroes for the first entry)
+    pub prev_mac: [u8; 32],
+    /// HMAC-SHA256 over all the fields above
+    pub mac: [u8; 32],
+}
+
+impl RoundAuditLog {
+    /// Entries of the log in order they were appended
+    pub fn entries(&self) -> &[AuditLogEntry] {
+        &self.entries
+    }
+
+    /// MAC of the last entry (zeroes for an empty log)
+    ///
+    /// Head commits to the whole log. It needs to be stored apart from the log, e.g. next to the
+    /// key generated by keygen, so the log can't be truncated along with it.
+    pub fn head(&self) -> [u8; 32] {
+        self.entries.last().map(|e| e.mac).unwrap_or([0u8; 32])
+    }
+
+    /// Verifies that chain is intact, every entry is authenticated by the `key`, and the log
+    /// ends with the `head` obtained via [RoundAuditLog::head]
+    pub fn verify(&self, key: &[u8; 32], head: &[u8; 32]) -> Result<(), AuditLogError> {
+        let mut prev_mac = [0u8; 32];
+        for (entry_i, entry) in self.entries.iter().enumerate() {
+            if entry.prev_mac != prev_mac {
+                return Err(AuditLogError::BrokenChain { entry: entry_i });
+            }
+            entry_mac(
+                key,
+                entry.round,
+                &entry.inputs,
+                &entry.outputs,
+                &entry.prev_mac,
+            )
+            .verify(&entry.mac)
+            .map_err(|_| AuditLogError::InvalidMac { entry: entry_i })?;
+            prev_mac = entry.mac;
+        }
+        if prev_mac != *head {
+            return Err(AuditLogError::HeadMismatch);
+        }
+        Ok(())
+    }
+}
+
+/// Collects message digests and appends entries to the log as keygen proceeds
+pub(super) struct AuditRecorder {
+    key: [u8; 32],
+    received: Vec<(u16, u16, [u8; 32])>,
+    log: RoundAuditLog,
+}
+
+impl AuditRecorder {
+    pub fn new(key: [u8; 32]) -> Self {
+        Self {
+            key,
+            received: vec![],
+            log: RoundAuditLog::default(),
+        }
+    }
+
+    pub fn log(&self) -> &RoundAuditLog {
+        &self.log
+    }
+
+    /// Records a message accepted by the keygen
+    pub fn received<H: Digest + Clone>(&mut self, msg: &Msg<ProtocolMessage<H>>) {
+        self.received
+            .push((msg.body.round_id(), msg.sender, message_digest(msg)));
+    }
+
+    /// Appends entry of `round` which has just proceeded and sent `msgs`
+    pub fn proceeded<H: Digest + Clone>(&mut self, round: u16, msgs: &[Msg<ProtocolMessage<H>>]) {
+        let mut received: Vec<_> = self
+            .received
+            .iter()
+            .filter(|(msg_round, _, _)| *msg_round == round)
+            .map(|(_, sender, digest)| (*sender, *digest))
+            .collect();
+        received.sort_by_key(|(sender, _)| *sender);
+        self.received
+            .retain(|(msg_round, _, _)| *msg_round != round);
+
+        let inputs: Vec<_> = received.into_iter().map(|(_, digest)| digest).collect();
+        let outputs: Vec<_> = msgs.iter().map(message_digest).collect();
+        let prev_mac = self.log.head();
+
+        let mut mac = [0u8; 32];
+        mac.copy_from_slice(
+            &entry_mac(&self.key, round, &inputs, &outputs, &prev_mac)
+                .finalize()
+                .into_bytes(),
+        );
+        self.log.entries.push(AuditLogEntry {
+            round,
+            inputs,
+            outputs,
+            prev_mac,
+            mac,
+        })
+    }
+}
+
+fn message_digest<H: Digest + Clone>(msg: &Msg<ProtocolMessage<H>>) -> [u8; 32] {
+    let bytes = serde_json::to_vec(&(msg.sender, msg.receiver, &msg.body))
+        .expect("serialize protocol message");
+    let mut digest = [0u8; 32];
+    digest.copy_from_slice(&Sha256::digest(&bytes));
+    digest
+}
+
+fn entry_mac(
+    key: &[u8; 32],
+    round: u16,
+    inputs: &[[u8; 32]],
+    outputs: &[[u8; 32]],
+    prev_mac: &[u8; 32],
+) -> HmacSha256 {
+    let mut mac = HmacSha256::new_varkey(key).expect("HMAC takes key of any size");
+    mac.update(&round.to_be_bytes());
+    for digests in &[inputs, outputs] {
+        mac.update(&(digests.len() as u64).to_be_bytes());
+        for digest in digests.iter() {
+            mac.update(digest);
+        }
+    }
+    mac.update(prev_mac);
+    mac
+}
+
+#[derive(Debug, Error)]
+pub enum AuditLogError {
+    #[error("entry {entry} doesn't refer to the previous entry")]
+    BrokenChain { entry: usize },
+    #[error("entry {entry} has invalid mac")]
+    InvalidMac { entry: usize },
+    #[error("log doesn't end with the expected head, entries were removed or appended")]
+    HeadMismatch,
+}
+
+#[cfg(test)]
+mod test {
+    use crate::testing::{deliver_to_receivers, run_in_place};
+
+    use super::super::Keygen;
+    use super::*;
+
+    #[test]
+    fn audit_log_chain_is_verified() {
+        let (t, n) = (1, 3);
+        let key = [7u8; 32];
+        let mut parties: Vec<_> = (1..=n)
+            .map(|i| Keygen::with_audit_log(i, t, n, key).unwrap())
+            .collect();
+
+        run_in_place(&mut parties, |parties, msg| {
+            deliver_to_receivers(parties, &msg)
+        });
+
+        let log = parties[0].audit_log().unwrap().clone();
+        let rounds: Vec<_> = log.entries().iter().map(|e| e.round).collect();
+        assert_eq!(rounds, vec![0, 1, 2, 3, 4]);
+        assert!(log.entries()[1..].iter().all(|e| e.inputs.len() == 2));
+        let head = log.head();
+        log.verify(&key, &head).unwrap();
+        assert!(matches!(
+            log.verify(&[8u8; 32], &head),
+            Err(AuditLogError::InvalidMac { entry: 0 })
+        ));
+
+        let mut tampered = log.clone();
+        tampered.entries[2].outputs[0][0] ^= 1;
+        assert!(matches!(
+            tampered.verify(&key, &head),
+            Err(AuditLogError::InvalidMac { entry: 2 })
+        ));
+
+        let mut truncated = log.clone();
+        truncated.entries.pop();
+        assert!(matches!(
+            truncated.verify(&key, &head),
+            Err(AuditLogError::HeadMismatch)
+        ));
+
+        let mut tampered = log;
+        tampered.entries.remove(1);
+        assert!(matches!(
+            tampered.verify(&key, &head),
+            Err(AuditLogError::BrokenChain { entry: 1 })
+        ));
+    }
+}
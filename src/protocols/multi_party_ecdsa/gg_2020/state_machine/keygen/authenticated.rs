@@ -0,0 +1,248 @@
+//! Keygen authenticating its messages with Ed25519 identity keys, available only with `ed25519`
+//! feature
+
+use std::convert::TryFrom;
+
+use curv::elliptic::curves::secp256_k1::Secp256k1;
+use ed25519_dalek::{Keypair, PublicKey, Signature, Signer};
+use round_based::{Msg, StateMachine};
+use serde::{Deserialize, Serialize};
+
+use crate::MessageRoundID;
+
+use super::{Error, Keygen, LocalKey, ProtocolMessage, Result};
+
+/// Keygen message signed by its sender, see [AuthenticatedKeygen]
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SignedMessage {
+    pub body: ProtocolMessage,
+    /// Ed25519 identity public key the sender claims to have
+    pub identity: [u8; 32],
+    /// Signature of round, sender, receiver and body of the message
+    pub signature: Vec<u8>,
+}
+
+/// [Keygen] signing every message it sends and verifying every message it receives
+///
+/// Every party has long-term Ed25519 identity key, distinct from keys generated by keygen and
+/// supplied by orchestrator along with a roster of identity public keys of all the parties.
+/// Outgoing messages are signed with party's identity key, incoming ones are handed to the
+/// keygen only if they're signed by the identity key the roster lists for their sender.
+/// Message tampered (or forged) by a relay is rejected with [Error::UnauthenticatedMessage]
+/// before it reaches round stores, so genuine message can still be delivered afterwards.
+///
+/// Authentication doesn't need TLS between parties and relay, but it doesn't encrypt anything:
+/// p2p messages must still be encrypted at keygen level (which is the default, see
+/// [TransportMode](super::TransportMode)).
+pub struct AuthenticatedKeygen {
+    keygen: Keygen,
+    identity: Keypair,
+    roster: Vec<PublicKey>,
+    msgs_queue: Vec<Msg<SignedMessage>>,
+}
+
+impl AuthenticatedKeygen {
+    /// Wraps `keygen`, signing messages with `identity`
+    ///
+    /// `roster` lists identity public keys of all the parties ordered by party index. Returns
+    /// [Error::InvalidPartyIdentities] if roster doesn't have a key for every party, or if the
+    /// key it has for this party is not the public key of `identity`.
+    pub fn new(keygen: Keygen, identity: Keypair, roster: Vec<PublicKey>) -> Result<Self> {
+        if roster.len() != usize::from(keygen.parties())
+            || roster[usize::from(keygen.party_i - 1)] != identity.public
+        {
+            return Err(Error::InvalidPartyIdentities);
+        }
+        let mut keygen = Self {
+            keygen,
+            identity,
+            roster,
+            msgs_queue: vec![],
+        };
+        keygen.sign_outgoing();
+        Ok(keygen)
+    }
+
+    /// Wrapped keygen
+    pub fn keygen(&self) -> &Keygen {
+        &self.keygen
+    }
+
+    /// Signs messages sent by the keygen and moves them to the queue
+    fn sign_outgoing(&mut self) {
+        for msg in self.keygen.message_queue().drain(..) {
+            let signature = self.identity.sign(&signed_payload(&msg));
+            self.msgs_queue.push(Msg {
+                round: msg.round,
+                sender: msg.sender,
+                receiver: msg.receiver,
+                body: SignedMessage {
+                    body: msg.body,
+                    identity: self.identity.public.to_bytes(),
+                    signature: signature.to_bytes().to_vec(),
+                },
+            });
+        }
+    }
+
+    /// Checks that `msg` is signed by identity key the roster lists for its sender
+    fn verify(&self, msg: &Msg<SignedMessage>) -> Result<()> {
+        let body = &msg.body;
+        let error = || Error::UnauthenticatedMessage {
+            sender: msg.sender,
+            msg_round: body.body.round_id(),
+        };
+        let sender_key = usize::from(self.keygen.indexing.to_internal(msg.sender))
+            .checked_sub(1)
+            .and_then(|i| self.roster.get(i));
+        let sender_key = match sender_key {
+            Some(key) if key.as_bytes() == &body.identity => key,
+            _ => return Err(error()),
+        };
+        let signature = Signature::try_from(&body.signature[..]).map_err(|_| error())?;
+        let unsigned = Msg {
+            round: msg.round,
+            sender: msg.sender,
+            receiver: msg.receiver,
+            body: body.body.clone(),
+        };
+        sender_key
+            .verify_strict(&signed_payload(&unsigned), &signature)
+            .map_err(|_| error())
+    }
+}
+
+/// Bytes signed by the sender of `msg`: routing metadata and serialized body
+fn signed_payload(msg: &Msg<ProtocolMessage>) -> Vec<u8> {
+    let mut payload = b"multi-party-ecdsa/keygen-message/v1".to_vec();
+    payload.extend_from_slice(&msg.round.to_be_bytes());
+    payload.extend_from_slice(&msg.sender.to_be_bytes());
+    match msg.receiver {
+        Some(j) => {
+            payload.push(1);
+            payload.extend_from_slice(&j.to_be_bytes());
+        }
+        None => payload.push(0),
+    }
+    let body = serde_json::to_vec(&msg.body).expect("serialization into Vec<u8> doesn't fail");
+    payload.extend_from_slice(&body);
+    payload
+}
+
+impl StateMachine for AuthenticatedKeygen {
+    type MessageBody = SignedMessage;
+    type Err = Error;
+    type Output = LocalKey<Secp256k1>;
+
+    fn handle_incoming(&mut self, msg: Msg<Self::MessageBody>) -> Result<()> {
+        self.verify(&msg)?;
+        let result = self.keygen.handle_incoming(Msg {
+            round: msg.round,
+            sender: msg.sender,
+            receiver: msg.receiver,
+            body: msg.body.body,
+        });
+        self.sign_outgoing();
+        result
+    }
+
+    fn message_queue(&mut self) -> &mut Vec<Msg<Self::MessageBody>> {
+        &mut self.msgs_queue
+    }
+
+    fn wants_to_proceed(&self) -> bool {
+        self.keygen.wants_to_proceed()
+    }
+
+    fn proceed(&mut self) -> Result<()> {
+        let result = self.keygen.proceed();
+        self.sign_outgoing();
+        result
+    }
+
+    fn is_finished(&self) -> bool {
+        self.keygen.is_finished()
+    }
+
+    fn pick_output(&mut self) -> Option<Result<Self::Output>> {
+        self.keygen.pick_output()
+    }
+
+    fn current_round(&self) -> u16 {
+        self.keygen.current_round()
+    }
+
+    fn total_rounds(&self) -> Option<u16> {
+        self.keygen.total_rounds()
+    }
+
+    fn party_ind(&self) -> u16 {
+        self.keygen.party_ind()
+    }
+
+    fn parties(&self) -> u16 {
+        self.keygen.parties()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ed25519_dalek::SecretKey;
+
+    use crate::testing::{deliver_to_receivers, run_in_place};
+
+    use super::*;
+
+    fn identity(i: u16) -> Keypair {
+        let secret = SecretKey::from_bytes(&[i as u8; 32]).unwrap();
+        let public = PublicKey::from(&secret);
+        Keypair { secret, public }
+    }
+
+    #[test]
+    fn tampered_message_is_rejected() {
+        let n = 3;
+        let roster: Vec<_> = (1..=n).map(|i| identity(i).public).collect();
+        let mut parties: Vec<_> = (1..=n)
+            .map(|i| {
+                let keygen = Keygen::new(i, 1, n).unwrap();
+                AuthenticatedKeygen::new(keygen, identity(i), roster.clone()).unwrap()
+            })
+            .collect();
+        assert!(matches!(
+            AuthenticatedKeygen::new(Keygen::new(1, 1, n).unwrap(), identity(2), roster.clone()),
+            Err(Error::InvalidPartyIdentities)
+        ));
+
+        let mut tampered = false;
+        run_in_place(&mut parties, |parties, msg| {
+            // Relay rewrites sender of round 2 decommitment of party 2 to party 3 on its way to
+            // party 1
+            if msg.sender == 2 && msg.round == 2 {
+                tampered = true;
+                let forged = Msg {
+                    sender: 3,
+                    ..msg.clone()
+                };
+                assert!(matches!(
+                    parties[0].handle_incoming(forged),
+                    Err(Error::UnauthenticatedMessage {
+                        sender: 3,
+                        msg_round: 2
+                    })
+                ));
+                let mut resigned = msg.clone();
+                resigned.body.identity = identity(3).public.to_bytes();
+                assert!(parties[0].handle_incoming(resigned).is_err());
+            }
+            deliver_to_receivers(parties, &msg);
+        });
+        assert!(tampered);
+
+        let keys: Vec<_> = parties
+            .iter_mut()
+            .map(|p| p.pick_output().unwrap().unwrap())
+            .collect();
+        assert!(keys.iter().all(|k| k.public_key() == keys[0].public_key()));
+    }
+}
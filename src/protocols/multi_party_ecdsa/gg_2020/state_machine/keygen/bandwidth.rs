@@ -0,0 +1,174 @@
+//! Analytical estimate of network traffic of a keygen party, for capacity planning
+
+use serde::Serialize;
+
+/// Encoding of keygen messages on the wire
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum WireFormat {
+    /// [serde_json], numbers are encoded in hex and byte arrays as arrays of decimal numbers
+    Json,
+    /// CBOR, see `ProtocolMessage::to_cbor` (available with `cbor` feature)
+    Cbor,
+}
+
+/// Estimated traffic of a single keygen round, in bytes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct RoundBandwidth {
+    pub round: u16,
+    /// Size of a single message the party sends at this round
+    pub message_size: u64,
+    /// Bytes the party sends to all the other parties together
+    pub sent: u64,
+    /// Bytes the party receives from all the other parties together
+    pub received: u64,
+}
+
+/// Estimated traffic of a keygen party, obtained via [estimate_keygen_bandwidth]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BandwidthEstimate {
+    /// Rounds 1 to 4, in order
+    pub rounds: Vec<RoundBandwidth>,
+    pub total_sent: u64,
+    pub total_received: u64,
+}
+
+/// Estimates how many bytes every party of `t`-out-of-`n` keygen sends and receives
+///
+/// Sizes are computed from structure of the messages, without running the protocol: round 1
+/// broadcast is dominated by Paillier key, `(N_tilde, h1, h2)` and proofs of their correctness
+/// (about 20 numbers of `paillier_bits` size), round 3 sends every other party a Paillier
+/// ciphertext with `t + 1` VSS commitments, rounds 2 and 4 carry a few curve points. Field
+/// names and punctuation are approximated by small constants, so expect estimate to be within
+/// 20% of the real size.
+///
+/// Broadcast is counted as sent to every other party separately, i.e. relay fanning out a
+/// single upload reduces `sent` of broadcast rounds by `n - 1` times. Estimate assumes keygen
+/// without [session id](super::Keygen::with_session_id), party identities, recovery backups and
+/// [public key confirmation](super::Keygen::with_public_key_confirmation), each of them adds
+/// less than a hundred bytes per message. Largest [message_size](RoundBandwidth::message_size)
+/// is a reasonable base for [message size limit](super::Keygen::set_message_size_limit).
+pub fn estimate_keygen_bandwidth(
+    t: u16,
+    n: u16,
+    paillier_bits: usize,
+    format: WireFormat,
+) -> BandwidthEstimate {
+    let bits = paillier_bits as u64;
+    let encoding = Encoding(format);
+    let others = u64::from(n.saturating_sub(1));
+
+    let round1 = encoding.number(2 * bits)
+        + 17 * encoding.number(bits)
+        + 2 * encoding.number(bits + 384)
+        + encoding.number(256)
+        + encoding.overhead(400);
+    let round2 = encoding.point() + encoding.number(256) + encoding.overhead(60);
+    let round3 = encoding.byte_array(bits / 4)
+        + (u64::from(t) + 1) * encoding.point()
+        + encoding.byte_array(32)
+        + encoding.overhead(120);
+    let round4 = 2 * encoding.point() + encoding.scalar() + encoding.overhead(80);
+
+    let rounds: Vec<_> = (1..)
+        .zip(&[round1, round2, round3, round4])
+        .map(|(round, &message_size)| RoundBandwidth {
+            round,
+            message_size,
+            sent: others * message_size,
+            received: others * message_size,
+        })
+        .collect();
+    BandwidthEstimate {
+        total_sent: rounds.iter().map(|r| r.sent).sum(),
+        total_received: rounds.iter().map(|r| r.received).sum(),
+        rounds,
+    }
+}
+
+/// Approximate sizes of values serialized in given format
+struct Encoding(WireFormat);
+
+impl Encoding {
+    /// Number of `bits` size: hex string in JSON, byte string in CBOR
+    fn number(&self, bits: u64) -> u64 {
+        match self.0 {
+            WireFormat::Json => bits / 4 + 3,
+            WireFormat::Cbor => bits / 8 + 3,
+        }
+    }
+
+    /// Array of `len` bytes: decimal numbers separated by commas in JSON, array of integers in
+    /// CBOR (most of them take 2 bytes)
+    fn byte_array(&self, len: u64) -> u64 {
+        match self.0 {
+            WireFormat::Json => len * 36 / 10,
+            WireFormat::Cbor => len * 19 / 10,
+        }
+    }
+
+    /// Compressed curve point with curve name
+    fn point(&self) -> u64 {
+        self.number(33 * 8) + self.overhead(30)
+    }
+
+    /// Scalar with curve name
+    fn scalar(&self) -> u64 {
+        self.number(32 * 8) + self.overhead(30)
+    }
+
+    /// Field names and punctuation taking `json_bytes` in JSON, CBOR takes about half as much
+    fn overhead(&self, json_bytes: u64) -> u64 {
+        match self.0 {
+            WireFormat::Json => json_bytes,
+            WireFormat::Cbor => json_bytes / 2,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use round_based::StateMachine;
+
+    use super::super::Keygen;
+    use super::*;
+
+    #[test]
+    fn estimate_matches_real_round1_message() {
+        let mut keygen = Keygen::new(1, 1, 3).unwrap();
+        keygen.proceed().unwrap();
+        let real = keygen.message_queue()[0].body.serialized_len() as u64;
+
+        let estimate = estimate_keygen_bandwidth(1, 3, 2048, WireFormat::Json);
+        let round1 = estimate.rounds[0];
+        assert!(
+            real * 8 / 10 <= round1.message_size && round1.message_size <= real * 12 / 10,
+            "estimated {} bytes, real message has {}",
+            round1.message_size,
+            real
+        );
+        assert_eq!(round1.sent, 2 * round1.message_size);
+    }
+
+    #[test]
+    fn estimate_scales_with_parameters() {
+        let small = estimate_keygen_bandwidth(1, 3, 2048, WireFormat::Json);
+        assert_eq!(small.rounds.len(), 4);
+        assert_eq!(
+            small.total_sent,
+            small.rounds.iter().map(|r| r.sent).sum::<u64>()
+        );
+        assert!(small
+            .rounds
+            .iter()
+            .all(|r| r.message_size < small.rounds[0].message_size));
+
+        let big = estimate_keygen_bandwidth(1, 5, 2048, WireFormat::Json);
+        assert_eq!(big.total_received, 2 * small.total_received);
+        let higher_threshold = estimate_keygen_bandwidth(3, 5, 2048, WireFormat::Json);
+        assert!(higher_threshold.rounds[2].message_size > big.rounds[2].message_size);
+        let bigger_keys = estimate_keygen_bandwidth(1, 3, 3072, WireFormat::Json);
+        assert!(bigger_keys.total_sent > small.total_sent);
+        let cbor = estimate_keygen_bandwidth(1, 3, 2048, WireFormat::Cbor);
+        assert!(cbor.total_sent < small.total_sent);
+    }
+}
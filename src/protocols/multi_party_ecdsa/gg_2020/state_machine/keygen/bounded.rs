@@ -0,0 +1,251 @@
+//! Decoding keygen messages from untrusted input in bounded memory
+
+use std::convert::TryFrom;
+use std::io::{self, Read};
+
+use round_based::{Msg, StateMachine};
+use serde::Serialize;
+use sha2::Digest;
+use thiserror::Error;
+
+use crate::protocols::multi_party_ecdsa::gg_2020::party_i::KeyGenBroadcastMessage1;
+
+use super::{Error, Keygen, ProtocolMessage};
+
+/// Structural limits of a message received from untrusted party
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DecodeLimits {
+    /// Maximum size of serialized message (in bytes)
+    pub max_bytes: u64,
+    /// Maximum number of elements in a single proof carried by the message
+    pub max_proof_elements: usize,
+}
+
+impl Default for DecodeLimits {
+    /// Limits fitting messages of parties using Paillier keys of default size with some margin
+    fn default() -> Self {
+        Self {
+            max_bytes: 256 * 1024,
+            max_proof_elements: 64,
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum DecodeError {
+    #[error("message exceeds {max_bytes} bytes")]
+    TooLarge { max_bytes: u64 },
+    #[error("{proof} has {elements} elements, at most {max} are allowed")]
+    TooManyProofElements {
+        proof: &'static str,
+        elements: usize,
+        max: usize,
+    },
+    #[error("malformed message: {0}")]
+    Malformed(#[source] serde_json::Error),
+}
+
+impl KeyGenBroadcastMessage1 {
+    /// Reads JSON-serialized message from `reader`, enforcing `limits`
+    ///
+    /// Reading stops as soon as message exceeds `limits.max_bytes`, so memory usage is bounded
+    /// regardless of how much data a malicious party sends. Proofs are checked against
+    /// `limits.max_proof_elements` right after decoding, before any expensive verification.
+    pub fn from_reader_with_limits<R: Read>(
+        reader: R,
+        limits: &DecodeLimits,
+    ) -> Result<Self, DecodeError> {
+        let mut reader = LimitedReader {
+            inner: reader,
+            remaining: limits.max_bytes,
+            exceeded: false,
+        };
+        let msg: Self = match serde_json::from_reader(&mut reader) {
+            Ok(msg) => msg,
+            Err(_) if reader.exceeded => {
+                return Err(DecodeError::TooLarge {
+                    max_bytes: limits.max_bytes,
+                })
+            }
+            Err(err) => return Err(DecodeError::Malformed(err)),
+        };
+
+        let proofs = [
+            ("correct key proof", count_elements(&msg.correct_key_proof)),
+            (
+                "composite dlog proof base h1",
+                count_elements(&msg.composite_dlog_proof_base_h1),
+            ),
+            (
+                "composite dlog proof base h2",
+                count_elements(&msg.composite_dlog_proof_base_h2),
+            ),
+        ];
+        for &(proof, elements) in proofs.iter() {
+            if elements > limits.max_proof_elements {
+                return Err(DecodeError::TooManyProofElements {
+                    proof,
+                    elements,
+                    max: limits.max_proof_elements,
+                });
+            }
+        }
+        Ok(msg)
+    }
+}
+
+impl<H: Digest + Clone> Keygen<H> {
+    /// Sets maximum size (in bytes) of a message accepted by
+    /// [handle_incoming_bytes](Self::handle_incoming_bytes)
+    ///
+    /// Default limit is derived from Paillier modulus size and number of parties, and fits any
+    /// message of honest party with a big margin.
+    pub fn set_message_size_limit(&mut self, max_bytes: u64) {
+        self.message_size_limit = max_bytes;
+    }
+
+    /// Decodes JSON-serialized message received from party `sender` and handles it
+    ///
+    /// Message exceeding [size limit](Self::set_message_size_limit) is rejected before decoding
+    /// with [Error::MessageTooLarge], so a party can't make others spend memory and time on
+    /// parsing a padded message. `sender` must be the party authenticated by the transport,
+    /// message claiming a different sender is rejected with [Error::MalformedMessage].
+    pub fn handle_incoming_bytes(&mut self, sender: u16, bytes: &[u8]) -> Result<(), Error> {
+        if bytes.len() as u64 > self.message_size_limit {
+            return Err(Error::MessageTooLarge {
+                sender,
+                size: bytes.len(),
+                max_bytes: self.message_size_limit,
+            });
+        }
+        let msg: Msg<ProtocolMessage<H>> =
+            serde_json::from_slice(bytes).map_err(|_| Error::MalformedMessage { sender })?;
+        if msg.sender != sender {
+            return Err(Error::MalformedMessage { sender });
+        }
+        self.handle_incoming(msg)
+    }
+}
+
+/// Default [message size limit](Keygen::set_message_size_limit) of keygen of `n` parties using
+/// Paillier modulus of `paillier_bits`
+///
+/// JSON encodes numbers in hex, i.e. number of modulus size takes `paillier_bits / 4` bytes.
+/// Round 1 message carries less than 48 such numbers (Paillier key, `(N_tilde, h1, h2)` and
+/// proofs), round 3 message carries up to `n` recovery backups, each is a Paillier ciphertext of
+/// doubled size encoded as an array of bytes (at most 4 characters per byte). Limit is 4 times
+/// the sum.
+pub(super) fn default_message_size_limit(paillier_bits: usize, n: u16) -> u64 {
+    let number = (paillier_bits / 4) as u64;
+    4 * (48 * number + u64::from(n) * 4 * number)
+}
+
+/// Counts leaf values of serialized `proof`, i.e. numbers it's made of
+fn count_elements<T: Serialize>(proof: &T) -> usize {
+    fn count(value: &serde_json::Value) -> usize {
+        match value {
+            serde_json::Value::Array(items) => items.iter().map(count).sum(),
+            serde_json::Value::Object(fields) => fields.values().map(count).sum(),
+            _ => 1,
+        }
+    }
+    count(&serde_json::to_value(proof).expect("serialization of proof doesn't fail"))
+}
+
+/// Reader failing once more than `remaining` bytes are read
+struct LimitedReader<R> {
+    inner: R,
+    remaining: u64,
+    exceeded: bool,
+}
+
+impl<R: Read> Read for LimitedReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        // Reads one byte over the limit to tell apart message of exactly `remaining` bytes
+        let max = buf
+            .len()
+            .min(usize::try_from(self.remaining.saturating_add(1)).unwrap_or(usize::MAX));
+        let read = self.inner.read(&mut buf[..max])?;
+        if read as u64 > self.remaining {
+            self.exceeded = true;
+            return Err(io::Error::new(io::ErrorKind::Other, "message is too large"));
+        }
+        self.remaining -= read as u64;
+        Ok(read)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::protocols::multi_party_ecdsa::gg_2020::party_i::{Keys, PaillierSetup};
+
+    use super::*;
+
+    #[test]
+    fn limits_are_enforced() {
+        let setup = PaillierSetup::generate();
+        let keys = Keys::create_from_paillier_setup(1, &setup);
+        let (bc1, _) = keys.phase1_broadcast_with_paillier_setup(&setup);
+        let bytes = serde_json::to_vec(&bc1).unwrap();
+
+        let limits = DecodeLimits::default();
+        let decoded =
+            KeyGenBroadcastMessage1::from_reader_with_limits(bytes.as_slice(), &limits).unwrap();
+        assert_eq!(decoded.e.n, bc1.e.n);
+        let exact = DecodeLimits {
+            max_bytes: bytes.len() as u64,
+            ..limits
+        };
+        assert!(KeyGenBroadcastMessage1::from_reader_with_limits(bytes.as_slice(), &exact).is_ok());
+
+        let small = DecodeLimits {
+            max_bytes: bytes.len() as u64 - 1,
+            ..limits
+        };
+        assert!(matches!(
+            KeyGenBroadcastMessage1::from_reader_with_limits(bytes.as_slice(), &small),
+            Err(DecodeError::TooLarge { .. })
+        ));
+
+        let few_elements = DecodeLimits {
+            max_proof_elements: 1,
+            ..limits
+        };
+        assert!(matches!(
+            KeyGenBroadcastMessage1::from_reader_with_limits(bytes.as_slice(), &few_elements),
+            Err(DecodeError::TooManyProofElements { .. })
+        ));
+
+        assert!(matches!(
+            KeyGenBroadcastMessage1::from_reader_with_limits(&bytes[..100], &limits),
+            Err(DecodeError::Malformed(_))
+        ));
+    }
+
+    #[test]
+    fn oversized_keygen_message_is_rejected_before_decoding() {
+        let mut party1 = Keygen::new(1, 1, 2).unwrap();
+        let mut party2 = Keygen::new(2, 1, 2).unwrap();
+        party2.proceed().unwrap();
+        let msg = party2.message_queue().pop().unwrap();
+        let bytes = serde_json::to_vec(&msg).unwrap();
+        assert!((bytes.len() as u64) < default_message_size_limit(2048, 2));
+
+        party1.set_message_size_limit(bytes.len() as u64 - 1);
+        assert!(matches!(
+            party1.handle_incoming_bytes(2, &bytes),
+            Err(Error::MessageTooLarge { sender: 2, .. })
+        ));
+        party1.set_message_size_limit(bytes.len() as u64);
+        assert!(matches!(
+            party1.handle_incoming_bytes(1, &bytes),
+            Err(Error::MalformedMessage { sender: 1 })
+        ));
+        assert!(matches!(
+            party1.handle_incoming_bytes(2, &bytes[..100]),
+            Err(Error::MalformedMessage { sender: 2 })
+        ));
+        party1.set_message_size_limit(u64::MAX);
+        party1.handle_incoming_bytes(2, &bytes).unwrap();
+    }
+}
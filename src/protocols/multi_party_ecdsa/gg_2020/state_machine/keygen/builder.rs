@@ -0,0 +1,301 @@
+//! Constructing keygen from named parameters
+
+use std::fmt;
+use std::sync::Arc;
+
+use thiserror::Error;
+
+use crate::protocols::multi_party_ecdsa::gg_2020::party_i::DEFAULT_PAILLIER_BITS;
+
+use super::{
+    AuditRecorder, Error, Indexing, Keygen, KeygenTranscript, RngProvider, TransportMode, R,
+};
+
+/// Builder of [Keygen] taking parameters by name
+///
+/// Unlike positional `Keygen::new(i, t, n)`, parameters can't be swapped silently, and every
+/// combination of them is validated at [build](Self::build) with an error naming the mistake.
+/// Options can be combined freely, unlike `Keygen::with_*` constructors taking one option each.
+///
+/// ## Example
+/// ```no_run
+/// # use multi_party_ecdsa::protocols::multi_party_ecdsa::gg_2020::state_machine::keygen::*;
+/// # fn main() -> Result<(), KeygenBuilderError> {
+/// let keygen = KeygenBuilder::new()
+///     .party_index(1)
+///     .threshold(1)
+///     .parties(3)
+///     .session_id([1; 32])
+///     .build()?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Default)]
+pub struct KeygenBuilder {
+    party_index: Option<u16>,
+    threshold: Option<u16>,
+    parties: Option<u16>,
+    paillier_bits: Option<usize>,
+    session_id: Option<[u8; 32]>,
+    indexing: Indexing,
+    transcript: bool,
+    audit_key: Option<[u8; 32]>,
+    transport_mode: TransportMode,
+    public_key_confirmation: bool,
+    rng_provider: Option<Arc<dyn RngProvider>>,
+}
+
+impl fmt::Debug for KeygenBuilder {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("KeygenBuilder")
+            .field("party_index", &self.party_index)
+            .field("threshold", &self.threshold)
+            .field("parties", &self.parties)
+            .field("paillier_bits", &self.paillier_bits)
+            .field("session_id", &self.session_id)
+            .field("indexing", &self.indexing)
+            .field("transcript", &self.transcript)
+            .field("audit_log", &self.audit_key.is_some())
+            .field("transport_mode", &self.transport_mode)
+            .field("public_key_confirmation", &self.public_key_confirmation)
+            .field("rng_provider", &self.rng_provider.is_some())
+            .finish()
+    }
+}
+
+impl KeygenBuilder {
+    /// Constructs a builder with no parameters set
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Index of this party, in range `[1; parties]`, or `[0; parties)` with
+    /// [zero-based indexing](Self::indexing) (required)
+    pub fn party_index(mut self, i: u16) -> Self {
+        self.party_index = Some(i);
+        self
+    }
+
+    /// Threshold `t`: any `t + 1` parties can sign (required)
+    pub fn threshold(mut self, t: u16) -> Self {
+        self.threshold = Some(t);
+        self
+    }
+
+    /// Number of parties `n` (required)
+    pub fn parties(mut self, n: u16) -> Self {
+        self.parties = Some(n);
+        self
+    }
+
+    /// Size of Paillier modulus and `N_tilde`, see [Keygen::with_paillier_bits]
+    pub fn paillier_bits(mut self, bits: usize) -> Self {
+        self.paillier_bits = Some(bits);
+        self
+    }
+
+    /// Binds keygen to session, see [Keygen::with_session_id]
+    pub fn session_id(mut self, session_id: [u8; 32]) -> Self {
+        self.session_id = Some(session_id);
+        self
+    }
+
+    /// Numbering of parties in messages and [party_index](Self::party_index), 1-based by default
+    ///
+    /// With [Indexing::ZeroBased] keygen takes `sender`/`receiver` of incoming messages and
+    /// sets them in outgoing ones starting from 0, so it can be used with 0-indexed transport
+    /// as is. Every party must be built with the same indexing.
+    pub fn indexing(mut self, indexing: Indexing) -> Self {
+        self.indexing = indexing;
+        self
+    }
+
+    /// Records messages keygen sends and receives, see [Keygen::with_transcript]
+    pub fn transcript(mut self, enabled: bool) -> Self {
+        self.transcript = enabled;
+        self
+    }
+
+    /// Keeps log of rounds authenticated with `audit_key`, see [Keygen::with_audit_log]
+    pub fn audit_log(mut self, audit_key: [u8; 32]) -> Self {
+        self.audit_key = Some(audit_key);
+        self
+    }
+
+    /// Delivery of shares to other parties, see [Keygen::with_transport_mode]
+    pub fn transport_mode(mut self, mode: TransportMode) -> Self {
+        self.transport_mode = mode;
+        self
+    }
+
+    /// Runs public key confirmation round, see [Keygen::with_public_key_confirmation]
+    pub fn public_key_confirmation(mut self, enabled: bool) -> Self {
+        self.public_key_confirmation = enabled;
+        self
+    }
+
+    /// Draws party's secrets from `rng_provider`, see [Keygen::with_rng_provider]
+    pub fn rng_provider(mut self, rng_provider: Arc<dyn RngProvider>) -> Self {
+        self.rng_provider = Some(rng_provider);
+        self
+    }
+
+    /// Validates parameters and constructs keygen
+    pub fn build(self) -> Result<Keygen, KeygenBuilderError> {
+        let party_index = self
+            .party_index
+            .ok_or(KeygenBuilderError::MissingParameter {
+                name: "party_index",
+            })?;
+        let t = self
+            .threshold
+            .ok_or(KeygenBuilderError::MissingParameter { name: "threshold" })?;
+        let n = self
+            .parties
+            .ok_or(KeygenBuilderError::MissingParameter { name: "parties" })?;
+        let i = self.indexing.to_internal(party_index);
+        let paillier_bits = self.paillier_bits.unwrap_or(DEFAULT_PAILLIER_BITS);
+        let mut keygen =
+            Keygen::with_paillier_bits(i, t, n, paillier_bits).map_err(|err| match err {
+                Error::TooFewParties => KeygenBuilderError::TooFewParties { parties: n },
+                Error::InvalidThreshold => KeygenBuilderError::InvalidThreshold {
+                    threshold: t,
+                    parties: n,
+                },
+                Error::InvalidPartyIndex => KeygenBuilderError::InvalidPartyIndex {
+                    party_index,
+                    parties: n,
+                    indexing: self.indexing,
+                },
+                Error::InvalidPaillierBits { bits, min_bits } => {
+                    KeygenBuilderError::InvalidPaillierBits { bits, min_bits }
+                }
+                err => KeygenBuilderError::Keygen(err),
+            })?;
+
+        if let R::Round0(round) = &mut keygen.round {
+            round.transport_mode = self.transport_mode;
+            round.rng_provider = self.rng_provider;
+        }
+        if self.transcript {
+            keygen.transcript = Some(KeygenTranscript::new(i, t, n));
+        }
+        if let Some(audit_key) = self.audit_key {
+            keygen.audit_log = Some(AuditRecorder::new(audit_key));
+        }
+        keygen.confirm_public_key = self.public_key_confirmation;
+        if let Some(session_id) = self.session_id {
+            keygen.set_session_id(session_id);
+        }
+        keygen.set_indexing(self.indexing);
+        Ok(keygen)
+    }
+}
+
+/// Error of [KeygenBuilder::build]
+#[derive(Debug, Error)]
+pub enum KeygenBuilderError {
+    #[error("parameter `{name}` is not set")]
+    MissingParameter { name: &'static str },
+    #[error("at least 2 parties are required, got {parties}")]
+    TooFewParties { parties: u16 },
+    #[error("threshold {threshold} is not less than number of parties {parties} or is zero")]
+    InvalidThreshold { threshold: u16, parties: u16 },
+    #[error(
+        "party index {party_index} is out of range for {parties} parties ({indexing:?} indexing)"
+    )]
+    InvalidPartyIndex {
+        party_index: u16,
+        parties: u16,
+        indexing: Indexing,
+    },
+    #[error("paillier modulus size {bits} must be even and at least {min_bits} bits")]
+    InvalidPaillierBits { bits: usize, min_bits: usize },
+    #[error("construct keygen: {0}")]
+    Keygen(#[source] Error),
+}
+
+#[cfg(test)]
+mod test {
+    use round_based::StateMachine;
+
+    use super::*;
+
+    #[test]
+    fn builder_validates_parameters() {
+        let valid = || KeygenBuilder::new().party_index(1).threshold(1).parties(3);
+        let keygen = valid().session_id([1; 32]).build().unwrap();
+        assert_eq!((keygen.party_ind(), keygen.parties()), (1, 3));
+        assert_eq!(keygen.session_id, Some([1; 32]));
+
+        assert!(matches!(
+            KeygenBuilder::new().party_index(1).parties(3).build(),
+            Err(KeygenBuilderError::MissingParameter { name: "threshold" })
+        ));
+        assert!(matches!(
+            valid().parties(1).build(),
+            Err(KeygenBuilderError::TooFewParties { parties: 1 })
+        ));
+        assert!(matches!(
+            valid().threshold(3).build(),
+            Err(KeygenBuilderError::InvalidThreshold {
+                threshold: 3,
+                parties: 3
+            })
+        ));
+        assert!(matches!(
+            valid().party_index(0).build(),
+            Err(KeygenBuilderError::InvalidPartyIndex {
+                party_index: 0,
+                parties: 3,
+                indexing: Indexing::OneBased
+            })
+        ));
+        assert!(matches!(
+            valid().party_index(3).indexing(Indexing::ZeroBased).build(),
+            Err(KeygenBuilderError::InvalidPartyIndex {
+                party_index: 3,
+                parties: 3,
+                indexing: Indexing::ZeroBased
+            })
+        ));
+        assert!(matches!(
+            valid().paillier_bits(1024).build(),
+            Err(KeygenBuilderError::InvalidPaillierBits { bits: 1024, .. })
+        ));
+        assert!(matches!(
+            valid().paillier_bits(3071).build(),
+            Err(KeygenBuilderError::InvalidPaillierBits { bits: 3071, .. })
+        ));
+    }
+
+    #[test]
+    fn builder_combines_options() {
+        let keygen = KeygenBuilder::new()
+            .party_index(1)
+            .threshold(1)
+            .parties(2)
+            .transcript(true)
+            .audit_log([2; 32])
+            .transport_mode(TransportMode::ExternalSecureChannel)
+            .public_key_confirmation(true)
+            .build()
+            .unwrap();
+        assert!(keygen.transcript().is_some());
+        assert!(keygen.audit_log().is_some());
+        assert!(keygen.confirm_public_key);
+        assert!(matches!(
+            &keygen.round,
+            R::Round0(round) if round.transport_mode == TransportMode::ExternalSecureChannel
+        ));
+
+        let keygen = KeygenBuilder::new()
+            .party_index(1)
+            .threshold(1)
+            .parties(2)
+            .build()
+            .unwrap();
+        assert!(keygen.transcript().is_none() && keygen.audit_log().is_none());
+        assert!(!keygen.confirm_public_key);
+    }
+}
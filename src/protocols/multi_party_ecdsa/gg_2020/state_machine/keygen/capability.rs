@@ -0,0 +1,154 @@
+//! Description of what a key share is able to do, for external policy engines
+
+use curv::elliptic::curves::{secp256_k1::Secp256k1, Curve};
+use serde::{Deserialize, Serialize};
+
+use super::LocalKey;
+use crate::protocols::multi_party_ecdsa::gg_2020::state_machine::quorum::canonicalize_quorum;
+
+/// Public description of signing capabilities of a key share
+///
+/// Serializes to JSON suitable for policy engines. Contains no secret material.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct CapabilityDescriptor {
+    pub curve: String,
+    /// Index of party holding the share
+    pub party_index: u16,
+    pub t: u16,
+    pub n: u16,
+    /// Minimal number of parties required to sign (`t+1`)
+    pub min_signers: u16,
+    /// Signing quorums this share can participate in
+    pub quorums: QuorumRule,
+    /// Compressed group public key, hex-encoded
+    pub public_key: String,
+    /// [Fingerprint](LocalKey::fingerprint) of the group public key, hex-encoded
+    pub fingerprint: String,
+}
+
+/// Set of quorums: any subset of `1..=n` of at least `min_size` parties that includes
+/// `must_include`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct QuorumRule {
+    pub min_size: u16,
+    pub max_size: u16,
+    pub must_include: u16,
+}
+
+impl LocalKey<Secp256k1> {
+    /// Checks that `quorum` is a valid signing quorum for this key
+    ///
+    /// Quorum must consist of at least `t+1` distinct parties' indexes in range `[1; n]`. Note
+    /// that it doesn't check whether this party is a member of the quorum.
+    pub fn can_sign_with(&self, quorum: &[u16]) -> bool {
+        let canonical = canonicalize_quorum(quorum);
+        canonical.len() == quorum.len()
+            && canonical.len() > usize::from(self.t)
+            && canonical.iter().all(|&j| j >= 1 && j <= self.n)
+    }
+
+    /// Picks a random valid signing quorum, handy for seeding property-based tests
+    ///
+    /// Quorum is sorted, has random size from `t+1` to `n` (or `n-1` if `include_self` is
+    /// false), and contains this party iff `include_self` is true. Available only with `testing`
+    /// feature.
+    ///
+    /// ## Panics
+    /// Panics if `include_self` is false and there are less than `t+1` other parties (`t = n-1`)
+    #[cfg(feature = "testing")]
+    pub fn arbitrary_quorum(&self, rng: &mut impl rand::Rng, include_self: bool) -> Vec<u16> {
+        use rand::seq::SliceRandom;
+
+        let min_size = self.t + 1;
+        let max_size = if include_self { self.n } else { self.n - 1 };
+        assert!(
+            min_size <= max_size,
+            "not enough other parties to form a quorum"
+        );
+        let size = rng.gen_range(min_size..=max_size);
+
+        let mut others: Vec<u16> = (1..=self.n).filter(|&j| j != self.i).collect();
+        others.shuffle(rng);
+        let mut quorum = others;
+        if include_self {
+            quorum.truncate(usize::from(size) - 1);
+            quorum.push(self.i);
+        } else {
+            quorum.truncate(usize::from(size));
+        }
+        quorum.sort_unstable();
+        quorum
+    }
+
+    /// Describes what this share can do, see [CapabilityDescriptor]
+    pub fn capability_descriptor(&self) -> CapabilityDescriptor {
+        let min_signers = self.t + 1;
+        CapabilityDescriptor {
+            curve: Secp256k1::CURVE_NAME.to_string(),
+            party_index: self.i,
+            t: self.t,
+            n: self.n,
+            min_signers,
+            quorums: QuorumRule {
+                min_size: min_signers,
+                max_size: self.n,
+                must_include: self.i,
+            },
+            public_key: to_hex(&self.y_sum_s.to_bytes(true)),
+            fingerprint: to_hex(&self.fingerprint()),
+        }
+    }
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::protocols::multi_party_ecdsa::gg_2020::state_machine::keygen::test::simulate_keygen;
+
+    #[test]
+    fn can_sign_with_checks_quorum() {
+        let keys = simulate_keygen(1, 3);
+        assert!(keys[0].can_sign_with(&[2, 3]));
+        assert!(keys[0].can_sign_with(&[3, 1, 2]));
+        assert!(!keys[0].can_sign_with(&[1]));
+        assert!(!keys[0].can_sign_with(&[1, 1]));
+        assert!(!keys[0].can_sign_with(&[1, 4]));
+        assert!(!keys[0].can_sign_with(&[0, 1]));
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn arbitrary_quorums_are_valid() {
+        let keys = simulate_keygen(1, 4);
+        let mut rng = rand::thread_rng();
+        for _ in 0..100 {
+            for include_self in [true, false].iter().copied() {
+                let quorum = keys[1].arbitrary_quorum(&mut rng, include_self);
+                assert!(keys[1].can_sign_with(&quorum));
+                assert_eq!(quorum.contains(&2), include_self);
+            }
+        }
+    }
+
+    #[test]
+    fn capability_descriptor_describes_share() {
+        let keys = simulate_keygen(1, 3);
+        let descriptor = keys[1].capability_descriptor();
+        assert_eq!(descriptor.min_signers, 2);
+        assert_eq!(descriptor.party_index, 2);
+        assert_eq!(descriptor.quorums.must_include, 2);
+
+        let fingerprint: String = keys[0]
+            .fingerprint()
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect();
+        assert_eq!(descriptor.fingerprint, fingerprint);
+
+        let json = serde_json::to_string(&descriptor).unwrap();
+        assert!(json.contains("\"min_signers\":2"));
+    }
+}
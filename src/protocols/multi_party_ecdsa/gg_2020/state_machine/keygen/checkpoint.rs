@@ -0,0 +1,300 @@
+//! Saving keygen state between rounds and resuming it after restart
+
+use std::sync::Arc;
+
+use round_based::StateMachine;
+use serde::{Deserialize, Serialize};
+use sha2::Digest;
+use thiserror::Error;
+
+use crate::protocols::multi_party_ecdsa::gg_2020::party_i::DEFAULT_PAILLIER_BITS;
+
+use super::{
+    default_message_size_limit, Indexing, Keygen, ReceivedDigests, RngProvider, Round1, Round2,
+    Round3, Round4, Round5, ShareCipher, R,
+};
+
+const STATE_VERSION: u16 = 1;
+
+/// Error of [saving](Keygen::save_state) or [loading](Keygen::load_state) keygen state
+#[derive(Debug, Error)]
+pub enum StateError {
+    #[error("keygen at round {round} has no intermediate state to save")]
+    NotInProgress { round: u16 },
+    #[error("unknown keygen state version {version}")]
+    UnknownVersion { version: u16 },
+    #[error("serialize keygen state: {0}")]
+    Serialize(#[source] serde_json::Error),
+    #[error("deserialize keygen state: {0}")]
+    Deserialize(#[source] serde_json::Error),
+    /// Keygen samples its secrets from rng provider, it must be resumed with
+    /// [load_state_with_rng_provider](Keygen::load_state_with_rng_provider)
+    #[error("keygen state was saved with rng provider, but it's not supplied")]
+    RngProviderRequired,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SavedState<R> {
+    version: u16,
+    party_i: u16,
+    party_t: u16,
+    party_n: u16,
+    confirm_public_key: bool,
+    session_id: Option<[u8; 32]>,
+    #[serde(default)]
+    indexing: Indexing,
+    #[serde(default)]
+    rng_provider: bool,
+    /// Not set in states saved by versions which didn't persist it
+    #[serde(default)]
+    message_size_limit: Option<u64>,
+    #[cfg(feature = "testing")]
+    #[serde(default)]
+    vss_seed: Option<[u8; 32]>,
+    round: R,
+}
+
+#[derive(Serialize)]
+#[serde(bound = "")]
+enum SavedRoundRef<'r, H: Digest + Clone> {
+    Round1(&'r Round1),
+    Round2(&'r Round2),
+    Round3(&'r Round3),
+    Round4(&'r Round4<H>),
+    Round5(&'r Round5),
+}
+
+#[derive(Deserialize)]
+#[serde(bound = "")]
+enum SavedRound<H: Digest + Clone> {
+    Round1(Round1),
+    Round2(Round2),
+    Round3(Round3),
+    Round4(Round4<H>),
+    Round5(Round5),
+}
+
+impl<H: Digest + Clone> Keygen<H> {
+    /// Serializes state of the round keygen is currently at
+    ///
+    /// State is meant to be saved right after [proceeding](round_based::StateMachine::proceed)
+    /// the round: messages received since then are not part of it, so after
+    /// [loading](Self::load_state) the state they must be delivered to the party again. Returns
+    /// [StateError::NotInProgress] if keygen is at round 0 (there's nothing to save yet) or if
+    /// it's already finished.
+    ///
+    /// **State contains party's secrets** (Paillier key, secret shares), the caller must
+    /// encrypt it before putting it at rest. Audit log, transcript, round callbacks,
+    /// [share cipher](Self::with_share_cipher) and [rng provider](Self::with_rng_provider) are
+    /// not saved either. State only records whether keygen still samples secrets from rng
+    /// provider, so it can't be resumed without one.
+    pub fn save_state(&self) -> Result<Vec<u8>, StateError> {
+        let rng_provider = match &self.round {
+            R::Round1(round) => round.uses_rng_provider(),
+            R::Round2(round) => round.uses_rng_provider(),
+            _ => false,
+        };
+        let round = match &self.round {
+            R::Round1(round) => SavedRoundRef::Round1(round),
+            R::Round2(round) => SavedRoundRef::Round2(round),
+            R::Round3(round) => SavedRoundRef::Round3(round),
+            R::Round4(round) => SavedRoundRef::Round4(round),
+            R::Round5(round) => SavedRoundRef::Round5(round),
+            R::Round0(_) | R::Final(_) | R::Gone => {
+                return Err(StateError::NotInProgress {
+                    round: self.current_round(),
+                })
+            }
+        };
+        let state = SavedState {
+            version: STATE_VERSION,
+            party_i: self.party_i,
+            party_t: self.party_t,
+            party_n: self.party_n,
+            confirm_public_key: self.confirm_public_key,
+            session_id: self.session_id,
+            indexing: self.indexing,
+            rng_provider,
+            message_size_limit: Some(self.message_size_limit),
+            #[cfg(feature = "testing")]
+            vss_seed: self.vss_seed,
+            round,
+        };
+        serde_json::to_vec(&state).map_err(StateError::Serialize)
+    }
+
+    /// Resumes keygen from state produced by [save_state](Self::save_state)
+    ///
+    /// Shares are encrypted with [PaillierShareCipher](super::PaillierShareCipher), use
+    /// [load_state_with_share_cipher](Self::load_state_with_share_cipher) to resume keygen
+    /// constructed with a custom one. [Warnings](Self::warnings) are reported by the original
+    /// keygen only. Returns [StateError::RngProviderRequired] if keygen was constructed
+    /// [with rng provider](Self::with_rng_provider) and still samples secrets from it, use
+    /// [load_state_with_rng_provider](Self::load_state_with_rng_provider) to resume it.
+    pub fn load_state(bytes: &[u8]) -> Result<Self, StateError> {
+        let (keygen, rng_provider) = Self::restore_state(bytes)?;
+        if rng_provider {
+            return Err(StateError::RngProviderRequired);
+        }
+        Ok(keygen)
+    }
+
+    /// Resumes keygen from saved state, sampling the rest of party's secrets from `rng_provider`
+    ///
+    /// `rng_provider` must be a provider of the same source keygen was
+    /// [constructed](Self::with_rng_provider) with. It's ignored if keygen already sampled all
+    /// secrets it takes from provider.
+    pub fn load_state_with_rng_provider(
+        bytes: &[u8],
+        rng_provider: Arc<dyn RngProvider>,
+    ) -> Result<Self, StateError> {
+        let (mut keygen, _) = Self::restore_state(bytes)?;
+        match &mut keygen.round {
+            R::Round1(round) => round.set_rng_provider(rng_provider),
+            R::Round2(round) => round.set_rng_provider(rng_provider),
+            _ => (),
+        }
+        Ok(keygen)
+    }
+
+    /// Deserializes saved state, also returns whether keygen needs rng provider
+    fn restore_state(bytes: &[u8]) -> Result<(Self, bool), StateError> {
+        let state: SavedState<SavedRound<H>> =
+            serde_json::from_slice(bytes).map_err(StateError::Deserialize)?;
+        if state.version != STATE_VERSION {
+            return Err(StateError::UnknownVersion {
+                version: state.version,
+            });
+        }
+        let (i, n) = (state.party_i, state.party_n);
+        let (round, current_round) = match state.round {
+            SavedRound::Round1(round) => (R::Round1(round), 1),
+            SavedRound::Round2(round) => (R::Round2(round), 2),
+            SavedRound::Round3(round) => (R::Round3(round), 3),
+            SavedRound::Round4(round) => (R::Round4(round), 4),
+            SavedRound::Round5(round) => (R::Round5(round), 5),
+        };
+        // Messages of rounds that are already proceeded are not expected anymore
+        let expects = |r: u16| current_round <= r;
+        let keygen = Self {
+            round,
+
+            msgs1: expects(1).then(|| Round1::expects_messages(i, n)),
+            msgs2: expects(2).then(|| Round2::expects_messages(i, n)),
+            msgs3: expects(3).then(|| Round3::expects_messages(i, n)),
+            msgs4: expects(4).then(|| Round4::expects_messages(i, n)),
+            msgs5: expects(5).then(|| Round5::expects_messages(i, n)),
+
+            msgs_queue: vec![],
+            received: ReceivedDigests::default(),
+            message_size_limit: state
+                .message_size_limit
+                .unwrap_or_else(|| default_message_size_limit(DEFAULT_PAILLIER_BITS, n)),
+            warnings: vec![],
+            audit_log: None,
+            transcript: None,
+            on_round_complete: None,
+            confirm_public_key: state.confirm_public_key,
+            session_id: state.session_id,
+            indexing: state.indexing,
+            #[cfg(feature = "testing")]
+            vss_seed: state.vss_seed,
+            party_i: i,
+            party_t: state.party_t,
+            party_n: n,
+        };
+        Ok((keygen, state.rng_provider))
+    }
+
+    /// Resumes keygen from saved state, encrypting shares with `share_cipher`
+    ///
+    /// `share_cipher` must be the one keygen was [constructed](Self::with_share_cipher) with.
+    pub fn load_state_with_share_cipher(
+        bytes: &[u8],
+        share_cipher: Arc<dyn ShareCipher>,
+    ) -> Result<Self, StateError> {
+        let mut keygen = Self::load_state(bytes)?;
+        match &mut keygen.round {
+            R::Round1(round) => round.set_share_cipher(share_cipher),
+            R::Round2(round) => round.set_share_cipher(share_cipher),
+            R::Round3(round) => round.set_share_cipher(share_cipher),
+            _ => (),
+        }
+        Ok(keygen)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rand::RngCore;
+    use sha2::{Sha256, Sha512};
+
+    use crate::testing::{deliver_to_receivers, run_in_place};
+
+    use super::*;
+
+    struct OsRngProvider;
+
+    impl RngProvider for OsRngProvider {
+        fn fill_bytes(&self, dest: &mut [u8]) {
+            rand::rngs::OsRng.fill_bytes(dest)
+        }
+    }
+
+    #[test]
+    fn state_saved_with_rng_provider_requires_it() {
+        let rng = Arc::new(OsRngProvider);
+        let mut party = Keygen::with_rng_provider(1, 1, 2, rng.clone()).unwrap();
+        party.proceed().unwrap();
+        let state = party.save_state().unwrap();
+
+        assert!(matches!(
+            Keygen::<Sha256>::load_state(&state),
+            Err(StateError::RngProviderRequired)
+        ));
+        let resumed = Keygen::<Sha256>::load_state_with_rng_provider(&state, rng).unwrap();
+        assert!(matches!(&resumed.round, R::Round1(round) if round.uses_rng_provider()));
+    }
+
+    #[test]
+    fn resumed_keygen_keeps_configuration() {
+        let mut party = Keygen::with_paillier_bits(1, 1, 2, 3072).unwrap();
+        party.set_message_size_limit(1 << 20);
+        party.proceed().unwrap();
+        let resumed = Keygen::<Sha256>::load_state(&party.save_state().unwrap()).unwrap();
+        assert_eq!(resumed.message_size_limit, 1 << 20);
+
+        let mut party = Keygen::<Sha512>::with_dlog_hash(1, 1, 2).unwrap();
+        party.proceed().unwrap();
+        let resumed = Keygen::<Sha512>::load_state(&party.save_state().unwrap()).unwrap();
+        assert_eq!(resumed.current_round(), 1);
+    }
+
+    #[test]
+    fn keygen_resumes_from_state_saved_after_every_round() {
+        let n = 3;
+        let mut parties: Vec<Keygen> = (1..=n).map(|i| Keygen::new(i, 1, n).unwrap()).collect();
+        assert!(matches!(
+            parties[0].save_state(),
+            Err(StateError::NotInProgress { round: 0 })
+        ));
+
+        let mut restarted_at = 0;
+        run_in_place(&mut parties, |parties, msg| {
+            // Party 1 restarts after every round, before receiving messages of the next one
+            let round = parties[0].current_round();
+            if round != restarted_at && !parties[0].is_finished() {
+                restarted_at = round;
+                let state = parties[0].save_state().unwrap();
+                parties[0] = Keygen::load_state(&state).unwrap();
+            }
+            deliver_to_receivers(parties, &msg);
+        });
+
+        let keys: Vec<_> = parties
+            .iter_mut()
+            .map(|p| p.pick_output().unwrap().unwrap())
+            .collect();
+        assert!(keys.iter().all(|k| k.public_key() == keys[0].public_key()));
+    }
+}
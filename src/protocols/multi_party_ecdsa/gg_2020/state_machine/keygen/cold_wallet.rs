@@ -0,0 +1,223 @@
+//! Compact representation of [LocalKey] for cold storage
+
+use curv::elliptic::curves::{secp256_k1::Secp256k1, Curve, Point, Scalar};
+use paillier::EncryptionKey;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use zk_paillier::zkproofs::DLogStatement;
+
+use crate::protocols::multi_party_ecdsa::gg_2018::VerifiableSS;
+use crate::protocols::multi_party_ecdsa::gg_2020::party_i::SharedKeys;
+use crate::protocols::multi_party_ecdsa::gg_2020::state_machine::sign::SigningSubset;
+
+use super::LocalKey;
+use crate::protocols::multi_party_ecdsa::gg_2020::state_machine::quorum::canonicalize_quorum;
+
+/// Minimal self-contained blob that is able to sign with a fixed quorum
+///
+/// Only public data of quorum members is kept, so the blob is noticeably smaller than [LocalKey].
+/// The blob contains secret share and Paillier decryption key, so it must be encrypted before
+/// being put into storage.
+///
+/// Key restored from a cold wallet fits only for signing with the quorum it was exported for, so
+/// it's restored as [SigningSubset] of that quorum, see [LocalKey::from_cold_wallet].
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ColdWalletBlob {
+    pub curve: String,
+    pub paillier_dk: paillier::DecryptionKey,
+    pub x_i: Scalar<Secp256k1>,
+    pub y_sum_s: Point<Secp256k1>,
+    pub vss_scheme: VerifiableSS<Secp256k1>,
+    /// Sorted list of parties' keygen indexes
+    pub quorum: Vec<u16>,
+    /// Public data of quorum members, ordered the same way as `quorum`
+    pub pk_vec: Vec<Point<Secp256k1>>,
+    pub paillier_key_vec: Vec<EncryptionKey>,
+    pub h1_h2_n_tilde_vec: Vec<DLogStatement>,
+    pub i: u16,
+    pub t: u16,
+    pub n: u16,
+}
+
+impl LocalKey<Secp256k1> {
+    /// Exports a [cold wallet](ColdWalletBlob) able to sign with `fixed_quorum`
+    ///
+    /// `fixed_quorum` is a list of parties' keygen indexes, it must contain this party and at
+    /// least `t+1` parties.
+    pub fn to_cold_wallet(&self, fixed_quorum: &[u16]) -> Result<ColdWalletBlob, ColdWalletError> {
+        let quorum = canonicalize_quorum(fixed_quorum);
+        if quorum.len() != fixed_quorum.len() || quorum.iter().any(|&j| j == 0 || j > self.n) {
+            return Err(ColdWalletError::InvalidQuorum);
+        }
+        if quorum.len() <= usize::from(self.t) {
+            return Err(ColdWalletError::QuorumTooSmall);
+        }
+        if quorum.binary_search(&self.i).is_err() {
+            return Err(ColdWalletError::NotQuorumMember);
+        }
+        let pick = |j: &u16| usize::from(*j - 1);
+
+        Ok(ColdWalletBlob {
+            curve: Secp256k1::CURVE_NAME.to_string(),
+            paillier_dk: self.paillier_dk.clone(),
+            x_i: self.keys_linear.x_i.clone(),
+            y_sum_s: self.y_sum_s.clone(),
+            vss_scheme: self.vss_scheme.clone(),
+            pk_vec: quorum
+                .iter()
+                .map(|j| self.pk_vec[pick(j)].clone())
+                .collect(),
+            paillier_key_vec: quorum
+                .iter()
+                .map(|j| self.paillier_key_vec[pick(j)].clone())
+                .collect(),
+            h1_h2_n_tilde_vec: quorum
+                .iter()
+                .map(|j| self.h1_h2_n_tilde_vec[pick(j)].clone())
+                .collect(),
+            quorum,
+            i: self.i,
+            t: self.t,
+            n: self.n,
+        })
+    }
+
+    /// Restores a key from [cold wallet](ColdWalletBlob) as a signing subset of the quorum it was
+    /// exported for
+    ///
+    /// Data of parties outside of the quorum is not available, so the key can't sign with any
+    /// other committee, and it's not handed out as a standalone [LocalKey]. Sign with
+    /// [OfflineStage::from_subset](super::super::sign::OfflineStage::from_subset).
+    pub fn from_cold_wallet(blob: ColdWalletBlob) -> Result<SigningSubset, ColdWalletError> {
+        if blob.curve != Secp256k1::CURVE_NAME {
+            return Err(ColdWalletError::CurveMismatch { curve: blob.curve });
+        }
+        let q = blob.quorum.len();
+        if q == 0
+            || blob.pk_vec.len() != q
+            || blob.paillier_key_vec.len() != q
+            || blob.h1_h2_n_tilde_vec.len() != q
+            || canonicalize_quorum(&blob.quorum) != blob.quorum
+            || blob.quorum.iter().any(|&j| j == 0 || j > blob.n)
+        {
+            return Err(ColdWalletError::InvalidQuorum);
+        }
+        if q <= usize::from(blob.t) {
+            return Err(ColdWalletError::QuorumTooSmall);
+        }
+        if blob.quorum.binary_search(&blob.i).is_err() {
+            return Err(ColdWalletError::NotQuorumMember);
+        }
+
+        let local_key = LocalKey {
+            paillier_dk: blob.paillier_dk,
+            pk_vec: blob.pk_vec,
+            keys_linear: SharedKeys {
+                y: blob.y_sum_s.clone(),
+                x_i: blob.x_i,
+            },
+            paillier_key_vec: blob.paillier_key_vec,
+            y_sum_s: blob.y_sum_s,
+            h1_h2_n_tilde_vec: blob.h1_h2_n_tilde_vec,
+            vss_scheme: blob.vss_scheme,
+            vss_scheme_vec: vec![],
+            party_identities: vec![],
+            recovery_backups: vec![],
+            i: blob.i,
+            t: blob.t,
+            n: blob.n,
+        };
+        // Public data is kept for quorum members only, the same as in subset of a full key
+        SigningSubset::from_committee_key(local_key, blob.quorum)
+            .map_err(|_| ColdWalletError::InvalidQuorum)
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum ColdWalletError {
+    #[error("quorum contains duplicates or parties out of range [1; n]")]
+    InvalidQuorum,
+    #[error("quorum must contain at least t+1 parties")]
+    QuorumTooSmall,
+    #[error("party is not a member of the quorum")]
+    NotQuorumMember,
+    #[error("cold wallet was exported for curve {curve}")]
+    CurveMismatch { curve: String },
+}
+
+#[cfg(test)]
+mod test {
+    use round_based::dev::Simulation;
+
+    use crate::protocols::multi_party_ecdsa::gg_2020::state_machine::keygen::test::simulate_keygen;
+    use crate::protocols::multi_party_ecdsa::gg_2020::state_machine::sign::test::simulate_signing;
+    use crate::protocols::multi_party_ecdsa::gg_2020::state_machine::sign::OfflineStage;
+
+    use super::*;
+
+    #[test]
+    fn cold_wallet_is_smaller_and_signs_with_fixed_quorum() {
+        let keys = simulate_keygen(1, 3);
+        let quorum = [1, 3];
+
+        let mut simulation = Simulation::new();
+        for &i in &quorum {
+            let key = &keys[usize::from(i - 1)];
+            let blob = key.to_cold_wallet(&quorum).unwrap();
+
+            let full_size = serde_json::to_vec(key).unwrap().len();
+            let cold_size = serde_json::to_vec(&blob).unwrap().len();
+            assert!(4 * cold_size < 3 * full_size);
+
+            let subset = LocalKey::from_cold_wallet(blob).unwrap();
+            assert_eq!(subset.committee(), &quorum);
+            simulation.add_party(OfflineStage::from_subset(subset));
+        }
+        let offline_stage = simulation.run().unwrap();
+        simulate_signing(offline_stage, b"ZenGo");
+    }
+
+    #[test]
+    fn cold_wallet_signs_along_with_full_key() {
+        let keys = simulate_keygen(1, 3);
+        let quorum = vec![1, 3];
+
+        let blob = keys[0].to_cold_wallet(&quorum).unwrap();
+        let cold = LocalKey::from_cold_wallet(blob).unwrap();
+        let full = SigningSubset::new(keys[2].clone(), quorum).unwrap();
+        assert_eq!(cold.local_key().pk_vec, full.local_key().pk_vec);
+
+        let mut simulation = Simulation::new();
+        simulation.add_party(OfflineStage::from_subset(cold));
+        simulation.add_party(OfflineStage::from_subset(full));
+        let offline_stage = simulation.run().unwrap();
+        simulate_signing(offline_stage, b"ZenGo");
+    }
+
+    #[test]
+    fn cold_wallet_rejects_invalid_quorum() {
+        let keys = simulate_keygen(1, 3);
+        assert!(matches!(
+            keys[0].to_cold_wallet(&[2, 3]),
+            Err(ColdWalletError::NotQuorumMember)
+        ));
+        assert!(matches!(
+            keys[0].to_cold_wallet(&[1]),
+            Err(ColdWalletError::QuorumTooSmall)
+        ));
+        assert!(matches!(
+            keys[0].to_cold_wallet(&[1, 4]),
+            Err(ColdWalletError::InvalidQuorum)
+        ));
+
+        // Quorum size is checked on import as well
+        let blob = ColdWalletBlob {
+            t: 2,
+            ..keys[0].to_cold_wallet(&[1, 2]).unwrap()
+        };
+        assert!(matches!(
+            LocalKey::from_cold_wallet(blob),
+            Err(ColdWalletError::QuorumTooSmall)
+        ));
+    }
+}
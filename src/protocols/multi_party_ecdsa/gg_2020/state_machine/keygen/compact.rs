@@ -0,0 +1,283 @@
+//! Compact binary representation of [LocalKey] for constrained storage
+
+use std::convert::TryFrom;
+use std::fmt;
+
+use curv::arithmetic::traits::*;
+use curv::elliptic::curves::{secp256_k1::Secp256k1, Point, Scalar};
+use curv::BigInt;
+use paillier::{DecryptionKey, EncryptionKey};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+use zeroize::Zeroize;
+use zk_paillier::zkproofs::DLogStatement;
+
+use crate::protocols::multi_party_ecdsa::gg_2018::VerifiableSS;
+use crate::protocols::multi_party_ecdsa::gg_2020::party_i::{Keys, SharedKeys};
+
+use super::LocalKey;
+
+/// Public data of a keygen, the same for every party of the group
+///
+/// Everything [LocalKey] carries except secrets of the party is either stored here, or derived
+/// from it. Context can be stored once per group, and every party keeps only its
+/// [CompactLocalKey].
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct GroupContext {
+    pub paillier_key_vec: Vec<EncryptionKey>,
+    pub h1_h2_n_tilde_vec: Vec<DLogStatement>,
+    pub vss_scheme_vec: Vec<VerifiableSS<Secp256k1>>,
+    pub party_identities: Vec<Point<Secp256k1>>,
+    pub t: u16,
+    pub n: u16,
+}
+
+impl GroupContext {
+    /// SHA-256 of the context, [compact keys](CompactLocalKey) commit to it
+    pub fn digest(&self) -> [u8; 32] {
+        let mut digest = [0u8; 32];
+        digest.copy_from_slice(&Sha256::digest(
+            &serde_json::to_vec(self).expect("serialize group context"),
+        ));
+        digest
+    }
+}
+
+/// Secrets of a party with commitment to [group context](GroupContext)
+///
+/// Keeps only secret share `x_i` and Paillier prime `p`, the other prime is recovered from
+/// party's Paillier key found in the context. Binary encoding (see
+/// [to_bytes](Self::to_bytes)) takes a few hundreds of bytes regardless of number of parties.
+#[derive(Clone)]
+pub struct CompactLocalKey {
+    pub i: u16,
+    pub context_digest: [u8; 32],
+    x_i: Scalar<Secp256k1>,
+    paillier_p: BigInt,
+}
+
+/// Wipes secret share and Paillier prime
+impl Drop for CompactLocalKey {
+    fn drop(&mut self) {
+        self.x_i = Scalar::zero();
+        self.paillier_p.zeroize();
+    }
+}
+
+/// Omits secret share and Paillier prime, so the key can be written to logs
+impl fmt::Debug for CompactLocalKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("CompactLocalKey")
+            .field("i", &self.i)
+            .field("context_digest", &self.context_digest)
+            .field("x_i", &"<redacted>")
+            .field("paillier_p", &"<redacted>")
+            .finish()
+    }
+}
+
+/// Error of converting [LocalKey] to and from [CompactLocalKey]
+#[derive(Debug, Error)]
+pub enum CompactKeyError {
+    #[error("key doesn't carry vss schemes of all parties")]
+    MissingVssSchemes,
+    #[error("compact key is truncated or malformed")]
+    Malformed,
+    #[error("compact key was created for different group context")]
+    ContextMismatch,
+    #[error("group context is inconsistent with party index {i}")]
+    InconsistentContext { i: u16 },
+    #[error("compact key doesn't match party's public share or Paillier key")]
+    KeyMismatch,
+}
+
+/// Size of binary encoding header: party index, context digest and secret share
+const HEADER_LEN: usize = 2 + 32 + 32;
+
+impl CompactLocalKey {
+    /// Encodes the key as `i || context_digest || x_i || p`
+    ///
+    /// Party index is big-endian `u16`, secret share is 32 bytes big-endian, and the rest of
+    /// the bytes is big-endian Paillier prime `p`. Encoded key contains secrets, so it must be
+    /// encrypted before being put into storage.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(HEADER_LEN + 128);
+        bytes.extend_from_slice(&self.i.to_be_bytes());
+        bytes.extend_from_slice(&self.context_digest);
+        bytes.extend_from_slice(&self.x_i.to_bytes());
+        bytes.extend(self.paillier_p.to_bytes());
+        bytes
+    }
+
+    /// Decodes the key encoded via [to_bytes](Self::to_bytes)
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, CompactKeyError> {
+        if bytes.len() <= HEADER_LEN {
+            return Err(CompactKeyError::Malformed);
+        }
+        let i = u16::from_be_bytes([bytes[0], bytes[1]]);
+        let context_digest =
+            <[u8; 32]>::try_from(&bytes[2..34]).map_err(|_| CompactKeyError::Malformed)?;
+        let x_i =
+            Scalar::from_bytes(&bytes[34..HEADER_LEN]).map_err(|_| CompactKeyError::Malformed)?;
+        Ok(Self {
+            i,
+            context_digest,
+            x_i,
+            paillier_p: BigInt::from_bytes(&bytes[HEADER_LEN..]),
+        })
+    }
+}
+
+impl LocalKey<Secp256k1> {
+    /// Public data of the keygen, see [GroupContext]
+    ///
+    /// Returns [CompactKeyError::MissingVssSchemes] if key was produced by older version of the
+    /// library that doesn't carry VSS schemes of all parties.
+    pub fn group_context(&self) -> Result<GroupContext, CompactKeyError> {
+        if self.vss_scheme_vec.len() != usize::from(self.n) {
+            return Err(CompactKeyError::MissingVssSchemes);
+        }
+        Ok(GroupContext {
+            paillier_key_vec: self.paillier_key_vec.clone(),
+            h1_h2_n_tilde_vec: self.h1_h2_n_tilde_vec.clone(),
+            vss_scheme_vec: self.vss_scheme_vec.clone(),
+            party_identities: self.party_identities.clone(),
+            t: self.t,
+            n: self.n,
+        })
+    }
+
+    /// Drops everything but secrets of the party, use [LocalKey::from_compact] to get the
+    /// key back
+    ///
+    /// Key must carry VSS schemes of all parties, as [group context](Self::group_context) is
+    /// derived from them. [Recovery backups](Self::recovery_backups) are not kept.
+    pub fn to_compact(&self) -> Result<CompactLocalKey, CompactKeyError> {
+        let context = self.group_context()?;
+        Ok(CompactLocalKey {
+            i: self.i,
+            context_digest: context.digest(),
+            x_i: self.keys_linear.x_i.clone(),
+            paillier_p: self.paillier_dk.p.clone(),
+        })
+    }
+
+    /// Rehydrates full key from [compact key](CompactLocalKey) and [group context](GroupContext)
+    ///
+    /// Checks that compact key commits to the context, and that its secrets match party's public
+    /// share and Paillier key.
+    pub fn from_compact(
+        compact: &CompactLocalKey,
+        context: &GroupContext,
+    ) -> Result<Self, CompactKeyError> {
+        if compact.context_digest != context.digest() {
+            return Err(CompactKeyError::ContextMismatch);
+        }
+        let (i, n) = (compact.i, usize::from(context.n));
+        if i == 0
+            || usize::from(i) > n
+            || context.vss_scheme_vec.len() != n
+            || context.paillier_key_vec.len() != n
+            || context.h1_h2_n_tilde_vec.len() != n
+        {
+            return Err(CompactKeyError::InconsistentContext { i });
+        }
+
+        let ek = &context.paillier_key_vec[usize::from(i - 1)];
+        let p = compact.paillier_p.clone();
+        if p <= BigInt::one() || p >= ek.n {
+            return Err(CompactKeyError::KeyMismatch);
+        }
+        let q = &ek.n / &p;
+        if &p * &q != ek.n {
+            return Err(CompactKeyError::KeyMismatch);
+        }
+        let pk_vec = Keys::get_commitments_to_xi(&context.vss_scheme_vec);
+        if Point::generator() * &compact.x_i != pk_vec[usize::from(i - 1)] {
+            return Err(CompactKeyError::KeyMismatch);
+        }
+        let y_sum_s: Point<Secp256k1> = context
+            .vss_scheme_vec
+            .iter()
+            .map(|vss| vss.commitments[0].clone())
+            .sum();
+
+        Ok(LocalKey {
+            paillier_dk: DecryptionKey { p, q },
+            pk_vec,
+            keys_linear: SharedKeys {
+                y: y_sum_s.clone(),
+                x_i: compact.x_i.clone(),
+            },
+            paillier_key_vec: context.paillier_key_vec.clone(),
+            y_sum_s,
+            h1_h2_n_tilde_vec: context.h1_h2_n_tilde_vec.clone(),
+            vss_scheme: context.vss_scheme_vec[usize::from(i - 1)].clone(),
+            vss_scheme_vec: context.vss_scheme_vec.clone(),
+            party_identities: context.party_identities.clone(),
+            recovery_backups: vec![],
+            i,
+            t: context.t,
+            n: context.n,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::protocols::multi_party_ecdsa::gg_2020::state_machine::keygen::test::simulate_keygen;
+    use crate::protocols::multi_party_ecdsa::gg_2020::state_machine::sign::test::{
+        simulate_offline_stage, simulate_signing,
+    };
+
+    use super::*;
+
+    #[test]
+    fn rehydrated_key_signs_for_the_same_public_key() {
+        let keys = simulate_keygen(1, 3);
+        let context = keys[0].group_context().unwrap();
+
+        let rehydrated: Vec<LocalKey<Secp256k1>> = keys
+            .iter()
+            .map(|key| {
+                let bytes = key.to_compact().unwrap().to_bytes();
+                assert!(bytes.len() * 10 < serde_json::to_vec(key).unwrap().len());
+                let compact = CompactLocalKey::from_bytes(&bytes).unwrap();
+                LocalKey::from_compact(&compact, &context).unwrap()
+            })
+            .collect();
+        for (key, rehydrated) in keys.iter().zip(&rehydrated) {
+            assert_eq!(
+                serde_json::to_string(rehydrated).unwrap(),
+                serde_json::to_string(key).unwrap()
+            );
+            rehydrated.verify_paillier_keypair().unwrap();
+        }
+        simulate_signing(simulate_offline_stage(rehydrated, &[1, 3]), b"rehydrated");
+
+        let mut other_context = context.clone();
+        other_context.t = 2;
+        let compact = keys[0].to_compact().unwrap();
+        assert!(matches!(
+            LocalKey::from_compact(&compact, &other_context),
+            Err(CompactKeyError::ContextMismatch)
+        ));
+    }
+
+    #[test]
+    fn debug_of_compact_key_has_no_secrets() {
+        let keys = simulate_keygen(1, 2);
+        let compact = keys[0].to_compact().unwrap();
+        let debug = format!("{:?}", compact);
+        assert!(debug.contains("<redacted>"));
+        for secret in [
+            keys[0].keys_linear.x_i.to_bigint().to_string(),
+            keys[0].paillier_dk.p.to_string(),
+        ]
+        .iter()
+        {
+            assert!(!debug.contains(secret.as_str()));
+        }
+    }
+}
@@ -0,0 +1,128 @@
+//! Compact in-memory representation of [LocalKey]
+
+use curv::elliptic::curves::{secp256_k1::Secp256k1, Point, Scalar};
+use paillier::EncryptionKey;
+use serde::{Deserialize, Serialize};
+use zk_paillier::zkproofs::DLogStatement;
+
+use crate::protocols::multi_party_ecdsa::gg_2018::VerifiableSS;
+use crate::protocols::multi_party_ecdsa::gg_2020::party_i::{Keys, SharedKeys};
+
+use super::LocalKey;
+
+/// [LocalKey] without fields that can be recomputed from VSS schemes
+///
+/// Public key, public shares `pk_vec`, and own VSS scheme are derived from VSS schemes of all
+/// parties. Keys produced by older versions of library don't carry VSS schemes of all parties,
+/// such keys are stored as is.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct CompressedLocalKey {
+    paillier_dk: paillier::DecryptionKey,
+    x_i: Scalar<Secp256k1>,
+    paillier_key_vec: Vec<EncryptionKey>,
+    h1_h2_n_tilde_vec: Vec<DLogStatement>,
+    vss_scheme_vec: Vec<VerifiableSS<Secp256k1>>,
+    party_identities: Vec<Point<Secp256k1>>,
+    #[serde(default)]
+    recovery_backups: Vec<Vec<Vec<u8>>>,
+    /// `(pk_vec, y_sum_s, vss_scheme)` of a key that doesn't carry VSS schemes of all parties
+    legacy: Option<(
+        Vec<Point<Secp256k1>>,
+        Point<Secp256k1>,
+        VerifiableSS<Secp256k1>,
+    )>,
+    i: u16,
+    t: u16,
+    n: u16,
+}
+
+impl LocalKey<Secp256k1> {
+    /// Drops fields that can be recomputed, use [CompressedLocalKey::expand] to get the key back
+    pub fn compress(&self) -> CompressedLocalKey {
+        let legacy = if self.vss_scheme_vec.is_empty() {
+            Some((
+                self.pk_vec.clone(),
+                self.y_sum_s.clone(),
+                self.vss_scheme.clone(),
+            ))
+        } else {
+            None
+        };
+        CompressedLocalKey {
+            paillier_dk: self.paillier_dk.clone(),
+            x_i: self.keys_linear.x_i.clone(),
+            paillier_key_vec: self.paillier_key_vec.clone(),
+            h1_h2_n_tilde_vec: self.h1_h2_n_tilde_vec.clone(),
+            vss_scheme_vec: self.vss_scheme_vec.clone(),
+            party_identities: self.party_identities.clone(),
+            recovery_backups: self.recovery_backups.clone(),
+            legacy,
+            i: self.i,
+            t: self.t,
+            n: self.n,
+        }
+    }
+}
+
+impl CompressedLocalKey {
+    /// Recomputes omitted fields and returns the full key
+    pub fn expand(self) -> LocalKey<Secp256k1> {
+        let (pk_vec, y_sum_s, vss_scheme) = match self.legacy {
+            Some(legacy) => legacy,
+            None => (
+                Keys::get_commitments_to_xi(&self.vss_scheme_vec),
+                self.vss_scheme_vec
+                    .iter()
+                    .map(|vss| vss.commitments[0].clone())
+                    .sum(),
+                self.vss_scheme_vec[usize::from(self.i - 1)].clone(),
+            ),
+        };
+        LocalKey {
+            paillier_dk: self.paillier_dk,
+            pk_vec,
+            keys_linear: SharedKeys {
+                y: y_sum_s.clone(),
+                x_i: self.x_i,
+            },
+            paillier_key_vec: self.paillier_key_vec,
+            y_sum_s,
+            h1_h2_n_tilde_vec: self.h1_h2_n_tilde_vec,
+            vss_scheme,
+            vss_scheme_vec: self.vss_scheme_vec,
+            party_identities: self.party_identities,
+            recovery_backups: self.recovery_backups,
+            i: self.i,
+            t: self.t,
+            n: self.n,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::protocols::multi_party_ecdsa::gg_2020::state_machine::keygen::test::simulate_keygen;
+
+    #[test]
+    fn compressed_key_expands_to_original() {
+        let keys = simulate_keygen(1, 3);
+        for key in keys {
+            let mut legacy = key.clone();
+            legacy.vss_scheme_vec.clear();
+
+            let compressed = key.compress();
+            assert!(
+                serde_json::to_vec(&compressed).unwrap().len()
+                    < serde_json::to_vec(&key).unwrap().len()
+            );
+            assert_eq!(
+                serde_json::to_string(&compressed.expand()).unwrap(),
+                serde_json::to_string(&key).unwrap()
+            );
+            assert_eq!(
+                serde_json::to_string(&legacy.compress().expand()).unwrap(),
+                serde_json::to_string(&legacy).unwrap()
+            );
+        }
+    }
+}
@@ -0,0 +1,106 @@
+//! Tolerating resends of the same message over at-least-once transports
+
+use std::collections::BTreeMap;
+
+use round_based::Msg;
+use sha2::{Digest, Sha256};
+
+use crate::MessageRoundID;
+
+use super::{Error, ProtocolMessage};
+
+/// Digests of messages accepted by keygen, one per sender and round
+///
+/// Resend of accepted message is recognized by the same digest and ignored, while a different
+/// message from the same sender at the same round is reported as
+/// [ConflictingMessage](Error::ConflictingMessage).
+#[derive(Default)]
+pub(super) struct ReceivedDigests {
+    digests: BTreeMap<(u16, u16), [u8; 32]>,
+}
+
+/// Outcome of [ReceivedDigests::check]
+pub(super) enum Received {
+    /// Message wasn't received before, `digest` must be [recorded](ReceivedDigests::record) once
+    /// the message is accepted
+    New { digest: [u8; 32] },
+    /// The same message was already accepted
+    Resend,
+}
+
+impl ReceivedDigests {
+    /// Checks whether `msg` was already received from its sender
+    pub fn check<H: Digest + Clone>(
+        &self,
+        msg: &Msg<ProtocolMessage<H>>,
+    ) -> Result<Received, Error> {
+        let msg_round = msg.body.round_id();
+        let digest = digest(&msg.body);
+        match self.digests.get(&(msg_round, msg.sender)) {
+            None => Ok(Received::New { digest }),
+            Some(accepted) if *accepted == digest => Ok(Received::Resend),
+            Some(_) => Err(Error::ConflictingMessage {
+                sender: msg.sender,
+                msg_round,
+            }),
+        }
+    }
+
+    /// Records digest of accepted message of `sender` at `msg_round`
+    pub fn record(&mut self, msg_round: u16, sender: u16, digest: [u8; 32]) {
+        self.digests.insert((msg_round, sender), digest);
+    }
+}
+
+fn digest<H: Digest + Clone>(body: &ProtocolMessage<H>) -> [u8; 32] {
+    let bytes = serde_json::to_vec(body).expect("serialization into Vec<u8> doesn't fail");
+    Sha256::digest(&bytes).into()
+}
+
+#[cfg(test)]
+mod test {
+    use round_based::StateMachine;
+
+    use crate::testing::{deliver_to_receivers, run_in_place};
+
+    use super::super::Keygen;
+    use super::*;
+
+    #[test]
+    fn resent_message_is_ignored_and_conflicting_one_is_rejected() {
+        let n = 3;
+        let mut parties: Vec<Keygen> = (1..=n).map(|i| Keygen::new(i, 1, n).unwrap()).collect();
+        let mut duplicated = false;
+        run_in_place(&mut parties, |parties, msg| {
+            // Round 1 broadcast of party 2 is delivered twice
+            if msg.sender == 2 && msg.body.round_id() == 1 {
+                duplicated = true;
+                deliver_to_receivers(parties, &msg);
+            }
+            deliver_to_receivers(parties, &msg);
+        });
+        assert!(duplicated);
+        let keys: Vec<_> = parties
+            .iter_mut()
+            .map(|p| p.pick_output().unwrap().unwrap())
+            .collect();
+        assert!(keys.iter().all(|k| k.public_key() == keys[0].public_key()));
+
+        // Party 2 sends different round 1 broadcast to party 1
+        let mut party1 = Keygen::new(1, 1, 2).unwrap();
+        let mut party2 = Keygen::new(2, 1, 2).unwrap();
+        let mut other_party2 = Keygen::new(2, 1, 2).unwrap();
+        party2.proceed().unwrap();
+        other_party2.proceed().unwrap();
+        party1
+            .handle_incoming(party2.message_queue().pop().unwrap())
+            .unwrap();
+        assert!(matches!(
+            party1.handle_incoming(other_party2.message_queue().pop().unwrap()),
+            Err(Error::ConflictingMessage {
+                sender: 2,
+                msg_round: 1
+            })
+        ));
+    }
+}
@@ -0,0 +1,139 @@
+//! BIP32 non-hardened derivation of child keys
+
+use curv::arithmetic::traits::*;
+use curv::elliptic::curves::{secp256_k1::Secp256k1, Point, Scalar};
+use curv::BigInt;
+use hmac::{Hmac, Mac, NewMac};
+use sha2::Sha512;
+
+use super::LocalKey;
+
+type HmacSha512 = Hmac<Sha512>;
+
+/// Indexes starting from `2^31` denote hardened derivation
+const HARDENED_INDEX: u32 = 1 << 31;
+
+impl LocalKey<Secp256k1> {
+    /// Derives non-hardened child key at `index` following BIP32 public derivation (`CKDpub`)
+    ///
+    /// Tweak `IL` and child chain code `IR` are obtained as `HMAC-SHA512(chain_code, serP(y) || ser32(index))`.
+    /// Every party adds the same tweak to its share, and `tweak * G` is added to public key and
+    /// public shares. Derivation doesn't require any interaction, every party gets the same child
+    /// public key and chain code. Returns child key and child chain code.
+    ///
+    /// Child key doesn't carry [recovery backups](Self::recovery_backups) as they back up shares
+    /// of the parent key.
+    ///
+    /// ## Panics
+    /// Panics if `index` denotes hardened derivation (`index >= 2^31`), as it requires the
+    /// secret key, or if the index produces an invalid child key (probability is lower than
+    /// `2^-127`, BIP32 suggests to proceed with the next index).
+    pub fn derive_child(
+        &self,
+        index: u32,
+        chain_code: &[u8; 32],
+    ) -> (LocalKey<Secp256k1>, [u8; 32]) {
+        assert!(
+            index < HARDENED_INDEX,
+            "hardened derivation is not supported"
+        );
+        let mut mac = HmacSha512::new_varkey(chain_code).expect("HMAC takes key of any size");
+        mac.update(&self.y_sum_s.to_bytes(true));
+        mac.update(&index.to_be_bytes());
+        let i = mac.finalize().into_bytes();
+        let (il, ir) = i.split_at(32);
+
+        let il = BigInt::from_bytes(il);
+        assert!(
+            &il < Scalar::<Secp256k1>::group_order(),
+            "child key is invalid, proceed with the next index"
+        );
+        let tweak = Scalar::<Secp256k1>::from_bigint(&il);
+        let tweak_g = Point::generator() * &tweak;
+
+        let mut child = self.clone();
+        child.y_sum_s = &self.y_sum_s + &tweak_g;
+        assert!(
+            !child.y_sum_s.is_zero(),
+            "child key is invalid, proceed with the next index"
+        );
+        child.keys_linear.y = child.y_sum_s.clone();
+        child.keys_linear.x_i = &self.keys_linear.x_i + &tweak;
+        for pk in &mut child.pk_vec {
+            *pk = &*pk + &tweak_g;
+        }
+        // Tweak is attributed to the sharing of party 1, so aggregate commitments stay consistent
+        if child.i == 1 {
+            child.vss_scheme.commitments[0] = &self.vss_scheme.commitments[0] + &tweak_g;
+        }
+        if let Some(vss) = child.vss_scheme_vec.first_mut() {
+            vss.commitments[0] = &vss.commitments[0] + &tweak_g;
+        }
+        child.recovery_backups = vec![];
+
+        let mut child_chain_code = [0u8; 32];
+        child_chain_code.copy_from_slice(ir);
+        (child, child_chain_code)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::protocols::multi_party_ecdsa::gg_2020::state_machine::keygen::test::simulate_keygen;
+    use crate::protocols::multi_party_ecdsa::gg_2020::state_machine::sign::test::{
+        simulate_offline_stage, simulate_signing,
+    };
+
+    use super::*;
+
+    #[test]
+    fn child_key_signs_for_derived_public_key() {
+        let keys = simulate_keygen(1, 3);
+        let chain_code = [42u8; 32];
+
+        let children: Vec<_> = keys
+            .iter()
+            .map(|key| key.derive_child(7, &chain_code))
+            .collect();
+        let (child, child_chain_code) = &children[0];
+        assert_ne!(child.public_key(), keys[0].public_key());
+        assert!(children
+            .iter()
+            .all(|(c, cc)| c.public_key() == child.public_key() && cc == child_chain_code));
+        assert_eq!(
+            child.aggregate_commitments()[0],
+            child.public_key(),
+            "aggregate commitments must commit to child key"
+        );
+        assert_ne!(keys[0].derive_child(8, &chain_code).1, *child_chain_code);
+
+        let child_keys = children.into_iter().map(|(c, _)| c).collect();
+        simulate_signing(simulate_offline_stage(child_keys, &[1, 3]), b"child");
+    }
+
+    #[test]
+    fn derivation_matches_bip32_test_vector() {
+        // BIP32 test vector 1: derivation of m/0H/1 from extended public key of m/0H
+        let parent_public_key =
+            hex::decode("035a784662a4a20a65bf6aab9ae98a6c068a81c52e4b032c0fb5400c706cfccc56")
+                .unwrap();
+        let mut parent_chain_code = [0u8; 32];
+        parent_chain_code.copy_from_slice(
+            &hex::decode("47fdacbd0f1097043b78c63c20c34ef4ed9a111d980047ad16282c7ae6236141")
+                .unwrap(),
+        );
+        let child_public_key =
+            hex::decode("03501e454bf00751f24b1b489aa925215d66af2234e3891c3b21a52bedb3cd711c")
+                .unwrap();
+        let child_chain_code =
+            hex::decode("2a7857631386ba23dacac34180dd1983734e444fdbf774041578e9b6adb37c19")
+                .unwrap();
+
+        // Derivation depends only on the public key, other data of the key is irrelevant
+        let mut key = simulate_keygen(1, 2).remove(0);
+        key.y_sum_s = Point::from_bytes(&parent_public_key).unwrap();
+        let (child, chain_code) = key.derive_child(1, &parent_chain_code);
+        assert_eq!(child.y_sum_s.to_bytes(true).to_vec(), child_public_key);
+        assert_eq!(chain_code.to_vec(), child_chain_code);
+    }
+}
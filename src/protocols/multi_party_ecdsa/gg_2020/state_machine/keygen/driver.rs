@@ -0,0 +1,160 @@
+//! Async driver running keygen over tokio channels
+
+use curv::elliptic::curves::secp256_k1::Secp256k1;
+use round_based::{IsCritical, Msg, StateMachine};
+use thiserror::Error;
+use tokio::sync::mpsc;
+
+use super::{Error, Keygen, LocalKey, ProtocolMessage};
+
+/// Error of [running keygen](run_keygen) over channels
+#[derive(Debug, Error)]
+pub enum DriverError {
+    #[error("keygen failed: {0}")]
+    Keygen(#[source] Error),
+    #[error("outgoing channel is closed")]
+    OutgoingClosed,
+    #[error("incoming channel is closed before keygen completed")]
+    IncomingClosed,
+}
+
+/// Runs `t`-out-of-`n` keygen as party `party_i`, exchanging messages via tokio channels
+///
+/// Every message the party sends is pushed to `tx`, regardless of whether it's broadcast or p2p,
+/// so the transport must route it by [Msg::receiver]. `rx` must deliver messages addressed to
+/// the party (including broadcast ones), in any order. See [drive_keygen] for running
+/// [Keygen] constructed in other ways than [Keygen::new].
+pub async fn run_keygen(
+    party_i: u16,
+    t: u16,
+    n: u16,
+    tx: mpsc::Sender<Msg<ProtocolMessage>>,
+    rx: mpsc::Receiver<Msg<ProtocolMessage>>,
+) -> Result<LocalKey<Secp256k1>, DriverError> {
+    let party = Keygen::new(party_i, t, n).map_err(DriverError::Keygen)?;
+    drive_keygen(party, tx, rx).await
+}
+
+/// Drives `party` to completion, exchanging messages via tokio channels
+///
+/// Messages of future rounds are kept until the party reaches their round, so the transport
+/// doesn't need to preserve the order. Non-critical errors of handling incoming message (e.g.
+/// a duplicate or a message of another session) are logged and the message is dropped.
+///
+/// Rounds computation is performed in the calling task, and some rounds take a few seconds, so
+/// consider running the driver in a dedicated task.
+pub async fn drive_keygen(
+    mut party: Keygen,
+    tx: mpsc::Sender<Msg<ProtocolMessage>>,
+    mut rx: mpsc::Receiver<Msg<ProtocolMessage>>,
+) -> Result<LocalKey<Secp256k1>, DriverError> {
+    let mut postponed: Vec<Msg<ProtocolMessage>> = vec![];
+    loop {
+        if party.wants_to_proceed() {
+            let round = party.current_round();
+            party.proceed().map_err(DriverError::Keygen)?;
+            if party.current_round() != round {
+                for msg in std::mem::take(&mut postponed) {
+                    handle_incoming(&mut party, msg, &mut postponed)?;
+                }
+            }
+        }
+
+        let outgoing: Vec<_> = party.message_queue().drain(..).collect();
+        for msg in outgoing {
+            tx.send(msg)
+                .await
+                .map_err(|_| DriverError::OutgoingClosed)?;
+        }
+
+        if party.is_finished() {
+            return party
+                .pick_output()
+                .expect("finished party must have output")
+                .map_err(DriverError::Keygen);
+        }
+
+        if !party.wants_to_proceed() {
+            let msg = rx.recv().await.ok_or(DriverError::IncomingClosed)?;
+            handle_incoming(&mut party, msg, &mut postponed)?;
+        }
+    }
+}
+
+fn handle_incoming(
+    party: &mut Keygen,
+    msg: Msg<ProtocolMessage>,
+    postponed: &mut Vec<Msg<ProtocolMessage>>,
+) -> Result<(), DriverError> {
+    let sender = msg.sender;
+    match party.handle_incoming(msg.clone()) {
+        Ok(()) => Ok(()),
+        Err(Error::ReceivedOutOfOrderMessage {
+            current_round,
+            msg_round,
+        }) if msg_round > current_round => {
+            postponed.push(msg);
+            Ok(())
+        }
+        Err(err) if !err.is_critical() => {
+            log::warn!("dropped message from party {}: {}", sender, err);
+            Ok(())
+        }
+        Err(err) => Err(DriverError::Keygen(err)),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use futures::future::join_all;
+
+    use super::*;
+
+    /// Routes messages between parties, delivering round 1 broadcast of party 1 to party 3 only
+    /// after round 2 broadcast of party 2
+    async fn route(
+        mut outgoing: mpsc::Receiver<Msg<ProtocolMessage>>,
+        inboxes: Vec<mpsc::Sender<Msg<ProtocolMessage>>>,
+    ) {
+        let mut held = vec![];
+        while let Some(msg) = outgoing.recv().await {
+            let receivers: Vec<u16> = match msg.receiver {
+                Some(j) => vec![j],
+                None => (1..=inboxes.len() as u16)
+                    .filter(|j| *j != msg.sender)
+                    .collect(),
+            };
+            for j in receivers {
+                if msg.round == 1 && msg.sender == 1 && j == 3 {
+                    held.push(msg.clone());
+                    continue;
+                }
+                // Receiver might have already finished the protocol and dropped its channel
+                let _ = inboxes[usize::from(j - 1)].send(msg.clone()).await;
+                if msg.round == 2 && msg.sender == 2 && j == 3 {
+                    for held in held.drain(..) {
+                        let _ = inboxes[2].send(held).await;
+                    }
+                }
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn keygen_completes_when_messages_arrive_out_of_order() {
+        let n = 3;
+        let (tx, outgoing) = mpsc::channel(64);
+        let (inboxes, receivers): (Vec<_>, Vec<_>) = (0..n).map(|_| mpsc::channel(64)).unzip();
+
+        let parties = join_all(
+            (1..=n)
+                .zip(receivers)
+                .map(|(i, rx)| run_keygen(i, 1, n, tx.clone(), rx)),
+        );
+        drop(tx);
+        let (keys, ()) = tokio::join!(parties, route(outgoing, inboxes));
+
+        let keys: Vec<_> = keys.into_iter().map(Result::unwrap).collect();
+        assert!(keys.iter().all(|k| k.public_key() == keys[0].public_key()));
+    }
+}
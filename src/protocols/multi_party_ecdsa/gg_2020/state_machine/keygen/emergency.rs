@@ -0,0 +1,139 @@
+//! Hook for emergency signing authorized by a break-glass key, available only with `ed25519`
+//! feature
+
+use std::convert::TryFrom;
+
+use curv::elliptic::curves::secp256_k1::Secp256k1;
+use ed25519_dalek::{Keypair, PublicKey, Signature, Signer};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+use crate::protocols::multi_party_ecdsa::gg_2020::state_machine::quorum::canonicalize_quorum;
+
+use super::LocalKey;
+
+/// Break-glass authorization of signing with a specific (reduced) set of signers
+///
+/// It's an Ed25519 signature of a break-glass key over group key fingerprint and the set of
+/// signers. The proof doesn't carry the break-glass public key: verifier supplies the key trusted
+/// by the deployment, see [LocalKey::emergency_quorum_check].
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct OverrideProof {
+    pub signature: Vec<u8>,
+}
+
+impl OverrideProof {
+    /// Authorizes `signers` (parties' keygen indexes) to sign with key of given `fingerprint`
+    pub fn authorize(break_glass_key: &Keypair, fingerprint: &[u8; 32], signers: &[u16]) -> Self {
+        let signature = break_glass_key.sign(&override_message(fingerprint, signers));
+        Self {
+            signature: signature.to_bytes().to_vec(),
+        }
+    }
+}
+
+impl LocalKey<Secp256k1> {
+    /// Checks that emergency signing with `signers` (parties' keygen indexes) is authorized by
+    /// `override_proof` signed with `break_glass_key`
+    ///
+    /// `break_glass_key` must be the break-glass public key trusted by the deployment, obtained
+    /// independently of the proof. Override only relaxes operational policy: signers still must
+    /// be at least `t+1` distinct parties, as it's the minimum required by the protocol.
+    pub fn emergency_quorum_check(
+        &self,
+        signers: &[u16],
+        break_glass_key: &PublicKey,
+        override_proof: &OverrideProof,
+    ) -> Result<(), EmergencyError> {
+        let quorum = canonicalize_quorum(signers);
+        if quorum.len() != signers.len() || quorum.iter().any(|&j| j == 0 || j > self.n) {
+            return Err(EmergencyError::InvalidSigners);
+        }
+        if quorum.len() <= usize::from(self.t) {
+            return Err(EmergencyError::BelowThreshold {
+                signers: quorum.len(),
+                min_signers: usize::from(self.t) + 1,
+            });
+        }
+
+        let signature = Signature::try_from(&override_proof.signature[..])
+            .map_err(|_| EmergencyError::InvalidOverride)?;
+        break_glass_key
+            .verify_strict(&override_message(&self.fingerprint(), &quorum), &signature)
+            .map_err(|_| EmergencyError::InvalidOverride)
+    }
+}
+
+fn override_message(fingerprint: &[u8; 32], signers: &[u16]) -> Vec<u8> {
+    let mut hasher = Sha256::new()
+        .chain(b"multi-party-ecdsa/emergency-override")
+        .chain(fingerprint);
+    for signer in canonicalize_quorum(signers) {
+        hasher.update(signer.to_be_bytes());
+    }
+    hasher.finalize().to_vec()
+}
+
+#[derive(Debug, Error)]
+pub enum EmergencyError {
+    #[error("signers list contains duplicates or parties out of range [1; n]")]
+    InvalidSigners,
+    #[error("{signers} signers is less than protocol minimum of {min_signers}")]
+    BelowThreshold { signers: usize, min_signers: usize },
+    #[error("override proof doesn't authorize given signers")]
+    InvalidOverride,
+}
+
+#[cfg(test)]
+mod test {
+    use ed25519_dalek::SecretKey;
+
+    use crate::protocols::multi_party_ecdsa::gg_2020::state_machine::keygen::test::simulate_keygen;
+
+    use super::*;
+
+    fn break_glass_key(seed: u8) -> Keypair {
+        let secret = SecretKey::from_bytes(&[seed; 32]).unwrap();
+        let public = PublicKey::from(&secret);
+        Keypair { secret, public }
+    }
+
+    #[test]
+    fn override_authorizes_exact_signers_set() {
+        let keys = simulate_keygen(1, 4);
+        let break_glass = break_glass_key(1);
+        let trusted = break_glass.public;
+
+        let proof = OverrideProof::authorize(&break_glass, &keys[0].fingerprint(), &[3, 1]);
+        keys[0]
+            .emergency_quorum_check(&[1, 3], &trusted, &proof)
+            .unwrap();
+        keys[2]
+            .emergency_quorum_check(&[3, 1], &trusted, &proof)
+            .unwrap();
+
+        assert!(matches!(
+            keys[0].emergency_quorum_check(&[1, 2], &trusted, &proof),
+            Err(EmergencyError::InvalidOverride)
+        ));
+        // Self-signed override doesn't pass for the trusted key
+        let untrusted = break_glass_key(2);
+        let forged = OverrideProof::authorize(&untrusted, &keys[0].fingerprint(), &[1, 3]);
+        assert!(matches!(
+            keys[0].emergency_quorum_check(&[1, 3], &trusted, &forged),
+            Err(EmergencyError::InvalidOverride)
+        ));
+        let malformed = OverrideProof { signature: vec![] };
+        assert!(matches!(
+            keys[0].emergency_quorum_check(&[1, 3], &trusted, &malformed),
+            Err(EmergencyError::InvalidOverride)
+        ));
+
+        let proof = OverrideProof::authorize(&break_glass, &keys[0].fingerprint(), &[1]);
+        assert!(matches!(
+            keys[0].emergency_quorum_check(&[1], &trusted, &proof),
+            Err(EmergencyError::BelowThreshold { .. })
+        ));
+    }
+}
@@ -0,0 +1,199 @@
+//! Self-describing envelope of keygen messages for generic transports
+
+use std::convert::TryFrom;
+
+use round_based::Msg;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::MessageRoundID;
+
+use super::parse::validate_message;
+use super::{session_tag, KeygenMessage, ParseError, ProtocolMessage};
+
+/// Kind of keygen message, determines round it belongs to
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum KeygenMessageType {
+    /// Round 1: commitment, Paillier key and `(N_tilde, h1, h2)`
+    Commitment,
+    /// Round 2: decommitment of party's public share
+    Decommitment,
+    /// Round 3: VSS scheme and share dealt to the receiver
+    Share,
+    /// Round 4: DLog proof of party's secret share
+    DLogProof,
+    /// Optional round 5: [public key confirmation](super::Keygen::with_public_key_confirmation)
+    PublicKeyConfirmation,
+}
+
+impl KeygenMessageType {
+    /// Type of messages sent at keygen `round`
+    pub fn of_round(round: u16) -> Option<Self> {
+        match round {
+            1 => Some(Self::Commitment),
+            2 => Some(Self::Decommitment),
+            3 => Some(Self::Share),
+            4 => Some(Self::DLogProof),
+            5 => Some(Self::PublicKeyConfirmation),
+            _ => None,
+        }
+    }
+
+    /// Round the message type belongs to
+    pub fn round(self) -> u16 {
+        match self {
+            Self::Commitment => 1,
+            Self::Decommitment => 2,
+            Self::Share => 3,
+            Self::DLogProof => 4,
+            Self::PublicKeyConfirmation => 5,
+        }
+    }
+}
+
+/// Keygen message with routing metadata, serialized with `"type"` discriminator
+///
+/// Relay can route the envelope by `session_id`, `sender` and `receiver` (`None` for broadcast)
+/// without understanding the body, and receiver can dispatch it to the round given by
+/// `type`. Convert envelope back via `TryFrom`: into [Msg] to be handled by
+/// [Keygen](super::Keygen), or into [KeygenMessage] to inspect validated contents.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct KeygenEnvelope {
+    #[serde(rename = "type")]
+    pub kind: KeygenMessageType,
+    /// Keygen session the message belongs to, see
+    /// [Keygen::with_session_id](super::Keygen::with_session_id)
+    pub session_id: Option<[u8; 32]>,
+    pub round: u16,
+    pub sender: u16,
+    pub receiver: Option<u16>,
+    pub body: ProtocolMessage,
+}
+
+/// Envelope metadata doesn't match its body, see [KeygenEnvelope]
+#[derive(Debug, Error)]
+pub enum EnvelopeError {
+    #[error("envelope of type {kind:?} at round {round} carries message of round {body_round}")]
+    RoundMismatch {
+        kind: KeygenMessageType,
+        round: u16,
+        body_round: u16,
+    },
+    #[error("message of party {sender} is not bound to the envelope session")]
+    SessionMismatch { sender: u16 },
+    #[error("message is invalid: {0}")]
+    InvalidMessage(#[source] ParseError),
+}
+
+impl KeygenEnvelope {
+    /// Wraps message sent by [Keygen](super::Keygen) of session `session_id`
+    ///
+    /// `session_id` must be the one keygen was [constructed](super::Keygen::with_session_id)
+    /// with, or `None` if keygen isn't bound to a session.
+    pub fn new(msg: Msg<ProtocolMessage>, session_id: Option<[u8; 32]>) -> Self {
+        let round = msg.body.round_id();
+        Self {
+            kind: KeygenMessageType::of_round(round).expect("keygen message of unknown round"),
+            session_id,
+            round,
+            sender: msg.sender,
+            receiver: msg.receiver,
+            body: msg.body,
+        }
+    }
+
+    /// Checks that `type`, `round` and `session_id` are consistent with the body
+    fn validate(&self) -> Result<(), EnvelopeError> {
+        let body_round = self.body.round_id();
+        if self.kind.round() != self.round || self.round != body_round {
+            return Err(EnvelopeError::RoundMismatch {
+                kind: self.kind,
+                round: self.round,
+                body_round,
+            });
+        }
+        let expected_tag = self
+            .session_id
+            .map(|id| session_tag(&id, self.round, self.sender));
+        if self.body.1 != expected_tag {
+            return Err(EnvelopeError::SessionMismatch {
+                sender: self.sender,
+            });
+        }
+        Ok(())
+    }
+}
+
+impl TryFrom<KeygenEnvelope> for Msg<ProtocolMessage> {
+    type Error = EnvelopeError;
+
+    fn try_from(envelope: KeygenEnvelope) -> Result<Self, Self::Error> {
+        envelope.validate()?;
+        Ok(Msg {
+            round: envelope.round,
+            sender: envelope.sender,
+            receiver: envelope.receiver,
+            body: envelope.body,
+        })
+    }
+}
+
+/// Performs the same checks as [parse_keygen_message](super::parse_keygen_message) in addition
+/// to checking envelope metadata
+impl TryFrom<KeygenEnvelope> for KeygenMessage {
+    type Error = EnvelopeError;
+
+    fn try_from(envelope: KeygenEnvelope) -> Result<Self, Self::Error> {
+        envelope.validate()?;
+        validate_message(envelope.body).map_err(EnvelopeError::InvalidMessage)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use round_based::StateMachine;
+
+    use super::super::Keygen;
+    use super::*;
+
+    #[test]
+    fn envelope_roundtrips_and_rejects_mislabeled_messages() {
+        let session_id = [7; 32];
+        let mut party1 = Keygen::with_session_id(1, 1, 2, session_id).unwrap();
+        let mut party2 = Keygen::with_session_id(2, 1, 2, session_id).unwrap();
+        party1.proceed().unwrap();
+        let msg = party1.message_queue().pop().unwrap();
+
+        let envelope = KeygenEnvelope::new(msg, Some(session_id));
+        let json: serde_json::Value = serde_json::to_value(&envelope).unwrap();
+        assert_eq!(json["type"], "commitment");
+
+        let received: KeygenEnvelope = serde_json::from_value(json).unwrap();
+        assert!(matches!(
+            KeygenMessage::try_from(received.clone()),
+            Ok(KeygenMessage::Round1 { .. })
+        ));
+        party2
+            .handle_incoming(Msg::try_from(received.clone()).unwrap())
+            .unwrap();
+
+        let mislabeled = KeygenEnvelope {
+            kind: KeygenMessageType::Share,
+            round: 3,
+            ..received.clone()
+        };
+        assert!(matches!(
+            Msg::<ProtocolMessage>::try_from(mislabeled),
+            Err(EnvelopeError::RoundMismatch { body_round: 1, .. })
+        ));
+        let other_session = KeygenEnvelope {
+            session_id: Some([8; 32]),
+            ..received
+        };
+        assert!(matches!(
+            Msg::<ProtocolMessage>::try_from(other_session),
+            Err(EnvelopeError::SessionMismatch { sender: 1 })
+        ));
+    }
+}
@@ -0,0 +1,173 @@
+//! Proof that two [LocalKey]s hold the same secret share
+
+use curv::cryptographic_primitives::hashing::{Digest, DigestExt};
+use curv::elliptic::curves::{secp256_k1::Secp256k1, Point, Scalar};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use thiserror::Error;
+
+use super::LocalKey;
+
+/// Domain separation of the Fiat-Shamir challenge
+const EQUALITY_PROOF_CONTEXT: &[u8] = b"multi-party-ecdsa/equality-proof/v1";
+
+/// Zero-knowledge proof that a key holds secret `x_i` behind public share `pk_vec[party-1]` of
+/// a known key
+///
+/// Schnorr proof of knowledge of discrete log of the public share, with party index, public key
+/// and the share bound into the challenge, so it can't be replayed for another party or key.
+/// Produced by [LocalKey::prove_same_secret], verified by [verify_same_secret] against public
+/// data the verifier already trusts.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct EqualityProof {
+    /// Index of the party holding the keys
+    pub party: u16,
+    /// Public key of the keys
+    pub public_key: Point<Secp256k1>,
+    pub commitment: Point<Secp256k1>,
+    pub response: Scalar<Secp256k1>,
+}
+
+impl LocalKey<Secp256k1> {
+    /// Proves that `other` key holds the same secret share as this one without revealing it
+    ///
+    /// Useful when migrating a key between storage formats. Keys must have the same party index,
+    /// public key and public shares. Proof is made with the secret of `other`, so it shows that
+    /// the migrated key holds the share.
+    pub fn prove_same_secret(
+        &self,
+        other: &LocalKey<Secp256k1>,
+    ) -> Result<EqualityProof, ProofError> {
+        if self.i != other.i || self.y_sum_s != other.y_sum_s || self.pk_vec != other.pk_vec {
+            return Err(ProofError::KeysMismatch);
+        }
+        if self.keys_linear.x_i != other.keys_linear.x_i {
+            return Err(ProofError::DifferentSecrets);
+        }
+        let public_share = self
+            .pk_vec
+            .get(usize::from(self.i) - 1)
+            .ok_or(ProofError::KeysMismatch)?;
+        if Point::generator() * &other.keys_linear.x_i != *public_share {
+            return Err(ProofError::DifferentSecrets);
+        }
+
+        let nonce = Scalar::<Secp256k1>::random();
+        let commitment = Point::generator() * &nonce;
+        let challenge = challenge(self.i, &self.y_sum_s, public_share, &commitment);
+        Ok(EqualityProof {
+            party: self.i,
+            public_key: self.y_sum_s.clone(),
+            commitment,
+            response: nonce + challenge * &other.keys_linear.x_i,
+        })
+    }
+}
+
+/// Verifies [EqualityProof] against public shares `pk_vec` and `public_key` of the known key
+///
+/// Returns [ProofError::KeysMismatch] if proof is made for another public key or party index is
+/// out of range, and [ProofError::InvalidProof] if prover doesn't know secret behind
+/// `pk_vec[party-1]`.
+pub fn verify_same_secret(
+    proof: &EqualityProof,
+    pk_vec: &[Point<Secp256k1>],
+    public_key: &Point<Secp256k1>,
+) -> Result<(), ProofError> {
+    let public_share = usize::from(proof.party)
+        .checked_sub(1)
+        .and_then(|i| pk_vec.get(i))
+        .ok_or(ProofError::KeysMismatch)?;
+    if proof.public_key != *public_key {
+        return Err(ProofError::KeysMismatch);
+    }
+    let challenge = challenge(proof.party, public_key, public_share, &proof.commitment);
+    if Point::generator() * &proof.response != &proof.commitment + public_share * &challenge {
+        return Err(ProofError::InvalidProof);
+    }
+    Ok(())
+}
+
+fn challenge(
+    party: u16,
+    public_key: &Point<Secp256k1>,
+    public_share: &Point<Secp256k1>,
+    commitment: &Point<Secp256k1>,
+) -> Scalar<Secp256k1> {
+    let hash = Sha256::new()
+        .chain(EQUALITY_PROOF_CONTEXT)
+        .chain(party.to_be_bytes())
+        .chain_points([public_key, public_share, commitment])
+        .result_bigint();
+    Scalar::from_bigint(&hash)
+}
+
+#[derive(Debug, Error)]
+pub enum ProofError {
+    #[error("keys have different party index, public key or public shares")]
+    KeysMismatch,
+    #[error("keys hold different secrets")]
+    DifferentSecrets,
+    #[error("proof of knowledge of secret is invalid")]
+    InvalidProof,
+}
+
+#[cfg(test)]
+mod test {
+    use crate::protocols::multi_party_ecdsa::gg_2020::state_machine::keygen::test::simulate_keygen;
+
+    use super::*;
+
+    #[test]
+    fn same_secret_is_proven_for_reserialized_key() {
+        let keys = simulate_keygen(1, 3);
+        let (pk_vec, public_key) = (&keys[0].pk_vec, &keys[0].y_sum_s);
+        let reserialized: LocalKey<Secp256k1> =
+            serde_json::from_slice(&serde_json::to_vec(&keys[0]).unwrap()).unwrap();
+
+        let proof = keys[0].prove_same_secret(&reserialized).unwrap();
+        verify_same_secret(&proof, pk_vec, public_key).unwrap();
+
+        assert!(matches!(
+            keys[0].prove_same_secret(&keys[1]),
+            Err(ProofError::KeysMismatch)
+        ));
+        let mut different = keys[0].clone();
+        different.keys_linear.x_i = keys[1].keys_linear.x_i.clone();
+        assert!(matches!(
+            keys[0].prove_same_secret(&different),
+            Err(ProofError::DifferentSecrets)
+        ));
+
+        // Proof is bound to the party and the public key
+        let other_party = EqualityProof {
+            party: 2,
+            ..proof.clone()
+        };
+        assert!(matches!(
+            verify_same_secret(&other_party, pk_vec, public_key),
+            Err(ProofError::InvalidProof)
+        ));
+        let other_keys = simulate_keygen(1, 3);
+        assert!(matches!(
+            verify_same_secret(&proof, &other_keys[0].pk_vec, &other_keys[0].y_sum_s),
+            Err(ProofError::KeysMismatch)
+        ));
+
+        // Knowledge of an arbitrary secret doesn't pass for the party's share
+        let x = Scalar::<Secp256k1>::random();
+        let nonce = Scalar::<Secp256k1>::random();
+        let commitment = Point::generator() * &nonce;
+        let forged = EqualityProof {
+            party: 1,
+            public_key: public_key.clone(),
+            response: nonce
+                + challenge(1, public_key, &(Point::generator() * &x), &commitment) * &x,
+            commitment,
+        };
+        assert!(matches!(
+            verify_same_secret(&forged, pk_vec, public_key),
+            Err(ProofError::InvalidProof)
+        ));
+    }
+}
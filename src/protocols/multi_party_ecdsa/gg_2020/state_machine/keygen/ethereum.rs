@@ -0,0 +1,47 @@
+//! Ethereum address of the shared public key, available only with `eth` feature
+
+use curv::elliptic::curves::{secp256_k1::Secp256k1, Point};
+use sha3::{Digest, Keccak256};
+
+use super::LocalKey;
+
+impl LocalKey<Secp256k1> {
+    /// Ethereum address of the [public key](Self::public_key)
+    ///
+    /// Address is the last 20 bytes of Keccak-256 of the
+    /// [uncompressed](Self::public_key_uncompressed) public key without `0x04` prefix. Checksum
+    /// (mixed-case) encoding is left to the caller.
+    pub fn ethereum_address(&self) -> [u8; 20] {
+        ethereum_address_of(&self.y_sum_s)
+    }
+}
+
+fn ethereum_address_of(public_key: &Point<Secp256k1>) -> [u8; 20] {
+    let hash = Keccak256::digest(&public_key.to_bytes(false)[1..]);
+    let mut address = [0u8; 20];
+    address.copy_from_slice(&hash[12..]);
+    address
+}
+
+#[cfg(test)]
+mod test {
+    use crate::protocols::multi_party_ecdsa::gg_2020::state_machine::keygen::test::simulate_keygen;
+
+    use super::*;
+
+    #[test]
+    fn address_matches_known_vector() {
+        // Address of private key 1
+        assert_eq!(
+            hex::encode(ethereum_address_of(&Point::generator().to_point())),
+            "7e5f4552091a69125d5dfcd7b8c2659029395bdf"
+        );
+
+        let keys = simulate_keygen(1, 2);
+        assert_eq!(keys[0].ethereum_address(), keys[1].ethereum_address());
+        assert_eq!(
+            keys[0].ethereum_address(),
+            ethereum_address_of(&keys[0].public_key())
+        );
+    }
+}
@@ -0,0 +1,109 @@
+//! Numbering of parties in messages exchanged with the transport
+
+use round_based::Msg;
+use serde::{Deserialize, Serialize};
+
+/// How parties are numbered in `sender`/`receiver` of messages and in
+/// [party_ind](round_based::StateMachine::party_ind), see
+/// [KeygenBuilder::indexing](super::KeygenBuilder::indexing)
+///
+/// Protocol numbers parties starting from 1 internally. With [Indexing::ZeroBased] keygen
+/// translates indexes of incoming and outgoing messages at the boundary, so it can be plugged
+/// into transport numbering parties from 0. Errors and [blame](super::ProceedError::blame) keep
+/// reporting parties by their internal 1-based indexes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Indexing {
+    /// Parties are numbered `1..=n`, as the protocol does internally
+    OneBased,
+    /// Parties are numbered `0..n`
+    ZeroBased,
+}
+
+impl Default for Indexing {
+    fn default() -> Self {
+        Indexing::OneBased
+    }
+}
+
+impl Indexing {
+    /// Converts index of transport into protocol's one
+    ///
+    /// Index that can't be converted (`u16::MAX` in zero-based numbering) is mapped to 0 which
+    /// is never a valid party, so such message is rejected as coming from unexpected sender.
+    pub(super) fn to_internal(self, index: u16) -> u16 {
+        match self {
+            Indexing::OneBased => index,
+            Indexing::ZeroBased => index.checked_add(1).unwrap_or(0),
+        }
+    }
+
+    /// Converts protocol's index into transport's one
+    pub(super) fn to_external(self, index: u16) -> u16 {
+        match self {
+            Indexing::OneBased => index,
+            Indexing::ZeroBased => index.wrapping_sub(1),
+        }
+    }
+
+    /// Translates `sender` and `receiver` of incoming message into protocol's numbering
+    pub(super) fn incoming<B>(self, msg: Msg<B>) -> Msg<B> {
+        Msg {
+            sender: self.to_internal(msg.sender),
+            receiver: msg.receiver.map(|j| self.to_internal(j)),
+            ..msg
+        }
+    }
+
+    /// Translates `sender` and `receiver` of messages sent by the keygen into transport's
+    /// numbering
+    pub(super) fn outgoing<B>(self, msgs: &mut [Msg<B>]) {
+        for msg in msgs {
+            msg.sender = self.to_external(msg.sender);
+            msg.receiver = msg.receiver.map(|j| self.to_external(j));
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use round_based::StateMachine;
+
+    use crate::testing::{deliver_to_receivers, run_in_place};
+
+    use super::super::{Keygen, KeygenBuilder};
+    use super::*;
+
+    #[test]
+    fn keygen_runs_with_both_indexings() {
+        let n = 3;
+        for (indexing, first) in [(Indexing::OneBased, 1), (Indexing::ZeroBased, 0)] {
+            let mut parties: Vec<Keygen> = (first..first + n)
+                .map(|i| {
+                    KeygenBuilder::new()
+                        .party_index(i)
+                        .threshold(1)
+                        .parties(n)
+                        .indexing(indexing)
+                        .build()
+                        .unwrap()
+                })
+                .collect();
+            let indexes: Vec<u16> = parties.iter().map(|p| p.party_ind()).collect();
+            assert_eq!(indexes, (first..first + n).collect::<Vec<_>>());
+
+            run_in_place(&mut parties, |parties, msg| {
+                assert!(indexes.contains(&msg.sender));
+                assert!(msg.receiver.map_or(true, |j| indexes.contains(&j)));
+                deliver_to_receivers(parties, &msg);
+            });
+
+            let keys: Vec<_> = parties
+                .iter_mut()
+                .map(|p| p.pick_output().unwrap().unwrap())
+                .collect();
+            // Local keys keep protocol's 1-based numbering
+            assert_eq!(keys.iter().map(|k| k.i).collect::<Vec<_>>(), [1, 2, 3]);
+            assert!(keys.iter().all(|k| k.public_key() == keys[0].public_key()));
+        }
+    }
+}
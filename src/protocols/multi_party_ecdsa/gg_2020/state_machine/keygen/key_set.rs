@@ -0,0 +1,395 @@
+//! Checking that a set of [LocalKey]s belongs to the same group
+
+use curv::elliptic::curves::{secp256_k1::Secp256k1, Point, Scalar};
+use curv::BigInt;
+use paillier::EncryptionKey;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use zk_paillier::zkproofs::DLogStatement;
+
+use crate::protocols::multi_party_ecdsa::gg_2018::VerifiableSS;
+
+use super::LocalKey;
+
+/// Error of [validating key set](validate_key_set)
+#[derive(Debug, Error, PartialEq)]
+pub enum KeySetError {
+    #[error("expected at least {expected} keys to form a threshold subset, got {actual}")]
+    NotEnoughKeys { expected: usize, actual: usize },
+    #[error("key of party {i} has t={t} n={n}, others have t={expected_t} n={expected_n}")]
+    ThresholdMismatch {
+        i: u16,
+        t: u16,
+        n: u16,
+        expected_t: u16,
+        expected_n: u16,
+    },
+    #[error("party index {i} is not in range [1; n]")]
+    InvalidIndex { i: u16 },
+    #[error("party index {i} appears more than once")]
+    DuplicateIndex { i: u16 },
+    #[error("key of party {i} has different public key")]
+    PublicKeyMismatch { i: u16 },
+    #[error("key of party {i} has different public shares")]
+    PublicSharesMismatch { i: u16 },
+    #[error("public shares of party {i} don't interpolate to the public key")]
+    PublicSharesInconsistent { i: u16 },
+    #[error("key of party {i} has {actual} entries of {field}, expected {expected}")]
+    LengthMismatch {
+        i: u16,
+        field: &'static str,
+        expected: usize,
+        actual: usize,
+    },
+    #[error("key of party {i} has different Paillier key of party {j}")]
+    PaillierKeyMismatch { i: u16, j: u16 },
+    #[error("key of party {i} has different (N_tilde, h1, h2) of party {j}")]
+    DLogStatementMismatch { i: u16, j: u16 },
+    #[error("vss scheme of party {i} is inconsistent with the group")]
+    VssSchemeMismatch { i: u16 },
+    #[error("vss schemes of all parties don't add up to public shares of the group")]
+    VssCommitmentsMismatch,
+}
+
+/// Public part of [LocalKey] the signer advertises to others before signing, see
+/// [validate_signing_subset]
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SignerPublicKey {
+    pub i: u16,
+    pub t: u16,
+    pub n: u16,
+    pub y_sum_s: Point<Secp256k1>,
+    pub pk_vec: Vec<Point<Secp256k1>>,
+    pub paillier_key_vec: Vec<EncryptionKey>,
+    pub h1_h2_n_tilde_vec: Vec<DLogStatement>,
+    /// VSS scheme dealt by the signer at keygen
+    pub vss_scheme: VerifiableSS<Secp256k1>,
+}
+
+impl LocalKey<Secp256k1> {
+    /// Public part of the key to be advertised to other signers
+    pub fn signer_public_key(&self) -> SignerPublicKey {
+        SignerPublicKey {
+            i: self.i,
+            t: self.t,
+            n: self.n,
+            y_sum_s: self.y_sum_s.clone(),
+            pk_vec: self.pk_vec.clone(),
+            paillier_key_vec: self.paillier_key_vec.clone(),
+            h1_h2_n_tilde_vec: self.h1_h2_n_tilde_vec.clone(),
+            vss_scheme: self.vss_scheme.clone(),
+        }
+    }
+}
+
+/// Checks that `keys` form a coherent group able to sign
+///
+/// Keys must share the same `t`, `n` and public key, their party indices must be distinct and in
+/// range `[1; n]`, and there must be at least `t+1` of them. The first key is taken as a
+/// reference, so errors are reported for the key diverging from it.
+pub fn validate_key_set(keys: &[LocalKey<Secp256k1>]) -> Result<(), KeySetError> {
+    let reference = match keys.first() {
+        Some(key) => key,
+        None => {
+            return Err(KeySetError::NotEnoughKeys {
+                expected: 1,
+                actual: 0,
+            })
+        }
+    };
+    let (t, n) = (reference.t, reference.n);
+
+    let mut seen = vec![false; usize::from(n)];
+    for key in keys {
+        check_membership(&mut seen, (key.i, key.t, key.n), (t, n))?;
+        if key.y_sum_s != reference.y_sum_s {
+            return Err(KeySetError::PublicKeyMismatch { i: key.i });
+        }
+    }
+    check_quorum(t, keys.len())
+}
+
+/// Checks that keys advertised by `signers` come from the same keygen
+///
+/// In addition to checks of [validate_key_set], signers must agree on public shares, Paillier
+/// keys and `(N_tilde, h1, h2)` of all parties, public shares must lie on a polynomial of degree
+/// `t` evaluating to the public key at zero, and VSS scheme of every signer must be dealt for
+/// the same `t` and `n`. If all `n` parties are signing, their VSS schemes must also add up to
+/// public shares. Catches mixing shares of different keygens before the signing starts.
+pub fn validate_signing_subset(signers: &[SignerPublicKey]) -> Result<(), KeySetError> {
+    let reference = match signers.first() {
+        Some(signer) => signer,
+        None => {
+            return Err(KeySetError::NotEnoughKeys {
+                expected: 1,
+                actual: 0,
+            })
+        }
+    };
+    let (t, n) = (reference.t, reference.n);
+
+    let mut seen = vec![false; usize::from(n)];
+    for signer in signers {
+        let i = signer.i;
+        check_membership(&mut seen, (i, signer.t, signer.n), (t, n))?;
+        let lengths = [
+            ("public shares", signer.pk_vec.len()),
+            ("Paillier keys", signer.paillier_key_vec.len()),
+            ("(N_tilde, h1, h2)", signer.h1_h2_n_tilde_vec.len()),
+        ];
+        for &(field, actual) in lengths.iter() {
+            if actual != usize::from(n) {
+                return Err(KeySetError::LengthMismatch {
+                    i,
+                    field,
+                    expected: usize::from(n),
+                    actual,
+                });
+            }
+        }
+        if signer.y_sum_s != reference.y_sum_s {
+            return Err(KeySetError::PublicKeyMismatch { i });
+        }
+        if signer.pk_vec != reference.pk_vec {
+            return Err(KeySetError::PublicSharesMismatch { i });
+        }
+        if let Some(j) = first_mismatch(
+            &signer.paillier_key_vec,
+            &reference.paillier_key_vec,
+            |a, b| a.n == b.n,
+        ) {
+            return Err(KeySetError::PaillierKeyMismatch { i, j });
+        }
+        if let Some(j) = first_mismatch(
+            &signer.h1_h2_n_tilde_vec,
+            &reference.h1_h2_n_tilde_vec,
+            |a, b| a.N == b.N && a.g == b.g && a.ni == b.ni,
+        ) {
+            return Err(KeySetError::DLogStatementMismatch { i, j });
+        }
+
+        let vss = &signer.vss_scheme;
+        let vss_valid = vss.parameters.threshold == t
+            && vss.parameters.share_count == n
+            && vss.commitments.len() == usize::from(t) + 1;
+        if !vss_valid {
+            return Err(KeySetError::VssSchemeMismatch { i });
+        }
+    }
+    check_quorum(t, signers.len())?;
+
+    if !shares_interpolate_to(&reference.pk_vec, t, &reference.y_sum_s) {
+        return Err(KeySetError::PublicSharesInconsistent { i: reference.i });
+    }
+    // VSS schemes of parties not taking part in signing are unknown, the sum can be checked only
+    // when all parties are present
+    if signers.len() == usize::from(n) {
+        let consistent = (1..=n).zip(&reference.pk_vec).all(|(j, pk)| {
+            signers
+                .iter()
+                .map(|signer| signer.vss_scheme.get_point_commitment(j))
+                .sum::<Point<Secp256k1>>()
+                == *pk
+        });
+        if !consistent {
+            return Err(KeySetError::VssCommitmentsMismatch);
+        }
+    }
+    Ok(())
+}
+
+/// Checks that `pk_vec` (public shares of parties `1..=n`) lie on a polynomial of degree `t`
+/// evaluating to `y` at zero
+///
+/// The polynomial is interpolated from shares of parties `1..=t+1`, then evaluated at zero and at
+/// indexes of the rest of parties.
+fn shares_interpolate_to(pk_vec: &[Point<Secp256k1>], t: u16, y: &Point<Secp256k1>) -> bool {
+    let base: Vec<u16> = (1..=t + 1).collect();
+    let evaluate = |x: u16| -> Point<Secp256k1> {
+        base.iter()
+            .zip(pk_vec)
+            .map(|(&k, pk)| pk * lagrange_coefficient_at(x, k, &base))
+            .sum()
+    };
+    evaluate(0) == *y
+        && (t + 2..)
+            .zip(&pk_vec[base.len()..])
+            .all(|(j, pk)| evaluate(j) == *pk)
+}
+
+/// Lagrange coefficient of point `k` of `points` for evaluating polynomial at `x`
+fn lagrange_coefficient_at(x: u16, k: u16, points: &[u16]) -> Scalar<Secp256k1> {
+    let scalar = |v: u16| Scalar::<Secp256k1>::from_bigint(&BigInt::from(u64::from(v)));
+    points
+        .iter()
+        .filter(|&&m| m != k)
+        .fold(Scalar::from_bigint(&BigInt::from(1)), |acc, &m| {
+            let denominator = (scalar(k) - scalar(m))
+                .invert()
+                .expect("points are distinct");
+            acc * (scalar(x) - scalar(m)) * denominator
+        })
+}
+
+fn check_membership(
+    seen: &mut [bool],
+    (i, t, n): (u16, u16, u16),
+    (expected_t, expected_n): (u16, u16),
+) -> Result<(), KeySetError> {
+    if t != expected_t || n != expected_n {
+        return Err(KeySetError::ThresholdMismatch {
+            i,
+            t,
+            n,
+            expected_t,
+            expected_n,
+        });
+    }
+    if i == 0 || i > n {
+        return Err(KeySetError::InvalidIndex { i });
+    }
+    if std::mem::replace(&mut seen[usize::from(i - 1)], true) {
+        return Err(KeySetError::DuplicateIndex { i });
+    }
+    Ok(())
+}
+
+/// Index of the first party whose entry in `items` differs from `reference`
+///
+/// `items` and `reference` are of the same length.
+fn first_mismatch<T>(items: &[T], reference: &[T], eq: impl Fn(&T, &T) -> bool) -> Option<u16> {
+    (1..)
+        .zip(items.iter().zip(reference))
+        .find(|(_, (a, b))| !eq(a, b))
+        .map(|(j, _)| j)
+}
+
+fn check_quorum(t: u16, actual: usize) -> Result<(), KeySetError> {
+    let expected = usize::from(t) + 1;
+    if actual < expected {
+        return Err(KeySetError::NotEnoughKeys { expected, actual });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::protocols::multi_party_ecdsa::gg_2020::state_machine::keygen::test::simulate_keygen;
+
+    use super::*;
+
+    #[test]
+    fn inconsistent_key_sets_are_reported() {
+        let keys = simulate_keygen(1, 3);
+        assert_eq!(validate_key_set(&keys), Ok(()));
+        assert_eq!(validate_key_set(&keys[1..]), Ok(()));
+        assert_eq!(
+            validate_key_set(&keys[..1]),
+            Err(KeySetError::NotEnoughKeys {
+                expected: 2,
+                actual: 1
+            })
+        );
+
+        let duplicated = vec![keys[0].clone(), keys[1].clone(), keys[0].clone()];
+        assert_eq!(
+            validate_key_set(&duplicated),
+            Err(KeySetError::DuplicateIndex { i: 1 })
+        );
+
+        let other_group = simulate_keygen(1, 3);
+        let mixed = vec![keys[0].clone(), other_group[1].clone()];
+        assert_eq!(
+            validate_key_set(&mixed),
+            Err(KeySetError::PublicKeyMismatch { i: 2 })
+        );
+
+        let mut other_threshold = keys[2].clone();
+        other_threshold.t = 2;
+        let mixed = vec![keys[0].clone(), other_threshold];
+        assert!(matches!(
+            validate_key_set(&mixed),
+            Err(KeySetError::ThresholdMismatch { i: 3, t: 2, .. })
+        ));
+    }
+
+    #[test]
+    fn signing_subset_from_different_keygens_is_rejected() {
+        let keys = simulate_keygen(1, 3);
+        let advertised: Vec<_> = keys.iter().map(|k| k.signer_public_key()).collect();
+        assert_eq!(validate_signing_subset(&advertised[1..]), Ok(()));
+
+        let other_group = simulate_keygen(1, 3);
+        let mixed = vec![advertised[0].clone(), other_group[1].signer_public_key()];
+        assert_eq!(
+            validate_signing_subset(&mixed),
+            Err(KeySetError::PublicKeyMismatch { i: 2 })
+        );
+
+        let mut forged = advertised[2].clone();
+        forged.paillier_key_vec[1] = other_group[1].paillier_key_vec[1].clone();
+        let mixed = vec![advertised[0].clone(), forged];
+        assert_eq!(
+            validate_signing_subset(&mixed),
+            Err(KeySetError::PaillierKeyMismatch { i: 3, j: 2 })
+        );
+
+        let mut forged = advertised[2].clone();
+        forged.h1_h2_n_tilde_vec[0] = other_group[0].h1_h2_n_tilde_vec[0].clone();
+        let mixed = vec![advertised[0].clone(), forged];
+        assert_eq!(
+            validate_signing_subset(&mixed),
+            Err(KeySetError::DLogStatementMismatch { i: 3, j: 1 })
+        );
+
+        let mut forged = advertised[1].clone();
+        forged.vss_scheme = simulate_keygen(2, 3)[1].vss_scheme.clone();
+        let mixed = vec![advertised[0].clone(), forged];
+        assert_eq!(
+            validate_signing_subset(&mixed),
+            Err(KeySetError::VssSchemeMismatch { i: 2 })
+        );
+    }
+
+    #[test]
+    fn inconsistent_public_data_is_rejected() {
+        let keys = simulate_keygen(1, 3);
+        let advertised: Vec<_> = keys.iter().map(|k| k.signer_public_key()).collect();
+        assert_eq!(validate_signing_subset(&advertised), Ok(()));
+
+        // All signers agree on public shares, but they don't belong to the public key
+        let other_group = simulate_keygen(1, 3);
+        let forged: Vec<_> = advertised
+            .iter()
+            .map(|signer| SignerPublicKey {
+                pk_vec: other_group[0].pk_vec.clone(),
+                ..signer.clone()
+            })
+            .collect();
+        assert_eq!(
+            validate_signing_subset(&forged[1..]),
+            Err(KeySetError::PublicSharesInconsistent { i: 2 })
+        );
+
+        // Signer advertises VSS scheme it didn't deal
+        let mut forged = advertised.clone();
+        forged[2].vss_scheme = other_group[2].vss_scheme.clone();
+        assert_eq!(
+            validate_signing_subset(&forged),
+            Err(KeySetError::VssCommitmentsMismatch)
+        );
+
+        let mut truncated = advertised[2].clone();
+        truncated.paillier_key_vec.pop();
+        let mixed = vec![advertised[0].clone(), truncated];
+        assert_eq!(
+            validate_signing_subset(&mixed),
+            Err(KeySetError::LengthMismatch {
+                i: 3,
+                field: "Paillier keys",
+                expected: 3,
+                actual: 2
+            })
+        );
+    }
+}
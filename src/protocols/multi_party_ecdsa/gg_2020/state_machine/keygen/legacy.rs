@@ -0,0 +1,191 @@
+//! Migrating key shares generated by [gg_2018](crate::protocols::multi_party_ecdsa::gg_2018) keygen
+
+use std::convert::TryFrom;
+
+use curv::elliptic::curves::{secp256_k1::Secp256k1, Point};
+use paillier::EncryptionKey;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use zk_paillier::zkproofs::DLogStatement;
+
+use crate::protocols::multi_party_ecdsa::gg_2018;
+use crate::protocols::multi_party_ecdsa::gg_2018::VerifiableSS;
+use crate::protocols::multi_party_ecdsa::gg_2020::party_i::SharedKeys;
+
+use super::{validate_dlog_statement, LocalKey};
+
+/// Key share produced by gg_2018 keygen
+///
+/// Fields match the tuple `(party_keys, shared_keys, party_index, vss_scheme_vec,
+/// paillier_key_vec, y_sum)` saved by `gg18_keygen_client` example. Share can be migrated to
+/// gg_2020 [LocalKey] via `TryFrom<(Gg18KeyShare, Vec<DLogStatement>)>`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Gg18KeyShare {
+    pub party_keys: gg_2018::party_i::Keys,
+    pub shared_keys: gg_2018::party_i::SharedKeys,
+    pub party_index: u16,
+    pub vss_scheme_vec: Vec<VerifiableSS<Secp256k1>>,
+    pub paillier_key_vec: Vec<EncryptionKey>,
+    pub y_sum: Point<Secp256k1>,
+}
+
+/// Error of migrating [Gg18KeyShare] to gg_2020 [LocalKey]
+#[derive(Debug, Error)]
+pub enum LegacyKeyError {
+    #[error("expected vss schemes and Paillier keys of {n} parties")]
+    InconsistentPartyCount { n: usize },
+    #[error("party index {party} is not in range [1; n]")]
+    InvalidPartyIndex { party: u16 },
+    #[error("expected dlog statements of {expected} parties, got {actual}")]
+    MissingDLogStatements { expected: usize, actual: usize },
+    #[error("dlog statement of party {party} is invalid")]
+    InvalidDLogStatement { party: u16 },
+    #[error("share doesn't match public key and vss schemes")]
+    InconsistentShare,
+}
+
+/// Migrates gg_2018 key share to gg_2020 [LocalKey]
+///
+/// VSS schemes and shares of both protocols are the same, so the share is reused as is. gg_2018
+/// has no equivalent of `(N_tilde, h1, h2)`, so DLog statements of all parties, ordered by party
+/// index, must be supplied by the caller. Their proofs of correctness must be verified by the
+/// caller beforehand, conversion only performs [sanity checks](validate_dlog_statement). Every
+/// party must use the same statements.
+///
+/// There's no conversion in the opposite direction: gg_2020 [LocalKey] doesn't carry the
+/// secret `u_i` of gg_2018 party keys.
+impl TryFrom<(Gg18KeyShare, Vec<DLogStatement>)> for LocalKey<Secp256k1> {
+    type Error = LegacyKeyError;
+
+    fn try_from(
+        (share, h1_h2_n_tilde_vec): (Gg18KeyShare, Vec<DLogStatement>),
+    ) -> Result<Self, Self::Error> {
+        let n = share.vss_scheme_vec.len();
+        let t = match share.vss_scheme_vec.first() {
+            Some(vss) => vss.parameters.threshold,
+            None => return Err(LegacyKeyError::InconsistentPartyCount { n }),
+        };
+        let consistent_params = share.vss_scheme_vec.iter().all(|vss| {
+            vss.parameters.threshold == t && usize::from(vss.parameters.share_count) == n
+        });
+        if !consistent_params || share.paillier_key_vec.len() != n {
+            return Err(LegacyKeyError::InconsistentPartyCount { n });
+        }
+        let i = share.party_index;
+        if i == 0 || usize::from(i) > n {
+            return Err(LegacyKeyError::InvalidPartyIndex { party: i });
+        }
+        if h1_h2_n_tilde_vec.len() != n {
+            return Err(LegacyKeyError::MissingDLogStatements {
+                expected: n,
+                actual: h1_h2_n_tilde_vec.len(),
+            });
+        }
+        for (party, statement) in (1..).zip(&h1_h2_n_tilde_vec) {
+            validate_dlog_statement(statement)
+                .map_err(|_| LegacyKeyError::InvalidDLogStatement { party })?;
+        }
+
+        let n = n as u16;
+        let pk_vec: Vec<Point<Secp256k1>> = (1..=n)
+            .map(|j| {
+                share
+                    .vss_scheme_vec
+                    .iter()
+                    .map(|vss| vss.get_point_commitment(j))
+                    .sum()
+            })
+            .collect();
+        let y_sum_s: Point<Secp256k1> = share
+            .vss_scheme_vec
+            .iter()
+            .map(|vss| vss.commitments[0].clone())
+            .sum();
+        if y_sum_s != share.y_sum
+            || share.shared_keys.y != share.y_sum
+            || Point::generator() * &share.shared_keys.x_i != pk_vec[usize::from(i - 1)]
+        {
+            return Err(LegacyKeyError::InconsistentShare);
+        }
+
+        Ok(LocalKey {
+            paillier_dk: share.party_keys.dk.clone(),
+            pk_vec,
+            keys_linear: SharedKeys {
+                y: share.shared_keys.y.clone(),
+                x_i: share.shared_keys.x_i.clone(),
+            },
+            paillier_key_vec: share.paillier_key_vec.clone(),
+            y_sum_s,
+            h1_h2_n_tilde_vec,
+            vss_scheme: share.vss_scheme_vec[usize::from(i - 1)].clone(),
+            vss_scheme_vec: share.vss_scheme_vec,
+            party_identities: vec![],
+            recovery_backups: vec![],
+
+            i,
+            t,
+            n,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use curv::elliptic::curves::Scalar;
+
+    use crate::protocols::multi_party_ecdsa::gg_2020::state_machine::keygen::test::simulate_keygen;
+    use crate::protocols::multi_party_ecdsa::gg_2020::state_machine::sign::test::{
+        simulate_offline_stage, simulate_signing,
+    };
+
+    use super::*;
+
+    /// Repackages gg_2020 key as gg_2018 share, as both protocols share the same VSS
+    fn as_gg18_share(key: &LocalKey<Secp256k1>) -> Gg18KeyShare {
+        let u_i = Scalar::random();
+        Gg18KeyShare {
+            party_keys: gg_2018::party_i::Keys {
+                y_i: Point::generator() * &u_i,
+                u_i,
+                dk: key.paillier_dk.clone(),
+                ek: key.paillier_key_vec[usize::from(key.i - 1)].clone(),
+                party_index: key.i,
+            },
+            shared_keys: gg_2018::party_i::SharedKeys {
+                y: key.keys_linear.y.clone(),
+                x_i: key.keys_linear.x_i.clone(),
+            },
+            party_index: key.i,
+            vss_scheme_vec: key.vss_scheme_vec.clone(),
+            paillier_key_vec: key.paillier_key_vec.clone(),
+            y_sum: key.y_sum_s.clone(),
+        }
+    }
+
+    #[test]
+    fn migrated_share_signs_for_the_same_public_key() {
+        let keys = simulate_keygen(1, 3);
+        let migrated: Vec<LocalKey<Secp256k1>> = keys
+            .iter()
+            .map(|key| {
+                LocalKey::try_from((as_gg18_share(key), key.h1_h2_n_tilde_vec.clone())).unwrap()
+            })
+            .collect();
+        for (key, migrated) in keys.iter().zip(&migrated) {
+            assert_eq!(migrated.public_key(), key.public_key());
+            assert_eq!(migrated.pk_vec, key.pk_vec);
+            migrated.verify_keys_linear_consistency().unwrap();
+        }
+        simulate_signing(simulate_offline_stage(migrated, &[1, 3]), b"migrated");
+
+        let result = LocalKey::try_from((as_gg18_share(&keys[0]), vec![]));
+        assert!(matches!(
+            result,
+            Err(LegacyKeyError::MissingDLogStatements {
+                expected: 3,
+                actual: 0
+            })
+        ));
+    }
+}
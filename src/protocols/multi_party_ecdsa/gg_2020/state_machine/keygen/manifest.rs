@@ -0,0 +1,139 @@
+//! Static description of cryptographic operations performed by a keygen party
+
+use serde::{Deserialize, Serialize};
+
+use super::Keygen;
+
+/// Cryptographic operation performed during keygen
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum CryptoOperation {
+    /// Generation of Paillier key pair
+    PaillierKeyGen,
+    /// Generation of `(N_tilde, h1, h2)` ring-Pedersen parameters
+    RingPedersenSetup,
+    /// Hash commitment to public share
+    Commitment,
+    /// Check that decommitment opens received commitment
+    CommitmentVerify,
+    /// Proof of correct Paillier key
+    PaillierKeyProof,
+    PaillierKeyProofVerify,
+    /// Composite DLog proof of correct `h1`, `h2`
+    CompositeDLogProof,
+    CompositeDLogProofVerify,
+    /// Feldman VSS of party's secret
+    VssShare,
+    /// Check of received share against VSS commitments
+    VssVerify,
+    PaillierEncryption,
+    PaillierDecryption,
+    /// Proof of knowledge of DLog of resulting secret share
+    DLogProof,
+    DLogProofVerify,
+}
+
+/// How many times an operation is performed, as a function of number of parties `n`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OperationCount {
+    /// Fixed number of times regardless of `n`
+    Fixed(usize),
+    /// Given number of times per every party, including this one (`k * n`)
+    PerParty(usize),
+    /// Given number of times per every other party (`k * (n - 1)`)
+    PerOtherParty(usize),
+}
+
+impl OperationCount {
+    /// Evaluates number of operations for keygen with `n` parties
+    pub fn evaluate(self, n: u16) -> usize {
+        let n = usize::from(n);
+        match self {
+            OperationCount::Fixed(k) => k,
+            OperationCount::PerParty(k) => k * n,
+            OperationCount::PerOtherParty(k) => k * n.saturating_sub(1),
+        }
+    }
+}
+
+/// Operations that party performs in specific round, see [Keygen::operation_manifest]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RoundOperations {
+    /// Round index, matches [current_round](round_based::StateMachine::current_round)
+    pub round: u16,
+    pub operations: Vec<(CryptoOperation, OperationCount)>,
+}
+
+impl Keygen {
+    /// Cryptographic operations that a party performs in every round of keygen
+    ///
+    /// It's static metadata, the same for any keygen. Optional features like
+    /// [recovery party](Keygen::with_recovery_party) add extra operations which are not listed.
+    pub fn operation_manifest() -> Vec<RoundOperations> {
+        use CryptoOperation::*;
+        use OperationCount::*;
+
+        let round = |round, operations| RoundOperations { round, operations };
+        vec![
+            round(
+                0,
+                vec![
+                    (PaillierKeyGen, Fixed(1)),
+                    (RingPedersenSetup, Fixed(1)),
+                    (PaillierKeyProof, Fixed(1)),
+                    (CompositeDLogProof, Fixed(2)),
+                    (Commitment, Fixed(1)),
+                ],
+            ),
+            round(1, vec![]),
+            round(
+                2,
+                vec![
+                    (CommitmentVerify, PerParty(1)),
+                    (PaillierKeyProofVerify, PerParty(1)),
+                    (CompositeDLogProofVerify, PerParty(2)),
+                    (VssShare, Fixed(1)),
+                    (PaillierEncryption, PerOtherParty(1)),
+                ],
+            ),
+            round(
+                3,
+                vec![
+                    (PaillierDecryption, PerOtherParty(1)),
+                    (VssVerify, PerParty(1)),
+                    (DLogProof, Fixed(1)),
+                ],
+            ),
+            round(4, vec![(DLogProofVerify, PerParty(1))]),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn count(round: &RoundOperations, operation: CryptoOperation, n: u16) -> usize {
+        round
+            .operations
+            .iter()
+            .filter(|(op, _)| *op == operation)
+            .map(|(_, count)| count.evaluate(n))
+            .sum()
+    }
+
+    #[test]
+    fn manifest_lists_paillier_operations() {
+        let manifest = Keygen::operation_manifest();
+        let n = 5;
+        assert_eq!(
+            count(&manifest[2], CryptoOperation::PaillierEncryption, n),
+            usize::from(n) - 1
+        );
+        // One decryption per share received from other parties
+        assert_eq!(
+            count(&manifest[3], CryptoOperation::PaillierDecryption, n),
+            usize::from(n) - 1
+        );
+        assert!((0..5).eq(manifest.iter().map(|r| r.round)));
+    }
+}
@@ -0,0 +1,96 @@
+//! Encryption to all parties of the keygen under their Paillier keys
+
+use curv::elliptic::curves::secp256_k1::Secp256k1;
+use curv::BigInt;
+use paillier::{Decrypt, Encrypt, EncryptionKey, Paillier, RawCiphertext, RawPlaintext};
+use serde::{Deserialize, Serialize};
+
+use super::LocalKey;
+
+/// Paillier encryption keys of all parties, ordered by party index
+///
+/// Encrypts a value (or a portion per party) to every party, so any party can decrypt its
+/// portion using [LocalKey::decrypt_portion]. Obtained via [LocalKey::combined_paillier_context].
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct CombinedPaillierContext {
+    pub keys: Vec<EncryptionKey>,
+}
+
+impl CombinedPaillierContext {
+    /// Encrypts the same `value` to every party
+    ///
+    /// `value` must be smaller than every party's Paillier modulus.
+    pub fn encrypt_to_all(&self, value: &BigInt) -> Vec<BigInt> {
+        self.keys.iter().map(|ek| encrypt(ek, value)).collect()
+    }
+
+    /// Encrypts `portions[j]` to party `j+1`
+    ///
+    /// Returns `None` if number of portions doesn't match number of parties. Every portion must
+    /// be smaller than Paillier modulus of its recipient.
+    pub fn encrypt_portions(&self, portions: &[BigInt]) -> Option<Vec<BigInt>> {
+        if portions.len() != self.keys.len() {
+            return None;
+        }
+        Some(
+            self.keys
+                .iter()
+                .zip(portions)
+                .map(|(ek, portion)| encrypt(ek, portion))
+                .collect(),
+        )
+    }
+}
+
+fn encrypt(ek: &EncryptionKey, value: &BigInt) -> BigInt {
+    Paillier::encrypt(ek, RawPlaintext::from(value.clone()))
+        .0
+        .into_owned()
+}
+
+impl LocalKey<Secp256k1> {
+    /// Bundles Paillier encryption keys of all parties
+    pub fn combined_paillier_context(&self) -> CombinedPaillierContext {
+        CombinedPaillierContext {
+            keys: self.paillier_key_vec.clone(),
+        }
+    }
+
+    /// Decrypts this party's portion from ciphertexts produced by [CombinedPaillierContext]
+    ///
+    /// Returns `None` if there's no ciphertext for this party.
+    pub fn decrypt_portion(&self, ciphertexts: &[BigInt]) -> Option<BigInt> {
+        let ciphertext = ciphertexts.get(usize::from(self.i).checked_sub(1)?)?;
+        let plaintext: RawPlaintext =
+            Paillier::decrypt(&self.paillier_dk, RawCiphertext::from(ciphertext.clone()));
+        Some(plaintext.0.into_owned())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use curv::arithmetic::traits::*;
+    use curv::BigInt;
+
+    use crate::protocols::multi_party_ecdsa::gg_2020::state_machine::keygen::test::simulate_keygen;
+
+    #[test]
+    fn every_party_decrypts_its_portion() {
+        let keys = simulate_keygen(1, 3);
+        let context = keys[0].combined_paillier_context();
+
+        let portions: Vec<_> = (0..3).map(|_| BigInt::sample(256)).collect();
+        let ciphertexts = context.encrypt_portions(&portions).unwrap();
+        for (key, portion) in keys.iter().zip(&portions) {
+            assert_eq!(key.decrypt_portion(&ciphertexts).as_ref(), Some(portion));
+        }
+
+        let value = BigInt::sample(256);
+        let ciphertexts = context.encrypt_to_all(&value);
+        assert!(keys
+            .iter()
+            .all(|key| key.decrypt_portion(&ciphertexts).as_ref() == Some(&value)));
+
+        assert!(context.encrypt_portions(&portions[1..]).is_none());
+    }
+}
@@ -0,0 +1,175 @@
+//! Replacing party's Paillier key without touching its share
+
+use std::iter;
+
+use curv::elliptic::curves::secp256_k1::Secp256k1;
+use curv::BigInt;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::protocols::multi_party_ecdsa::gg_2020::party_i::{
+    KeyGenBroadcastMessage1, KeyGenDecommitMessage1, Keys, PaillierSetup,
+};
+
+use super::{parties_sharing_modulus, validate_dlog_statement, LocalKey};
+
+/// Party's new Paillier key and `DLogStatement` with proofs of their correctness
+///
+/// Produced by [LocalKey::rotate_paillier_only], must be delivered to every other party which
+/// applies it via [LocalKey::apply_paillier_rotation].
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PaillierRotation {
+    /// Index of party which rotated its key
+    ///
+    /// Index is not authenticated by the rotation itself: anyone can produce a valid rotation
+    /// claiming any index. Rotation must be received over a channel authenticating the sender,
+    /// and receiver must check that the sender is `party`.
+    pub party: u16,
+    bc1: KeyGenBroadcastMessage1,
+    decom1: KeyGenDecommitMessage1,
+}
+
+/// Error of [applying](LocalKey::apply_paillier_rotation) Paillier key rotation
+#[derive(Debug, Error)]
+pub enum PaillierRotationError {
+    #[error("party index {party} is not in range [1; n]")]
+    InvalidPartyIndex { party: u16 },
+    #[error("rotation of own key must be completed via rotate_paillier_only")]
+    OwnRotation,
+    #[error("party {party} sent invalid Paillier key or dlog statement")]
+    InvalidPaillierKey { party: u16 },
+    #[error("party {party} reuses Paillier or dlog modulus of another party")]
+    DuplicateModulus { party: u16 },
+}
+
+impl LocalKey<Secp256k1> {
+    /// Replaces party's own Paillier key and `DLogStatement` with fresh ones
+    ///
+    /// Returns updated key and rotation that needs to be sent to every other party. Share of
+    /// ECDSA key, public key and public shares stay unchanged. Updated key must not be used for
+    /// signing until every other party applied the rotation.
+    pub fn rotate_paillier_only(&self) -> (LocalKey<Secp256k1>, PaillierRotation) {
+        let setup = PaillierSetup::generate();
+        let keys = Keys::create_from_paillier_setup(usize::from(self.i), &setup);
+        let (bc1, decom1) = keys.phase1_broadcast_with_paillier_setup(&setup);
+
+        let mut rotated = self.clone();
+        let i = usize::from(self.i - 1);
+        rotated.paillier_dk = keys.dk.clone();
+        rotated.paillier_key_vec[i] = bc1.e.clone();
+        rotated.h1_h2_n_tilde_vec[i] = bc1.dlog_statement.clone();
+        (
+            rotated,
+            PaillierRotation {
+                party: self.i,
+                bc1,
+                decom1,
+            },
+        )
+    }
+
+    /// Verifies Paillier key rotation of another party and updates its Paillier key and
+    /// `DLogStatement` in the local key
+    ///
+    /// Rotation must come from party [`rotation.party`](PaillierRotation::party) over
+    /// authenticated channel. Returns [PaillierRotationError::DuplicateModulus] if new Paillier
+    /// or dlog modulus is used by any other party, either as Paillier or as dlog modulus.
+    pub fn apply_paillier_rotation(
+        &self,
+        rotation: &PaillierRotation,
+    ) -> Result<LocalKey<Secp256k1>, PaillierRotationError> {
+        let party = rotation.party;
+        if party == 0 || party > self.n {
+            return Err(PaillierRotationError::InvalidPartyIndex { party });
+        }
+        if party == self.i {
+            return Err(PaillierRotationError::OwnRotation);
+        }
+        if !rotation.bc1.verify_with_decommit(&rotation.decom1)
+            || validate_dlog_statement(&rotation.bc1.dlog_statement).is_err()
+        {
+            return Err(PaillierRotationError::InvalidPaillierKey { party });
+        }
+        if !self.is_modulus_unique(party, &rotation.bc1.e.n)
+            || !self.is_modulus_unique(party, &rotation.bc1.dlog_statement.N)
+        {
+            return Err(PaillierRotationError::DuplicateModulus { party });
+        }
+
+        let mut rotated = self.clone();
+        let j = usize::from(party - 1);
+        rotated.paillier_key_vec[j] = rotation.bc1.e.clone();
+        rotated.h1_h2_n_tilde_vec[j] = rotation.bc1.dlog_statement.clone();
+        Ok(rotated)
+    }
+
+    /// Checks that `modulus` of `party` is neither Paillier nor dlog modulus of any other party
+    fn is_modulus_unique(&self, party: u16, modulus: &BigInt) -> bool {
+        let others = (1..=self.n).filter(|&j| j != party).flat_map(|j| {
+            let j = usize::from(j - 1);
+            vec![&self.paillier_key_vec[j].n, &self.h1_h2_n_tilde_vec[j].N]
+        });
+        // `modulus` goes after moduli of other parties
+        let position = 2 * (self.n - 1) + 1;
+        !parties_sharing_modulus(others.chain(iter::once(modulus))).contains(&position)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::protocols::multi_party_ecdsa::gg_2020::state_machine::keygen::test::simulate_keygen;
+    use crate::protocols::multi_party_ecdsa::gg_2020::state_machine::sign::test::{
+        simulate_offline_stage, simulate_signing,
+    };
+
+    use super::*;
+
+    #[test]
+    fn signing_works_after_paillier_rotation() {
+        let keys = simulate_keygen(1, 3);
+        let (rotated, rotation) = keys[1].rotate_paillier_only();
+        assert_ne!(rotated.paillier_dk.p, keys[1].paillier_dk.p);
+        assert_eq!(rotated.keys_linear.x_i, keys[1].keys_linear.x_i);
+
+        let keys: Vec<_> = keys
+            .iter()
+            .map(|key| match key.i {
+                2 => rotated.clone(),
+                _ => key.apply_paillier_rotation(&rotation).unwrap(),
+            })
+            .collect();
+        assert!(matches!(
+            rotated.apply_paillier_rotation(&rotation),
+            Err(PaillierRotationError::OwnRotation)
+        ));
+        assert!(keys.iter().all(|k| k.public_key() == rotated.public_key()
+            && k.paillier_key_vec[1].n == rotated.paillier_key_vec[1].n));
+
+        simulate_signing(simulate_offline_stage(keys.clone(), &[1, 2]), b"rotated");
+        simulate_signing(simulate_offline_stage(keys, &[2, 3]), b"rotated");
+    }
+
+    #[test]
+    fn tampered_rotation_is_rejected() {
+        let keys = simulate_keygen(1, 2);
+        let (_, mut rotation) = keys[1].rotate_paillier_only();
+        rotation.bc1.dlog_statement = keys[1].h1_h2_n_tilde_vec[0].clone();
+        assert!(matches!(
+            keys[0].apply_paillier_rotation(&rotation),
+            Err(PaillierRotationError::InvalidPaillierKey { party: 2 })
+        ));
+    }
+
+    #[test]
+    fn rotation_to_modulus_of_another_party_is_rejected() {
+        let keys = simulate_keygen(1, 3);
+        // Party 1 already knows rotated Paillier key of party 3, party 2 replays the rotation
+        let (_, mut rotation) = keys[2].rotate_paillier_only();
+        let key1 = keys[0].apply_paillier_rotation(&rotation).unwrap();
+        rotation.party = 2;
+        assert!(matches!(
+            key1.apply_paillier_rotation(&rotation),
+            Err(PaillierRotationError::DuplicateModulus { party: 2 })
+        ));
+    }
+}
@@ -0,0 +1,208 @@
+//! Stateless parsing and validation of keygen messages
+
+use curv::arithmetic::traits::*;
+use curv::cryptographic_primitives::proofs::sigma_dlog::DLogProof;
+use curv::elliptic::curves::{secp256_k1::Secp256k1, Curve, Point};
+use curv::BigInt;
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+use crate::protocols::multi_party_ecdsa::gg_2018::VerifiableSS;
+use crate::protocols::multi_party_ecdsa::gg_2020::party_i::{
+    KeyGenBroadcastMessage1, KeyGenDecommitMessage1,
+};
+use crate::MessageRoundID;
+
+use super::{
+    validate_dlog_statement, DLogStatementError, ProtocolMessage, PublicKeyConfirmation,
+    Round3Message, ShareEncryptionProof, M,
+};
+
+/// Keygen message that passed [parse_keygen_message]
+///
+/// `H` is the hash function of DLog proofs, see [Keygen::with_dlog_hash](super::Keygen::with_dlog_hash).
+#[derive(Clone, Debug)]
+pub enum KeygenMessage<H: Digest + Clone = Sha256> {
+    Round1 {
+        bc1: KeyGenBroadcastMessage1,
+        party_identities: Vec<Point<Secp256k1>>,
+    },
+    Round2(KeyGenDecommitMessage1),
+    Round3 {
+        vss_scheme: VerifiableSS<Secp256k1>,
+        encrypted_share: Vec<u8>,
+        curve: String,
+        recovery_backups: Vec<Vec<u8>>,
+        binding_tag: [u8; 32],
+        encryption_proof: Option<ShareEncryptionProof>,
+    },
+    Round4(DLogProof<Secp256k1, H>),
+    Round5(PublicKeyConfirmation),
+}
+
+/// Error of [parsing](parse_keygen_message) keygen message
+#[derive(Debug, Error)]
+pub enum ParseError {
+    #[error("message is malformed: {0}")]
+    Malformed(#[source] serde_json::Error),
+    #[error("expected message of round {expected}, got round {found}")]
+    UnexpectedRound { expected: u16, found: u16 },
+    #[error("Paillier key or dlog statement proofs are invalid")]
+    InvalidKeyProofs,
+    #[error("dlog statement is invalid: {0}")]
+    InvalidDLogStatement(#[source] DLogStatementError),
+    #[error("decommitted public key is zero")]
+    ZeroPublicKey,
+    #[error("vss scheme is malformed or made for another curve")]
+    InvalidVssScheme,
+    #[error("dlog proof is invalid")]
+    InvalidDLogProof,
+}
+
+/// Parses JSON-serialized [ProtocolMessage] of keygen `round`, and validates it
+///
+/// Does everything that can be checked without protocol state: structure of the message, points
+/// being on curve (it's enforced while deserializing), round numbers, proofs of correctness of
+/// Paillier key and `(N_tilde, h1, h2)`, shape of VSS scheme and DLog proof. Checks involving
+/// messages of other parties (e.g. opening commitments) are left to the state machine.
+///
+/// Doesn't advance any state, so it's suitable as a fuzzing target. Transport layer may use it to
+/// drop invalid messages before they reach the state machine. `H` must be the hash function of
+/// DLog proofs keygen runs with, it's [Sha256] for keygen constructed by
+/// [Keygen::new](super::Keygen::new).
+pub fn parse_keygen_message<H: Digest + Clone>(
+    round: u16,
+    bytes: &[u8],
+) -> Result<KeygenMessage<H>, ParseError> {
+    let msg: ProtocolMessage<H> = serde_json::from_slice(bytes).map_err(ParseError::Malformed)?;
+    let found = msg.round_id();
+    if found != round {
+        return Err(ParseError::UnexpectedRound {
+            expected: round,
+            found,
+        });
+    }
+    validate_message(msg)
+}
+
+/// Performs stateless checks of [parse_keygen_message] on already deserialized message
+pub(super) fn validate_message<H: Digest + Clone>(
+    msg: ProtocolMessage<H>,
+) -> Result<KeygenMessage<H>, ParseError> {
+    match msg.0 {
+        M::Round1((bc1, party_identities)) => {
+            validate_dlog_statement(&bc1.dlog_statement)
+                .map_err(ParseError::InvalidDLogStatement)?;
+            // Proofs verification doesn't expect degenerate moduli
+            let modulus_valid = |n: &BigInt| n > &BigInt::one() && n.test_bit(0);
+            if !modulus_valid(&bc1.e.n) || !modulus_valid(&bc1.dlog_statement.N) {
+                return Err(ParseError::InvalidKeyProofs);
+            }
+            if !bc1.verify_proofs() {
+                return Err(ParseError::InvalidKeyProofs);
+            }
+            Ok(KeygenMessage::Round1 {
+                bc1,
+                party_identities,
+            })
+        }
+        M::Round2(decom) => {
+            if decom.y_i.is_zero() {
+                return Err(ParseError::ZeroPublicKey);
+            }
+            Ok(KeygenMessage::Round2(decom))
+        }
+        M::Round3(Round3Message {
+            vss_scheme,
+            encrypted_share,
+            curve,
+            recovery_backups,
+            binding_tag,
+            encryption_proof,
+        }) => {
+            let params = &vss_scheme.parameters;
+            let well_formed = curve == Secp256k1::CURVE_NAME
+                && params.threshold < params.share_count
+                && vss_scheme.commitments.len() == usize::from(params.threshold) + 1
+                && !vss_scheme.commitments.iter().any(|c| c.is_zero());
+            if !well_formed {
+                return Err(ParseError::InvalidVssScheme);
+            }
+            Ok(KeygenMessage::Round3 {
+                vss_scheme,
+                encrypted_share,
+                curve,
+                recovery_backups,
+                binding_tag,
+                encryption_proof,
+            })
+        }
+        M::Round4(proof) => {
+            DLogProof::verify(&proof).map_err(|_| ParseError::InvalidDLogProof)?;
+            Ok(KeygenMessage::Round4(proof))
+        }
+        M::Round5(confirmation) => Ok(KeygenMessage::Round5(confirmation)),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use curv::elliptic::curves::Scalar;
+    use round_based::StateMachine;
+    use sha2::Sha512;
+
+    use super::super::Keygen;
+    use super::*;
+
+    #[test]
+    fn parses_messages_of_honest_parties_and_rejects_garbage() {
+        let mut party = Keygen::new(1, 1, 2).unwrap();
+        party.proceed().unwrap();
+        let bytes = serde_json::to_vec(&party.message_queue()[0].body).unwrap();
+
+        assert!(matches!(
+            parse_keygen_message::<Sha256>(1, &bytes),
+            Ok(KeygenMessage::Round1 { .. })
+        ));
+        assert!(matches!(
+            parse_keygen_message::<Sha256>(2, &bytes),
+            Err(ParseError::UnexpectedRound {
+                expected: 2,
+                found: 1
+            })
+        ));
+        for len in [0, 1, bytes.len() / 2, bytes.len() - 1].iter() {
+            assert!(matches!(
+                parse_keygen_message::<Sha256>(1, &bytes[..*len]),
+                Err(ParseError::Malformed(_))
+            ));
+        }
+
+        let mut msg: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        let bc1 = &mut msg["Round1"][0];
+        assert_ne!(
+            bc1["composite_dlog_proof_base_h1"],
+            bc1["composite_dlog_proof_base_h2"]
+        );
+        bc1["composite_dlog_proof_base_h1"] = bc1["composite_dlog_proof_base_h2"].clone();
+        let tampered = serde_json::to_vec(&msg).unwrap();
+        assert!(matches!(
+            parse_keygen_message::<Sha256>(1, &tampered),
+            Err(ParseError::InvalidKeyProofs)
+        ));
+    }
+
+    #[test]
+    fn parses_dlog_proof_made_with_custom_hash() {
+        let proof = DLogProof::<Secp256k1, Sha512>::prove(&Scalar::random());
+        let bytes = serde_json::to_vec(&ProtocolMessage(M::Round4(proof), None)).unwrap();
+        assert!(matches!(
+            parse_keygen_message::<Sha512>(4, &bytes),
+            Ok(KeygenMessage::Round4(_))
+        ));
+        assert!(matches!(
+            parse_keygen_message::<Sha256>(4, &bytes),
+            Err(ParseError::InvalidDLogProof)
+        ));
+    }
+}
@@ -0,0 +1,103 @@
+//! Reconstruction of the shared secret key, for disaster recovery only
+
+use curv::elliptic::curves::{secp256_k1::Secp256k1, Point, Scalar};
+use thiserror::Error;
+
+use super::LocalKey;
+
+impl LocalKey<Secp256k1> {
+    /// Reconstructs **the full private key** from `t+1` or more keys of the same keygen
+    ///
+    /// **Dangerous:** reconstructed key alone is enough to sign anything, which defeats the point
+    /// of threshold signing. Only use it for escrow or disaster recovery, in a controlled
+    /// environment, and wipe the result as soon as possible.
+    ///
+    /// Secret is interpolated from `keys_linear` shares, and checked against the public key.
+    /// Returns error if fewer than `t+1` distinct parties' keys are given, or keys don't belong
+    /// to the same keygen.
+    pub fn reconstruct_secret(
+        keys: &[LocalKey<Secp256k1>],
+    ) -> Result<Scalar<Secp256k1>, ReconstructError> {
+        let first = keys.first().ok_or(ReconstructError::NotEnoughShares {
+            required: 1,
+            got: 0,
+        })?;
+        if keys
+            .iter()
+            .any(|k| k.y_sum_s != first.y_sum_s || k.t != first.t || k.n != first.n)
+        {
+            return Err(ReconstructError::KeysMismatch);
+        }
+
+        let mut indexes: Vec<u16> = keys.iter().map(|k| k.i).collect();
+        indexes.sort_unstable();
+        if let Some(pair) = indexes.windows(2).find(|pair| pair[0] == pair[1]) {
+            return Err(ReconstructError::DuplicateShare { party: pair[0] });
+        }
+        let required = usize::from(first.t) + 1;
+        if keys.len() < required {
+            return Err(ReconstructError::NotEnoughShares {
+                required,
+                got: keys.len(),
+            });
+        }
+
+        let indexes: Vec<u16> = keys.iter().map(|k| k.i - 1).collect();
+        let shares: Vec<Scalar<Secp256k1>> =
+            keys.iter().map(|k| k.keys_linear.x_i.clone()).collect();
+        let secret = first.vss_scheme.reconstruct(&indexes, &shares);
+        if Point::generator() * &secret != first.y_sum_s {
+            return Err(ReconstructError::PublicKeyMismatch);
+        }
+        Ok(secret)
+    }
+}
+
+/// Error of [LocalKey::reconstruct_secret]
+#[derive(Debug, Error)]
+pub enum ReconstructError {
+    #[error("at least {required} keys are required, got {got}")]
+    NotEnoughShares { required: usize, got: usize },
+    #[error("key of party {party} is given more than once")]
+    DuplicateShare { party: u16 },
+    #[error("keys don't belong to the same keygen")]
+    KeysMismatch,
+    #[error("reconstructed secret doesn't match public key")]
+    PublicKeyMismatch,
+}
+
+#[cfg(test)]
+mod test {
+    use crate::protocols::multi_party_ecdsa::gg_2020::state_machine::keygen::test::simulate_keygen;
+
+    use super::*;
+
+    #[test]
+    fn secret_is_reconstructed_from_threshold_of_keys() {
+        let keys = simulate_keygen(1, 3);
+        let secret = LocalKey::reconstruct_secret(&keys[1..]).unwrap();
+        assert_eq!(Point::generator() * &secret, keys[0].public_key());
+        assert_eq!(
+            LocalKey::reconstruct_secret(&[keys[2].clone(), keys[0].clone()]).unwrap(),
+            secret
+        );
+
+        assert!(matches!(
+            LocalKey::reconstruct_secret(&keys[..1]),
+            Err(ReconstructError::NotEnoughShares {
+                required: 2,
+                got: 1
+            })
+        ));
+        assert!(matches!(
+            LocalKey::reconstruct_secret(&[keys[1].clone(), keys[1].clone()]),
+            Err(ReconstructError::DuplicateShare { party: 2 })
+        ));
+
+        let other_keygen = simulate_keygen(1, 3);
+        assert!(matches!(
+            LocalKey::reconstruct_secret(&[keys[0].clone(), other_keygen[1].clone()]),
+            Err(ReconstructError::KeysMismatch)
+        ));
+    }
+}
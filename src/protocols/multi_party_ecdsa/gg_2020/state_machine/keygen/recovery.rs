@@ -0,0 +1,89 @@
+//! Designated recovery party holding encrypted backups of every party's share
+
+use curv::arithmetic::traits::*;
+use curv::elliptic::curves::{secp256_k1::Secp256k1, Scalar};
+use curv::BigInt;
+use paillier::{Decrypt, DecryptionKey, EncryptionKey, Paillier, RawCiphertext, RawPlaintext};
+use serde::{Deserialize, Serialize};
+
+use super::LocalKey;
+
+/// Party that additionally receives backups of all the shares at keygen
+///
+/// Every dealer encrypts shares it deals to all the parties under recovery Paillier key `ek` and
+/// sends them to party `index` in round 3. The recovery party only stores the ciphertexts, so
+/// the matching decryption key should be kept offline, separately from the [LocalKey]: holder
+/// of both can reconstruct the whole signing key (see
+/// [Keygen::with_recovery_party](super::Keygen::with_recovery_party)).
+///
+/// Every party must be configured with the same recovery party.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RecoveryParty {
+    /// Index of the recovery party in range `[1; n]`
+    pub index: u16,
+    /// Recovery Paillier key, backups are encrypted under it
+    pub ek: EncryptionKey,
+}
+
+impl LocalKey<Secp256k1> {
+    /// Reconstructs secret share of `party` (index in range `[1; n]`) from recovery backups
+    ///
+    /// Returns `None` if this key doesn't belong to the recovery party, or `party` is out of
+    /// range. Backups are not verified at keygen as only `recovery_dk` holder can decrypt them,
+    /// so reconstructed share should be checked against party's public share
+    /// (`pk_vec[party - 1]`).
+    pub fn recover_share(
+        &self,
+        party: u16,
+        recovery_dk: &DecryptionKey,
+    ) -> Option<Scalar<Secp256k1>> {
+        let j = usize::from(party).checked_sub(1)?;
+        if self.recovery_backups.len() != usize::from(self.n) {
+            return None;
+        }
+        let mut share = Scalar::<Secp256k1>::zero();
+        for dealt in &self.recovery_backups {
+            let c = RawCiphertext::from(BigInt::from_bytes(dealt.get(j)?));
+            let dealt_share: RawPlaintext<'_> = Paillier::decrypt(recovery_dk, c);
+            share = share + Scalar::from_bigint(&dealt_share.0.into_owned());
+        }
+        Some(share)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use curv::elliptic::curves::Point;
+    use paillier::KeyGeneration;
+    use round_based::dev::Simulation;
+
+    use super::*;
+    use crate::protocols::multi_party_ecdsa::gg_2020::state_machine::keygen::Keygen;
+
+    #[test]
+    fn recovery_party_reconstructs_any_share() {
+        let (t, n) = (1, 3);
+        let (recovery_ek, recovery_dk) = Paillier::keypair().keys();
+        let recovery = RecoveryParty {
+            index: 1,
+            ek: recovery_ek,
+        };
+
+        let mut simulation = Simulation::new();
+        for i in 1..=n {
+            simulation.add_party(Keygen::with_recovery_party(i, t, n, recovery.clone()).unwrap());
+        }
+        let keys = simulation.run().unwrap();
+
+        assert!(keys[1..].iter().all(|k| k.recovery_backups.is_empty()));
+        for key in &keys {
+            let share = keys[0].recover_share(key.i, &recovery_dk).unwrap();
+            assert_eq!(share, key.keys_linear.x_i);
+            assert_eq!(
+                Point::generator() * &share,
+                key.pk_vec[usize::from(key.i - 1)]
+            );
+        }
+        assert!(keys[1].recover_share(1, &recovery_dk).is_none());
+    }
+}
@@ -0,0 +1,70 @@
+//! Human-readable summary of [LocalKey]
+
+use std::fmt::Write;
+
+use curv::arithmetic::traits::*;
+use curv::elliptic::curves::{secp256_k1::Secp256k1, Curve};
+
+use super::LocalKey;
+
+impl LocalKey<Secp256k1> {
+    /// Multi-line textual summary of the key, suitable for attaching to support tickets
+    ///
+    /// Report contains party index, threshold parameters, public key fingerprint, Paillier modulus
+    /// sizes and result of [consistency check](Self::verify_keys_linear_consistency). It never
+    /// includes secret material (secret share and Paillier decryption key).
+    pub fn report(&self) -> String {
+        let fingerprint: String = self
+            .fingerprint()
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect();
+        let validation = match self.verify_keys_linear_consistency() {
+            Ok(()) => "ok".to_string(),
+            Err(err) => format!("failed: {}", err),
+        };
+
+        let mut report = String::new();
+        // Writing to a String never fails
+        let _ = writeln!(report, "curve: {}", Secp256k1::CURVE_NAME);
+        let _ = writeln!(report, "party: {}", self.i);
+        let _ = writeln!(report, "parameters: t={}, n={}", self.t, self.n);
+        let _ = writeln!(report, "public key fingerprint: {}", fingerprint);
+        for (j, ek) in (1..).zip(&self.paillier_key_vec) {
+            let _ = writeln!(
+                report,
+                "paillier modulus of party {}: {} bits",
+                j,
+                ek.n.bit_length()
+            );
+        }
+        let _ = write!(report, "validation: {}", validation);
+        report
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use curv::arithmetic::traits::*;
+
+    use crate::protocols::multi_party_ecdsa::gg_2020::state_machine::keygen::test::simulate_keygen;
+
+    #[test]
+    fn report_contains_public_info_only() {
+        let keys = simulate_keygen(1, 3);
+        let report = keys[0].report();
+
+        let fingerprint: String = keys[0]
+            .fingerprint()
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect();
+        assert!(report.contains(&fingerprint));
+        assert!(report.contains("t=1, n=3"));
+        assert!(report.contains("validation: ok"));
+
+        let x_i = keys[0].keys_linear.x_i.to_bigint();
+        assert!(!report.contains(&x_i.to_hex()));
+        assert!(!report.contains(&keys[0].paillier_dk.p.to_hex()));
+    }
+}
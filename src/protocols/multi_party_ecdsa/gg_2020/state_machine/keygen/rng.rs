@@ -0,0 +1,12 @@
+//! Plugging external source of randomness (e.g. HSM or certified DRBG) into keygen
+
+/// Source of random bytes for party's secrets, see
+/// [Keygen::with_rng_provider](super::Keygen::with_rng_provider)
+///
+/// Provider must be cryptographically secure: secret key share of the party is only as secret as
+/// the bytes it produces. It's shared between rounds, so it takes `&self` and must synchronize
+/// internal state itself.
+pub trait RngProvider: Send + Sync {
+    /// Fills `dest` with random bytes
+    fn fill_bytes(&self, dest: &mut [u8]);
+}
@@ -0,0 +1,56 @@
+//! Keygen metrics recorded via `metrics` crate, available only with `metrics` feature
+//!
+//! * `mp_ecdsa_keygen_round_duration_seconds` — histogram of round proceeding time, labeled
+//!   by `round` and `expensive`
+//! * `mp_ecdsa_keygen_messages_sent_total` / `mp_ecdsa_keygen_messages_received_total` —
+//!   counters of messages, labeled by `round` the message belongs to
+//! * `mp_ecdsa_keygen_round0_broadcast_bytes` — gauge of serialized size of party's round 0
+//!   broadcast (commitment, Paillier key and `(N_tilde, h1, h2)` with proofs)
+
+use std::time::Duration;
+
+use round_based::Msg;
+use sha2::Digest;
+
+use crate::MessageRoundID;
+
+use super::ProtocolMessage;
+
+/// Records round `round` that has just proceeded in `duration` and sent `sent` messages
+pub(super) fn round_proceeded<H: Digest + Clone>(
+    round: u16,
+    expensive: bool,
+    duration: Duration,
+    sent: &[Msg<ProtocolMessage<H>>],
+) {
+    metrics::histogram!(
+        "mp_ecdsa_keygen_round_duration_seconds",
+        duration.as_secs_f64(),
+        "round" => round.to_string(),
+        "expensive" => if expensive { "true" } else { "false" }
+    );
+    for msg in sent {
+        metrics::counter!(
+            "mp_ecdsa_keygen_messages_sent_total",
+            1,
+            "round" => msg.body.round_id().to_string()
+        );
+    }
+    if round == 0 {
+        let size: usize = sent
+            .iter()
+            .filter(|msg| msg.receiver.is_none())
+            .map(|msg| serde_json::to_vec(&msg.body).map_or(0, |bytes| bytes.len()))
+            .sum();
+        metrics::gauge!("mp_ecdsa_keygen_round0_broadcast_bytes", size as f64);
+    }
+}
+
+/// Records a message of round `round` accepted by the keygen
+pub(super) fn message_received(round: u16) {
+    metrics::counter!(
+        "mp_ecdsa_keygen_messages_received_total",
+        1,
+        "round" => round.to_string()
+    );
+}
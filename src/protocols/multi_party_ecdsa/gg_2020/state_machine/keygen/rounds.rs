@@ -1,15 +1,22 @@
-use curv::arithmetic::Converter;
+use curv::arithmetic::traits::*;
 use curv::cryptographic_primitives::proofs::sigma_dlog::DLogProof;
 use curv::elliptic::curves::{secp256_k1::Secp256k1, Curve, Point, Scalar};
 use curv::BigInt;
-use sha2::Sha256;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
 
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
+use zeroize::{Zeroize, ZeroizeOnDrop};
 
 use paillier::Paillier;
 use paillier::{Decrypt, Encrypt};
-use paillier::{EncryptionKey, RawCiphertext, RawPlaintext};
+use paillier::{EncryptionKey, RawPlaintext};
+#[cfg(feature = "testing")]
+use rand::{CryptoRng, RngCore};
 use round_based::containers::push::Push;
 use round_based::containers::{self, BroadcastMsgs, MessageStore, P2PMsgs, P2PMsgsStore, Store};
 use round_based::{IsCritical, Msg};
@@ -17,63 +24,309 @@ use zk_paillier::zkproofs::DLogStatement;
 
 use crate::protocols::multi_party_ecdsa::gg_2018::VerifiableSS;
 use crate::protocols::multi_party_ecdsa::gg_2020::party_i::{
-    KeyGenBroadcastMessage1, KeyGenDecommitMessage1, Keys,
+    self, KeyGenBroadcastMessage1, KeyGenDecommitMessage1, Keys, PaillierSetup, SafePrimePool,
+    SignatureRecid, DEFAULT_PAILLIER_BITS,
 };
+use crate::protocols::multi_party_ecdsa::gg_2020::state_machine::sign;
 use crate::protocols::multi_party_ecdsa::gg_2020::{self, ErrorType};
+use crate::utilities::merkle;
+
+use super::recovery::RecoveryParty;
+use super::rng::RngProvider;
+use super::share_cipher::{PaillierShareCipher, ShareCipher};
+use super::share_proof::{encrypt_share_with_proof, verify_share_encryption, ShareEncryptionProof};
 
 pub struct Round0 {
     pub party_i: u16,
     pub t: u16,
     pub n: u16,
+    /// Use insecure, dev-only Paillier setup (see [PaillierSetup::generate_insecure_dev_fast])
+    ///
+    /// Not public, so the flag can be set only via
+    /// [Keygen::new_dev_fast_proofs](super::Keygen::new_dev_fast_proofs)
+    #[cfg(feature = "dev_fast_proofs")]
+    pub(super) dev_fast_proofs: bool,
+    /// Party keys created from [prime pool](Self::with_prime_pool) or supplied by caller (see
+    /// [Round0::proceed_with_keys])
+    pub pool_keys: Option<Keys>,
+    /// Precomputed Paillier key and proofs of its correctness
+    pub paillier_setup: Option<PaillierSetup>,
+    /// Size of Paillier modulus and `N_tilde` (in bits), every party must use the same size
+    pub paillier_bits: usize,
+    /// Long-term identities of parties, ordered by party index (empty if not used)
+    pub party_identities: Vec<Point<Secp256k1>>,
+    /// Keygen session refuses to proceed once it gets older than given age
+    pub session_expiry: Option<SessionExpiry>,
+    /// Party receiving encrypted backups of all the shares (see [RecoveryParty])
+    pub recovery_party: Option<RecoveryParty>,
+    /// Encryption of shares sent to other parties
+    pub share_cipher: Arc<dyn ShareCipher>,
+    /// How shares are delivered to other parties, every party must use the same mode
+    pub transport_mode: TransportMode,
+    /// Attach [proofs of correct encryption](ShareEncryptionProof) to shares sent at round 3 and
+    /// require them from other parties, see
+    /// [Keygen::with_share_encryption_proofs](super::Keygen::with_share_encryption_proofs)
+    pub share_encryption_proofs: bool,
+    /// Source of randomness for fresh party keys and VSS polynomial, OS RNG is used if not set
+    pub rng_provider: Option<Arc<dyn RngProvider>>,
+}
+
+/// Wipes [pool keys](Round0::pool_keys) and decryption key of [Paillier setup](Round0::paillier_setup)
+impl Zeroize for Round0 {
+    fn zeroize(&mut self) {
+        if let Some(keys) = &mut self.pool_keys {
+            keys.zeroize();
+        }
+        if let Some(setup) = &mut self.paillier_setup {
+            setup.dk.p.zeroize();
+            setup.dk.q.zeroize();
+            setup.xhi.zeroize();
+            setup.xhi_inv.zeroize();
+        }
+    }
+}
+
+impl Drop for Round0 {
+    fn drop(&mut self) {
+        self.zeroize()
+    }
 }
 
 impl Round0 {
-    pub fn proceed<O>(self, mut output: O) -> Result<Round1>
+    /// Abandons keygen at this round, wiping party's secrets
+    ///
+    /// Every round wipes its secrets when it's dropped, so it's the same as dropping the round,
+    /// but makes giving up on a faulty session explicit. Every round has the same method, see
+    /// also [Keygen::abort](super::Keygen::abort).
+    pub fn abort(self) {
+        drop(self)
+    }
+
+    pub fn new(party_i: u16, t: u16, n: u16) -> Self {
+        Self {
+            party_i,
+            t,
+            n,
+            #[cfg(feature = "dev_fast_proofs")]
+            dev_fast_proofs: false,
+            pool_keys: None,
+            paillier_setup: None,
+            paillier_bits: DEFAULT_PAILLIER_BITS,
+            party_identities: vec![],
+            recovery_party: None,
+            session_expiry: None,
+            share_cipher: Arc::new(PaillierShareCipher),
+            transport_mode: TransportMode::Paillier,
+            share_encryption_proofs: false,
+            rng_provider: None,
+        }
+    }
+
+    /// Constructs round which uses primes drawn from the `pool` to create party keys
+    ///
+    /// Returns `None` if pool doesn't have enough primes.
+    pub fn with_prime_pool(party_i: u16, t: u16, n: u16, pool: &mut SafePrimePool) -> Option<Self> {
+        let keys = Keys::create_from_prime_pool(party_i as usize, pool)?;
+        let mut round = Self::new(party_i, t, n);
+        round.pool_keys = Some(keys);
+        Some(round)
+    }
+
+    pub fn proceed<O>(mut self, mut output: O) -> Result<Round1>
     where
-        O: Push<Msg<gg_2020::party_i::KeyGenBroadcastMessage1>>,
+        O: Push<Msg<(KeyGenBroadcastMessage1, Vec<Point<Secp256k1>>)>>,
     {
-        let party_keys = Keys::create(self.party_i as usize);
-        let (bc1, decom1) =
-            party_keys.phase1_broadcast_phase3_proof_of_correct_key_proof_of_correct_h1h2();
+        if self.n < 2 || self.t >= self.n || self.party_i == 0 || self.party_i > self.n {
+            return Err(ProceedError::InvalidParameters {
+                party_i: self.party_i,
+                t: self.t,
+                n: self.n,
+            });
+        }
+        check_session_expiry(self.session_expiry)?;
+        let index = self.party_i as usize;
+        if let Some(keys) = &self.pool_keys {
+            if keys.party_index != index {
+                return Err(ProceedError::KeysPartyMismatch {
+                    party_i: self.party_i,
+                    keys_party_index: keys.party_index,
+                });
+            }
+        }
+        // Keys from prime pool take precedence over Paillier setup
+        let setup = match &self.pool_keys {
+            Some(_) => None,
+            None => self.paillier_setup.as_ref(),
+        };
+        #[cfg(feature = "dev_fast_proofs")]
+        let dev_setup = match setup {
+            None if self.pool_keys.is_none() && self.dev_fast_proofs => {
+                Some(PaillierSetup::generate_insecure_dev_fast())
+            }
+            _ => None,
+        };
+        #[cfg(feature = "dev_fast_proofs")]
+        let setup = setup.or(dev_setup.as_ref());
+        let party_keys = match (self.pool_keys.take(), setup) {
+            (Some(keys), _) => keys,
+            (None, Some(setup)) => Keys::create_from_paillier_setup(index, setup),
+            (None, None) => self.create_keys(index),
+        };
+        let (bc1, decom1) = match setup {
+            Some(setup) => party_keys.phase1_broadcast_with_paillier_setup(setup),
+            None => party_keys.phase1_broadcast_phase3_proof_of_correct_key_proof_of_correct_h1h2(),
+        };
 
         output.push(Msg {
             round: 1,
             sender: self.party_i,
             receiver: None,
-            body: bc1.clone(),
+            body: (bc1.clone(), self.party_identities.clone()),
         });
         Ok(Round1 {
             keys: party_keys,
             bc1,
             decom1,
+            party_identities: std::mem::take(&mut self.party_identities),
+            session_expiry: self.session_expiry,
+            recovery_party: self.recovery_party.take(),
+            paillier_bits: self.paillier_bits,
+            share_cipher: self.share_cipher.clone(),
+            transport_mode: self.transport_mode,
+            share_encryption_proofs: self.share_encryption_proofs,
+            rng_provider: self.rng_provider.take(),
             party_i: self.party_i,
             t: self.t,
             n: self.n,
         })
     }
+    /// Creates fresh party keys, sampling them from [rng provider](Self::rng_provider) if it's set
+    fn create_keys(&self, index: usize) -> Keys {
+        match &self.rng_provider {
+            Some(rng) => {
+                Keys::create_with_random_bytes(index, self.paillier_bits, |b| rng.fill_bytes(b))
+            }
+            None => Keys::create_with_modulus_bits(index, self.paillier_bits),
+        }
+    }
+    /// Proceeds round using pre-generated party `keys` instead of generating fresh ones
+    ///
+    /// Allows generating Paillier keys elsewhere (e.g. in HSM) or reusing fixture keys in tests.
+    /// Keys must be created for this party, i.e. `keys.party_index` must be equal to `party_i`,
+    /// otherwise [ProceedError::KeysPartyMismatch] is returned.
+    pub fn proceed_with_keys<O>(mut self, keys: Keys, output: O) -> Result<Round1>
+    where
+        O: Push<Msg<(KeyGenBroadcastMessage1, Vec<Point<Secp256k1>>)>>,
+    {
+        self.pool_keys = Some(keys);
+        self.proceed(output)
+    }
     pub fn is_expensive(&self) -> bool {
         true
     }
 }
 
+/// Share cipher of rounds restored from [saved state](super::Keygen::load_state)
+fn default_share_cipher() -> Arc<dyn ShareCipher> {
+    Arc::new(PaillierShareCipher)
+}
+
+#[derive(Serialize, Deserialize)]
 pub struct Round1 {
     keys: Keys,
     bc1: KeyGenBroadcastMessage1,
     decom1: KeyGenDecommitMessage1,
+    party_identities: Vec<Point<Secp256k1>>,
+    session_expiry: Option<SessionExpiry>,
+    recovery_party: Option<RecoveryParty>,
+    paillier_bits: usize,
+    #[serde(skip, default = "default_share_cipher")]
+    share_cipher: Arc<dyn ShareCipher>,
+    transport_mode: TransportMode,
+    #[serde(default)]
+    share_encryption_proofs: bool,
+    #[serde(skip)]
+    rng_provider: Option<Arc<dyn RngProvider>>,
     party_i: u16,
     t: u16,
     n: u16,
 }
 
+/// Wipes party keys
+impl Zeroize for Round1 {
+    fn zeroize(&mut self) {
+        self.keys.zeroize();
+    }
+}
+
 impl Round1 {
+    /// Abandons keygen at this round, wiping party's secrets, see [Round0::abort]
+    pub fn abort(self) {
+        drop(self)
+    }
+
+    /// Replaces share cipher, which is not part of [saved state](super::Keygen::save_state)
+    pub(super) fn set_share_cipher(&mut self, share_cipher: Arc<dyn ShareCipher>) {
+        self.share_cipher = share_cipher;
+    }
+
+    /// Indicates whether party samples its secrets from [rng provider](RngProvider)
+    pub(super) fn uses_rng_provider(&self) -> bool {
+        self.rng_provider.is_some()
+    }
+
+    /// Replaces rng provider, which is not part of [saved state](super::Keygen::save_state)
+    pub(super) fn set_rng_provider(&mut self, rng_provider: Arc<dyn RngProvider>) {
+        self.rng_provider = Some(rng_provider);
+    }
+
     pub fn proceed<O>(
         self,
-        input: BroadcastMsgs<KeyGenBroadcastMessage1>,
+        input: BroadcastMsgs<(KeyGenBroadcastMessage1, Vec<Point<Secp256k1>>)>,
         mut output: O,
     ) -> Result<Round2>
     where
         O: Push<Msg<gg_2020::party_i::KeyGenDecommitMessage1>>,
     {
+        check_session_expiry(self.session_expiry)?;
+        let (received_comm, received_identities): (Vec<_>, Vec<_>) = collect_broadcasts(
+            1,
+            input,
+            self.party_i,
+            (self.bc1, self.party_identities.clone()),
+            self.n,
+        )?
+        .into_iter()
+        .unzip();
+        let own_n = &self.keys.ek.n;
+        debug_assert!(
+            check_own_slot(1, &received_comm, self.party_i, |bc1| &bc1.e.n == own_n).is_ok(),
+            "own round 1 commitment is misplaced"
+        );
+        let mismatched: Vec<u16> = (1..)
+            .zip(&received_identities)
+            .filter(|(_, identities)| **identities != self.party_identities)
+            .map(|(j, _)| j)
+            .collect();
+        if !mismatched.is_empty() {
+            return Err(ProceedError::PartyIdentitiesMismatch {
+                parties: mismatched,
+            });
+        }
+        let expected_bits = (self.paillier_bits - 1)..=self.paillier_bits;
+        for (party, bc1) in (1..).zip(&received_comm) {
+            let bits = bc1.e.n.bit_length();
+            if !expected_bits.contains(&bits) {
+                return Err(ProceedError::PaillierKeySizeMismatch { party, bits });
+            }
+        }
+        for (party, bc1) in (1..).zip(&received_comm) {
+            let bits = bc1.dlog_statement.N.bit_length();
+            if !expected_bits.contains(&bits) {
+                return Err(ProceedError::DLogStatementSizeMismatch { party, bits });
+            }
+        }
+
         output.push(Msg {
             round: 2,
             sender: self.party_i,
@@ -82,8 +335,16 @@ impl Round1 {
         });
         Ok(Round2 {
             keys: self.keys,
-            received_comm: input.into_vec_including_me(self.bc1),
+            received_comm,
             decom: self.decom1,
+            party_identities: self.party_identities,
+            session_expiry: self.session_expiry,
+            recovery_party: self.recovery_party,
+            paillier_bits: self.paillier_bits,
+            share_cipher: self.share_cipher,
+            transport_mode: self.transport_mode,
+            share_encryption_proofs: self.share_encryption_proofs,
+            rng_provider: self.rng_provider,
 
             party_i: self.party_i,
             t: self.t,
@@ -93,62 +354,266 @@ impl Round1 {
     pub fn is_expensive(&self) -> bool {
         false
     }
-    pub fn expects_messages(i: u16, n: u16) -> Store<BroadcastMsgs<KeyGenBroadcastMessage1>> {
+    pub fn expects_messages(
+        i: u16,
+        n: u16,
+    ) -> Store<BroadcastMsgs<(KeyGenBroadcastMessage1, Vec<Point<Secp256k1>>)>> {
         containers::BroadcastMsgsStore::new(i, n)
     }
 }
 
+#[derive(Serialize, Deserialize)]
 pub struct Round2 {
     keys: gg_2020::party_i::Keys,
     received_comm: Vec<KeyGenBroadcastMessage1>,
     decom: KeyGenDecommitMessage1,
+    party_identities: Vec<Point<Secp256k1>>,
+    session_expiry: Option<SessionExpiry>,
+    recovery_party: Option<RecoveryParty>,
+    paillier_bits: usize,
+    #[serde(skip, default = "default_share_cipher")]
+    share_cipher: Arc<dyn ShareCipher>,
+    transport_mode: TransportMode,
+    #[serde(default)]
+    share_encryption_proofs: bool,
+    #[serde(skip)]
+    rng_provider: Option<Arc<dyn RngProvider>>,
 
     party_i: u16,
     t: u16,
     n: u16,
 }
 
+/// Wipes party keys
+impl Zeroize for Round2 {
+    fn zeroize(&mut self) {
+        self.keys.zeroize();
+    }
+}
+
 impl Round2 {
+    /// Abandons keygen at this round, wiping party's secrets, see [Round0::abort]
+    pub fn abort(self) {
+        drop(self)
+    }
+
+    /// Replaces share cipher, which is not part of [saved state](super::Keygen::save_state)
+    pub(super) fn set_share_cipher(&mut self, share_cipher: Arc<dyn ShareCipher>) {
+        self.share_cipher = share_cipher;
+    }
+
+    /// Indicates whether party samples its secrets from [rng provider](RngProvider)
+    pub(super) fn uses_rng_provider(&self) -> bool {
+        self.rng_provider.is_some()
+    }
+
+    /// Replaces rng provider, which is not part of [saved state](super::Keygen::save_state)
+    pub(super) fn set_rng_provider(&mut self, rng_provider: Arc<dyn RngProvider>) {
+        self.rng_provider = Some(rng_provider);
+    }
+
+    /// Snapshots the round, so it can be proceeded several times with different inputs
+    ///
+    /// Available only with `testing` feature.
+    #[cfg(feature = "testing")]
+    pub fn clone_state(&self) -> Self {
+        Self {
+            keys: self.keys.clone(),
+            received_comm: self.received_comm.clone(),
+            decom: self.decom.clone(),
+            party_identities: self.party_identities.clone(),
+            session_expiry: self.session_expiry,
+            recovery_party: self.recovery_party.clone(),
+            paillier_bits: self.paillier_bits,
+            share_cipher: self.share_cipher.clone(),
+            transport_mode: self.transport_mode,
+            share_encryption_proofs: self.share_encryption_proofs,
+            rng_provider: self.rng_provider.clone(),
+
+            party_i: self.party_i,
+            t: self.t,
+            n: self.n,
+        }
+    }
+
     pub fn proceed<O>(
+        self,
+        input: BroadcastMsgs<KeyGenDecommitMessage1>,
+        output: O,
+    ) -> Result<Round3>
+    where
+        O: Push<Msg<Round3Message>>,
+    {
+        let sharing = self.rng_provider.as_ref().map(|rng| {
+            share_with_random_bytes(self.t, self.n, &self.keys.u_i, |b| rng.fill_bytes(b))
+        });
+        self.proceed_with_sharing(input, output, sharing)
+    }
+
+    /// Same as [proceed](Self::proceed), but samples party's VSS polynomial from `rng`
+    ///
+    /// Along with [Round0::proceed_with_keys] it makes party's shares reproducible. **Insecure**:
+    /// anyone who knows rng seed learns party's secret. Available only with `testing` feature.
+    #[cfg(feature = "testing")]
+    pub fn proceed_with_rng<O, G>(
+        self,
+        input: BroadcastMsgs<KeyGenDecommitMessage1>,
+        output: O,
+        rng: &mut G,
+    ) -> Result<Round3>
+    where
+        G: RngCore + CryptoRng,
+        O: Push<Msg<Round3Message>>,
+    {
+        let sharing =
+            share_with_random_bytes(self.t, self.n, &self.keys.u_i, |b| rng.fill_bytes(b));
+        self.proceed_with_sharing(input, output, Some(sharing))
+    }
+
+    /// Proceeds round, uses given `sharing` of party's secret instead of fresh one if it's `Some`
+    fn proceed_with_sharing<O>(
         self,
         input: BroadcastMsgs<KeyGenDecommitMessage1>,
         mut output: O,
+        sharing: Option<(VerifiableSS<Secp256k1>, Vec<Scalar<Secp256k1>>)>,
     ) -> Result<Round3>
     where
-        O: Push<Msg<(VerifiableSS<Secp256k1>, Vec<u8>)>>,
+        O: Push<Msg<Round3Message>>,
     {
+        // Round restored from a saved state might have skipped the checks of Round0
+        if self.party_i == 0 || self.party_i > self.n {
+            return Err(ProceedError::InvalidParameters {
+                party_i: self.party_i,
+                t: self.t,
+                n: self.n,
+            });
+        }
+        check_session_expiry(self.session_expiry)?;
         let params = gg_2020::party_i::Parameters {
             threshold: self.t,
             share_count: self.n,
         };
-        let received_decom = input.into_vec_including_me(self.decom);
+        let received_decom = collect_broadcasts(2, input, self.party_i, self.decom, self.n)?;
+        let own_y = &self.keys.y_i;
+        check_own_slot(2, &received_decom, self.party_i, |decom| {
+            &decom.y_i == own_y
+        })?;
+        for (sender, decom) in (1..).zip(&received_decom) {
+            validate_public_share(&decom.y_i)
+                .map_err(|_| ProceedError::InvalidPublicShare { sender })?;
+        }
 
-        log::info!("MP-ECDSA : Round 2 : threshold {:?}", params.threshold);
-        log::info!("MP-ECDSA : Round 2 : share_count {:?}", params.share_count);
-        log::info!("MP-ECDSA : Round 2 : received_decom {:?}", received_decom);
+        for (party, bc1) in (1..).zip(&self.received_comm) {
+            validate_dlog_statement(&bc1.dlog_statement)
+                .map_err(|_| ProceedError::InvalidDLogStatement { party })?;
+        }
+        let paillier_keys: Vec<EncryptionKey> =
+            self.received_comm.iter().map(|bc1| bc1.e.clone()).collect();
+        check_distinct_paillier_keys(&paillier_keys)?;
 
-        let vss_result = self
+        #[cfg(feature = "tracing")]
+        {
+            tracing::debug!(
+                party_i = self.party_i,
+                round = 2,
+                t = params.threshold,
+                n = params.share_count,
+                "received decommitments"
+            );
+            tracing::trace!(?received_decom);
+        }
+        #[cfg(not(feature = "tracing"))]
+        {
+            log::debug!(
+                "MP-ECDSA : Round 2 : party {} threshold {} share_count {}",
+                self.party_i,
+                params.threshold,
+                params.share_count
+            );
+            log::trace!("MP-ECDSA : Round 2 : received_decom {:?}", received_decom);
+        }
+
+        let mut vss_result = self
             .keys
-            .phase1_verify_com_phase3_verify_correct_key_verify_dlog_phase2_distribute(
+            .phase1_verify_com_phase3_verify_correct_key_verify_dlog_phase2_distribute_with_paillier_bits(
                 &params,
                 &received_decom,
                 &self.received_comm,
+                self.paillier_bits,
             )
             .map_err(ProceedError::Round2VerifyCommitments)?;
+        if let Some((vss, shares)) = sharing {
+            vss_result.0 = vss;
+            vss_result.1 = shares;
+        }
+        if vss_result.1.len() != usize::from(self.n) {
+            return Err(ProceedError::InvalidShareCount {
+                expected: self.n,
+                actual: vss_result.1.len(),
+            });
+        }
+
+        let backups: Vec<Vec<u8>> = match &self.recovery_party {
+            Some(recovery) => vss_result
+                .1
+                .iter()
+                .map(|share| {
+                    Paillier::encrypt(&recovery.ek, RawPlaintext::from(share.to_bigint()))
+                        .0
+                        .to_bytes()
+                })
+                .collect(),
+            None => vec![],
+        };
+        let recovery_index = self.recovery_party.as_ref().map(|r| r.index);
 
         for (i, share) in vss_result.1.iter().enumerate() {
             if i + 1 == usize::from(self.party_i) {
                 continue;
             }
+            let receiver = i as u16 + 1;
 
-            let enc_key_for_recipient = &self.received_comm[i].e;
-            let encrypted_share =
-                Paillier::encrypt(enc_key_for_recipient, RawPlaintext::from(share.to_bigint()));
+            let (encrypted_share, encryption_proof) = match self.transport_mode {
+                TransportMode::Paillier if self.share_encryption_proofs => {
+                    // Range proof is bound to receiver's DLog statement, as the one of sender
+                    // can't be trusted
+                    let bc1 = &self.received_comm[i];
+                    let (ciphertext, proof) =
+                        encrypt_share_with_proof(share, &bc1.e, &bc1.dlog_statement);
+                    (ciphertext, Some(proof))
+                }
+                TransportMode::Paillier => {
+                    let enc_key_for_recipient = &self.received_comm[i].e;
+                    (
+                        self.share_cipher.encrypt(enc_key_for_recipient, share),
+                        None,
+                    )
+                }
+                TransportMode::ExternalSecureChannel => (share.to_bytes().to_vec(), None),
+            };
+            let binding_tag = share_binding_tag(
+                self.transport_mode,
+                self.party_i,
+                receiver,
+                &vss_result.0,
+                &encrypted_share,
+            );
             output.push(Msg {
                 round: 3,
                 sender: self.party_i,
-                receiver: Some(i as u16 + 1),
-                body: (vss_result.0.clone(), encrypted_share.0.to_bytes()),
+                receiver: Some(receiver),
+                body: Round3Message {
+                    vss_scheme: vss_result.0.clone(),
+                    encrypted_share,
+                    curve: Secp256k1::CURVE_NAME.to_string(),
+                    recovery_backups: if recovery_index == Some(receiver) {
+                        backups.clone()
+                    } else {
+                        vec![]
+                    },
+                    binding_tag,
+                    encryption_proof,
+                },
             })
         }
 
@@ -160,6 +625,13 @@ impl Round2 {
 
             own_vss: vss_result.0.clone(),
             own_share: vss_result.1[usize::from(self.party_i - 1)].clone(),
+            own_backups: backups,
+            party_identities: self.party_identities,
+            session_expiry: self.session_expiry,
+            recovery_party: self.recovery_party,
+            share_cipher: self.share_cipher,
+            transport_mode: self.transport_mode,
+            share_encryption_proofs: self.share_encryption_proofs,
 
             party_i: self.party_i,
             t: self.t,
@@ -174,6 +646,32 @@ impl Round2 {
     }
 }
 
+/// Message party sends to every other party at round 3
+///
+/// Sent over P2P channel. Replaced the tuple sent by earlier versions, so serialized message is
+/// not compatible with them.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Round3Message {
+    /// VSS scheme of party's secret
+    pub vss_scheme: VerifiableSS<Secp256k1>,
+    /// Share of the receiver, encrypted as [transport mode](TransportMode) prescribes
+    pub encrypted_share: Vec<u8>,
+    /// Name of the curve VSS scheme is made for
+    pub curve: String,
+    /// Backups of all the shares dealt by the party, encrypted for the recovery party; empty
+    /// unless receiver is the [recovery party](RecoveryParty)
+    pub recovery_backups: Vec<Vec<u8>>,
+    /// Binds encrypted share to the sender, the receiver and the VSS scheme
+    pub binding_tag: [u8; 32],
+    /// Proof that `encrypted_share` encrypts the share committed in `vss_scheme`, made for
+    /// receiver's DLog statement; sent only if [share encryption
+    /// proofs](super::Keygen::with_share_encryption_proofs) are enabled
+    #[serde(default)]
+    pub encryption_proof: Option<ShareEncryptionProof>,
+}
+
+#[cfg_attr(test, derive(Clone))]
+#[derive(Serialize, Deserialize)]
 pub struct Round3 {
     keys: gg_2020::party_i::Keys,
 
@@ -182,33 +680,155 @@ pub struct Round3 {
 
     own_vss: VerifiableSS<Secp256k1>,
     own_share: Scalar<Secp256k1>,
+    /// Backups of shares this party dealt, encrypted for the recovery party
+    own_backups: Vec<Vec<u8>>,
+    party_identities: Vec<Point<Secp256k1>>,
+    session_expiry: Option<SessionExpiry>,
+    recovery_party: Option<RecoveryParty>,
+    #[serde(skip, default = "default_share_cipher")]
+    share_cipher: Arc<dyn ShareCipher>,
+    transport_mode: TransportMode,
+    #[serde(default)]
+    share_encryption_proofs: bool,
 
     party_i: u16,
     t: u16,
     n: u16,
 }
 
+/// Wipes party keys and the share party dealt to itself
+impl Zeroize for Round3 {
+    fn zeroize(&mut self) {
+        self.keys.zeroize();
+        self.own_share = Scalar::zero();
+    }
+}
+
+impl Drop for Round3 {
+    fn drop(&mut self) {
+        self.zeroize()
+    }
+}
+
 impl Round3 {
-    pub fn proceed<O>(
-        self,
-        input: P2PMsgs<(VerifiableSS<Secp256k1>, Vec<u8>)>,
-        mut output: O,
-    ) -> Result<Round4>
+    /// Abandons keygen at this round, wiping party's secrets, see [Round0::abort]
+    pub fn abort(self) {
+        drop(self)
+    }
+
+    /// Replaces share cipher, which is not part of [saved state](super::Keygen::save_state)
+    pub(super) fn set_share_cipher(&mut self, share_cipher: Arc<dyn ShareCipher>) {
+        self.share_cipher = share_cipher;
+    }
+
+    pub fn proceed<O>(self, input: P2PMsgs<Round3Message>, output: O) -> Result<Round4>
     where
         O: Push<Msg<DLogProof<Secp256k1, Sha256>>>,
     {
+        self.proceed_with_hash(input, output)
+    }
+
+    /// Same as [proceed](Self::proceed), but DLog proof of party's secret share uses hash
+    /// function `H`, see [Keygen::with_dlog_hash](super::Keygen::with_dlog_hash)
+    pub fn proceed_with_hash<O, H>(
+        mut self,
+        input: P2PMsgs<Round3Message>,
+        mut output: O,
+    ) -> Result<Round4<H>>
+    where
+        O: Push<Msg<DLogProof<Secp256k1, H>>>,
+        H: Digest + Clone,
+    {
+        check_session_expiry(self.session_expiry)?;
         let params = gg_2020::party_i::Parameters {
             threshold: self.t,
             share_count: self.n,
         };
+        let recovery_ek = self
+            .recovery_party
+            .as_ref()
+            .filter(|r| r.index == self.party_i)
+            .map(|r| &r.ek);
+        let mut recovery_backups = vec![vec![]; usize::from(self.n)];
+        if recovery_ek.is_some() {
+            recovery_backups[usize::from(self.party_i - 1)] = self.own_backups.clone();
+        }
         let input: P2PMsgs<(VerifiableSS<Secp256k1>, Scalar<Secp256k1>)> = {
             let encrypted_input = input.into_iter_indexed();
             let mut decrypted_input = P2PMsgsStore::new(self.party_i, self.n);
-            for (i, (vss, encrypted_share)) in encrypted_input {
-                let v = BigInt::from_bytes(&encrypted_share);
-                let c = RawCiphertext::from(v);
-                let raw_share: RawPlaintext<'_> = Paillier::decrypt(&self.keys.dk, c);
-                let share = Scalar::from_bigint(&raw_share.0.into_owned());
+            for (i, msg) in encrypted_input {
+                let Round3Message {
+                    vss_scheme: vss,
+                    encrypted_share,
+                    curve,
+                    recovery_backups: backups,
+                    binding_tag,
+                    encryption_proof,
+                } = msg;
+                if curve != Secp256k1::CURVE_NAME {
+                    return Err(ProceedError::CurveMismatch { sender: i });
+                }
+                // Sender committed to its public share at round 1 and revealed it at round 2,
+                // vss scheme sent over p2p channel must share exactly that secret
+                let vss_consistent = vss.parameters.threshold == self.t
+                    && vss.parameters.share_count == self.n
+                    && vss.commitments.len() == usize::from(self.t) + 1
+                    && vss.commitments[0] == self.y_vec[usize::from(i - 1)];
+                if !vss_consistent {
+                    return Err(ProceedError::VssEquivocation { sender: i });
+                }
+                let tag_of =
+                    |mode| share_binding_tag(mode, i, self.party_i, &vss, &encrypted_share);
+                if tag_of(self.transport_mode) != binding_tag {
+                    if tag_of(self.transport_mode.opposite()) == binding_tag {
+                        return Err(ProceedError::TransportModeMismatch { sender: i });
+                    }
+                    return Err(ProceedError::ShareBindingMismatch { sender: i });
+                }
+                let backups_valid = match recovery_ek {
+                    Some(ek) => {
+                        backups.len() == usize::from(self.n)
+                            && backups
+                                .iter()
+                                .all(|backup| validate_encrypted_share(backup, ek).is_ok())
+                    }
+                    None => backups.is_empty(),
+                };
+                if !backups_valid {
+                    return Err(ProceedError::InvalidRecoveryBackups { sender: i });
+                }
+                recovery_backups[usize::from(i - 1)] = backups;
+                let share = match self.transport_mode {
+                    TransportMode::Paillier => {
+                        validate_encrypted_share(&encrypted_share, &self.keys.ek)
+                            .map_err(|_| ProceedError::InvalidCiphertext { sender: i })?;
+                        if self.share_encryption_proofs {
+                            let own_statement =
+                                &self.bc_vec[usize::from(self.party_i - 1)].dlog_statement;
+                            let proof_valid = encryption_proof.as_ref().map_or(false, |proof| {
+                                verify_share_encryption(
+                                    &vss,
+                                    self.party_i,
+                                    &encrypted_share,
+                                    &self.keys.ek,
+                                    own_statement,
+                                    proof,
+                                )
+                                .is_ok()
+                            });
+                            if !proof_valid {
+                                return Err(ProceedError::InvalidShareEncryptionProof {
+                                    sender: i,
+                                });
+                            }
+                        }
+                        self.share_cipher
+                            .decrypt(&self.keys.dk, &encrypted_share)
+                            .ok_or(ProceedError::Round3DecryptShare { from: i })?
+                    }
+                    TransportMode::ExternalSecureChannel => Scalar::from_bytes(&encrypted_share)
+                        .map_err(|_| ProceedError::Round3DecryptShare { from: i })?,
+                };
                 let _ = decrypted_input.push_msg(Msg {
                     round: 4,
                     sender: i,
@@ -220,12 +840,16 @@ impl Round3 {
         };
 
         let (vss_schemes, party_shares): (Vec<_>, Vec<_>) = input
-            .into_vec_including_me((self.own_vss, self.own_share))
+            .into_vec_including_me((self.own_vss.clone(), self.own_share.clone()))
             .into_iter()
             .unzip();
+        let own_y = &self.keys.y_i;
+        check_own_slot(3, &vss_schemes, self.party_i, |vss| {
+            vss.commitments.first() == Some(own_y)
+        })?;
         let (shared_keys, dlog_proof) = self
             .keys
-            .phase2_verify_vss_construct_keypair_phase3_pok_dlog(
+            .phase2_verify_vss_construct_keypair_phase3_pok_dlog_with_hash(
                 &params,
                 &self.y_vec,
                 &party_shares,
@@ -244,10 +868,17 @@ impl Round3 {
         Ok(Round4 {
             keys: self.keys.clone(),
             y_vec: self.y_vec.clone(),
-            bc_vec: self.bc_vec,
+            bc_vec: std::mem::take(&mut self.bc_vec),
             shared_keys,
             own_dlog_proof: dlog_proof,
             vss_vec: vss_schemes,
+            party_identities: std::mem::take(&mut self.party_identities),
+            session_expiry: self.session_expiry,
+            recovery_backups: if recovery_ek.is_some() {
+                recovery_backups
+            } else {
+                vec![]
+            },
 
             party_i: self.party_i,
             t: self.t,
@@ -257,34 +888,57 @@ impl Round3 {
     pub fn is_expensive(&self) -> bool {
         true
     }
-    pub fn expects_messages(i: u16, n: u16) -> Store<P2PMsgs<(VerifiableSS<Secp256k1>, Vec<u8>)>> {
+    pub fn expects_messages(i: u16, n: u16) -> Store<P2PMsgs<Round3Message>> {
         containers::P2PMsgsStore::new(i, n)
     }
 }
 
-pub struct Round4 {
+#[cfg_attr(test, derive(Clone))]
+#[derive(Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct Round4<H: Digest + Clone = Sha256> {
     keys: gg_2020::party_i::Keys,
     y_vec: Vec<Point<Secp256k1>>,
     bc_vec: Vec<gg_2020::party_i::KeyGenBroadcastMessage1>,
     shared_keys: gg_2020::party_i::SharedKeys<Secp256k1>,
-    own_dlog_proof: DLogProof<Secp256k1, Sha256>,
+    own_dlog_proof: DLogProof<Secp256k1, H>,
     vss_vec: Vec<VerifiableSS<Secp256k1>>,
+    party_identities: Vec<Point<Secp256k1>>,
+    session_expiry: Option<SessionExpiry>,
+    recovery_backups: Vec<Vec<Vec<u8>>>,
 
     party_i: u16,
     t: u16,
     n: u16,
 }
 
-impl Round4 {
+/// Wipes party keys and the secret share
+impl<H: Digest + Clone> Zeroize for Round4<H> {
+    fn zeroize(&mut self) {
+        self.keys.zeroize();
+        self.shared_keys.zeroize();
+    }
+}
+
+impl<H: Digest + Clone> Round4<H> {
+    /// Abandons keygen at this round, wiping party's secrets, see [Round0::abort]
+    pub fn abort(self) {
+        drop(self)
+    }
+
     pub fn proceed(
         self,
-        input: BroadcastMsgs<DLogProof<Secp256k1, Sha256>>,
+        input: BroadcastMsgs<DLogProof<Secp256k1, H>>,
     ) -> Result<LocalKey<Secp256k1>> {
+        check_session_expiry(self.session_expiry)?;
         let params = gg_2020::party_i::Parameters {
             threshold: self.t,
             share_count: self.n,
         };
         let dlog_proofs = input.into_vec_including_me(self.own_dlog_proof.clone());
+        check_own_slot(4, &dlog_proofs, self.party_i, |proof| {
+            proof.pk == self.own_dlog_proof.pk
+        })?;
 
         Keys::verify_dlog_proofs_check_against_vss(
             &params,
@@ -297,7 +951,104 @@ impl Round4 {
             .map(|i| dlog_proofs[i].pk.clone())
             .collect::<Vec<Point<Secp256k1>>>();
 
-        let paillier_key_vec = (0..params.share_count)
+        self.finalize(pk_vec)
+    }
+
+    /// Same as [proceed](Self::proceed), but reports progress of DLog proofs verification
+    ///
+    /// `progress(party, valid)` is called once for every party (including this one) right after
+    /// its DLog proof is verified, in order of party index.
+    pub fn proceed_with_progress<F>(
+        self,
+        input: BroadcastMsgs<DLogProof<Secp256k1, H>>,
+        progress: F,
+    ) -> Result<LocalKey<Secp256k1>>
+    where
+        F: Fn(u16, bool),
+    {
+        check_session_expiry(self.session_expiry)?;
+        let dlog_proofs = input.into_vec_including_me(self.own_dlog_proof.clone());
+        check_own_slot(4, &dlog_proofs, self.party_i, |proof| {
+            proof.pk == self.own_dlog_proof.pk
+        })?;
+        let xi_commitments = Keys::get_commitments_to_xi(&self.vss_vec);
+
+        let mut bad_actors = vec![];
+        for (i, (proof, xi_commitment)) in dlog_proofs.iter().zip(&xi_commitments).enumerate() {
+            let valid = DLogProof::verify(proof).is_ok() && *xi_commitment == proof.pk;
+            progress(i as u16 + 1, valid);
+            if !valid {
+                bad_actors.push(i);
+            }
+        }
+        if !bad_actors.is_empty() {
+            return Err(ProceedError::Round4VerifyDLogProof(ErrorType {
+                error_type: "bad dlog proof".to_string(),
+                bad_actors,
+                data: Vec::new(),
+            }));
+        }
+
+        self.finalize(dlog_proofs.into_iter().map(|proof| proof.pk).collect())
+    }
+
+    /// Same as [proceed](Self::proceed), but verifies DLog proofs one by one as they're yielded
+    /// by iterator instead of collecting them into a vector first
+    ///
+    /// `input` must yield `(party_index, proof)` exactly once for every party except this one,
+    /// otherwise returns [ProceedError::UnexpectedSenders].
+    pub fn proceed_iter<I>(self, input: I) -> Result<LocalKey<Secp256k1>>
+    where
+        I: IntoIterator<Item = (u16, DLogProof<Secp256k1, H>)>,
+    {
+        check_session_expiry(self.session_expiry)?;
+        let n = usize::from(self.n);
+        let xi_commitments = Keys::get_commitments_to_xi(&self.vss_vec);
+        let mut pk_vec: Vec<Option<Point<Secp256k1>>> = vec![None; n];
+        pk_vec[usize::from(self.party_i - 1)] = Some(self.own_dlog_proof.pk.clone());
+
+        let mut bad_actors = vec![];
+        let mut unexpected = vec![];
+        for (party, proof) in input {
+            let i = match usize::from(party).checked_sub(1) {
+                Some(i) if i < n && pk_vec[i].is_none() => i,
+                _ => {
+                    unexpected.push(party);
+                    continue;
+                }
+            };
+            let valid = DLogProof::verify(&proof).is_ok() && xi_commitments[i] == proof.pk;
+            if !valid {
+                bad_actors.push(i);
+            }
+            pk_vec[i] = Some(proof.pk);
+        }
+        let missing: Vec<u16> = (1..=self.n)
+            .zip(&pk_vec)
+            .filter(|(_, pk)| pk.is_none())
+            .map(|(j, _)| j)
+            .collect();
+        if !unexpected.is_empty() || !missing.is_empty() {
+            return Err(ProceedError::UnexpectedSenders {
+                round: 4,
+                unexpected,
+                missing,
+            });
+        }
+        if !bad_actors.is_empty() {
+            bad_actors.sort_unstable();
+            return Err(ProceedError::Round4VerifyDLogProof(ErrorType {
+                error_type: "bad dlog proof".to_string(),
+                bad_actors,
+                data: Vec::new(),
+            }));
+        }
+
+        self.finalize(pk_vec.into_iter().flatten().collect())
+    }
+
+    fn finalize(self, pk_vec: Vec<Point<Secp256k1>>) -> Result<LocalKey<Secp256k1>> {
+        let paillier_key_vec = (0..self.n)
             .map(|i| self.bc_vec[i as usize].e.clone())
             .collect::<Vec<EncryptionKey>>();
         let h1_h2_n_tilde_vec = self
@@ -305,12 +1056,19 @@ impl Round4 {
             .iter()
             .map(|bc1| bc1.dlog_statement.clone())
             .collect::<Vec<DLogStatement>>();
+        // Statements are checked at round 2 already. Round 4 might be resumed from a state saved
+        // by older version though, so they're checked again before they get into the key
+        for (party, statement) in (1..).zip(&h1_h2_n_tilde_vec) {
+            validate_dlog_statement(statement)
+                .map_err(|_| ProceedError::InvalidDLogStatement { party })?;
+        }
+        check_distinct_dlog_statements(&h1_h2_n_tilde_vec)?;
 
         let (head, tail) = self.y_vec.split_at(1);
         let y_sum = tail.iter().fold(head[0].clone(), |acc, x| acc + x);
 
-        let local_key = LocalKey {
-            paillier_dk: self.keys.dk,
+        Ok(LocalKey {
+            paillier_dk: self.keys.dk.clone(),
             pk_vec,
 
             keys_linear: self.shared_keys.clone(),
@@ -319,23 +1077,401 @@ impl Round4 {
             h1_h2_n_tilde_vec,
 
             vss_scheme: self.vss_vec[usize::from(self.party_i - 1)].clone(),
+            vss_scheme_vec: self.vss_vec.clone(),
+            party_identities: self.party_identities,
+            recovery_backups: self.recovery_backups,
 
             i: self.party_i,
             t: self.t,
             n: self.n,
-        };
-
-        Ok(local_key)
+        })
     }
     pub fn is_expensive(&self) -> bool {
         true
     }
-    pub fn expects_messages(i: u16, n: u16) -> Store<BroadcastMsgs<DLogProof<Secp256k1, Sha256>>> {
+    pub fn expects_messages(i: u16, n: u16) -> Store<BroadcastMsgs<DLogProof<Secp256k1, H>>> {
+        containers::BroadcastMsgsStore::new(i, n)
+    }
+}
+
+/// Round 5 message: hash of the public key derived by the party, see
+/// [Keygen::with_public_key_confirmation](super::Keygen::with_public_key_confirmation)
+pub type PublicKeyConfirmation = [u8; 32];
+
+/// Optional confirmation round: parties make sure they all derived the same public key
+#[derive(Serialize, Deserialize)]
+pub struct Round5 {
+    local_key: LocalKey<Secp256k1>,
+    own_confirmation: PublicKeyConfirmation,
+}
+
+/// Wipes the key being confirmed
+impl Zeroize for Round5 {
+    fn zeroize(&mut self) {
+        self.local_key.zeroize();
+    }
+}
+
+impl Round5 {
+    /// Abandons keygen at this round, wiping party's secrets, see [Round0::abort]
+    pub fn abort(self) {
+        drop(self)
+    }
+
+    pub fn new<O>(local_key: LocalKey<Secp256k1>, mut output: O) -> Self
+    where
+        O: Push<Msg<PublicKeyConfirmation>>,
+    {
+        let own_confirmation = public_key_confirmation(&local_key.y_sum_s);
+        output.push(Msg {
+            round: 5,
+            sender: local_key.i,
+            receiver: None,
+            body: own_confirmation,
+        });
+        Self {
+            local_key,
+            own_confirmation,
+        }
+    }
+    pub fn proceed(
+        self,
+        input: BroadcastMsgs<PublicKeyConfirmation>,
+    ) -> Result<LocalKey<Secp256k1>> {
+        let (i, n) = (self.local_key.i, self.local_key.n);
+        let confirmations = collect_broadcasts(5, input, i, self.own_confirmation, n)?;
+        check_own_slot(5, &confirmations, i, |c| *c == self.own_confirmation)?;
+        check_public_key_confirmations(&confirmations)?;
+        Ok(self.local_key)
+    }
+    pub fn is_expensive(&self) -> bool {
+        false
+    }
+    pub fn expects_messages(i: u16, n: u16) -> Store<BroadcastMsgs<PublicKeyConfirmation>> {
         containers::BroadcastMsgsStore::new(i, n)
     }
 }
 
+fn public_key_confirmation(y_sum_s: &Point<Secp256k1>) -> PublicKeyConfirmation {
+    let mut confirmation = [0u8; 32];
+    confirmation.copy_from_slice(
+        &Sha256::new()
+            .chain(b"gg20-keygen-public-key-confirmation")
+            .chain(&*y_sum_s.to_bytes(true))
+            .finalize(),
+    );
+    confirmation
+}
+
+/// Checks that all parties confirmed the same public key
+///
+/// `confirmations` are ordered by party index. Parties whose confirmation differs from the one
+/// sent by most parties are reported, so a single diverged party is pinpointed even if it's this
+/// party. If there's no single most common confirmation, nobody can be blamed and
+/// [ProceedError::PublicKeyDisagreement] is returned.
+fn check_public_key_confirmations(confirmations: &[PublicKeyConfirmation]) -> Result<()> {
+    let count =
+        |c: &PublicKeyConfirmation| confirmations.iter().filter(|other| *other == c).count();
+    let majority = match confirmations.iter().max_by_key(|c| count(c)) {
+        Some(majority) => majority,
+        None => return Ok(()),
+    };
+    let majority_count = count(majority);
+    if confirmations
+        .iter()
+        .any(|c| c != majority && count(c) == majority_count)
+    {
+        return Err(ProceedError::PublicKeyDisagreement);
+    }
+    let parties: Vec<u16> = (1..)
+        .zip(confirmations)
+        .filter(|(_, c)| *c != majority)
+        .map(|(i, _)| i)
+        .collect();
+    if parties.is_empty() {
+        Ok(())
+    } else {
+        Err(ProceedError::PublicKeyMismatch { parties })
+    }
+}
+
+/// How secret shares are delivered to other parties at keygen round 3, see
+/// [Keygen::with_transport_mode](super::Keygen::with_transport_mode)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TransportMode {
+    /// Shares are encrypted under recipient's Paillier key with [ShareCipher]
+    Paillier,
+    /// Shares are sent in clear, confidentiality is provided by the transport
+    ///
+    /// **Insecure** unless every p2p message is delivered over an authenticated and encrypted
+    /// channel (e.g. Noise) between the sender and the recipient.
+    ExternalSecureChannel,
+}
+
+impl Default for TransportMode {
+    fn default() -> Self {
+        TransportMode::Paillier
+    }
+}
+
+impl TransportMode {
+    fn opposite(self) -> Self {
+        match self {
+            TransportMode::Paillier => TransportMode::ExternalSecureChannel,
+            TransportMode::ExternalSecureChannel => TransportMode::Paillier,
+        }
+    }
+}
+
+/// Age limit of keygen session, see [Keygen::with_session_expiry](super::Keygen::with_session_expiry)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SessionExpiry {
+    /// Time when the session was created
+    pub created_at: SystemTime,
+    /// Maximal age of the session, past it parties refuse to proceed
+    pub max_age: Duration,
+}
+
+impl SessionExpiry {
+    /// Checks whether session is older than `max_age` at the moment `now`
+    ///
+    /// Session with `created_at` in the future (e.g. due to clock skew) is not expired.
+    pub fn is_expired_at(&self, now: SystemTime) -> bool {
+        now.duration_since(self.created_at)
+            .map(|age| age > self.max_age)
+            .unwrap_or(false)
+    }
+}
+
+fn check_session_expiry(expiry: Option<SessionExpiry>) -> Result<()> {
+    match expiry {
+        Some(expiry) if expiry.is_expired_at(SystemTime::now()) => {
+            Err(ProceedError::SessionExpired)
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Coefficient-wise sum of VSS commitments, i.e. commitments to sum of shared polynomials
+pub(crate) fn add_vss(
+    a: &VerifiableSS<Secp256k1>,
+    b: &VerifiableSS<Secp256k1>,
+) -> VerifiableSS<Secp256k1> {
+    let mut sum = a.clone();
+    sum.commitments = a
+        .commitments
+        .iter()
+        .zip(&b.commitments)
+        .map(|(a_k, b_k)| a_k + b_k)
+        .collect();
+    sum
+}
+
+/// Shares `secret` via Feldman VSS with polynomial coefficients sampled from `fill_bytes`
+fn share_with_random_bytes(
+    t: u16,
+    n: u16,
+    secret: &Scalar<Secp256k1>,
+    mut fill_bytes: impl FnMut(&mut [u8]),
+) -> (VerifiableSS<Secp256k1>, Vec<Scalar<Secp256k1>>) {
+    let coefficients: Vec<Scalar<Secp256k1>> = std::iter::once(secret.clone())
+        .chain((0..t).map(|_| {
+            let mut bytes = [0u8; 64];
+            fill_bytes(&mut bytes);
+            let coefficient = Scalar::from_bigint(&BigInt::from_bytes(&bytes));
+            bytes.zeroize();
+            coefficient
+        }))
+        .collect();
+    let shares = (1..=n)
+        .map(|x| {
+            let x = Scalar::<Secp256k1>::from_bigint(&BigInt::from(u64::from(x)));
+            coefficients
+                .iter()
+                .rev()
+                .fold(Scalar::zero(), |acc, a| acc * &x + a)
+        })
+        .collect();
+    // Reuses fresh VSS scheme for its parameters, commitments are replaced
+    let (mut vss, _) = VerifiableSS::share(t, n, secret);
+    vss.commitments = coefficients
+        .iter()
+        .map(|a| Point::generator() * a)
+        .collect();
+    (vss, shares)
+}
+
+/// Collects broadcast messages of `round` ordered by sender, including party's `own` message
+///
+/// Checks that every party in `[1; n]` sent exactly one message, so messages can be indexed by
+/// party index.
+pub(crate) fn collect_broadcasts<T>(
+    round: u16,
+    input: BroadcastMsgs<T>,
+    party_i: u16,
+    own: T,
+    n: u16,
+) -> Result<Vec<T>> {
+    let mut msgs: Vec<Option<T>> = (0..n).map(|_| None).collect();
+    let mut unexpected = vec![];
+    for (sender, msg) in input
+        .into_iter_indexed()
+        .chain(std::iter::once((party_i, own)))
+    {
+        match usize::from(sender)
+            .checked_sub(1)
+            .and_then(|j| msgs.get_mut(j))
+        {
+            Some(slot) if slot.is_none() => *slot = Some(msg),
+            _ => unexpected.push(sender),
+        }
+    }
+    let missing: Vec<u16> = (1..)
+        .zip(&msgs)
+        .filter(|(_, msg)| msg.is_none())
+        .map(|(j, _)| j)
+        .collect();
+    if !unexpected.is_empty() || !missing.is_empty() {
+        return Err(ProceedError::UnexpectedSenders {
+            round,
+            unexpected,
+            missing,
+        });
+    }
+    Ok(msgs.into_iter().flatten().collect())
+}
+
+/// Checks that party's own message is at index `party_i - 1` of messages collected at `round`
+///
+/// `is_own` compares public commitment of the message to the party's one. Misplaced own message
+/// would silently corrupt everything computed from the messages, so rounds check it before using
+/// them: expensive rounds in release builds too, cheap ones under `debug_assert`.
+pub(crate) fn check_own_slot<T>(
+    round: u16,
+    msgs: &[T],
+    party_i: u16,
+    is_own: impl FnOnce(&T) -> bool,
+) -> Result<()> {
+    match usize::from(party_i)
+        .checked_sub(1)
+        .and_then(|i| msgs.get(i))
+    {
+        Some(msg) if is_own(msg) => Ok(()),
+        _ => Err(ProceedError::OwnMessageMisplaced { round }),
+    }
+}
+
+/// Checks that no two parties share the same N_tilde
+pub(crate) fn check_distinct_dlog_statements(statements: &[DLogStatement]) -> Result<()> {
+    let parties = parties_sharing_modulus(statements.iter().map(|s| &s.N));
+    if parties.is_empty() {
+        Ok(())
+    } else {
+        Err(ProceedError::DuplicateDLogStatement { parties })
+    }
+}
+
+/// Checks that no two parties share the same Paillier modulus
+///
+/// Party copying Paillier key of another party passes correctness proof, as the proof is copied
+/// too, so it needs to be checked explicitly.
+pub(crate) fn check_distinct_paillier_keys(keys: &[EncryptionKey]) -> Result<()> {
+    let parties = parties_sharing_modulus(keys.iter().map(|ek| &ek.n));
+    if parties.is_empty() {
+        Ok(())
+    } else {
+        Err(ProceedError::DuplicatePaillierKey { parties })
+    }
+}
+
+/// Returns sorted indexes of parties whose modulus is also used by another party
+///
+/// `moduli` are listed in order of parties, starting from party 1.
+pub(crate) fn parties_sharing_modulus<'a>(
+    moduli: impl IntoIterator<Item = &'a BigInt>,
+) -> Vec<u16> {
+    let mut owners = BTreeMap::<&BigInt, Vec<u16>>::new();
+    for (i, modulus) in (1..).zip(moduli) {
+        owners.entry(modulus).or_default().push(i);
+    }
+    let mut parties: Vec<u16> = owners
+        .into_iter()
+        .filter(|(_, owners)| owners.len() > 1)
+        .flat_map(|(_, owners)| owners)
+        .collect();
+    parties.sort_unstable();
+    parties
+}
+
+/// Checks that party's public share `y_i` is a valid non-identity point
+///
+/// Deserialization already rejects points which are not on curve, and secp256k1 has cofactor 1,
+/// so every point on curve is in the prime-order group. Identity point deserializes fine though,
+/// and it would make `y_sum_s` independent of the party's secret. Point is also re-encoded and
+/// decoded back to make sure it wasn't constructed bypassing the validation.
+fn validate_public_share(y_i: &Point<Secp256k1>) -> std::result::Result<(), InvalidPoint> {
+    if y_i.is_zero() {
+        return Err(InvalidPoint);
+    }
+    match Point::<Secp256k1>::from_bytes(&y_i.to_bytes(true)) {
+        Ok(decoded) if decoded == *y_i => Ok(()),
+        _ => Err(InvalidPoint),
+    }
+}
+
+/// Point didn't pass [validation](validate_public_share)
+struct InvalidPoint;
+
+/// Sanity checks party's DLog statement `(N_tilde, h1, h2)`
+///
+/// Checks that `h1` and `h2` are distinct, in range `(1; N_tilde)` and coprime with `N_tilde`.
+/// Otherwise, they don't generate the intended subgroup and range proofs at signing could be
+/// forged. Size of `N_tilde` is checked by keygen, as it depends on keygen settings.
+pub fn validate_dlog_statement(
+    statement: &DLogStatement,
+) -> std::result::Result<(), DLogStatementError> {
+    if statement.g == statement.ni {
+        return Err(DLogStatementError::SameGenerators);
+    }
+    let one = BigInt::one();
+    for h in [&statement.g, &statement.ni].iter() {
+        if **h <= one || **h >= statement.N {
+            return Err(DLogStatementError::OutOfRange);
+        }
+        if h.gcd(&statement.N) != one {
+            return Err(DLogStatementError::NotCoprime);
+        }
+    }
+    Ok(())
+}
+
+/// Checks that encrypted share is a valid Paillier ciphertext under `ek`, i.e. it's in range
+/// `[0; N^2)`, it's neither 0 nor 1, and it's coprime with `N`
+///
+/// Decryption of a value outside of multiplicative group modulo `N^2` is undefined, and sender
+/// could pick such value to shift share the recipient reconstructs. Ciphertext 1 is a valid
+/// encryption of zero without randomness, honest sender never produces it.
+pub fn validate_encrypted_share(
+    ct_bytes: &[u8],
+    ek: &EncryptionKey,
+) -> std::result::Result<(), CiphertextError> {
+    let c = BigInt::from_bytes(ct_bytes);
+    if c >= ek.nn {
+        return Err(CiphertextError::OutOfRange);
+    }
+    if c <= BigInt::one() {
+        return Err(CiphertextError::Trivial);
+    }
+    if c.gcd(&ek.n) != BigInt::one() {
+        return Err(CiphertextError::NotCoprime);
+    }
+    Ok(())
+}
+
 /// Local secret obtained by party after [keygen](super::Keygen) protocol is completed
+///
+/// Serialized field names are part of the storage format: renaming a field breaks reading of
+/// stored keys unless the old name is kept via `#[serde(rename)]`.
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct LocalKey<E: Curve> {
     pub paillier_dk: paillier::DecryptionKey,
@@ -345,16 +1481,395 @@ pub struct LocalKey<E: Curve> {
     pub y_sum_s: Point<E>,
     pub h1_h2_n_tilde_vec: Vec<DLogStatement>,
     pub vss_scheme: VerifiableSS<E>,
+    /// VSS schemes of all parties, ordered by party index
+    ///
+    /// Keys produced by older versions of the library don't have this field, it's deserialized
+    /// as an empty vector.
+    #[serde(default)]
+    pub vss_scheme_vec: Vec<VerifiableSS<E>>,
+    /// Long-term identities of parties bound at keygen, ordered by party index
+    ///
+    /// Empty if keygen was run without [identities](super::Keygen::with_party_identities).
+    #[serde(default)]
+    pub party_identities: Vec<Point<E>>,
+    /// Backups of all the shares encrypted for the [recovery party](super::RecoveryParty), indexed
+    /// by `[dealer - 1][holder - 1]`
+    ///
+    /// Present only in the key of the recovery party, see [LocalKey::recover_share].
+    #[serde(default)]
+    pub recovery_backups: Vec<Vec<Vec<u8>>>,
     pub i: u16,
     pub t: u16,
     pub n: u16,
 }
 
+/// Wipes Paillier primes and secret share `keys_linear.x_i`
+impl<E: Curve> Zeroize for LocalKey<E> {
+    fn zeroize(&mut self) {
+        self.paillier_dk.p.zeroize();
+        self.paillier_dk.q.zeroize();
+        self.keys_linear.zeroize();
+    }
+}
+
+impl<E: Curve> Drop for LocalKey<E> {
+    fn drop(&mut self) {
+        self.zeroize()
+    }
+}
+
+impl<E: Curve> ZeroizeOnDrop for LocalKey<E> {}
+
 impl LocalKey<Secp256k1> {
     /// Public key of secret shared between parties
     pub fn public_key(&self) -> Point<Secp256k1> {
         self.y_sum_s.clone()
     }
+
+    /// [Public key](Self::public_key) in SEC1 compressed form
+    ///
+    /// Can't fail for a key produced by keygen: its public key is never the identity point, as
+    /// parties commit to their public shares before revealing them and can't make them cancel
+    /// each other out. Panics for a hand-crafted key with identity public key.
+    pub fn public_key_compressed(&self) -> [u8; 33] {
+        let mut bytes = [0u8; 33];
+        bytes.copy_from_slice(&self.y_sum_s.to_bytes(true));
+        bytes
+    }
+
+    /// [Public key](Self::public_key) in SEC1 uncompressed form
+    ///
+    /// Can't fail for a key produced by keygen, see
+    /// [public_key_compressed](Self::public_key_compressed).
+    pub fn public_key_uncompressed(&self) -> [u8; 65] {
+        let mut bytes = [0u8; 65];
+        bytes.copy_from_slice(&self.y_sum_s.to_bytes(false));
+        bytes
+    }
+
+    /// SHA-256 of compressed [public key](Self::public_key)
+    ///
+    /// Fingerprint is the same for every party of the same keygen.
+    pub fn fingerprint(&self) -> [u8; 32] {
+        let mut fingerprint = [0u8; 32];
+        fingerprint.copy_from_slice(&Sha256::digest(&self.y_sum_s.to_bytes(true)));
+        fingerprint
+    }
+
+    /// Debug representation of the key without secrets
+    ///
+    /// Unlike [Debug](std::fmt::Debug), it contains neither Paillier decryption key nor secret
+    /// share: only party index, threshold, short [fingerprint](Self::fingerprint) of the public
+    /// key and sizes of the vectors the key carries. Safe to be written to logs.
+    pub fn redacted_debug(&self) -> String {
+        format!(
+            "LocalKey {{ i: {}, t: {}, n: {}, fingerprint: {}, pk_vec: {}, paillier_key_vec: {}, \
+             h1_h2_n_tilde_vec: {}, vss_scheme_vec: {}, party_identities: {}, \
+             recovery_backups: {} }}",
+            self.i,
+            self.t,
+            self.n,
+            self.short_fingerprint(),
+            self.pk_vec.len(),
+            self.paillier_key_vec.len(),
+            self.h1_h2_n_tilde_vec.len(),
+            self.vss_scheme_vec.len(),
+            self.party_identities.len(),
+            self.recovery_backups.len(),
+        )
+    }
+
+    /// First 4 bytes of [fingerprint](Self::fingerprint) in hex
+    fn short_fingerprint(&self) -> String {
+        self.fingerprint()[..4]
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect()
+    }
+
+    /// Merkle root of VSS commitments of all parties
+    ///
+    /// Root is the same for every party of the same keygen, so it can be published once and
+    /// later used to check [inclusion](Self::vss_inclusion_proof) of any party's VSS scheme.
+    pub fn vss_commitment_root(&self) -> [u8; 32] {
+        let leaves: Vec<_> = self.vss_scheme_vec.iter().map(vss_merkle_leaf).collect();
+        merkle::merkle_root(&leaves)
+    }
+
+    /// Proof that VSS scheme of this party is included under [vss_commitment_root](Self::vss_commitment_root)
+    ///
+    /// Verify it with [verify_vss_inclusion]. Returns `None` if the key doesn't carry VSS schemes
+    /// of all parties (i.e. it was produced by an older version of the library).
+    pub fn vss_inclusion_proof(&self) -> Option<merkle::MerkleProof> {
+        let leaves: Vec<_> = self.vss_scheme_vec.iter().map(vss_merkle_leaf).collect();
+        merkle::merkle_proof(&leaves, usize::from(self.i - 1))
+    }
+
+    /// Number of message rounds of GG20 signing: offline stage followed by online signing
+    ///
+    /// Number of rounds doesn't depend on number of `signers`, it's fixed by the protocol.
+    pub fn signing_round_count(&self, _signers: &[u16]) -> usize {
+        usize::from(sign::OFFLINE_ROUNDS + sign::ONLINE_ROUNDS)
+    }
+
+    /// Indicates whether `t + 1 = n`, i.e. every party of keygen must participate in signing
+    pub fn is_full_consensus(&self) -> bool {
+        usize::from(self.t) + 1 == usize::from(self.n)
+    }
+
+    /// Indicates whether the key was produced by 2-of-2 keygen (`t = 1`, `n = 2`), e.g. by
+    /// [Keygen::two_party](super::Keygen::two_party)
+    ///
+    /// It's derived from threshold and number of parties rather than stored, so a 2-party key has
+    /// the same format as any other one. Signing committee of such key always consists of both
+    /// parties, so signing takes their Lagrange coefficients from a constant instead of
+    /// interpolating them.
+    pub fn is_two_party(&self) -> bool {
+        self.t == 1 && self.n == 2
+    }
+
+    /// Lagrange coefficient of `party` (index in range `[1; 2]`) for 2-party committee `{1, 2}`
+    ///
+    /// Interpolating at zero gives `2` for party 1 and `-1` for party 2.
+    pub(crate) fn two_party_lagrange_coefficient(party: u16) -> Scalar<Secp256k1> {
+        match party {
+            1 => Scalar::from(&BigInt::from(2)),
+            2 => Scalar::from(&(Scalar::<Secp256k1>::group_order() - BigInt::from(1))),
+            _ => panic!("party index of 2-party key is not in range [1; 2]"),
+        }
+    }
+
+    /// Lagrange coefficient of this party for signing committee `signer_indices`
+    ///
+    /// Indices are the ones parties got at keygen (in range `[1; n]`), the same as in
+    /// [OfflineStage](sign::OfflineStage) `s_l`. Multiplying secret share `x_i` by the
+    /// coefficient gives party's additive share of the secret key: sum of additive shares of
+    /// the committee is equal to the secret key.
+    ///
+    /// ## Panics
+    /// Panics if `signer_indices` don't contain index of this party, contain fewer than `t+1`
+    /// distinct indices, or contain index out of range `[1; n]`
+    pub fn lagrange_coefficient(&self, signer_indices: &[u16]) -> Scalar<Secp256k1> {
+        assert!(
+            signer_indices.contains(&self.i),
+            "signer indices don't contain index of the party"
+        );
+        assert!(
+            signer_indices.len() > usize::from(self.t),
+            "at least t+1 signers are required"
+        );
+        assert!(
+            signer_indices.iter().all(|&j| j >= 1 && j <= self.n),
+            "signer index is not in range [1; n]"
+        );
+        let mut distinct = signer_indices.to_vec();
+        distinct.sort_unstable();
+        distinct.dedup();
+        assert_eq!(
+            distinct.len(),
+            signer_indices.len(),
+            "signer indices must be distinct"
+        );
+
+        if self.is_two_party() {
+            return Self::two_party_lagrange_coefficient(self.i);
+        }
+        let zero_based: Vec<u16> = signer_indices.iter().map(|j| j - 1).collect();
+        VerifiableSS::<Secp256k1>::map_share_to_new_params(
+            &self.vss_scheme.parameters,
+            self.i - 1,
+            &zero_based,
+        )
+    }
+
+    /// Long-term identity of `party` (index in range `[1; n]`) bound at keygen
+    ///
+    /// Returns `None` if keygen was run without identities or `party` is out of range.
+    pub fn identity_of(&self, party: u16) -> Option<&Point<Secp256k1>> {
+        usize::from(party)
+            .checked_sub(1)
+            .and_then(|j| self.party_identities.get(j))
+    }
+
+    /// Checks that `partial_pubkey` is the public share of `party` (index in range `[1; n]`)
+    /// obtained at keygen
+    ///
+    /// Allows linking signing-time artifacts back to the keygen. Returns `false` if `party` is out
+    /// of range.
+    pub fn verify_partial_sig_origin(&self, party: u16, partial_pubkey: &Point<Secp256k1>) -> bool {
+        usize::from(party)
+            .checked_sub(1)
+            .and_then(|j| self.pk_vec.get(j))
+            .map(|pk| pk == partial_pubkey)
+            .unwrap_or(false)
+    }
+
+    /// Checks that `signature` is a valid ECDSA signature of `message_hash` under the
+    /// [public key](Self::public_key)
+    ///
+    /// `message_hash` is interpreted as big-endian integer, the same way signing does.
+    pub fn verify_signature(&self, message_hash: &[u8; 32], signature: &SignatureRecid) -> bool {
+        party_i::verify(signature, &self.y_sum_s, &BigInt::from_bytes(message_hash)).is_ok()
+    }
+
+    /// Checks that `paillier_dk` is the decryption key of party's own Paillier key
+    ///
+    /// Encrypts a random plaintext under `paillier_key_vec[i-1]` and checks that `paillier_dk`
+    /// decrypts it back. Catches a corrupted or mismatched decryption key, e.g. when loading the
+    /// key from storage.
+    pub fn verify_paillier_keypair(&self) -> std::result::Result<(), PaillierMismatch> {
+        let ek = usize::from(self.i)
+            .checked_sub(1)
+            .and_then(|i| self.paillier_key_vec.get(i))
+            .ok_or(PaillierMismatch)?;
+        let plaintext = BigInt::sample_below(&ek.n);
+        let ciphertext = Paillier::encrypt(ek, RawPlaintext::from(plaintext.clone()));
+        let decrypted: RawPlaintext = Paillier::decrypt(&self.paillier_dk, ciphertext);
+        if decrypted.0.into_owned() == plaintext {
+            Ok(())
+        } else {
+            Err(PaillierMismatch)
+        }
+    }
+
+    /// Commitments to coefficients of the group's sharing polynomial
+    ///
+    /// Coefficient-wise sum of all parties' VSS commitments. Returns `t+1` points, the first one
+    /// is the [public key](Self::public_key). Returns an empty vector if the key doesn't carry
+    /// VSS schemes of all parties.
+    pub fn aggregate_commitments(&self) -> Vec<Point<Secp256k1>> {
+        let (first, rest) = match self.vss_scheme_vec.split_first() {
+            Some(schemes) => schemes,
+            None => return vec![],
+        };
+        rest.iter().fold(first.commitments.clone(), |acc, vss| {
+            acc.iter()
+                .zip(&vss.commitments)
+                .map(|(a, b)| a + b)
+                .collect()
+        })
+    }
+
+    /// Checks that linear shared key `keys_linear` is consistent with the rest of the key
+    ///
+    /// Verifies that `keys_linear.y` is the shared public key, and that `keys_linear.x_i` is the
+    /// party's Shamir share: `g^x_i` must match party's public share in `pk_vec` and, if the key
+    /// carries VSS schemes of all parties, the share committed by those schemes.
+    pub fn verify_keys_linear_consistency(&self) -> std::result::Result<(), ConsistencyError> {
+        if self.keys_linear.y != self.y_sum_s {
+            return Err(ConsistencyError::PublicKeyMismatch);
+        }
+
+        let g_x_i = Point::generator() * &self.keys_linear.x_i;
+        let public_share = usize::from(self.i)
+            .checked_sub(1)
+            .and_then(|i| self.pk_vec.get(i))
+            .ok_or(ConsistencyError::PublicShareMismatch)?;
+        if *public_share != g_x_i {
+            return Err(ConsistencyError::PublicShareMismatch);
+        }
+
+        if !self.vss_scheme_vec.is_empty() {
+            let committed_share: Point<Secp256k1> = self
+                .vss_scheme_vec
+                .iter()
+                .map(|vss| vss.get_point_commitment(self.i))
+                .sum();
+            if committed_share != g_x_i {
+                return Err(ConsistencyError::VssShareMismatch);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Checks that parties' secret contributions sum up to the shared secret
+    ///
+    /// Constant term of party's VSS polynomial is its contribution to the shared secret, so sum
+    /// of constant term commitments `vss_scheme_vec[i].commitments[0]` must be equal to the
+    /// public key `y_sum_s`.
+    pub fn verify_contribution_sum(&self) -> std::result::Result<(), ContributionError> {
+        if self.vss_scheme_vec.len() != usize::from(self.n) {
+            return Err(ContributionError::MissingVssSchemes);
+        }
+        let contributions_sum: Point<Secp256k1> = self
+            .vss_scheme_vec
+            .iter()
+            .map(|vss| vss.commitments[0].clone())
+            .sum();
+        if contributions_sum != self.y_sum_s {
+            return Err(ContributionError::SumMismatch);
+        }
+        Ok(())
+    }
+}
+
+/// Summarizes non-secret metadata of the key, e.g. `party 1 of 3 (t=1), key 1f2e3d4c`
+///
+/// Key is identified by short [fingerprint](LocalKey::fingerprint) of the public key, so output
+/// is the same for every party of the keygen up to party index. See also
+/// [LocalKey::redacted_debug].
+impl std::fmt::Display for LocalKey<Secp256k1> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "party {} of {} (t={}), key {}",
+            self.i,
+            self.n,
+            self.t,
+            self.short_fingerprint()
+        )
+    }
+}
+
+/// Verifies that `vss_scheme` is included under published VSS commitment root
+pub fn verify_vss_inclusion(
+    root: &[u8; 32],
+    vss_scheme: &VerifiableSS<Secp256k1>,
+    proof: &merkle::MerkleProof,
+) -> bool {
+    merkle::verify_merkle_proof(root, &vss_merkle_leaf(vss_scheme), proof)
+}
+
+/// Binds encrypted share to transport mode, its sender, recipient and sender's VSS scheme
+///
+/// It's a plain hash, not a MAC: it doesn't authenticate the sender, but catches shares which
+/// were misrouted, swapped between recipients or truncated before they're decrypted.
+fn share_binding_tag(
+    mode: TransportMode,
+    sender: u16,
+    receiver: u16,
+    vss_scheme: &VerifiableSS<Secp256k1>,
+    encrypted_share: &[u8],
+) -> [u8; 32] {
+    let domain: &[u8] = match mode {
+        TransportMode::Paillier => b"multi-party-ecdsa/share-binding/v1",
+        TransportMode::ExternalSecureChannel => {
+            b"multi-party-ecdsa/share-binding/external-channel/v1"
+        }
+    };
+    let mut tag = [0u8; 32];
+    tag.copy_from_slice(
+        &Sha256::new()
+            .chain(domain)
+            .chain(sender.to_be_bytes())
+            .chain(receiver.to_be_bytes())
+            .chain(vss_merkle_leaf(vss_scheme))
+            .chain((encrypted_share.len() as u64).to_be_bytes())
+            .chain(encrypted_share)
+            .finalize(),
+    );
+    tag
+}
+
+fn vss_merkle_leaf(vss_scheme: &VerifiableSS<Secp256k1>) -> Vec<u8> {
+    let mut leaf = vec![];
+    leaf.extend_from_slice(&vss_scheme.parameters.threshold.to_be_bytes());
+    leaf.extend_from_slice(&vss_scheme.parameters.share_count.to_be_bytes());
+    for commitment in &vss_scheme.commitments {
+        leaf.extend_from_slice(&commitment.to_bytes(true));
+    }
+    leaf
 }
 
 // Errors
@@ -373,6 +1888,162 @@ pub enum ProceedError {
     Round3VerifyVssConstruct(ErrorType),
     #[error("round 4: verify dlog proof: {0:?}")]
     Round4VerifyDLogProof(ErrorType),
+    #[error("round 3: party {sender} sent vss scheme for different curve")]
+    CurveMismatch { sender: u16 },
+    #[error("round 1: party {party} sent Paillier key of unexpected size ({bits} bits)")]
+    PaillierKeySizeMismatch { party: u16, bits: usize },
+    #[error("round 1: party {party} sent N_tilde of unexpected size ({bits} bits)")]
+    DLogStatementSizeMismatch { party: u16, bits: usize },
+    #[error("round 3: party {sender} sent invalid share ciphertext")]
+    InvalidCiphertext { sender: u16 },
+    #[error("round 3: share sent by party {from} can't be decrypted into a valid scalar")]
+    Round3DecryptShare { from: u16 },
+    #[error("round 3: party {sender} uses different transport mode")]
+    TransportModeMismatch { sender: u16 },
+    #[error(
+        "round 3: share sent by party {sender} isn't bound to this party and sender's vss scheme"
+    )]
+    ShareBindingMismatch { sender: u16 },
+    #[error("round 3: party {sender} sent vss scheme inconsistent with its round 2 decommitment")]
+    VssEquivocation { sender: u16 },
+    #[error("round 3: party {sender} sent malformed recovery backups")]
+    InvalidRecoveryBackups { sender: u16 },
+    #[error("round 3: party {sender} didn't prove that its share is encrypted correctly")]
+    InvalidShareEncryptionProof { sender: u16 },
+    #[error("keygen session is expired")]
+    SessionExpired,
+    #[error("round 1: parties {parties:?} have different view on party identities")]
+    PartyIdentitiesMismatch { parties: Vec<u16> },
+    #[error("party {party} sent invalid dlog statement")]
+    InvalidDLogStatement { party: u16 },
+    #[error("round 4: parties {parties:?} have the same dlog statement")]
+    DuplicateDLogStatement { parties: Vec<u16> },
+    #[error("round 2: parties {parties:?} use the same Paillier key")]
+    DuplicatePaillierKey { parties: Vec<u16> },
+    #[error("round 0: invalid parameters party_i={party_i} t={t} n={n}, expected 1 <= party_i <= n, n >= 2 and t < n")]
+    InvalidParameters { party_i: u16, t: u16, n: u16 },
+    #[error("round 0: supplied keys were created for party {keys_party_index}, but keygen runs as party {party_i}")]
+    KeysPartyMismatch {
+        party_i: u16,
+        keys_party_index: usize,
+    },
+    #[error("round {round}: messages from parties {unexpected:?} are duplicated or out of range, parties {missing:?} are missing")]
+    UnexpectedSenders {
+        round: u16,
+        unexpected: Vec<u16>,
+        missing: Vec<u16>,
+    },
+    #[error("round 2: party {sender} sent invalid public share y_i")]
+    InvalidPublicShare { sender: u16 },
+    #[error("round 5: parties {parties:?} derived different public key")]
+    PublicKeyMismatch { parties: Vec<u16> },
+    /// Parties derived different public keys, and no key is confirmed by more parties than any
+    /// other one
+    #[error("round 5: parties derived different public keys, none of them is confirmed by most")]
+    PublicKeyDisagreement,
+    #[error("round 2: vss produced {actual} shares, expected {expected}")]
+    InvalidShareCount { expected: u16, actual: usize },
+    #[error("round {round}: party's own message is not at its index (bug)")]
+    OwnMessageMisplaced { round: u16 },
+}
+
+/// Encrypted share didn't pass [validation](validate_encrypted_share)
+#[derive(Debug, Error)]
+pub enum CiphertextError {
+    #[error("ciphertext is not in range [0; N^2)")]
+    OutOfRange,
+    #[error("ciphertext is 0 or 1")]
+    Trivial,
+    #[error("ciphertext is not coprime with N")]
+    NotCoprime,
+}
+
+/// Paillier decryption key doesn't match party's encryption key, see
+/// [LocalKey::verify_paillier_keypair]
+#[derive(Debug, Error)]
+#[error("paillier decryption key doesn't match party's encryption key")]
+pub struct PaillierMismatch;
+
+/// Key didn't pass [consistency check](LocalKey::verify_keys_linear_consistency)
+#[derive(Debug, Error)]
+pub enum ConsistencyError {
+    #[error("keys_linear.y doesn't match shared public key")]
+    PublicKeyMismatch,
+    #[error("keys_linear.x_i doesn't match party's public share")]
+    PublicShareMismatch,
+    #[error("keys_linear.x_i doesn't match share committed by VSS schemes")]
+    VssShareMismatch,
+}
+
+/// Error returned by [LocalKey::verify_contribution_sum]
+#[derive(Debug, Error)]
+pub enum ContributionError {
+    #[error("key doesn't carry vss schemes of all parties")]
+    MissingVssSchemes,
+    #[error("sum of parties' contributions doesn't match shared public key")]
+    SumMismatch,
+}
+
+/// DLog statement didn't pass [validation](validate_dlog_statement)
+#[derive(Debug, Error)]
+pub enum DLogStatementError {
+    #[error("h1 or h2 is not in range (1; N_tilde)")]
+    OutOfRange,
+    #[error("h1 or h2 is not coprime with N_tilde")]
+    NotCoprime,
+    #[error("h1 equals h2")]
+    SameGenerators,
+}
+
+impl ProceedError {
+    /// Indexes of parties (in range `[1; n]`) responsible for the error
+    ///
+    /// Returns `None` if error can't be attributed to specific parties (e.g. session expired).
+    /// Orchestrator may exclude returned parties and restart keygen with the rest of them.
+    pub fn blame(&self) -> Option<Vec<u16>> {
+        match self {
+            ProceedError::Round2VerifyCommitments(err)
+            | ProceedError::Round3VerifyVssConstruct(err)
+            | ProceedError::Round4VerifyDLogProof(err) => Some(
+                err.bad_actors
+                    .iter()
+                    .map(|&i| u16::try_from(i + 1).expect("party index doesn't fit into u16"))
+                    .collect(),
+            ),
+            ProceedError::CurveMismatch { sender }
+            | ProceedError::InvalidCiphertext { sender }
+            | ProceedError::ShareBindingMismatch { sender }
+            | ProceedError::TransportModeMismatch { sender }
+            | ProceedError::InvalidRecoveryBackups { sender }
+            | ProceedError::InvalidShareEncryptionProof { sender }
+            | ProceedError::VssEquivocation { sender }
+            | ProceedError::InvalidPublicShare { sender }
+            | ProceedError::Round3DecryptShare { from: sender } => Some(vec![*sender]),
+            ProceedError::PaillierKeySizeMismatch { party, .. }
+            | ProceedError::DLogStatementSizeMismatch { party, .. }
+            | ProceedError::InvalidDLogStatement { party } => Some(vec![*party]),
+            ProceedError::PartyIdentitiesMismatch { parties }
+            | ProceedError::DuplicateDLogStatement { parties }
+            | ProceedError::DuplicatePaillierKey { parties }
+            | ProceedError::PublicKeyMismatch { parties } => Some(parties.clone()),
+            ProceedError::UnexpectedSenders {
+                unexpected,
+                missing,
+                ..
+            } => {
+                let mut parties: Vec<u16> = unexpected.iter().chain(missing).copied().collect();
+                parties.sort_unstable();
+                parties.dedup();
+                Some(parties)
+            }
+            ProceedError::SessionExpired
+            | ProceedError::InvalidParameters { .. }
+            | ProceedError::KeysPartyMismatch { .. }
+            | ProceedError::InvalidShareCount { .. }
+            | ProceedError::PublicKeyDisagreement
+            | ProceedError::OwnMessageMisplaced { .. } => None,
+        }
+    }
 }
 
 impl IsCritical for ProceedError {
@@ -380,3 +2051,351 @@ impl IsCritical for ProceedError {
         true
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::protocols::multi_party_ecdsa::gg_2020::state_machine::keygen::test::simulate_keygen;
+
+    use super::*;
+
+    #[test]
+    fn local_key_serialized_field_names_are_stable() {
+        let keys = simulate_keygen(1, 2);
+        let json = serde_json::to_value(&keys[0]).unwrap();
+        let names = |value: &serde_json::Value| {
+            let mut names: Vec<String> = value.as_object().unwrap().keys().cloned().collect();
+            names.sort();
+            names
+        };
+        assert_eq!(
+            names(&json),
+            [
+                "h1_h2_n_tilde_vec",
+                "i",
+                "keys_linear",
+                "n",
+                "paillier_dk",
+                "paillier_key_vec",
+                "party_identities",
+                "pk_vec",
+                "recovery_backups",
+                "t",
+                "vss_scheme",
+                "vss_scheme_vec",
+                "y_sum_s",
+            ]
+        );
+        assert_eq!(names(&json["keys_linear"]), ["x_i", "y"]);
+    }
+
+    #[test]
+    fn public_key_is_encoded_in_sec1_forms() {
+        let keys = simulate_keygen(1, 2);
+        let compressed = keys[0].public_key_compressed();
+        let uncompressed = keys[0].public_key_uncompressed();
+        assert!(compressed[0] == 2 || compressed[0] == 3);
+        assert_eq!(uncompressed[0], 4);
+        assert_eq!(compressed[1..], uncompressed[1..33]);
+        for bytes in [&compressed[..], &uncompressed[..]].iter() {
+            assert_eq!(
+                Point::<Secp256k1>::from_bytes(bytes).unwrap(),
+                keys[1].public_key()
+            );
+        }
+    }
+
+    #[test]
+    fn display_doesnt_leak_secrets() {
+        let keys = simulate_keygen(1, 2);
+        let key = &keys[0];
+        let short_fingerprint: String = key.fingerprint()[..4]
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect();
+        assert_eq!(
+            key.to_string(),
+            format!("party 1 of 2 (t=1), key {}", short_fingerprint)
+        );
+
+        let restored: LocalKey<Secp256k1> =
+            serde_json::from_str(&serde_json::to_string(key).unwrap()).unwrap();
+        assert_eq!(restored.to_string(), key.to_string());
+        assert_eq!(restored.redacted_debug(), key.redacted_debug());
+
+        let debug = key.redacted_debug();
+        assert!(debug.contains(&short_fingerprint));
+        for secret in [
+            key.paillier_dk.p.to_string(),
+            key.paillier_dk.q.to_string(),
+            key.keys_linear.x_i.to_bigint().to_string(),
+        ]
+        .iter()
+        {
+            assert!(!debug.contains(secret.as_str()));
+        }
+    }
+
+    #[test]
+    fn lagrange_coefficients_of_committee_recover_public_key() {
+        let keys = simulate_keygen(2, 4);
+        for committee in [vec![1, 2, 3], vec![4, 2, 1], vec![1, 2, 3, 4]].iter() {
+            let public_key: Point<Secp256k1> = committee
+                .iter()
+                .map(|&j| {
+                    let key = &keys[usize::from(j - 1)];
+                    &key.pk_vec[usize::from(j - 1)] * &key.lagrange_coefficient(committee)
+                })
+                .sum();
+            assert_eq!(public_key, keys[0].y_sum_s);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "at least t+1 signers are required")]
+    fn lagrange_coefficient_requires_threshold_committee() {
+        let keys = simulate_keygen(2, 4);
+        keys[0].lagrange_coefficient(&[1, 2]);
+    }
+
+    #[test]
+    fn duplicate_dlog_statements_are_reported() {
+        let keys = simulate_keygen(1, 3);
+        let mut statements = keys[0].h1_h2_n_tilde_vec.clone();
+        check_distinct_dlog_statements(&statements).unwrap();
+
+        statements[2] = statements[0].clone();
+        match check_distinct_dlog_statements(&statements) {
+            Err(ProceedError::DuplicateDLogStatement { parties }) => assert_eq!(parties, [1, 3]),
+            result => panic!("unexpected result: {:?}", result),
+        }
+    }
+
+    #[test]
+    fn duplicate_paillier_keys_are_reported() {
+        let keys = simulate_keygen(1, 3);
+        let mut paillier_keys = keys[0].paillier_key_vec.clone();
+        check_distinct_paillier_keys(&paillier_keys).unwrap();
+
+        paillier_keys[1] = paillier_keys[2].clone();
+        match check_distinct_paillier_keys(&paillier_keys) {
+            Err(err @ ProceedError::DuplicatePaillierKey { .. }) => {
+                assert_eq!(err.blame(), Some(vec![2, 3]))
+            }
+            result => panic!("unexpected result: {:?}", result),
+        }
+    }
+
+    #[test]
+    fn identity_public_share_is_rejected() {
+        let keys = simulate_keygen(1, 2);
+        assert!(validate_public_share(&keys[0].pk_vec[0]).is_ok());
+        assert!(validate_public_share(&keys[0].y_sum_s).is_ok());
+        assert!(validate_public_share(&Point::zero()).is_err());
+    }
+
+    #[test]
+    fn diverged_public_key_is_reported() {
+        let keys = simulate_keygen(1, 3);
+        let mut confirmations: Vec<_> = keys
+            .iter()
+            .map(|key| public_key_confirmation(&key.y_sum_s))
+            .collect();
+        check_public_key_confirmations(&confirmations).unwrap();
+
+        confirmations[0] = public_key_confirmation(&keys[0].pk_vec[0]);
+        match check_public_key_confirmations(&confirmations) {
+            Err(err @ ProceedError::PublicKeyMismatch { .. }) => {
+                assert_eq!(err.blame(), Some(vec![1]))
+            }
+            result => panic!("unexpected result: {:?}", result),
+        }
+
+        // Every party confirmed its own key, nobody is in majority
+        confirmations[1] = public_key_confirmation(&keys[0].pk_vec[1]);
+        match check_public_key_confirmations(&confirmations) {
+            Err(err @ ProceedError::PublicKeyDisagreement) => assert_eq!(err.blame(), None),
+            result => panic!("unexpected result: {:?}", result),
+        }
+    }
+
+    #[test]
+    fn duplicated_and_missing_broadcasts_are_reported() {
+        let mut store = containers::BroadcastMsgsStore::new(1, 3);
+        for sender in 2..=3 {
+            store
+                .push_msg(Msg {
+                    round: 2,
+                    sender,
+                    receiver: None,
+                    body: sender,
+                })
+                .unwrap();
+        }
+        let input = store.finish().unwrap();
+        assert_eq!(collect_broadcasts(2, input, 1, 1, 3).unwrap(), [1, 2, 3]);
+
+        let mut store = containers::BroadcastMsgsStore::new(1, 3);
+        for sender in 2..=3 {
+            store
+                .push_msg(Msg {
+                    round: 2,
+                    sender,
+                    receiver: None,
+                    body: sender,
+                })
+                .unwrap();
+        }
+        // Own message is attributed to party 3 which already sent one, party 1 goes missing
+        let input = store.finish().unwrap();
+        match collect_broadcasts(2, input, 3, 3, 3) {
+            Err(err @ ProceedError::UnexpectedSenders { .. }) => {
+                assert_eq!(err.blame(), Some(vec![1, 3]))
+            }
+            result => panic!("unexpected result: {:?}", result),
+        }
+    }
+
+    #[test]
+    fn misplaced_own_message_is_detected() {
+        let msgs = [10, 20, 30];
+        assert!(check_own_slot(4, &msgs, 2, |m| *m == 20).is_ok());
+        for party_i in [1, 3, 0, 4] {
+            assert!(matches!(
+                check_own_slot(4, &msgs, party_i, |m| *m == 20),
+                Err(ProceedError::OwnMessageMisplaced { round: 4 })
+            ));
+        }
+    }
+
+    #[test]
+    fn round2_rejects_sharing_with_wrong_number_of_shares() {
+        let n = 2;
+        let mut msgs1 = vec![];
+        let parties: Vec<Round1> = (1..=n)
+            .map(|i| Round0::new(i, 1, n).proceed(&mut msgs1).unwrap())
+            .collect();
+        let mut msgs2 = vec![];
+        let mut parties: Vec<Round2> = parties
+            .into_iter()
+            .zip(1..)
+            .map(|(party, i)| {
+                let mut store = containers::BroadcastMsgsStore::new(i, n);
+                for msg in msgs1.iter().filter(|m: &&Msg<_>| m.sender != i) {
+                    store.push_msg(msg.clone()).unwrap();
+                }
+                party.proceed(store.finish().unwrap(), &mut msgs2).unwrap()
+            })
+            .collect();
+
+        let party = parties.remove(0);
+        let mut store = containers::BroadcastMsgsStore::new(1, n);
+        for msg in msgs2.into_iter().filter(|m| m.sender != 1) {
+            store.push_msg(msg).unwrap();
+        }
+        let (vss, shares) = VerifiableSS::share(1, n, &party.keys.u_i);
+        let mut msgs3 = vec![];
+        let result = party.proceed_with_sharing(
+            store.finish().unwrap(),
+            &mut msgs3,
+            Some((vss, shares[..1].to_vec())),
+        );
+        assert!(matches!(
+            result,
+            Err(ProceedError::InvalidShareCount {
+                expected: 2,
+                actual: 1
+            })
+        ));
+        assert!(msgs3.is_empty());
+    }
+
+    /// Rounds wipe their secrets via `zeroize` when dropped or [aborted](Round0::abort), so
+    /// wiping is checked on a live round
+    #[test]
+    fn rounds_wipe_secrets() {
+        let n = 2;
+        let mut msgs1 = vec![];
+        let parties: Vec<Round1> = (1..=n)
+            .map(|i| Round0::new(i, 1, n).proceed(&mut msgs1).unwrap())
+            .collect();
+        let mut wiped_msgs = vec![];
+        let mut wiped = Round0::new(1, 1, n).proceed(&mut wiped_msgs).unwrap();
+        assert_ne!(wiped.keys.u_i, Scalar::zero());
+        wiped.zeroize();
+        assert_eq!(wiped.keys.u_i, Scalar::zero());
+        assert_eq!(wiped.keys.dk.p, BigInt::zero());
+        assert_eq!(wiped.keys.dk.q, BigInt::zero());
+        wiped.abort();
+
+        let mut msgs2 = vec![];
+        let parties: Vec<Round2> = parties
+            .into_iter()
+            .zip(1..)
+            .map(|(party, i)| {
+                let mut store = containers::BroadcastMsgsStore::new(i, n);
+                for msg in msgs1.iter().filter(|m: &&Msg<_>| m.sender != i) {
+                    store.push_msg(msg.clone()).unwrap();
+                }
+                party.proceed(store.finish().unwrap(), &mut msgs2).unwrap()
+            })
+            .collect();
+        let mut msgs3 = vec![];
+        let mut parties: Vec<Round3> = parties
+            .into_iter()
+            .zip(1..)
+            .map(|(party, i)| {
+                let mut store = containers::BroadcastMsgsStore::new(i, n);
+                for msg in msgs2.iter().filter(|m: &&Msg<_>| m.sender != i) {
+                    store.push_msg(msg.clone()).unwrap();
+                }
+                party.proceed(store.finish().unwrap(), &mut msgs3).unwrap()
+            })
+            .collect();
+
+        let mut round3 = parties.remove(0);
+        assert_ne!(round3.own_share, Scalar::zero());
+        round3.zeroize();
+        assert_eq!(round3.keys.u_i, Scalar::zero());
+        assert_eq!(round3.own_share, Scalar::zero());
+        round3.abort();
+        parties.remove(0).abort();
+    }
+
+    #[test]
+    fn round0_rejects_invalid_parameters() {
+        for &(party_i, t, n) in &[(0, 1, 3), (4, 1, 3), (1, 3, 3), (1, 0, 1)] {
+            let mut msgs = vec![];
+            match Round0::new(party_i, t, n).proceed(&mut msgs) {
+                Err(ProceedError::InvalidParameters { .. }) => (),
+                Err(err) => panic!("unexpected error: {:?}", err),
+                Ok(_) => panic!("invalid parameters were accepted"),
+            }
+            assert!(msgs.is_empty());
+        }
+    }
+
+    #[test]
+    fn round0_uses_supplied_keys() {
+        let setup = PaillierSetup::generate();
+        let keys = Keys::create_from_paillier_setup(2, &setup);
+
+        let mut msgs = vec![];
+        let round1 = match Round0::new(2, 1, 3).proceed_with_keys(keys.clone(), &mut msgs) {
+            Ok(round1) => round1,
+            Err(err) => panic!("supplied keys were rejected: {:?}", err),
+        };
+        assert_eq!(round1.bc1.e.n, setup.ek.n);
+        assert_eq!(msgs[0].body.0.e.n, setup.ek.n);
+
+        let mut msgs = vec![];
+        match Round0::new(3, 1, 3).proceed_with_keys(keys, &mut msgs) {
+            Err(ProceedError::KeysPartyMismatch {
+                party_i: 3,
+                keys_party_index: 2,
+            }) => (),
+            Err(err) => panic!("unexpected error: {:?}", err),
+            Ok(_) => panic!("keys of another party were accepted"),
+        }
+        assert!(msgs.is_empty());
+    }
+}
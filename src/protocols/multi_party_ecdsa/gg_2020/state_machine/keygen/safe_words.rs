@@ -0,0 +1,50 @@
+//! Human-verifiable representation of the group key
+
+use curv::elliptic::curves::secp256_k1::Secp256k1;
+use sha2::{Digest, Sha256};
+
+use super::LocalKey;
+
+/// 64 distinct emoji, so every byte of the hash stream maps to a word without bias
+const WORDLIST: [&str; 64] = [
+    "🐶", "🐱", "🐭", "🐹", "🐰", "🦊", "🐻", "🐼", "🐨", "🐯", "🦁", "🐮", "🐷", "🐸", "🐵", "🐔",
+    "🐧", "🐦", "🦆", "🦉", "🐴", "🦄", "🐝", "🐛", "🦋", "🐌", "🐞", "🐢", "🐍", "🐙", "🦀", "🐬",
+    "🐳", "🦈", "🐘", "🦒", "🌵", "🌲", "🍀", "🍁", "🍄", "🌻", "🌙", "⭐", "🔥", "🌈", "❄️", "💧",
+    "🍎", "🍋", "🍌", "🍉", "🍇", "🍓", "🍒", "🥕", "🌽", "🍞", "🧀", "🍕", "🎈", "🎁", "🔑", "⚓",
+];
+
+impl LocalKey<Secp256k1> {
+    /// Derives `count` safe words from the [key fingerprint](Self::fingerprint)
+    ///
+    /// Words are the same for every party of the same keygen, so they can be displayed on
+    /// different devices and compared by a user to make sure devices hold shares of the same key.
+    pub fn safe_words(&self, count: usize) -> Vec<String> {
+        let fingerprint = self.fingerprint();
+        (0u32..)
+            .flat_map(|block| {
+                Sha256::new()
+                    .chain(&fingerprint)
+                    .chain(&block.to_be_bytes())
+                    .finalize()
+            })
+            .take(count)
+            .map(|byte| WORDLIST[usize::from(byte) % WORDLIST.len()].to_string())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::protocols::multi_party_ecdsa::gg_2020::state_machine::keygen::test::simulate_keygen;
+
+    #[test]
+    fn safe_words_are_same_within_group() {
+        let keys = simulate_keygen(1, 3);
+        let words = keys[0].safe_words(40);
+        assert_eq!(words.len(), 40);
+        assert!(keys.iter().all(|k| k.safe_words(40) == words));
+
+        let other_keys = simulate_keygen(1, 3);
+        assert_ne!(other_keys[0].safe_words(40), words);
+    }
+}
@@ -0,0 +1,112 @@
+//! Proofs that a public share is assigned to specific party index
+
+use curv::elliptic::curves::{secp256_k1::Secp256k1, Point, Scalar};
+use serde::{Deserialize, Serialize};
+
+use super::LocalKey;
+
+/// Proof that a public share belongs to `party`, see [LocalKey::share_assignment_proof]
+///
+/// Consists of terms `C_k * party^k` of the group's sharing polynomial committed by
+/// [aggregate commitments](LocalKey::aggregate_commitments) `C_k`, evaluated at party's index.
+/// Sum of the terms is the party's public share.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ShareAssignmentProof {
+    pub party: u16,
+    pub terms: Vec<Point<Secp256k1>>,
+}
+
+impl LocalKey<Secp256k1> {
+    /// Proves that `pk_vec[party-1]` is the public share of `party`
+    ///
+    /// Verify it with [verify_share_assignment] against [aggregate commitments](Self::aggregate_commitments),
+    /// which are the same for every party of the keygen. Third party doesn't need keygen
+    /// transcript to verify the proof.
+    ///
+    /// ## Panics
+    /// Panics if `party` is not in range `[1; n]` or the key doesn't carry VSS schemes of all
+    /// parties
+    pub fn share_assignment_proof(&self, party: u16) -> ShareAssignmentProof {
+        assert!(
+            party >= 1 && party <= self.n,
+            "party index is not in range [1; n]"
+        );
+        let root_commitments = self.aggregate_commitments();
+        assert!(
+            !root_commitments.is_empty(),
+            "key doesn't carry vss schemes of all parties"
+        );
+        ShareAssignmentProof {
+            party,
+            terms: evaluation_terms(&root_commitments, party),
+        }
+    }
+}
+
+/// Verifies that `pk` is the public share of `party` given aggregate commitments to the group's
+/// sharing polynomial, see [LocalKey::share_assignment_proof]
+pub fn verify_share_assignment(
+    root_commitments: &[Point<Secp256k1>],
+    party: u16,
+    pk: &Point<Secp256k1>,
+    proof: &ShareAssignmentProof,
+) -> bool {
+    if party == 0 || proof.party != party || root_commitments.is_empty() {
+        return false;
+    }
+    if proof.terms != evaluation_terms(root_commitments, party) {
+        return false;
+    }
+    let public_share: Point<Secp256k1> = proof.terms.iter().cloned().sum();
+    public_share == *pk
+}
+
+fn evaluation_terms(root_commitments: &[Point<Secp256k1>], party: u16) -> Vec<Point<Secp256k1>> {
+    let x = Scalar::<Secp256k1>::from(u64::from(party));
+    let mut x_k = Scalar::<Secp256k1>::from(1u64);
+    root_commitments
+        .iter()
+        .map(|commitment| {
+            let term = commitment * &x_k;
+            x_k = &x_k * &x;
+            term
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::protocols::multi_party_ecdsa::gg_2020::state_machine::keygen::test::simulate_keygen;
+
+    use super::*;
+
+    #[test]
+    fn share_assignment_proof_is_verified() {
+        let keys = simulate_keygen(1, 3);
+        let root_commitments = keys[0].aggregate_commitments();
+        let pk = &keys[0].pk_vec[1];
+
+        let proof = keys[0].share_assignment_proof(2);
+        assert!(verify_share_assignment(&root_commitments, 2, pk, &proof));
+        assert!(!verify_share_assignment(
+            &root_commitments,
+            2,
+            &keys[0].pk_vec[0],
+            &proof
+        ));
+
+        let wrong_party = keys[0].share_assignment_proof(3);
+        assert!(!verify_share_assignment(
+            &root_commitments,
+            2,
+            pk,
+            &wrong_party
+        ));
+        assert!(!verify_share_assignment(
+            &root_commitments,
+            3,
+            pk,
+            &wrong_party
+        ));
+    }
+}
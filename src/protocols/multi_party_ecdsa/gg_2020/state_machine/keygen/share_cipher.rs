@@ -0,0 +1,153 @@
+//! Encryption of secret shares sent between parties at keygen
+
+use curv::arithmetic::traits::*;
+use curv::elliptic::curves::{secp256_k1::Secp256k1, Scalar};
+use curv::BigInt;
+use paillier::{
+    Decrypt, DecryptionKey, Encrypt, EncryptionKey, Paillier, RawCiphertext, RawPlaintext,
+};
+
+/// Encryption of secret shares sent to other parties at keygen round 3
+///
+/// Share is encrypted under recipient's Paillier key. Default implementation is
+/// [PaillierShareCipher], custom implementation can be set via
+/// [Keygen::with_share_cipher](super::Keygen::with_share_cipher), e.g. to use hardware-accelerated
+/// Paillier. Every party of the keygen must use compatible implementations.
+pub trait ShareCipher: Send + Sync {
+    /// Encrypts `share` under recipient's key `ek`
+    fn encrypt(&self, ek: &EncryptionKey, share: &Scalar<Secp256k1>) -> Vec<u8>;
+    /// Decrypts share received from another party
+    ///
+    /// Ciphertext is chosen by the sender, so it must not be trusted. Returns `None` if it's
+    /// malformed or doesn't decrypt into a valid scalar, i.e. a number less than curve order.
+    fn decrypt(&self, dk: &DecryptionKey, ciphertext: &[u8]) -> Option<Scalar<Secp256k1>>;
+}
+
+/// [ShareCipher] encrypting shares with Paillier cryptosystem
+///
+/// Received ciphertext is blinded before decryption: it's multiplied by a fresh encryption of
+/// zero `r^N mod N^2`, so the value going through CRT exponentiation is unrelated to the
+/// ciphertext chosen by the sender. It's not a constant-time implementation: BigInt arithmetic
+/// backing Paillier is variable-time, blinding only prevents the sender from correlating
+/// timings with ciphertexts it crafts.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PaillierShareCipher;
+
+impl ShareCipher for PaillierShareCipher {
+    fn encrypt(&self, ek: &EncryptionKey, share: &Scalar<Secp256k1>) -> Vec<u8> {
+        Paillier::encrypt(ek, RawPlaintext::from(share.to_bigint()))
+            .0
+            .to_bytes()
+    }
+
+    fn decrypt(&self, dk: &DecryptionKey, ciphertext: &[u8]) -> Option<Scalar<Secp256k1>> {
+        let c = BigInt::from_bytes(ciphertext);
+        let n = &dk.p * &dk.q;
+        // Valid ciphertext is an element of multiplicative group modulo N^2
+        if c >= &n * &n || c.gcd(&n) != BigInt::one() {
+            return None;
+        }
+        let raw_share: RawPlaintext<'_> = Paillier::decrypt(dk, RawCiphertext::from(blind(dk, &c)));
+        let share = raw_share.0.into_owned();
+        if &share >= Scalar::<Secp256k1>::group_order() {
+            return None;
+        }
+        Some(Scalar::from_bigint(&share))
+    }
+}
+
+/// Re-randomizes ciphertext `c`, decryption of resulting ciphertext gives the same plaintext
+fn blind(dk: &DecryptionKey, c: &BigInt) -> BigInt {
+    let n = &dk.p * &dk.q;
+    let nn = &n * &n;
+    let r = loop {
+        let r = BigInt::sample_below(&n);
+        if r != BigInt::zero() && r.gcd(&n) == BigInt::one() {
+            break r;
+        }
+    };
+    BigInt::mod_mul(c, &BigInt::mod_pow(&r, &n, &nn), &nn)
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    use paillier::KeyGeneration;
+    use round_based::dev::Simulation;
+
+    use super::super::Keygen;
+    use super::*;
+
+    /// Sends shares in clear, counting calls
+    #[derive(Default)]
+    struct MockCipher {
+        encrypted: AtomicUsize,
+        decrypted: AtomicUsize,
+    }
+
+    impl ShareCipher for MockCipher {
+        fn encrypt(&self, _ek: &EncryptionKey, share: &Scalar<Secp256k1>) -> Vec<u8> {
+            self.encrypted.fetch_add(1, Ordering::SeqCst);
+            share.to_bigint().to_bytes()
+        }
+
+        fn decrypt(&self, _dk: &DecryptionKey, ciphertext: &[u8]) -> Option<Scalar<Secp256k1>> {
+            self.decrypted.fetch_add(1, Ordering::SeqCst);
+            Some(Scalar::from_bigint(&BigInt::from_bytes(ciphertext)))
+        }
+    }
+
+    #[test]
+    fn keygen_uses_custom_share_cipher() {
+        let (t, n) = (1, 3);
+        let cipher = Arc::new(MockCipher::default());
+
+        let mut simulation = Simulation::new();
+        for i in 1..=n {
+            simulation.add_party(Keygen::with_share_cipher(i, t, n, cipher.clone()).unwrap());
+        }
+        let keys = simulation.run().unwrap();
+
+        assert!(keys.iter().all(|k| k.public_key() == keys[0].public_key()));
+        assert_eq!(cipher.encrypted.load(Ordering::SeqCst), 6);
+        assert_eq!(cipher.decrypted.load(Ordering::SeqCst), 6);
+    }
+
+    #[test]
+    fn paillier_share_cipher_roundtrip() {
+        let (ek, dk) = Paillier::keypair_with_modulus_size(1024).keys();
+        let share = Scalar::<Secp256k1>::random();
+        let ciphertext = PaillierShareCipher.encrypt(&ek, &share);
+        assert_eq!(PaillierShareCipher.decrypt(&dk, &ciphertext), Some(share));
+    }
+
+    #[test]
+    fn paillier_share_cipher_rejects_malformed_ciphertext() {
+        let (ek, dk) = Paillier::keypair_with_modulus_size(1024).keys();
+        let order = Scalar::<Secp256k1>::group_order();
+        let out_of_field = Paillier::encrypt(&ek, RawPlaintext::from(order.clone()))
+            .0
+            .to_bytes();
+        let zero = BigInt::zero().to_bytes();
+        let not_invertible = dk.p.to_bytes();
+        let too_large = (&ek.nn + BigInt::one()).to_bytes();
+
+        for ciphertext in [out_of_field, zero, not_invertible, too_large].iter() {
+            assert_eq!(PaillierShareCipher.decrypt(&dk, ciphertext), None);
+        }
+    }
+
+    #[test]
+    fn blinding_preserves_plaintext() {
+        let (ek, dk) = Paillier::keypair_with_modulus_size(1024).keys();
+        let share = Scalar::<Secp256k1>::random();
+        let c = BigInt::from_bytes(&PaillierShareCipher.encrypt(&ek, &share));
+
+        let blinded = blind(&dk, &c);
+        assert_ne!(blinded, c);
+        let raw_share: RawPlaintext<'_> = Paillier::decrypt(&dk, RawCiphertext::from(blinded));
+        assert_eq!(Scalar::from_bigint(&raw_share.0.into_owned()), share);
+    }
+}
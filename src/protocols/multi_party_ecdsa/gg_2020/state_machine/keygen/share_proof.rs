@@ -0,0 +1,164 @@
+//! Publicly verifiable encryption of secret shares, for keygen complaint rounds
+//!
+//! By default [Keygen](super::Keygen) sends shares without the proofs. Once enabled (see
+//! [Keygen::with_share_encryption_proofs](super::Keygen::with_share_encryption_proofs)), every
+//! round 3 message carries the proof, so ciphertext with the proof can be published once the
+//! receiver complains about the share, and anyone can check whether the complaint is justified.
+
+use curv::arithmetic::traits::*;
+use curv::elliptic::curves::{secp256_k1::Secp256k1, Point, Scalar};
+use curv::BigInt;
+use paillier::traits::EncryptWithChosenRandomness;
+use paillier::{EncryptionKey, Paillier, Randomness, RawPlaintext};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use zk_paillier::zkproofs::DLogStatement;
+
+use crate::protocols::multi_party_ecdsa::gg_2018::VerifiableSS;
+use crate::utilities::zk_pdl_with_slack::{PDLwSlackProof, PDLwSlackStatement, PDLwSlackWitness};
+
+use super::{validate_encrypted_share, CiphertextError};
+
+/// Zero-knowledge proof that Paillier ciphertext encrypts the share committed in VSS scheme
+///
+/// Proves that ciphertext `c` under receiver's key encrypts `x` such that `x·G` is the dealer's
+/// [VSS commitment](VerifiableSS::get_point_commitment) at receiver's index. Anyone knowing the
+/// VSS scheme and receiver's Paillier key can verify it without receiver's decryption key, e.g.
+/// to resolve a complaint of the receiver about the share. Produced by
+/// [encrypt_share_with_proof], verified by [verify_share_encryption].
+///
+/// The proof has a slack: it shows that `x` is in range `[-q^3; q^3]` rather than `[0; q)`, so
+/// ciphertext of such out-of-range `x` passes verification but the receiver can't decrypt it
+/// into a share. The receiver can prove that by revealing decryption of the ciphertext.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ShareEncryptionProof(PDLwSlackProof);
+
+/// Encrypts `share` under receiver's key `ek`, and proves that it's encrypted correctly
+///
+/// Ciphertext is compatible with [PaillierShareCipher](super::PaillierShareCipher). Range proof is
+/// bound to `statement`, which must not be generated by the dealer: use DLog statement of the
+/// party verifying the proof, or receiver's one.
+pub fn encrypt_share_with_proof(
+    share: &Scalar<Secp256k1>,
+    ek: &EncryptionKey,
+    statement: &DLogStatement,
+) -> (Vec<u8>, ShareEncryptionProof) {
+    let randomness = BigInt::sample_below(&ek.n);
+    let ciphertext = Paillier::encrypt_with_chosen_randomness(
+        ek,
+        RawPlaintext::from(share.to_bigint()),
+        &Randomness::from(randomness.clone()),
+    )
+    .0
+    .into_owned();
+    let witness = PDLwSlackWitness {
+        x: share.clone(),
+        r: randomness,
+    };
+    let proof = PDLwSlackProof::prove(
+        &witness,
+        &pdl_statement(&ciphertext, ek, Point::generator() * share, statement),
+    );
+    (ciphertext.to_bytes(), ShareEncryptionProof(proof))
+}
+
+/// Verifies that `ciphertext` sent by the dealer of `vss` to party `receiver` (index in range
+/// `[1; n]`) encrypts the share committed in `vss`
+///
+/// `ek` is receiver's Paillier key, `statement` is the one proof was produced for.
+pub fn verify_share_encryption(
+    vss: &VerifiableSS<Secp256k1>,
+    receiver: u16,
+    ciphertext: &[u8],
+    ek: &EncryptionKey,
+    statement: &DLogStatement,
+    proof: &ShareEncryptionProof,
+) -> Result<(), ShareProofError> {
+    if receiver == 0 || receiver > vss.parameters.share_count {
+        return Err(ShareProofError::InvalidReceiver { receiver });
+    }
+    validate_encrypted_share(ciphertext, ek).map_err(ShareProofError::InvalidCiphertext)?;
+    let ciphertext = BigInt::from_bytes(ciphertext);
+    let expected = vss.get_point_commitment(receiver);
+    proof
+        .0
+        .verify(&pdl_statement(&ciphertext, ek, expected, statement))
+        .map_err(|_| ShareProofError::InvalidProof)
+}
+
+fn pdl_statement(
+    ciphertext: &BigInt,
+    ek: &EncryptionKey,
+    q: Point<Secp256k1>,
+    statement: &DLogStatement,
+) -> PDLwSlackStatement {
+    PDLwSlackStatement {
+        ciphertext: ciphertext.clone(),
+        ek: ek.clone(),
+        Q: q,
+        G: Point::generator().to_point(),
+        h1: statement.g.clone(),
+        h2: statement.ni.clone(),
+        N_tilde: statement.N.clone(),
+    }
+}
+
+/// Error of [verify_share_encryption]
+#[derive(Debug, Error)]
+pub enum ShareProofError {
+    #[error("receiver index {receiver} is not in range [1; n]")]
+    InvalidReceiver { receiver: u16 },
+    #[error("ciphertext is invalid: {0}")]
+    InvalidCiphertext(#[source] CiphertextError),
+    #[error("ciphertext doesn't encrypt the share committed in vss scheme")]
+    InvalidProof,
+}
+
+#[cfg(test)]
+mod test {
+    use paillier::{DecryptionKey, KeyGeneration};
+
+    use super::super::{PaillierShareCipher, ShareCipher};
+    use super::*;
+    use crate::protocols::multi_party_ecdsa::gg_2020::party_i::generate_h1_h2_N_tilde_from;
+
+    #[test]
+    fn share_encryption_is_verified_without_decryption_key() {
+        let (ek, dk): (EncryptionKey, DecryptionKey) =
+            Paillier::keypair_with_modulus_size(2048).keys();
+        let (n_tilde, h1, h2, _, _) = generate_h1_h2_N_tilde_from(&ek, &dk);
+        let statement = DLogStatement {
+            N: n_tilde,
+            g: h1,
+            ni: h2,
+        };
+        let (vss, shares) = VerifiableSS::share(1, 3, &Scalar::random());
+
+        let (ciphertext, proof) = encrypt_share_with_proof(&shares[1], &ek, &statement);
+        verify_share_encryption(&vss, 2, &ciphertext, &ek, &statement, &proof).unwrap();
+        assert_eq!(
+            PaillierShareCipher.decrypt(&dk, &ciphertext),
+            Some(shares[1].clone())
+        );
+
+        assert!(matches!(
+            verify_share_encryption(&vss, 3, &ciphertext, &ek, &statement, &proof),
+            Err(ShareProofError::InvalidProof)
+        ));
+        let (other_ciphertext, _) = encrypt_share_with_proof(&shares[1], &ek, &statement);
+        assert!(matches!(
+            verify_share_encryption(&vss, 2, &other_ciphertext, &ek, &statement, &proof),
+            Err(ShareProofError::InvalidProof)
+        ));
+        assert!(matches!(
+            verify_share_encryption(&vss, 4, &ciphertext, &ek, &statement, &proof),
+            Err(ShareProofError::InvalidReceiver { receiver: 4 })
+        ));
+        assert!(matches!(
+            verify_share_encryption(&vss, 2, &ek.n.to_bytes(), &ek, &statement, &proof),
+            Err(ShareProofError::InvalidCiphertext(
+                CiphertextError::NotCoprime
+            ))
+        ));
+    }
+}
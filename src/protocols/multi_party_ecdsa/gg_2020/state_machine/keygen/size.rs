@@ -0,0 +1,123 @@
+//! Storage footprint of [LocalKey] and wire size of keygen messages
+
+use std::io;
+
+use curv::elliptic::curves::secp256_k1::Secp256k1;
+use serde::Serialize;
+
+use super::{LocalKey, ProtocolMessage};
+
+/// Sizes (in bytes) of [LocalKey] components serialized as JSON
+///
+/// Obtained via [LocalKey::size_breakdown]. Sizes of components don't include JSON punctuation
+/// and field names, so they add up to slightly less than `total`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct SizeBreakdown {
+    pub paillier_dk: usize,
+    pub paillier_key_vec: usize,
+    pub pk_vec: usize,
+    pub vss_scheme: usize,
+    pub vss_scheme_vec: usize,
+    pub h1_h2_n_tilde_vec: usize,
+    /// Remaining fields: `keys_linear`, `y_sum_s`, `i`, `t`, `n`
+    pub other: usize,
+    /// Size of the whole serialized key
+    pub total: usize,
+}
+
+impl LocalKey<Secp256k1> {
+    /// Reports serialized size of every logical component of the key
+    ///
+    /// Useful for storage planning: Paillier keys and DLog statements usually dominate.
+    pub fn size_breakdown(&self) -> SizeBreakdown {
+        let paillier_dk = json_size(&self.paillier_dk);
+        let paillier_key_vec = json_size(&self.paillier_key_vec);
+        let pk_vec = json_size(&self.pk_vec);
+        let vss_scheme = json_size(&self.vss_scheme);
+        let vss_scheme_vec = json_size(&self.vss_scheme_vec);
+        let h1_h2_n_tilde_vec = json_size(&self.h1_h2_n_tilde_vec);
+        let other = json_size(&self.keys_linear)
+            + json_size(&self.y_sum_s)
+            + json_size(&self.i)
+            + json_size(&self.t)
+            + json_size(&self.n);
+
+        SizeBreakdown {
+            paillier_dk,
+            paillier_key_vec,
+            pk_vec,
+            vss_scheme,
+            vss_scheme_vec,
+            h1_h2_n_tilde_vec,
+            other,
+            total: json_size(self),
+        }
+    }
+}
+
+impl ProtocolMessage {
+    /// Size of message serialized as JSON (in bytes)
+    ///
+    /// Size is counted without allocating serialized message. Round 1 message is the largest one:
+    /// it carries party's Paillier key, `DLogStatement` and proofs of their correctness.
+    pub fn serialized_len(&self) -> usize {
+        json_size(self)
+    }
+}
+
+fn json_size<T: Serialize + ?Sized>(value: &T) -> usize {
+    let mut counter = ByteCounter(0);
+    serde_json::to_writer(&mut counter, value).expect("writing to counter doesn't fail");
+    counter.0
+}
+
+/// Writer that discards bytes, counting them
+struct ByteCounter(usize);
+
+impl io::Write for ByteCounter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0 += buf.len();
+        Ok(buf.len())
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use round_based::StateMachine;
+
+    use crate::protocols::multi_party_ecdsa::gg_2020::state_machine::keygen::test::simulate_keygen;
+    use crate::protocols::multi_party_ecdsa::gg_2020::state_machine::keygen::Keygen;
+
+    #[test]
+    fn components_add_up_to_total_size() {
+        let keys = simulate_keygen(1, 3);
+        let sizes = keys[0].size_breakdown();
+
+        let sum = sizes.paillier_dk
+            + sizes.paillier_key_vec
+            + sizes.pk_vec
+            + sizes.vss_scheme
+            + sizes.vss_scheme_vec
+            + sizes.h1_h2_n_tilde_vec
+            + sizes.other;
+        assert!(sum <= sizes.total);
+        // Difference is just field names and punctuation
+        assert!(sizes.total - sum < 300);
+        assert!(sizes.paillier_key_vec > sizes.pk_vec);
+    }
+
+    #[test]
+    fn serialized_len_matches_serialized_message() {
+        let mut keygen = Keygen::new(1, 1, 2).unwrap();
+        keygen.proceed().unwrap();
+        let msg = keygen.message_queue().pop().unwrap().body;
+
+        let len = msg.serialized_len();
+        assert_eq!(len, serde_json::to_vec(&msg).unwrap().len());
+        // Paillier key proofs dominate round 1 message
+        assert!(len > 10_000);
+    }
+}
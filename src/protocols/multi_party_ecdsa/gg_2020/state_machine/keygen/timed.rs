@@ -0,0 +1,172 @@
+//! Keygen that gives up on parties which don't send their messages in time
+
+use std::time::{Duration, Instant};
+
+use curv::elliptic::curves::secp256_k1::Secp256k1;
+use round_based::{Msg, StateMachine};
+
+use super::super::traits::RoundBlame;
+use super::{Error, Keygen, LocalKey, ProtocolMessage, Result};
+
+/// [Keygen] with a deadline for every round
+///
+/// Every round is given `round_timeout` to receive messages of all the parties, starting from
+/// the moment the party entered the round. Once the deadline is missed, keygen fails with
+/// [Error::RoundTimeout] listing parties which didn't send their messages, so the orchestrator
+/// can abort keygen and restart it without them.
+///
+/// Deadline is checked by [TimedKeygen::check_deadline]. Executors that respect
+/// [StateMachine::round_timeout] (e.g. `round_based::AsyncProtocol`) get the same error without
+/// explicit checks.
+pub struct TimedKeygen {
+    keygen: Keygen,
+    round_timeout: Duration,
+    round: u16,
+    round_started: Instant,
+}
+
+impl TimedKeygen {
+    /// Wraps `keygen`, current round of `keygen` is considered to start now
+    pub fn new(keygen: Keygen, round_timeout: Duration) -> Self {
+        Self {
+            round: keygen.current_round(),
+            round_started: Instant::now(),
+            keygen,
+            round_timeout,
+        }
+    }
+
+    /// Wrapped keygen
+    pub fn keygen(&self) -> &Keygen {
+        &self.keygen
+    }
+
+    /// Returns the moment current round misses its deadline
+    pub fn round_deadline(&self) -> Instant {
+        self.round_started + self.round_timeout
+    }
+
+    /// Fails with [Error::RoundTimeout] if current round missed deadline while waiting for
+    /// messages of other parties
+    ///
+    /// Round which received all the messages it needs doesn't time out, even if it's late.
+    pub fn check_deadline(&self) -> Result<()> {
+        self.check_deadline_at(Instant::now())
+    }
+
+    /// Same as [check_deadline](Self::check_deadline), but takes current time explicitly
+    pub fn check_deadline_at(&self, now: Instant) -> Result<()> {
+        if now < self.round_deadline() {
+            return Ok(());
+        }
+        let (_, missing) = self.keygen.round_blame();
+        if missing.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::RoundTimeout {
+                round: self.round,
+                missing,
+            })
+        }
+    }
+
+    fn track_round(&mut self) {
+        let round = self.keygen.current_round();
+        if round != self.round {
+            self.round = round;
+            self.round_started = Instant::now();
+        }
+    }
+}
+
+impl StateMachine for TimedKeygen {
+    type MessageBody = ProtocolMessage;
+    type Err = Error;
+    type Output = LocalKey<Secp256k1>;
+
+    fn handle_incoming(&mut self, msg: Msg<Self::MessageBody>) -> Result<()> {
+        let result = self.keygen.handle_incoming(msg);
+        self.track_round();
+        result
+    }
+
+    fn message_queue(&mut self) -> &mut Vec<Msg<Self::MessageBody>> {
+        self.keygen.message_queue()
+    }
+
+    fn wants_to_proceed(&self) -> bool {
+        self.keygen.wants_to_proceed()
+    }
+
+    fn proceed(&mut self) -> Result<()> {
+        let result = self.keygen.proceed();
+        self.track_round();
+        result
+    }
+
+    fn round_timeout(&self) -> Option<Duration> {
+        Some(self.round_timeout)
+    }
+
+    fn round_timeout_reached(&mut self) -> Self::Err {
+        let (_, missing) = self.keygen.round_blame();
+        Error::RoundTimeout {
+            round: self.round,
+            missing,
+        }
+    }
+
+    fn is_finished(&self) -> bool {
+        self.keygen.is_finished()
+    }
+
+    fn pick_output(&mut self) -> Option<Result<Self::Output>> {
+        self.keygen.pick_output()
+    }
+
+    fn current_round(&self) -> u16 {
+        self.keygen.current_round()
+    }
+
+    fn total_rounds(&self) -> Option<u16> {
+        self.keygen.total_rounds()
+    }
+
+    fn party_ind(&self) -> u16 {
+        self.keygen.party_ind()
+    }
+
+    fn parties(&self) -> u16 {
+        self.keygen.parties()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn silent_party_is_reported_missing() {
+        let timeout = Duration::from_secs(60);
+        let mut party1 = TimedKeygen::new(Keygen::new(1, 1, 3).unwrap(), timeout);
+        let mut party2 = Keygen::new(2, 1, 3).unwrap();
+        // Party 3 never sends its round 1 message
+
+        party1.proceed().unwrap();
+        party2.proceed().unwrap();
+        for msg in party2.message_queue().drain(..) {
+            party1.handle_incoming(msg).unwrap();
+        }
+        assert_eq!(party1.current_round(), 1);
+
+        party1.check_deadline().unwrap();
+        match party1.check_deadline_at(party1.round_deadline()) {
+            Err(Error::RoundTimeout { round: 1, missing }) => assert_eq!(missing, [3]),
+            result => panic!("unexpected result: {:?}", result),
+        }
+        assert!(matches!(
+            party1.round_timeout_reached(),
+            Error::RoundTimeout { round: 1, .. }
+        ));
+    }
+}
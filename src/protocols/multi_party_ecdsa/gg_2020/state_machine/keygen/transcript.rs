@@ -0,0 +1,343 @@
+//! Public verification of recorded keygen transcripts
+
+use curv::cryptographic_primitives::proofs::sigma_dlog::DLogProof;
+use curv::elliptic::curves::{secp256_k1::Secp256k1, Point};
+use round_based::Msg;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+use crate::protocols::multi_party_ecdsa::gg_2018::VerifiableSS;
+use crate::protocols::multi_party_ecdsa::gg_2020::party_i::{
+    KeyGenBroadcastMessage1, KeyGenDecommitMessage1, Keys,
+};
+
+use super::{LocalKey, PaillierShareCipher, ProtocolMessage, ShareCipher, M};
+
+/// Public messages of a keygen, recorded by an observer (e.g. a relay delivering messages)
+///
+/// Only public parts of messages are recorded: round 3 shares are not kept.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct RecordedTranscript {
+    t: u16,
+    n: u16,
+    round1: Vec<Option<KeyGenBroadcastMessage1>>,
+    round2: Vec<Option<KeyGenDecommitMessage1>>,
+    round3: Vec<Option<VerifiableSS<Secp256k1>>>,
+    round4: Vec<Option<DLogProof<Secp256k1, Sha256>>>,
+}
+
+impl RecordedTranscript {
+    /// Constructs an empty transcript of `t`-out-of-`n` keygen
+    pub fn new(t: u16, n: u16) -> Self {
+        let n_usize = usize::from(n);
+        Self {
+            t,
+            n,
+            round1: vec![None; n_usize],
+            round2: vec![None; n_usize],
+            round3: vec![None; n_usize],
+            round4: vec![None; n_usize],
+        }
+    }
+
+    /// Records a message sent by any party of the keygen
+    ///
+    /// Messages from senders out of range `[1; n]` are ignored. If a party sent several messages
+    /// at the same round (i.e. round 3 P2P messages), only the first one is kept.
+    pub fn record(&mut self, msg: &Msg<ProtocolMessage>) {
+        let i = match usize::from(msg.sender).checked_sub(1) {
+            Some(i) if i < usize::from(self.n) => i,
+            _ => return,
+        };
+        match &msg.body.0 {
+            M::Round1((bc1, _)) => {
+                self.round1[i].get_or_insert_with(|| bc1.clone());
+            }
+            M::Round2(decom) => {
+                self.round2[i].get_or_insert_with(|| decom.clone());
+            }
+            M::Round3(round3) => {
+                self.round3[i].get_or_insert_with(|| round3.vss_scheme.clone());
+            }
+            M::Round4(proof) => {
+                self.round4[i].get_or_insert_with(|| proof.clone());
+            }
+            // Public key confirmation is not needed to verify the transcript
+            M::Round5(_) => (),
+        }
+    }
+}
+
+/// Verifies recorded keygen transcript using public data only
+///
+/// Returns public key of the keygen if transcript is valid.
+pub fn verify_transcript(
+    transcript: &RecordedTranscript,
+) -> Result<Point<Secp256k1>, TranscriptError> {
+    fn complete<T: Clone>(round: u16, msgs: &[Option<T>]) -> Result<Vec<T>, TranscriptError> {
+        msgs.iter()
+            .cloned()
+            .collect::<Option<Vec<T>>>()
+            .ok_or(TranscriptError::Incomplete { round })
+    }
+    let bc1_vec = complete(1, &transcript.round1)?;
+    let decom_vec = complete(2, &transcript.round2)?;
+    let vss_vec = complete(3, &transcript.round3)?;
+    let dlog_proofs = complete(4, &transcript.round4)?;
+
+    for (party, (bc1, decom)) in (1..).zip(bc1_vec.iter().zip(&decom_vec)) {
+        if !bc1.verify_with_decommit(decom) {
+            return Err(TranscriptError::InvalidRound1 { party });
+        }
+    }
+    for (party, (vss, decom)) in (1..).zip(vss_vec.iter().zip(&decom_vec)) {
+        let valid = vss.parameters.threshold == transcript.t
+            && vss.parameters.share_count == transcript.n
+            && vss.commitments.len() == usize::from(transcript.t) + 1
+            && vss.commitments[0] == decom.y_i;
+        if !valid {
+            return Err(TranscriptError::InvalidVss { party });
+        }
+    }
+    let xi_commitments = Keys::get_commitments_to_xi(&vss_vec);
+    for (party, (proof, xi_commitment)) in (1..).zip(dlog_proofs.iter().zip(&xi_commitments)) {
+        if DLogProof::verify(proof).is_err() || proof.pk != *xi_commitment {
+            return Err(TranscriptError::InvalidDLogProof { party });
+        }
+    }
+
+    Ok(decom_vec.into_iter().map(|decom| decom.y_i).sum())
+}
+
+/// Every message a party sent and received during keygen, in order, see
+/// [Keygen::with_transcript](super::Keygen::with_transcript)
+///
+/// Round 3 shares are kept encrypted under their receivers' Paillier keys, so transcript is safe
+/// to store as long as keygen uses the default [PaillierShareCipher]. Checked against keygen
+/// output via [verify_party_transcript].
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(bound = "")]
+pub struct KeygenTranscript<H: Digest + Clone = Sha256> {
+    party_i: u16,
+    t: u16,
+    n: u16,
+    sent: Vec<Msg<ProtocolMessage<H>>>,
+    received: Vec<Msg<ProtocolMessage<H>>>,
+}
+
+impl<H: Digest + Clone> KeygenTranscript<H> {
+    pub(super) fn new(party_i: u16, t: u16, n: u16) -> Self {
+        Self {
+            party_i,
+            t,
+            n,
+            sent: vec![],
+            received: vec![],
+        }
+    }
+
+    /// Records messages sent by the party
+    pub(super) fn sent(&mut self, msgs: &[Msg<ProtocolMessage<H>>]) {
+        self.sent.extend_from_slice(msgs)
+    }
+
+    /// Records a message accepted by the party
+    pub(super) fn received(&mut self, msg: Msg<ProtocolMessage<H>>) {
+        self.received.push(msg)
+    }
+
+    /// Messages sent by the party, in order they were sent
+    pub fn sent_messages(&self) -> &[Msg<ProtocolMessage<H>>] {
+        &self.sent
+    }
+
+    /// Messages received by the party, in order they were accepted
+    pub fn received_messages(&self) -> &[Msg<ProtocolMessage<H>>] {
+        &self.received
+    }
+}
+
+/// Verifies that keygen recorded in `transcript` produces `local_key`
+///
+/// Re-runs public checks of [verify_transcript] over all the recorded messages, then checks that
+/// public data of `local_key` is the one derived from the transcript, and that every share party
+/// received decrypts (with Paillier key of `local_key`) into a share consistent with the sender's
+/// VSS commitment. Returns [TranscriptError::LocalKeyMismatch] if the key doesn't match.
+pub fn verify_party_transcript(
+    transcript: &KeygenTranscript,
+    local_key: &LocalKey<Secp256k1>,
+) -> Result<(), TranscriptError> {
+    if (local_key.i, local_key.t, local_key.n) != (transcript.party_i, transcript.t, transcript.n) {
+        return Err(TranscriptError::LocalKeyMismatch);
+    }
+    let mut recorded = RecordedTranscript::new(transcript.t, transcript.n);
+    for msg in transcript.sent.iter().chain(&transcript.received) {
+        recorded.record(msg);
+    }
+    let public_key = verify_transcript(&recorded)?;
+
+    let vss_vec: Vec<_> = recorded.round3.into_iter().flatten().collect();
+    let bc1_vec: Vec<_> = recorded.round1.into_iter().flatten().collect();
+    let public_data_matches = local_key.y_sum_s == public_key
+        && local_key.vss_scheme_vec.len() == vss_vec.len()
+        && local_key
+            .vss_scheme_vec
+            .iter()
+            .zip(&vss_vec)
+            .all(|(a, b)| a.commitments == b.commitments)
+        && local_key.pk_vec == Keys::get_commitments_to_xi(&vss_vec)
+        && local_key.paillier_key_vec.len() == bc1_vec.len()
+        && local_key.h1_h2_n_tilde_vec.len() == bc1_vec.len()
+        && bc1_vec.iter().enumerate().all(|(k, bc1)| {
+            let statement = &local_key.h1_h2_n_tilde_vec[k];
+            local_key.paillier_key_vec[k].n == bc1.e.n
+                && (&statement.N, &statement.g, &statement.ni)
+                    == (
+                        &bc1.dlog_statement.N,
+                        &bc1.dlog_statement.g,
+                        &bc1.dlog_statement.ni,
+                    )
+        })
+        && Point::generator() * &local_key.keys_linear.x_i
+            == local_key.pk_vec[usize::from(local_key.i - 1)];
+    if !public_data_matches {
+        return Err(TranscriptError::LocalKeyMismatch);
+    }
+
+    for msg in &transcript.received {
+        if let M::Round3(round3) = &msg.body.0 {
+            let party = msg.sender;
+            let share = PaillierShareCipher
+                .decrypt(&local_key.paillier_dk, &round3.encrypted_share)
+                .ok_or(TranscriptError::InvalidShare { party })?;
+            round3
+                .vss_scheme
+                .validate_share(&share, local_key.i)
+                .map_err(|_| TranscriptError::InvalidShare { party })?;
+        }
+    }
+    Ok(())
+}
+
+/// Verifies many transcripts in parallel, results are in the same order as `transcripts`
+///
+/// Available only with `rayon` feature.
+#[cfg(feature = "rayon")]
+pub fn verify_transcripts_parallel(
+    transcripts: &[RecordedTranscript],
+) -> Vec<Result<Point<Secp256k1>, TranscriptError>> {
+    use rayon::prelude::*;
+    transcripts.par_iter().map(verify_transcript).collect()
+}
+
+#[derive(Debug, Error)]
+pub enum TranscriptError {
+    #[error("transcript misses messages of round {round}")]
+    Incomplete { round: u16 },
+    #[error("party {party} sent invalid commitment, Paillier key or dlog statement")]
+    InvalidRound1 { party: u16 },
+    #[error("party {party} sent invalid vss scheme")]
+    InvalidVss { party: u16 },
+    #[error("party {party} sent invalid dlog proof")]
+    InvalidDLogProof { party: u16 },
+    #[error("local key is not the one produced by the transcript")]
+    LocalKeyMismatch,
+    #[error("party {party} sent share inconsistent with its vss scheme")]
+    InvalidShare { party: u16 },
+}
+
+#[cfg(test)]
+mod test {
+    use round_based::StateMachine;
+
+    use crate::testing::{deliver_to_receivers, run_in_place};
+
+    use super::super::Keygen;
+    use super::*;
+
+    /// Runs keygen in place and records every message parties send
+    fn record_keygen(t: u16, n: u16) -> (RecordedTranscript, Point<Secp256k1>) {
+        let mut parties: Vec<_> = (1..=n).map(|i| Keygen::new(i, t, n).unwrap()).collect();
+        let mut transcript = RecordedTranscript::new(t, n);
+
+        run_in_place(&mut parties, |parties, msg| {
+            transcript.record(&msg);
+            deliver_to_receivers(parties, &msg);
+        });
+
+        let public_key = parties[0].pick_output().unwrap().unwrap().public_key();
+        (transcript, public_key)
+    }
+
+    #[test]
+    fn recorded_transcript_is_verified() {
+        let (transcript, public_key) = record_keygen(1, 3);
+        assert_eq!(verify_transcript(&transcript).unwrap(), public_key);
+
+        let mut incomplete = transcript.clone();
+        incomplete.round2[1] = None;
+        assert!(matches!(
+            verify_transcript(&incomplete),
+            Err(TranscriptError::Incomplete { round: 2 })
+        ));
+    }
+
+    #[test]
+    fn party_transcript_produces_its_local_key() {
+        let (t, n) = (1, 3);
+        let mut parties: Vec<_> = (1..=n)
+            .map(|i| Keygen::with_transcript(i, t, n).unwrap())
+            .collect();
+        run_in_place(&mut parties, |parties, msg| {
+            deliver_to_receivers(parties, &msg)
+        });
+        let transcripts: Vec<KeygenTranscript> = parties
+            .iter()
+            .map(|p| p.transcript().unwrap().clone())
+            .collect();
+        let keys: Vec<_> = parties
+            .iter_mut()
+            .map(|p| p.pick_output().unwrap().unwrap())
+            .collect();
+
+        // Party sends round 1, 2, 4 broadcasts and a share to every other party
+        assert_eq!(transcripts[0].sent_messages().len(), 3 + 2);
+        assert_eq!(transcripts[0].received_messages().len(), 4 * 2);
+        let stored = serde_json::to_vec(&transcripts[0]).unwrap();
+        let transcript: KeygenTranscript = serde_json::from_slice(&stored).unwrap();
+        verify_party_transcript(&transcript, &keys[0]).unwrap();
+
+        assert!(matches!(
+            verify_party_transcript(&transcripts[0], &keys[1]),
+            Err(TranscriptError::LocalKeyMismatch)
+        ));
+        let mut truncated = transcripts[1].clone();
+        truncated.received.retain(|msg| msg.sender != 3);
+        assert!(matches!(
+            verify_party_transcript(&truncated, &keys[1]),
+            Err(TranscriptError::Incomplete { round: 1 })
+        ));
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn transcripts_are_verified_in_parallel() {
+        let recorded: Vec<_> = (0..4).map(|_| record_keygen(1, 2)).collect();
+        let mut transcripts: Vec<_> = recorded.iter().map(|(t, _)| t.clone()).collect();
+        transcripts[2].round4.swap(0, 1);
+
+        let results = verify_transcripts_parallel(&transcripts);
+        assert_eq!(results.len(), 4);
+        for (k, (result, (_, public_key))) in results.iter().zip(&recorded).enumerate() {
+            if k == 2 {
+                assert!(matches!(
+                    result,
+                    Err(TranscriptError::InvalidDLogProof { party: 1 })
+                ));
+            } else {
+                assert_eq!(result.as_ref().unwrap(), public_key);
+            }
+        }
+    }
+}
@@ -0,0 +1,123 @@
+//! Self-describing serialization of [LocalKey] for long-term storage
+
+use std::convert::TryFrom;
+
+use curv::elliptic::curves::Curve;
+use serde::{de::DeserializeOwned, Serialize};
+use thiserror::Error;
+
+use super::LocalKey;
+
+const MAGIC: &[u8; 4] = b"MPEK";
+
+impl<E: Curve> LocalKey<E>
+where
+    LocalKey<E>: Serialize + DeserializeOwned,
+{
+    /// Version of [versioned bytes](Self::to_versioned_bytes) format produced by this release
+    pub const FORMAT_VERSION: u16 = 1;
+
+    /// Serializes the key prefixed with a header identifying format version and curve
+    ///
+    /// Header is `b"MPEK"`, big-endian `u16` [format version](Self::FORMAT_VERSION), one byte of
+    /// curve name length and the curve name. It's followed by JSON-serialized key. Tooling can
+    /// read the header without knowing the key layout.
+    pub fn to_versioned_bytes(&self) -> Vec<u8> {
+        let curve = E::CURVE_NAME.as_bytes();
+        let curve_len = u8::try_from(curve.len()).expect("curve name is too long");
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend_from_slice(&Self::FORMAT_VERSION.to_be_bytes());
+        bytes.push(curve_len);
+        bytes.extend_from_slice(curve);
+        bytes.extend(serde_json::to_vec(self).expect("serialize local key"));
+        bytes
+    }
+
+    /// Deserializes the key produced by [to_versioned_bytes](Self::to_versioned_bytes)
+    ///
+    /// Returns error if header is malformed, format version is unknown, or the key was generated
+    /// for another curve than `E`.
+    pub fn from_versioned_bytes(bytes: &[u8]) -> Result<Self, VersionedKeyError> {
+        let rest = bytes
+            .strip_prefix(&MAGIC[..])
+            .ok_or(VersionedKeyError::NotVersionedKey)?;
+        if rest.len() < 3 {
+            return Err(VersionedKeyError::Truncated);
+        }
+        let version = u16::from_be_bytes([rest[0], rest[1]]);
+        if version != Self::FORMAT_VERSION {
+            return Err(VersionedKeyError::UnknownVersion { version });
+        }
+        let curve_len = usize::from(rest[2]);
+        let rest = &rest[3..];
+        if rest.len() < curve_len {
+            return Err(VersionedKeyError::Truncated);
+        }
+        let (curve, key) = rest.split_at(curve_len);
+        if curve != E::CURVE_NAME.as_bytes() {
+            return Err(VersionedKeyError::CurveMismatch {
+                expected: E::CURVE_NAME,
+                found: String::from_utf8_lossy(curve).into_owned(),
+            });
+        }
+        serde_json::from_slice(key).map_err(VersionedKeyError::Deserialize)
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum VersionedKeyError {
+    #[error("bytes don't start with versioned key header")]
+    NotVersionedKey,
+    #[error("versioned key header is truncated")]
+    Truncated,
+    #[error("unknown key format version {version}")]
+    UnknownVersion { version: u16 },
+    #[error("key is for curve {found}, expected {expected}")]
+    CurveMismatch {
+        expected: &'static str,
+        found: String,
+    },
+    #[error("deserialize key: {0}")]
+    Deserialize(#[source] serde_json::Error),
+}
+
+#[cfg(test)]
+mod test {
+    use curv::elliptic::curves::secp256_k1::Secp256k1;
+
+    use crate::protocols::multi_party_ecdsa::gg_2020::state_machine::keygen::test::simulate_keygen;
+
+    use super::*;
+
+    #[test]
+    fn versioned_bytes_roundtrip() {
+        let keys = simulate_keygen(1, 2);
+        let bytes = keys[0].to_versioned_bytes();
+        assert_eq!(&bytes[..4], MAGIC);
+
+        let restored = LocalKey::<Secp256k1>::from_versioned_bytes(&bytes).unwrap();
+        assert_eq!(
+            serde_json::to_vec(&restored).unwrap(),
+            serde_json::to_vec(&keys[0]).unwrap()
+        );
+
+        let mut future = bytes.clone();
+        future[5] = 2;
+        assert!(matches!(
+            LocalKey::<Secp256k1>::from_versioned_bytes(&future),
+            Err(VersionedKeyError::UnknownVersion { version: 2 })
+        ));
+
+        let mut other_curve = bytes.clone();
+        let curve_len = usize::from(bytes[6]);
+        other_curve[7..7 + curve_len].copy_from_slice(&b"secp256r1"[..curve_len]);
+        assert!(matches!(
+            LocalKey::<Secp256k1>::from_versioned_bytes(&other_curve),
+            Err(VersionedKeyError::CurveMismatch { found, .. }) if found == "secp256r1"
+        ));
+        assert!(matches!(
+            LocalKey::<Secp256k1>::from_versioned_bytes(&bytes[..6]),
+            Err(VersionedKeyError::Truncated)
+        ));
+    }
+}
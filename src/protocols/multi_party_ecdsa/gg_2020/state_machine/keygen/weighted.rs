@@ -0,0 +1,234 @@
+//! Keygen of a participant holding several share indices, i.e. having bigger weight
+
+use std::time::Duration;
+
+use curv::elliptic::curves::secp256_k1::Secp256k1;
+use round_based::{Msg, StateMachine};
+
+use super::{Error, Keygen, LocalKey, ProtocolMessage, Result};
+
+/// Keygen of participant whose weight is the number of share indices it holds
+///
+/// Weighted `t`-out-of-`n` keygen is an ordinary keygen over `n` share indices, where every
+/// participant holds one or more of them. `WeightedKeygen` runs a [Keygen] for every index of
+/// the participant, delivers messages between them locally, and exposes the rest of messages
+/// for sending to other participants. Messages keep share indices as sender and receiver, so
+/// transport must know which participant holds which index: p2p message goes to participant
+/// holding the receiver index, broadcast message goes to every other participant once.
+///
+/// Output is a [LocalKey] per index, all of them have the same public key. Participant signs
+/// by running [OfflineStage](crate::protocols::multi_party_ecdsa::gg_2020::state_machine::sign::OfflineStage)
+/// for every key, each one as a separate signer. Any set of participants holding at least `t+1`
+/// indices in total can sign.
+///
+/// ## Cost
+/// Weight isn't free: participant of weight `w` does the work of `w` parties. Keygen generates
+/// `w` Paillier keys and `w` sets of proofs, and sends and receives `w` times more messages.
+/// Signing is the same: every index takes part in offline stage as a separate signer, so
+/// computation and traffic of the participant grow linearly with the number of indices it
+/// signs with, and the overall signing cost grows with the total number of indices `t+1`.
+pub struct WeightedKeygen {
+    parties: Vec<Keygen>,
+    msgs_queue: Vec<Msg<ProtocolMessage>>,
+}
+
+impl WeightedKeygen {
+    /// Constructs a participant holding share `indices` of `t`-out-of-`n` keygen
+    ///
+    /// `n` is the total number of indices of all participants. Returns [Error::InvalidPartyIndex]
+    /// if `indices` are empty or contain duplicates, and the same errors as [Keygen::new]
+    /// otherwise.
+    pub fn new(indices: &[u16], t: u16, n: u16) -> Result<Self> {
+        let mut sorted = indices.to_vec();
+        sorted.sort_unstable();
+        sorted.dedup();
+        if sorted.is_empty() || sorted.len() != indices.len() {
+            return Err(Error::InvalidPartyIndex);
+        }
+        let parties = sorted
+            .into_iter()
+            .map(|i| Keygen::new(i, t, n))
+            .collect::<Result<Vec<_>>>()?;
+        let mut keygen = Self {
+            parties,
+            msgs_queue: vec![],
+        };
+        keygen.route_outgoing()?;
+        Ok(keygen)
+    }
+
+    /// Share indices held by the participant, in ascending order
+    pub fn indices(&self) -> Vec<u16> {
+        self.parties.iter().map(|p| p.party_ind()).collect()
+    }
+
+    /// Delivers `msg` to every index it's addressed to
+    fn deliver(&mut self, msg: &Msg<ProtocolMessage>) -> Result<()> {
+        for party in &mut self.parties {
+            let i = party.party_ind();
+            if msg.sender != i && msg.receiver.map_or(true, |j| j == i) {
+                party.handle_incoming(msg.clone())?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Moves messages sent by indices of the participant to each other, and queues the rest for
+    /// other participants
+    fn route_outgoing(&mut self) -> Result<()> {
+        let indices = self.indices();
+        loop {
+            let outgoing: Vec<_> = self
+                .parties
+                .iter_mut()
+                .flat_map(|p| p.message_queue().drain(..))
+                .collect();
+            if outgoing.is_empty() {
+                return Ok(());
+            }
+            for msg in outgoing {
+                self.deliver(&msg)?;
+                match msg.receiver {
+                    Some(j) if indices.contains(&j) => (),
+                    _ => self.msgs_queue.push(msg),
+                }
+            }
+        }
+    }
+}
+
+impl StateMachine for WeightedKeygen {
+    type MessageBody = ProtocolMessage;
+    type Err = Error;
+    type Output = Vec<LocalKey<Secp256k1>>;
+
+    /// Handles message of other participant
+    ///
+    /// Broadcast message is handled by every index of the participant, p2p message by the
+    /// receiver index only.
+    fn handle_incoming(&mut self, msg: Msg<ProtocolMessage>) -> Result<()> {
+        self.deliver(&msg)?;
+        self.route_outgoing()
+    }
+
+    /// Messages ought to be sent to other participants
+    fn message_queue(&mut self) -> &mut Vec<Msg<ProtocolMessage>> {
+        &mut self.msgs_queue
+    }
+
+    /// Indicates whether any index of the participant is ready to proceed
+    fn wants_to_proceed(&self) -> bool {
+        self.parties.iter().any(|p| p.wants_to_proceed())
+    }
+
+    /// Proceeds every index that is ready to proceed
+    fn proceed(&mut self) -> Result<()> {
+        for party in &mut self.parties {
+            if party.wants_to_proceed() {
+                party.proceed()?;
+            }
+        }
+        self.route_outgoing()
+    }
+
+    fn round_timeout(&self) -> Option<Duration> {
+        None
+    }
+
+    fn round_timeout_reached(&mut self) -> Self::Err {
+        panic!("no timeout was set")
+    }
+
+    /// Indicates whether keygen is completed for every index
+    fn is_finished(&self) -> bool {
+        self.parties.iter().all(|p| p.is_finished())
+    }
+
+    /// Keys of every index of the participant, ordered as [indices](WeightedKeygen::indices)
+    ///
+    /// Returns `None` if keygen is not finished yet.
+    fn pick_output(&mut self) -> Option<Result<Self::Output>> {
+        if !self.is_finished() {
+            return None;
+        }
+        Some(
+            self.parties
+                .iter_mut()
+                .map(|p| p.pick_output().unwrap_or(Err(Error::DoublePickOutput)))
+                .collect(),
+        )
+    }
+
+    /// Round of the index which is the furthest behind
+    fn current_round(&self) -> u16 {
+        self.parties
+            .iter()
+            .map(|p| p.current_round())
+            .min()
+            .expect("participant holds at least one index")
+    }
+
+    fn total_rounds(&self) -> Option<u16> {
+        self.parties[0].total_rounds()
+    }
+
+    /// The smallest index held by the participant
+    ///
+    /// Messages addressed to any of [indices](WeightedKeygen::indices) must be delivered to the
+    /// participant.
+    fn party_ind(&self) -> u16 {
+        self.parties[0].party_ind()
+    }
+
+    /// Total number of indices of all participants
+    fn parties(&self) -> u16 {
+        self.parties[0].parties()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::protocols::multi_party_ecdsa::gg_2020::state_machine::sign::test::{
+        simulate_offline_stage, simulate_signing,
+    };
+
+    use crate::testing::run_in_place;
+
+    use super::*;
+
+    #[test]
+    fn heavier_participant_signs_with_any_other() {
+        // Participant 0 holds two indices out of four, t=2 requires three indices to sign
+        let holdings: [&[u16]; 3] = [&[1, 2], &[3], &[4]];
+        let mut participants: Vec<WeightedKeygen> = holdings
+            .iter()
+            .map(|indices| WeightedKeygen::new(indices, 2, 4).unwrap())
+            .collect();
+
+        run_in_place(&mut participants, |participants, msg| {
+            for (p, indices) in participants.iter_mut().zip(&holdings) {
+                let addressed = msg.receiver.map_or(true, |j| indices.contains(&j));
+                if !indices.contains(&msg.sender) && addressed {
+                    p.handle_incoming(msg.clone()).unwrap();
+                }
+            }
+        });
+        let keys: Vec<LocalKey<Secp256k1>> = participants
+            .iter_mut()
+            .flat_map(|p| p.pick_output().unwrap().unwrap())
+            .collect();
+        assert_eq!(keys.iter().map(|k| k.i).collect::<Vec<_>>(), [1, 2, 3, 4]);
+        assert!(keys.iter().all(|k| k.y_sum_s == keys[0].y_sum_s));
+
+        simulate_signing(
+            simulate_offline_stage(keys.clone(), &[1, 2, 4]),
+            b"weighted",
+        );
+        simulate_signing(simulate_offline_stage(keys, &[1, 2, 3]), b"weighted");
+
+        assert!(matches!(
+            WeightedKeygen::new(&[1, 1], 1, 3),
+            Err(Error::InvalidPartyIndex)
+        ));
+    }
+}
@@ -0,0 +1,50 @@
+//! Compact binary encoding of keygen messages
+
+use std::io;
+
+use super::ProtocolMessage;
+
+/// Error of decoding [ProtocolMessage] from CBOR
+pub type CborError = ciborium::de::Error<io::Error>;
+
+impl ProtocolMessage {
+    /// Encodes message as CBOR
+    ///
+    /// CBOR is considerably more compact than JSON for round 1 message, which carries Paillier
+    /// key with proofs of its correctness. Receiving side must decode message via
+    /// [ProtocolMessage::from_cbor].
+    pub fn to_cbor(&self) -> Vec<u8> {
+        let mut bytes = vec![];
+        ciborium::ser::into_writer(self, &mut bytes)
+            .expect("serialization into Vec<u8> doesn't fail");
+        bytes
+    }
+
+    /// Decodes message encoded via [ProtocolMessage::to_cbor]
+    pub fn from_cbor(bytes: &[u8]) -> Result<Self, CborError> {
+        ciborium::de::from_reader(bytes)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use round_based::StateMachine;
+
+    use super::super::Keygen;
+    use super::*;
+
+    #[test]
+    fn cbor_is_smaller_than_json_for_round1_message() {
+        let mut keygen = Keygen::new(1, 1, 2).unwrap();
+        keygen.proceed().unwrap();
+        let msg = keygen.message_queue().pop().unwrap().body;
+
+        let cbor = msg.to_cbor();
+        let json = serde_json::to_vec(&msg).unwrap();
+        assert!(cbor.len() < json.len());
+
+        let decoded = ProtocolMessage::from_cbor(&cbor).unwrap();
+        assert_eq!(serde_json::to_vec(&decoded).unwrap(), json);
+        assert!(ProtocolMessage::from_cbor(&cbor[..cbor.len() - 1]).is_err());
+    }
+}
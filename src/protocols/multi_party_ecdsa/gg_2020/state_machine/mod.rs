@@ -1,3 +1,8 @@
+pub mod batch;
 pub mod keygen;
+pub mod quorum;
+pub mod refresh;
+pub mod reshare;
+mod session;
 pub mod sign;
 pub mod traits;
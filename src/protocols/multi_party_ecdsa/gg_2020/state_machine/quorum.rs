@@ -0,0 +1,134 @@
+//! Helpers for reasoning about sets of parties (quorums) participating in signing
+
+/// Sorts list of parties' indexes and removes duplicates
+pub fn canonicalize_quorum(quorum: &[u16]) -> Vec<u16> {
+    let mut quorum = quorum.to_vec();
+    quorum.sort_unstable();
+    quorum.dedup();
+    quorum
+}
+
+/// Computes difference between two signing quorums
+///
+/// Returns a pair of lists: parties that are present only in `a`, and parties that are present
+/// only in `b`. Both lists are sorted and don't contain duplicates.
+pub fn quorum_diff(a: &[u16], b: &[u16]) -> (Vec<u16>, Vec<u16>) {
+    let a = canonicalize_quorum(a);
+    let b = canonicalize_quorum(b);
+
+    let only_a = a
+        .iter()
+        .filter(|i| b.binary_search(i).is_err())
+        .cloned()
+        .collect();
+    let only_b = b
+        .iter()
+        .filter(|i| a.binary_search(i).is_err())
+        .cloned()
+        .collect();
+    (only_a, only_b)
+}
+
+/// Tolerance of `t`-of-`n` key to faulty parties, see [security_margin]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SecurityMargin {
+    /// Number of parties that may go offline while remaining parties are still able to sign
+    /// (`n - (t+1)`)
+    pub fault_tolerance: u16,
+    /// Number of corrupted parties that can't learn anything about the secret key even when
+    /// colluding (`t`)
+    pub corruption_tolerance: u16,
+}
+
+/// Computes security margin of a key shared with threshold `t` between `n` parties
+///
+/// Any `t+1` parties are able to sign, so up to `n - (t+1)` parties can be offline, and up to `t`
+/// parties can be corrupted without compromising the key. Note that GG20 identifiable abort
+/// additionally relies on an honest majority among the parties, i.e. `t < n/2`.
+///
+/// Returns `None` if `t >= n`, i.e. no `t+1` parties exist to sign.
+pub fn security_margin(t: u16, n: u16) -> Option<SecurityMargin> {
+    if t >= n {
+        return None;
+    }
+    Some(SecurityMargin {
+        fault_tolerance: n - (t + 1),
+        corruption_tolerance: t,
+    })
+}
+
+/// Probability that at least `t+1` parties of `n` are online at the same time
+///
+/// `party_uptimes[i]` is the probability that party `i+1` is online, parties are assumed to go
+/// offline independently. Summing over all `2^n` subsets of parties is infeasible for large `n`,
+/// so distribution of number of online parties is computed incrementally party by party instead,
+/// which takes `O(n^2)` steps.
+///
+/// ## Panics
+/// Panics if `party_uptimes.len() != n`
+pub fn quorum_availability(t: u16, n: u16, party_uptimes: &[f64]) -> f64 {
+    assert_eq!(
+        party_uptimes.len(),
+        usize::from(n),
+        "expected uptime of every party"
+    );
+
+    // online[k] is the probability that exactly k of already considered parties are online
+    let mut online = vec![0f64; usize::from(n) + 1];
+    online[0] = 1.;
+    for (considered, &uptime) in party_uptimes.iter().enumerate() {
+        for k in (0..=considered + 1).rev() {
+            let came_online = if k > 0 { online[k - 1] * uptime } else { 0. };
+            online[k] = online[k] * (1. - uptime) + came_online;
+        }
+    }
+
+    online.iter().skip(usize::from(t) + 1).sum()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn quorum_diff_of_overlapping_quorums() {
+        assert_eq!(quorum_diff(&[1, 2, 3], &[2, 3, 4]), (vec![1], vec![4]));
+    }
+
+    #[test]
+    fn quorum_diff_canonicalizes_input() {
+        assert_eq!(
+            quorum_diff(&[3, 1, 1, 2], &[4, 2, 3, 4]),
+            (vec![1], vec![4])
+        );
+        assert_eq!(quorum_diff(&[1, 2], &[2, 1]), (vec![], vec![]));
+    }
+
+    #[test]
+    fn quorum_availability_of_2_out_of_3() {
+        let p = 0.9;
+        let expected = 3. * p * p * (1. - p) + p * p * p;
+        let availability = quorum_availability(1, 3, &[p; 3]);
+        assert!((availability - expected).abs() < 1e-12);
+    }
+
+    #[test]
+    fn quorum_availability_with_many_parties() {
+        assert!((quorum_availability(0, 100, &[0.5; 100]) - 1.).abs() < 1e-12);
+        assert!(quorum_availability(99, 100, &[0.5; 100]) < 1e-29);
+    }
+
+    #[test]
+    fn security_margin_of_2_out_of_3() {
+        let margin = security_margin(1, 3).unwrap();
+        assert_eq!(margin.corruption_tolerance, 1);
+        assert_eq!(margin.fault_tolerance, 1);
+    }
+
+    #[test]
+    fn security_margin_requires_threshold_below_n() {
+        assert_eq!(security_margin(2, 3).unwrap().fault_tolerance, 0);
+        assert_eq!(security_margin(3, 3), None);
+        assert_eq!(security_margin(u16::MAX, u16::MAX), None);
+    }
+}
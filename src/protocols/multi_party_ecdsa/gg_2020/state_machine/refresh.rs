@@ -0,0 +1,462 @@
+//! Key refresh (proactive resharing) protocol
+//!
+//! Parties holding [LocalKey] of the same group re-randomize their secret shares by adding shares
+//! of zero, and replace their Paillier keys and `(N_tilde, h1, h2)`. Public key stays the same,
+//! while shares leaked before refresh become useless in combination with shares obtained after it.
+
+use std::fmt;
+use std::mem::replace;
+use std::sync::Arc;
+use std::time::Duration;
+
+use curv::elliptic::curves::secp256_k1::Secp256k1;
+use round_based::containers::{
+    push::{Push, PushExt},
+    *,
+};
+use round_based::{IsCritical, Msg, StateMachine};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::protocols::multi_party_ecdsa::gg_2020::state_machine::keygen::{
+    LocalKey, PaillierShareCipher, ShareCipher,
+};
+
+mod rounds;
+
+use private::InternalError;
+pub use rounds::{ProceedError, RefreshBroadcast};
+use rounds::{Round0, Round1, Round2};
+
+/// Key refresh protocol state machine
+///
+/// Takes [LocalKey] obtained at [keygen](super::keygen), and outputs refreshed [LocalKey] of the
+/// same public key. All `n` parties of the keygen must take part.
+pub struct Refresh {
+    round: R,
+
+    msgs1: Option<Store<BroadcastMsgs<RefreshBroadcast>>>,
+    msgs2: Option<Store<P2PMsgs<Vec<u8>>>>,
+
+    msgs_queue: Vec<Msg<ProtocolMessage>>,
+
+    party_i: u16,
+    party_n: u16,
+}
+
+impl Refresh {
+    /// Constructs a party of key refresh protocol
+    ///
+    /// Party index, threshold and number of parties are taken from `local_key`.
+    ///
+    /// Returns [Error::InvalidLocalKey] if `local_key` is inconsistent, i.e. number of public
+    /// shares or Paillier keys doesn't match `n`.
+    pub fn new(local_key: LocalKey<Secp256k1>) -> Result<Self> {
+        Self::with_share_cipher(local_key, Arc::new(PaillierShareCipher))
+    }
+
+    /// Constructs a party of key refresh protocol that encrypts shares of zero with
+    /// `share_cipher`
+    ///
+    /// Same as [keygen](super::keygen::Keygen::with_share_cipher), every party must use
+    /// compatible cipher.
+    pub fn with_share_cipher(
+        local_key: LocalKey<Secp256k1>,
+        share_cipher: Arc<dyn ShareCipher>,
+    ) -> Result<Self> {
+        let (i, n) = (local_key.i, local_key.n);
+        if i == 0
+            || i > n
+            || local_key.pk_vec.len() != usize::from(n)
+            || local_key.paillier_key_vec.len() != usize::from(n)
+        {
+            return Err(Error::InvalidLocalKey);
+        }
+        let mut state = Self {
+            round: R::Round0(Round0 {
+                local_key,
+                share_cipher,
+            }),
+
+            msgs1: Some(Round1::expects_messages(i, n)),
+            msgs2: Some(Round2::expects_messages(i, n)),
+
+            msgs_queue: vec![],
+
+            party_i: i,
+            party_n: n,
+        };
+
+        state.proceed_round(false)?;
+        Ok(state)
+    }
+
+    fn gmap_queue<'a, T, F>(&'a mut self, mut f: F) -> impl Push<Msg<T>> + 'a
+    where
+        F: FnMut(T) -> M + 'a,
+    {
+        (&mut self.msgs_queue).gmap(move |m: Msg<T>| m.map_body(|m| ProtocolMessage(f(m))))
+    }
+
+    /// Proceeds round state if it received enough messages and if it's cheap to compute or
+    /// `may_block == true`
+    fn proceed_round(&mut self, may_block: bool) -> Result<()> {
+        let store1_wants_more = self.msgs1.as_ref().map(|s| s.wants_more()).unwrap_or(false);
+        let store2_wants_more = self.msgs2.as_ref().map(|s| s.wants_more()).unwrap_or(false);
+
+        let next_state: R;
+        let try_again: bool = match replace(&mut self.round, R::Gone) {
+            R::Round0(round) if !round.is_expensive() || may_block => {
+                next_state = round
+                    .proceed(self.gmap_queue(M::Round1))
+                    .map(R::Round1)
+                    .map_err(Error::ProceedRound)?;
+                true
+            }
+            s @ R::Round0(_) => {
+                next_state = s;
+                false
+            }
+            R::Round1(round) if !store1_wants_more && (!round.is_expensive() || may_block) => {
+                let store = self.msgs1.take().ok_or(InternalError::StoreGone)?;
+                let msgs = store
+                    .finish()
+                    .map_err(InternalError::RetrieveRoundMessages)?;
+                next_state = round
+                    .proceed(msgs, self.gmap_queue(M::Round2))
+                    .map(R::Round2)
+                    .map_err(Error::ProceedRound)?;
+                true
+            }
+            s @ R::Round1(_) => {
+                next_state = s;
+                false
+            }
+            R::Round2(round) if !store2_wants_more && (!round.is_expensive() || may_block) => {
+                let store = self.msgs2.take().ok_or(InternalError::StoreGone)?;
+                let msgs = store
+                    .finish()
+                    .map_err(InternalError::RetrieveRoundMessages)?;
+                next_state = round
+                    .proceed(msgs)
+                    .map(R::Final)
+                    .map_err(Error::ProceedRound)?;
+                true
+            }
+            s @ R::Round2(_) => {
+                next_state = s;
+                false
+            }
+            s @ R::Final(_) | s @ R::Gone => {
+                next_state = s;
+                false
+            }
+        };
+
+        self.round = next_state;
+        if try_again {
+            self.proceed_round(may_block)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl StateMachine for Refresh {
+    type MessageBody = ProtocolMessage;
+    type Err = Error;
+    type Output = LocalKey<Secp256k1>;
+
+    fn handle_incoming(&mut self, msg: Msg<Self::MessageBody>) -> Result<()> {
+        let current_round = self.current_round();
+
+        match msg.body {
+            ProtocolMessage(M::Round1(m)) => {
+                let store = self
+                    .msgs1
+                    .as_mut()
+                    .ok_or(Error::ReceivedOutOfOrderMessage {
+                        current_round,
+                        msg_round: 1,
+                    })?;
+                store
+                    .push_msg(Msg {
+                        round: current_round,
+                        sender: msg.sender,
+                        receiver: msg.receiver,
+                        body: m,
+                    })
+                    .map_err(Error::HandleMessage)?;
+            }
+            ProtocolMessage(M::Round2(m)) => {
+                let store = self
+                    .msgs2
+                    .as_mut()
+                    .ok_or(Error::ReceivedOutOfOrderMessage {
+                        current_round,
+                        msg_round: 2,
+                    })?;
+                store
+                    .push_msg(Msg {
+                        round: current_round,
+                        sender: msg.sender,
+                        receiver: msg.receiver,
+                        body: m,
+                    })
+                    .map_err(Error::HandleMessage)?;
+            }
+        }
+
+        self.proceed_round(false)
+    }
+
+    fn message_queue(&mut self) -> &mut Vec<Msg<Self::MessageBody>> {
+        &mut self.msgs_queue
+    }
+
+    fn wants_to_proceed(&self) -> bool {
+        let store1_wants_more = self.msgs1.as_ref().map(|s| s.wants_more()).unwrap_or(false);
+        let store2_wants_more = self.msgs2.as_ref().map(|s| s.wants_more()).unwrap_or(false);
+
+        match &self.round {
+            R::Round0(_) => true,
+            R::Round1(_) => !store1_wants_more,
+            R::Round2(_) => !store2_wants_more,
+            R::Final(_) | R::Gone => false,
+        }
+    }
+
+    fn proceed(&mut self) -> Result<()> {
+        self.proceed_round(true)
+    }
+
+    fn round_timeout(&self) -> Option<Duration> {
+        None
+    }
+
+    fn round_timeout_reached(&mut self) -> Self::Err {
+        panic!("no timeout was set")
+    }
+
+    fn is_finished(&self) -> bool {
+        matches!(self.round, R::Final(_))
+    }
+
+    fn pick_output(&mut self) -> Option<Result<Self::Output>> {
+        match self.round {
+            R::Final(_) => (),
+            R::Gone => return Some(Err(Error::DoublePickOutput)),
+            _ => return None,
+        }
+
+        match replace(&mut self.round, R::Gone) {
+            R::Final(result) => Some(Ok(result)),
+            _ => unreachable!("guaranteed by match expression above"),
+        }
+    }
+
+    fn current_round(&self) -> u16 {
+        match &self.round {
+            R::Round0(_) => 0,
+            R::Round1(_) => 1,
+            R::Round2(_) => 2,
+            R::Final(_) | R::Gone => 3,
+        }
+    }
+
+    fn total_rounds(&self) -> Option<u16> {
+        Some(2)
+    }
+
+    fn party_ind(&self) -> u16 {
+        self.party_i
+    }
+
+    fn parties(&self) -> u16 {
+        self.party_n
+    }
+}
+
+impl super::traits::RoundBlame for Refresh {
+    /// Returns number of unwilling parties and a vector of their party indexes.
+    fn round_blame(&self) -> (u16, Vec<u16>) {
+        let store1_blame = self.msgs1.as_ref().map(|s| s.blame()).unwrap_or_default();
+        let store2_blame = self.msgs2.as_ref().map(|s| s.blame()).unwrap_or_default();
+
+        let default = (0, vec![]);
+        match &self.round {
+            R::Round0(_) => default,
+            R::Round1(_) => store1_blame,
+            R::Round2(_) => store2_blame,
+            R::Final(_) | R::Gone => default,
+        }
+    }
+}
+
+impl fmt::Debug for Refresh {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let current_round = match &self.round {
+            R::Round0(_) => "0",
+            R::Round1(_) => "1",
+            R::Round2(_) => "2",
+            R::Final(_) => "[Final]",
+            R::Gone => "[Gone]",
+        };
+        let msgs1 = match self.msgs1.as_ref() {
+            Some(msgs) => format!("[{}/{}]", msgs.messages_received(), msgs.messages_total()),
+            None => "[None]".into(),
+        };
+        let msgs2 = match self.msgs2.as_ref() {
+            Some(msgs) => format!("[{}/{}]", msgs.messages_received(), msgs.messages_total()),
+            None => "[None]".into(),
+        };
+        write!(
+            f,
+            "{{Refresh at round={} msgs1={} msgs2={} queue=[len={}]}}",
+            current_round,
+            msgs1,
+            msgs2,
+            self.msgs_queue.len()
+        )
+    }
+}
+
+// Rounds
+
+enum R {
+    Round0(Round0),
+    Round1(Round1),
+    Round2(Round2),
+    Final(LocalKey<Secp256k1>),
+    Gone,
+}
+
+// Messages
+
+/// Protocol message which parties send on wire
+///
+/// Hides actual messages structure so it could be changed without breaking semver policy.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProtocolMessage(M);
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+enum M {
+    Round1(RefreshBroadcast),
+    Round2(Vec<u8>),
+}
+
+impl crate::MessageRoundID for ProtocolMessage {
+    fn round_id(&self) -> u16 {
+        match self.0 {
+            M::Round1(_) => 1,
+            M::Round2(_) => 2,
+        }
+    }
+}
+
+// Error
+
+type Result<T> = std::result::Result<T, Error>;
+
+/// Error type of key refresh protocol
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum Error {
+    /// Round proceeding resulted in error
+    #[error("proceed round: {0}")]
+    ProceedRound(#[source] ProceedError),
+
+    /// Local key is inconsistent with its party index and number of parties
+    #[error("local key is inconsistent")]
+    InvalidLocalKey,
+
+    /// Received message didn't pass pre-validation
+    #[error("received message didn't pass pre-validation: {0}")]
+    HandleMessage(#[source] StoreErr),
+    /// Received message which we didn't expect to receive now (e.g. message from previous round)
+    #[error(
+        "didn't expect to receive message from round {msg_round} (being at round {current_round})"
+    )]
+    ReceivedOutOfOrderMessage { current_round: u16, msg_round: u16 },
+    /// [Refresh::pick_output] called twice
+    #[error("pick_output called twice")]
+    DoublePickOutput,
+
+    /// Some internal assertions were failed, which is a bug
+    #[doc(hidden)]
+    #[error("internal error: {0:?}")]
+    InternalError(InternalError),
+}
+
+impl IsCritical for Error {
+    fn is_critical(&self) -> bool {
+        match self {
+            Error::ProceedRound(e) => e.is_critical(),
+            Error::HandleMessage(e) => !matches!(
+                e,
+                StoreErr::MsgOverwrite | StoreErr::NotForMe | StoreErr::WantsMoreMessages
+            ),
+            Error::ReceivedOutOfOrderMessage { .. } => false,
+            Error::DoublePickOutput | Error::InvalidLocalKey | Error::InternalError(_) => true,
+        }
+    }
+}
+
+impl From<InternalError> for Error {
+    fn from(err: InternalError) -> Self {
+        Self::InternalError(err)
+    }
+}
+
+mod private {
+    #[derive(Debug)]
+    #[non_exhaustive]
+    pub enum InternalError {
+        /// [Messages store](super::MessageStore) reported that it received all messages it wanted to receive,
+        /// but refused to return message container
+        RetrieveRoundMessages(super::StoreErr),
+        #[doc(hidden)]
+        StoreGone,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use curv::elliptic::curves::Point;
+    use round_based::dev::Simulation;
+
+    use crate::protocols::multi_party_ecdsa::gg_2020::state_machine::keygen::test::simulate_keygen;
+    use crate::protocols::multi_party_ecdsa::gg_2020::state_machine::sign::test::{
+        simulate_offline_stage, simulate_signing,
+    };
+
+    use super::*;
+
+    fn simulate_refresh(local_keys: Vec<LocalKey<Secp256k1>>) -> Vec<LocalKey<Secp256k1>> {
+        let mut simulation = Simulation::new();
+        for local_key in local_keys {
+            simulation.add_party(Refresh::new(local_key).unwrap());
+        }
+        simulation.run().unwrap()
+    }
+
+    #[test]
+    fn refreshed_key_signs_for_the_same_public_key() {
+        let keys = simulate_keygen(1, 3);
+        simulate_signing(simulate_offline_stage(keys.clone(), &[1, 2]), b"before");
+
+        let refreshed = simulate_refresh(keys.clone());
+        for (old, new) in keys.iter().zip(&refreshed) {
+            assert_eq!(old.public_key(), new.public_key());
+            assert_ne!(old.keys_linear.x_i, new.keys_linear.x_i);
+            assert_ne!(old.paillier_dk.p, new.paillier_dk.p);
+            assert_eq!(
+                Point::generator() * &new.keys_linear.x_i,
+                new.pk_vec[usize::from(new.i - 1)]
+            );
+        }
+
+        simulate_signing(simulate_offline_stage(refreshed.clone(), &[1, 2]), b"after");
+        simulate_signing(simulate_offline_stage(refreshed, &[2, 3]), b"after");
+    }
+}
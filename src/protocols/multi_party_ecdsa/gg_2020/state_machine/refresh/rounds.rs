@@ -0,0 +1,311 @@
+use std::sync::Arc;
+
+use curv::elliptic::curves::{secp256_k1::Secp256k1, Point, Scalar};
+use paillier::EncryptionKey;
+use thiserror::Error;
+
+use round_based::containers::push::Push;
+use round_based::containers::{self, BroadcastMsgs, P2PMsgs, Store};
+use round_based::{IsCritical, Msg};
+
+use crate::protocols::multi_party_ecdsa::gg_2018::VerifiableSS;
+use crate::protocols::multi_party_ecdsa::gg_2020::party_i::{
+    KeyGenBroadcastMessage1, KeyGenDecommitMessage1, Keys, PaillierSetup, SharedKeys,
+};
+use crate::protocols::multi_party_ecdsa::gg_2020::state_machine::keygen::{
+    add_vss, check_distinct_dlog_statements, check_distinct_paillier_keys, check_own_slot,
+    collect_broadcasts, validate_dlog_statement, validate_encrypted_share, LocalKey,
+    ProceedError as KeygenError, ShareCipher,
+};
+
+/// Round 1 message: fresh Paillier key with proofs of its correctness, and commitments to
+/// party's sharing of zero
+///
+/// Commitment to the Paillier key is opened in the same message, it's only there to reuse
+/// [KeyGenBroadcastMessage1::verify_with_decommit].
+pub type RefreshBroadcast = (
+    KeyGenBroadcastMessage1,
+    KeyGenDecommitMessage1,
+    VerifiableSS<Secp256k1>,
+);
+
+pub struct Round0 {
+    pub local_key: LocalKey<Secp256k1>,
+    pub share_cipher: Arc<dyn ShareCipher>,
+}
+
+impl Round0 {
+    pub fn proceed<O>(self, mut output: O) -> Result<Round1>
+    where
+        O: Push<Msg<RefreshBroadcast>>,
+    {
+        let (i, t, n) = (self.local_key.i, self.local_key.t, self.local_key.n);
+        let setup = PaillierSetup::generate();
+        let keys = Keys::create_from_paillier_setup(usize::from(i), &setup);
+        let (bc1, decom1) = keys.phase1_broadcast_with_paillier_setup(&setup);
+        let (zero_vss, zero_shares) = VerifiableSS::share(t, n, &Scalar::zero());
+
+        output.push(Msg {
+            round: 1,
+            sender: i,
+            receiver: None,
+            body: (bc1.clone(), decom1.clone(), zero_vss.clone()),
+        });
+        Ok(Round1 {
+            local_key: self.local_key,
+            share_cipher: self.share_cipher,
+            keys,
+            own_msg: (bc1, decom1, zero_vss),
+            zero_shares: zero_shares.to_vec(),
+        })
+    }
+    pub fn is_expensive(&self) -> bool {
+        true
+    }
+}
+
+pub struct Round1 {
+    local_key: LocalKey<Secp256k1>,
+    share_cipher: Arc<dyn ShareCipher>,
+    keys: Keys,
+    own_msg: RefreshBroadcast,
+    zero_shares: Vec<Scalar<Secp256k1>>,
+}
+
+impl Round1 {
+    pub fn proceed<O>(self, input: BroadcastMsgs<RefreshBroadcast>, mut output: O) -> Result<Round2>
+    where
+        O: Push<Msg<Vec<u8>>>,
+    {
+        let (i, t, n) = (self.local_key.i, self.local_key.t, self.local_key.n);
+        let received = collect_broadcasts(1, input, i, self.own_msg, n)?;
+        let own_n = &self.keys.ek.n;
+        check_own_slot(1, &received, i, |(bc1, _, _)| &bc1.e.n == own_n)?;
+
+        for (party, (bc1, decom1, zero_vss)) in (1..).zip(&received) {
+            if !bc1.verify_with_decommit(decom1)
+                || validate_dlog_statement(&bc1.dlog_statement).is_err()
+            {
+                return Err(ProceedError::InvalidPaillierKey { party });
+            }
+            let zero_sharing_valid = zero_vss.parameters.threshold == t
+                && zero_vss.parameters.share_count == n
+                && zero_vss.commitments.len() == usize::from(t) + 1
+                && zero_vss.commitments[0].is_zero();
+            if !zero_sharing_valid {
+                return Err(ProceedError::InvalidZeroSharing { party });
+            }
+        }
+        let paillier_keys: Vec<EncryptionKey> =
+            received.iter().map(|(bc1, _, _)| bc1.e.clone()).collect();
+        check_distinct_paillier_keys(&paillier_keys)?;
+        let dlog_statements: Vec<_> = received
+            .iter()
+            .map(|(bc1, _, _)| bc1.dlog_statement.clone())
+            .collect();
+        check_distinct_dlog_statements(&dlog_statements)?;
+
+        for ((j, share), (bc1, _, _)) in (1..).zip(&self.zero_shares).zip(&received) {
+            if j == i {
+                continue;
+            }
+            let encrypted_share = self.share_cipher.encrypt(&bc1.e, share);
+            output.push(Msg {
+                round: 2,
+                sender: i,
+                receiver: Some(j),
+                body: encrypted_share,
+            })
+        }
+
+        let (bc1_vec, zero_vss_vec) = received
+            .into_iter()
+            .map(|(bc1, _, zero_vss)| (bc1, zero_vss))
+            .unzip();
+        Ok(Round2 {
+            own_share: self.zero_shares[usize::from(i - 1)].clone(),
+            local_key: self.local_key,
+            share_cipher: self.share_cipher,
+            keys: self.keys,
+            bc1_vec,
+            zero_vss_vec,
+        })
+    }
+    pub fn is_expensive(&self) -> bool {
+        true
+    }
+    pub fn expects_messages(i: u16, n: u16) -> Store<BroadcastMsgs<RefreshBroadcast>> {
+        containers::BroadcastMsgsStore::new(i, n)
+    }
+}
+
+pub struct Round2 {
+    local_key: LocalKey<Secp256k1>,
+    share_cipher: Arc<dyn ShareCipher>,
+    keys: Keys,
+    bc1_vec: Vec<KeyGenBroadcastMessage1>,
+    zero_vss_vec: Vec<VerifiableSS<Secp256k1>>,
+    own_share: Scalar<Secp256k1>,
+}
+
+impl Round2 {
+    pub fn proceed(self, input: P2PMsgs<Vec<u8>>) -> Result<LocalKey<Secp256k1>> {
+        let old = &self.local_key;
+        let (i, n) = (old.i, old.n);
+
+        let mut delta = self.own_share.clone();
+        for (sender, encrypted_share) in input.into_iter_indexed() {
+            validate_encrypted_share(&encrypted_share, &self.keys.ek)
+                .map_err(|_| ProceedError::InvalidCiphertext { sender })?;
+            let share = self
+                .share_cipher
+                .decrypt(&self.keys.dk, &encrypted_share)
+                .ok_or(ProceedError::DecryptShare { sender })?;
+            if self.zero_vss_vec[usize::from(sender - 1)]
+                .validate_share(&share, i)
+                .is_err()
+            {
+                return Err(ProceedError::InvalidShare { sender });
+            }
+            delta = delta + share;
+        }
+
+        let pk_vec = (1..)
+            .zip(&old.pk_vec)
+            .map(|(j, pk)| {
+                self.zero_vss_vec
+                    .iter()
+                    .fold(pk.clone(), |acc, vss| acc + vss.get_point_commitment(j))
+            })
+            .collect::<Vec<Point<Secp256k1>>>();
+        let vss_scheme_vec = if old.vss_scheme_vec.len() == usize::from(n) {
+            old.vss_scheme_vec
+                .iter()
+                .zip(&self.zero_vss_vec)
+                .map(|(vss, zero_vss)| add_vss(vss, zero_vss))
+                .collect()
+        } else {
+            vec![]
+        };
+
+        Ok(LocalKey {
+            paillier_dk: self.keys.dk.clone(),
+            pk_vec,
+            keys_linear: SharedKeys {
+                y: old.keys_linear.y.clone(),
+                x_i: &old.keys_linear.x_i + &delta,
+            },
+            paillier_key_vec: self.bc1_vec.iter().map(|bc1| bc1.e.clone()).collect(),
+            y_sum_s: old.y_sum_s.clone(),
+            h1_h2_n_tilde_vec: self
+                .bc1_vec
+                .iter()
+                .map(|bc1| bc1.dlog_statement.clone())
+                .collect(),
+            vss_scheme: add_vss(&old.vss_scheme, &self.zero_vss_vec[usize::from(i - 1)]),
+            vss_scheme_vec,
+            party_identities: old.party_identities.clone(),
+            // Backups were encrypted shares of the old key, they're useless after refresh
+            recovery_backups: vec![],
+
+            i,
+            t: old.t,
+            n,
+        })
+    }
+    pub fn is_expensive(&self) -> bool {
+        true
+    }
+    pub fn expects_messages(i: u16, n: u16) -> Store<P2PMsgs<Vec<u8>>> {
+        containers::P2PMsgsStore::new(i, n)
+    }
+}
+
+// Errors
+
+type Result<T> = std::result::Result<T, ProceedError>;
+
+/// Proceeding protocol error
+///
+/// Subset of [refresh errors](enum@super::Error) that can occur at protocol proceeding (i.e. after
+/// every message was received and pre-validated).
+#[derive(Debug, Error)]
+pub enum ProceedError {
+    #[error("round 1: party {party} sent invalid Paillier key or dlog statement")]
+    InvalidPaillierKey { party: u16 },
+    #[error("round 1: party {party} sent invalid sharing of zero")]
+    InvalidZeroSharing { party: u16 },
+    #[error("round 2: party {sender} sent invalid share ciphertext")]
+    InvalidCiphertext { sender: u16 },
+    #[error("round 2: share sent by party {sender} can't be decrypted")]
+    DecryptShare { sender: u16 },
+    #[error("round 2: party {sender} sent share which doesn't match its sharing of zero")]
+    InvalidShare { sender: u16 },
+    /// Messages didn't pass a check shared with keygen, e.g. parties reuse Paillier keys
+    #[error(transparent)]
+    Keygen(#[from] KeygenError),
+}
+
+impl ProceedError {
+    /// Indexes of parties (in range `[1; n]`) responsible for the error
+    ///
+    /// Returns `None` if error can't be attributed to specific parties, see
+    /// [keygen blame](KeygenError::blame).
+    pub fn blame(&self) -> Option<Vec<u16>> {
+        match self {
+            ProceedError::InvalidPaillierKey { party }
+            | ProceedError::InvalidZeroSharing { party } => Some(vec![*party]),
+            ProceedError::InvalidCiphertext { sender }
+            | ProceedError::DecryptShare { sender }
+            | ProceedError::InvalidShare { sender } => Some(vec![*sender]),
+            ProceedError::Keygen(err) => err.blame(),
+        }
+    }
+}
+
+impl IsCritical for ProceedError {
+    fn is_critical(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use round_based::containers::MessageStore;
+
+    use crate::protocols::multi_party_ecdsa::gg_2020::state_machine::keygen::test::simulate_keygen;
+    use crate::protocols::multi_party_ecdsa::gg_2020::state_machine::keygen::PaillierShareCipher;
+
+    use super::*;
+
+    #[test]
+    fn copied_paillier_key_is_blamed() {
+        let mut msgs = vec![];
+        let mut parties: Vec<Round1> = simulate_keygen(1, 3)
+            .into_iter()
+            .map(|local_key| {
+                let round = Round0 {
+                    local_key,
+                    share_cipher: Arc::new(PaillierShareCipher),
+                };
+                round.proceed(&mut msgs).unwrap()
+            })
+            .collect();
+
+        // Party 3 copies Paillier key of party 2 along with its proofs
+        msgs[2].body = msgs[1].body.clone();
+        let mut store = Round1::expects_messages(1, 3);
+        for msg in &msgs[1..] {
+            store.push_msg(msg.clone()).unwrap();
+        }
+        let mut output = vec![];
+        match parties
+            .remove(0)
+            .proceed(store.finish().unwrap(), &mut output)
+        {
+            Err(err) => assert_eq!(err.blame(), Some(vec![2, 3])),
+            Ok(_) => panic!("copied Paillier key was accepted"),
+        }
+        assert!(output.is_empty());
+    }
+}
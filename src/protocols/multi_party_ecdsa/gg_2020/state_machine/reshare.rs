@@ -0,0 +1,845 @@
+//! Resharing of the key to a new committee
+//!
+//! At least `t+1` parties holding [LocalKey] hand out shares of the same secret key to a new
+//! committee of `n'` parties with threshold `t'`. Public key stays the same. Old and new committees
+//! may overlap or be disjoint, parties of old committee that aren't members of new one don't get
+//! a new key.
+
+use std::convert::TryFrom;
+use std::fmt;
+use std::mem::replace;
+use std::sync::Arc;
+use std::time::Duration;
+
+use curv::elliptic::curves::{secp256_k1::Secp256k1, Point};
+use round_based::containers::{
+    push::{Push, PushExt},
+    *,
+};
+use round_based::{IsCritical, Msg, StateMachine};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::protocols::multi_party_ecdsa::gg_2020::state_machine::keygen::{
+    LocalKey, PaillierShareCipher, ShareCipher,
+};
+
+mod rounds;
+
+use private::InternalError;
+pub use rounds::{NewPartyKey, ProceedError, ResharedShare, ResharingCommitments};
+use rounds::{Round0, Round1, Round2};
+
+/// Role of a resharing participant, see [ReshareCommittee]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PartyRole {
+    /// Index of the party at keygen, if party holds a share of old committee
+    pub old_index: Option<u16>,
+    /// Index of the party in new committee, if party receives a new share
+    pub new_index: Option<u16>,
+}
+
+/// Participants of resharing, must be the same for every party
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReshareCommittee {
+    /// Threshold of old committee
+    pub old_t: u16,
+    /// Threshold of new committee
+    pub new_t: u16,
+    /// Public key being reshared
+    pub public_key: Point<Secp256k1>,
+    /// Public shares of old committee ordered by keygen index, i.e.
+    /// [pk_vec](LocalKey::pk_vec) of old key
+    ///
+    /// Resharing of every old party is checked against its public share, so a party resharing
+    /// anything but its own share is blamed.
+    pub old_pk_vec: Vec<Point<Secp256k1>>,
+    /// `roles[i-1]` is a role of resharing participant `i`
+    pub roles: Vec<PartyRole>,
+    /// Long-term identities of new committee ordered by new index, carried to
+    /// [party_identities](LocalKey::party_identities) of new keys
+    ///
+    /// Empty if new committee doesn't use identities.
+    #[serde(default)]
+    pub new_party_identities: Vec<Point<Secp256k1>>,
+}
+
+impl ReshareCommittee {
+    /// Number of parties in new committee
+    pub fn new_n(&self) -> u16 {
+        self.roles.iter().filter(|r| r.new_index.is_some()).count() as u16
+    }
+
+    /// Number of resharing participants
+    fn n(&self) -> u16 {
+        self.roles.len() as u16
+    }
+
+    /// Maps indexes in new committee to indexes of resharing participants, sorted
+    fn parties_of_new_indexes(&self, new_indexes: Vec<u16>) -> Vec<u16> {
+        let mut parties: Vec<u16> = (1..)
+            .zip(&self.roles)
+            .filter(|(_, role)| role.new_index.map_or(false, |j| new_indexes.contains(&j)))
+            .map(|(party, _)| party)
+            .collect();
+        parties.sort_unstable();
+        parties
+    }
+
+    /// Committee resharing the key to the same `n` parties with threshold `new_t`
+    ///
+    /// Every party keeps its index and [identity](LocalKey::party_identities). Parties listed in
+    /// `old_parties` contribute their shares, the rest take part only to receive a new share.
+    pub fn change_threshold(key: &LocalKey<Secp256k1>, new_t: u16, old_parties: &[u16]) -> Self {
+        Self {
+            old_t: key.t,
+            new_t,
+            public_key: key.y_sum_s.clone(),
+            old_pk_vec: key.pk_vec.clone(),
+            roles: (1..=key.n)
+                .map(|i| PartyRole {
+                    old_index: old_parties.contains(&i).then(|| i),
+                    new_index: Some(i),
+                })
+                .collect(),
+            new_party_identities: key.party_identities.clone(),
+        }
+    }
+
+    fn validate(&self) -> Result<()> {
+        let mut old_indexes: Vec<u16> = self.roles.iter().filter_map(|r| r.old_index).collect();
+        let mut new_indexes: Vec<u16> = self.roles.iter().filter_map(|r| r.new_index).collect();
+        old_indexes.sort_unstable();
+        new_indexes.sort_unstable();
+        let old_n = old_indexes.len();
+        old_indexes.dedup();
+        if old_indexes.len() != old_n
+            || old_indexes.contains(&0)
+            || old_indexes
+                .iter()
+                .any(|&i| usize::from(i) > self.old_pk_vec.len())
+        {
+            return Err(Error::InvalidCommittee);
+        }
+        if old_indexes.len() <= usize::from(self.old_t) {
+            return Err(Error::TooFewOldParties);
+        }
+        if new_indexes.len() < 2 {
+            return Err(Error::TooFewParties);
+        }
+        if !new_indexes.iter().copied().eq(1..=self.new_n()) {
+            return Err(Error::InvalidCommittee);
+        }
+        if self.new_t == 0 || self.new_t >= self.new_n() {
+            return Err(Error::InvalidThreshold);
+        }
+        if self
+            .roles
+            .iter()
+            .any(|r| r.old_index.is_none() && r.new_index.is_none())
+        {
+            return Err(Error::InvalidCommittee);
+        }
+        if !self.new_party_identities.is_empty()
+            && self.new_party_identities.len() != new_indexes.len()
+        {
+            return Err(Error::InvalidCommittee);
+        }
+        Ok(())
+    }
+}
+
+/// Resharing protocol state machine
+///
+/// Outputs [LocalKey] of the same public key for members of new committee, and `None` for the
+/// parties leaving.
+pub struct Reshare {
+    round: R,
+
+    msgs1: Option<Store<BroadcastMsgs<NewPartyKey>>>,
+    msgs2: Option<Store<BroadcastMsgs<ResharingCommitments>>>,
+    msgs2_shares: Option<Store<P2PMsgs<ResharedShare>>>,
+
+    msgs_queue: Vec<Msg<ProtocolMessage>>,
+
+    party_i: u16,
+    party_n: u16,
+}
+
+impl Reshare {
+    /// Constructs a party of resharing protocol
+    ///
+    /// Takes party index `i` (in range `[1; committee.roles.len()]`), `committee` which must be
+    /// the same for all participants, and `old_key` which must be present iff the party holds a
+    /// share of old committee.
+    ///
+    /// Returns error if:
+    /// * `i` is not in range `[1; committee.roles.len()]`, returns [Error::InvalidPartyIndex]
+    /// * `t+1` or more parties of old committee don't participate, returns [Error::TooFewOldParties]
+    /// * new committee has less than 2 parties, returns [Error::TooFewParties]
+    /// * `new_t` is not in range `[1; n'-1]`, returns [Error::InvalidThreshold]
+    /// * old or new indexes are not distinct, new indexes are not `[1; n']`, or some party has
+    ///   no role, returns [Error::InvalidCommittee]
+    /// * old index exceeds number of [old public shares](ReshareCommittee::old_pk_vec), returns
+    ///   [Error::InvalidCommittee]
+    /// * `old_key` doesn't match party's role or the committee, returns [Error::LocalKeyMismatch]
+    /// * [new identities](ReshareCommittee::new_party_identities) are neither empty nor one per
+    ///   member of new committee, returns [Error::InvalidCommittee]
+    pub fn new(
+        i: u16,
+        committee: ReshareCommittee,
+        old_key: Option<LocalKey<Secp256k1>>,
+    ) -> Result<Self> {
+        Self::with_share_cipher(i, committee, old_key, Arc::new(PaillierShareCipher))
+    }
+
+    /// Constructs a party of resharing protocol that encrypts shares sent to new committee with
+    /// `share_cipher`
+    ///
+    /// Same as [keygen](super::keygen::Keygen::with_share_cipher), every party must use
+    /// compatible cipher.
+    pub fn with_share_cipher(
+        i: u16,
+        committee: ReshareCommittee,
+        old_key: Option<LocalKey<Secp256k1>>,
+        share_cipher: Arc<dyn ShareCipher>,
+    ) -> Result<Self> {
+        let n = u16::try_from(committee.roles.len()).map_err(|_| Error::InvalidCommittee)?;
+        if i == 0 || i > n {
+            return Err(Error::InvalidPartyIndex);
+        }
+        committee.validate()?;
+        let role = committee.roles[usize::from(i - 1)];
+        let key_matches = match (&old_key, role.old_index) {
+            (Some(key), Some(old_i)) => {
+                key.i == old_i
+                    && key.t == committee.old_t
+                    && key.y_sum_s == committee.public_key
+                    && key.pk_vec == committee.old_pk_vec
+            }
+            (None, None) => true,
+            _ => false,
+        };
+        if !key_matches {
+            return Err(Error::LocalKeyMismatch);
+        }
+
+        let (msgs2, msgs2_shares) = Round2::expects_messages(i, n);
+        let mut state = Self {
+            round: R::Round0(Round0 {
+                party_i: i,
+                committee,
+                old_key,
+                share_cipher,
+            }),
+
+            msgs1: Some(Round1::expects_messages(i, n)),
+            msgs2: Some(msgs2),
+            msgs2_shares: Some(msgs2_shares),
+
+            msgs_queue: vec![],
+
+            party_i: i,
+            party_n: n,
+        };
+
+        state.proceed_round(false)?;
+        Ok(state)
+    }
+
+    fn gmap_queue<'a, T, F>(&'a mut self, mut f: F) -> impl Push<Msg<T>> + 'a
+    where
+        F: FnMut(T) -> M + 'a,
+    {
+        (&mut self.msgs_queue).gmap(move |m: Msg<T>| m.map_body(|m| ProtocolMessage(f(m))))
+    }
+
+    /// Proceeds round state if it received enough messages and if it's cheap to compute or
+    /// `may_block == true`
+    fn proceed_round(&mut self, may_block: bool) -> Result<()> {
+        let store1_wants_more = self.msgs1.as_ref().map(|s| s.wants_more()).unwrap_or(false);
+        let store2_wants_more = self.msgs2.as_ref().map(|s| s.wants_more()).unwrap_or(false)
+            || self
+                .msgs2_shares
+                .as_ref()
+                .map(|s| s.wants_more())
+                .unwrap_or(false);
+
+        let next_state: R;
+        let try_again: bool = match replace(&mut self.round, R::Gone) {
+            R::Round0(round) if !round.is_expensive() || may_block => {
+                next_state = round
+                    .proceed(self.gmap_queue(M::Round1))
+                    .map(R::Round1)
+                    .map_err(Error::ProceedRound)?;
+                true
+            }
+            s @ R::Round0(_) => {
+                next_state = s;
+                false
+            }
+            R::Round1(round) if !store1_wants_more && (!round.is_expensive() || may_block) => {
+                let store = self.msgs1.take().ok_or(InternalError::StoreGone)?;
+                let msgs = store
+                    .finish()
+                    .map_err(InternalError::RetrieveRoundMessages)?;
+                let (mut commitments, mut shares) = (vec![], vec![]);
+                let result = round.proceed(msgs, &mut commitments, &mut shares);
+                let commitments = commitments
+                    .into_iter()
+                    .map(|m| m.map_body(|m| ProtocolMessage(M::Round2(m))));
+                let shares = shares
+                    .into_iter()
+                    .map(|m| m.map_body(|m| ProtocolMessage(M::Round2Share(m))));
+                self.msgs_queue.extend(commitments.chain(shares));
+                next_state = result.map(R::Round2).map_err(Error::ProceedRound)?;
+                true
+            }
+            s @ R::Round1(_) => {
+                next_state = s;
+                false
+            }
+            R::Round2(round) if !store2_wants_more && (!round.is_expensive() || may_block) => {
+                let store = self.msgs2.take().ok_or(InternalError::StoreGone)?;
+                let commitments = store
+                    .finish()
+                    .map_err(InternalError::RetrieveRoundMessages)?;
+                let store = self.msgs2_shares.take().ok_or(InternalError::StoreGone)?;
+                let shares = store
+                    .finish()
+                    .map_err(InternalError::RetrieveRoundMessages)?;
+                next_state = round
+                    .proceed(commitments, shares)
+                    .map(R::Final)
+                    .map_err(Error::ProceedRound)?;
+                true
+            }
+            s @ R::Round2(_) => {
+                next_state = s;
+                false
+            }
+            s @ R::Final(_) | s @ R::Gone => {
+                next_state = s;
+                false
+            }
+        };
+
+        self.round = next_state;
+        if try_again {
+            self.proceed_round(may_block)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Changes threshold of the key to `new_t`, keeping the same parties and public key
+///
+/// Runs [Reshare] locally with [changed threshold](ReshareCommittee::change_threshold), so it's
+/// only suitable when all the `keys` are in one place; otherwise parties should run [Reshare]
+/// themselves. `keys` of at least `t+1` parties are required. Returns new keys of all `n`
+/// parties ordered by party index, including parties whose keys weren't given.
+///
+/// Returns [Error::TooFewOldParties] if fewer than `t+1` keys are given, [Error::InvalidThreshold]
+/// if `new_t` is not in range `[1; n-1]`, and [Error::LocalKeyMismatch] if keys don't belong to
+/// the same keygen.
+pub fn reshare_threshold(
+    keys: &[LocalKey<Secp256k1>],
+    new_t: u16,
+) -> Result<Vec<LocalKey<Secp256k1>>> {
+    let first = keys.first().ok_or(Error::TooFewOldParties)?;
+    let old_parties: Vec<u16> = keys.iter().map(|k| k.i).collect();
+    let committee = ReshareCommittee::change_threshold(first, new_t, &old_parties);
+
+    let mut simulation = round_based::dev::Simulation::new();
+    for i in 1..=first.n {
+        let old_key = keys.iter().find(|k| k.i == i).cloned();
+        simulation.add_party(Reshare::new(i, committee.clone(), old_key)?);
+    }
+    let outputs = simulation.run()?;
+    outputs
+        .into_iter()
+        .map(|key| key.ok_or_else(|| InternalError::MissingNewKey.into()))
+        .collect()
+}
+
+impl StateMachine for Reshare {
+    type MessageBody = ProtocolMessage;
+    type Err = Error;
+    type Output = Option<LocalKey<Secp256k1>>;
+
+    fn handle_incoming(&mut self, msg: Msg<Self::MessageBody>) -> Result<()> {
+        let current_round = self.current_round();
+
+        match msg.body {
+            ProtocolMessage(M::Round1(m)) => {
+                let store = self
+                    .msgs1
+                    .as_mut()
+                    .ok_or(Error::ReceivedOutOfOrderMessage {
+                        current_round,
+                        msg_round: 1,
+                    })?;
+                store
+                    .push_msg(Msg {
+                        round: current_round,
+                        sender: msg.sender,
+                        receiver: msg.receiver,
+                        body: m,
+                    })
+                    .map_err(Error::HandleMessage)?;
+            }
+            ProtocolMessage(M::Round2(m)) => {
+                let store = self
+                    .msgs2
+                    .as_mut()
+                    .ok_or(Error::ReceivedOutOfOrderMessage {
+                        current_round,
+                        msg_round: 2,
+                    })?;
+                store
+                    .push_msg(Msg {
+                        round: current_round,
+                        sender: msg.sender,
+                        receiver: msg.receiver,
+                        body: m,
+                    })
+                    .map_err(Error::HandleMessage)?;
+            }
+            ProtocolMessage(M::Round2Share(m)) => {
+                let store = self
+                    .msgs2_shares
+                    .as_mut()
+                    .ok_or(Error::ReceivedOutOfOrderMessage {
+                        current_round,
+                        msg_round: 2,
+                    })?;
+                store
+                    .push_msg(Msg {
+                        round: current_round,
+                        sender: msg.sender,
+                        receiver: msg.receiver,
+                        body: m,
+                    })
+                    .map_err(Error::HandleMessage)?;
+            }
+        }
+
+        self.proceed_round(false)
+    }
+
+    fn message_queue(&mut self) -> &mut Vec<Msg<Self::MessageBody>> {
+        &mut self.msgs_queue
+    }
+
+    fn wants_to_proceed(&self) -> bool {
+        let store1_wants_more = self.msgs1.as_ref().map(|s| s.wants_more()).unwrap_or(false);
+        let store2_wants_more = self.msgs2.as_ref().map(|s| s.wants_more()).unwrap_or(false)
+            || self
+                .msgs2_shares
+                .as_ref()
+                .map(|s| s.wants_more())
+                .unwrap_or(false);
+
+        match &self.round {
+            R::Round0(_) => true,
+            R::Round1(_) => !store1_wants_more,
+            R::Round2(_) => !store2_wants_more,
+            R::Final(_) | R::Gone => false,
+        }
+    }
+
+    fn proceed(&mut self) -> Result<()> {
+        self.proceed_round(true)
+    }
+
+    fn round_timeout(&self) -> Option<Duration> {
+        None
+    }
+
+    fn round_timeout_reached(&mut self) -> Self::Err {
+        panic!("no timeout was set")
+    }
+
+    fn is_finished(&self) -> bool {
+        matches!(self.round, R::Final(_))
+    }
+
+    fn pick_output(&mut self) -> Option<Result<Self::Output>> {
+        match self.round {
+            R::Final(_) => (),
+            R::Gone => return Some(Err(Error::DoublePickOutput)),
+            _ => return None,
+        }
+
+        match replace(&mut self.round, R::Gone) {
+            R::Final(result) => Some(Ok(result)),
+            _ => unreachable!("guaranteed by match expression above"),
+        }
+    }
+
+    fn current_round(&self) -> u16 {
+        match &self.round {
+            R::Round0(_) => 0,
+            R::Round1(_) => 1,
+            R::Round2(_) => 2,
+            R::Final(_) | R::Gone => 3,
+        }
+    }
+
+    fn total_rounds(&self) -> Option<u16> {
+        Some(2)
+    }
+
+    fn party_ind(&self) -> u16 {
+        self.party_i
+    }
+
+    fn parties(&self) -> u16 {
+        self.party_n
+    }
+}
+
+impl super::traits::RoundBlame for Reshare {
+    /// Returns number of unwilling parties and a vector of their party indexes.
+    fn round_blame(&self) -> (u16, Vec<u16>) {
+        let store1_blame = self.msgs1.as_ref().map(|s| s.blame()).unwrap_or_default();
+        let (mut store2_unwilling, mut store2_blame) =
+            self.msgs2.as_ref().map(|s| s.blame()).unwrap_or_default();
+        let (_, shares_blame) = self
+            .msgs2_shares
+            .as_ref()
+            .map(|s| s.blame())
+            .unwrap_or_default();
+        for party in shares_blame {
+            if !store2_blame.contains(&party) {
+                store2_blame.push(party);
+                store2_unwilling += 1;
+            }
+        }
+        store2_blame.sort_unstable();
+
+        let default = (0, vec![]);
+        match &self.round {
+            R::Round0(_) => default,
+            R::Round1(_) => store1_blame,
+            R::Round2(_) => (store2_unwilling, store2_blame),
+            R::Final(_) | R::Gone => default,
+        }
+    }
+}
+
+impl fmt::Debug for Reshare {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let current_round = match &self.round {
+            R::Round0(_) => "0",
+            R::Round1(_) => "1",
+            R::Round2(_) => "2",
+            R::Final(_) => "[Final]",
+            R::Gone => "[Gone]",
+        };
+        let msgs1 = match self.msgs1.as_ref() {
+            Some(msgs) => format!("[{}/{}]", msgs.messages_received(), msgs.messages_total()),
+            None => "[None]".into(),
+        };
+        let msgs2 = match self.msgs2.as_ref() {
+            Some(msgs) => format!("[{}/{}]", msgs.messages_received(), msgs.messages_total()),
+            None => "[None]".into(),
+        };
+        let msgs2_shares = match self.msgs2_shares.as_ref() {
+            Some(msgs) => format!("[{}/{}]", msgs.messages_received(), msgs.messages_total()),
+            None => "[None]".into(),
+        };
+        write!(
+            f,
+            "{{Reshare at round={} msgs1={} msgs2={} msgs2_shares={} queue=[len={}]}}",
+            current_round,
+            msgs1,
+            msgs2,
+            msgs2_shares,
+            self.msgs_queue.len()
+        )
+    }
+}
+
+// Rounds
+
+enum R {
+    Round0(Round0),
+    Round1(Round1),
+    Round2(Round2),
+    Final(Option<LocalKey<Secp256k1>>),
+    Gone,
+}
+
+// Messages
+
+/// Protocol message which parties send on wire
+///
+/// Hides actual messages structure so it could be changed without breaking semver policy.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProtocolMessage(M);
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+enum M {
+    Round1(NewPartyKey),
+    Round2(ResharingCommitments),
+    Round2Share(ResharedShare),
+}
+
+impl crate::MessageRoundID for ProtocolMessage {
+    fn round_id(&self) -> u16 {
+        match self.0 {
+            M::Round1(_) => 1,
+            M::Round2(_) | M::Round2Share(_) => 2,
+        }
+    }
+}
+
+// Error
+
+type Result<T> = std::result::Result<T, Error>;
+
+/// Error type of resharing protocol
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum Error {
+    /// Round proceeding resulted in error
+    #[error("proceed round: {0}")]
+    ProceedRound(#[source] ProceedError),
+
+    /// New committee has less than 2 parties
+    #[error("at least 2 parties are required in new committee")]
+    TooFewParties,
+    /// Less than `t+1` parties of old committee participate
+    #[error("at least t+1 parties of old committee must participate")]
+    TooFewOldParties,
+    /// New threshold value is not in range `[1; n'-1]`
+    #[error("new threshold is not in range [1; n'-1]")]
+    InvalidThreshold,
+    /// Party index `i` is not in range `[1; n]`
+    #[error("party index is not in range [1; n]")]
+    InvalidPartyIndex,
+    /// Committee roles are malformed
+    #[error("committee roles are malformed")]
+    InvalidCommittee,
+    /// Local key is missing, present for a party without old share, or doesn't match committee
+    #[error("local key doesn't match party's role")]
+    LocalKeyMismatch,
+
+    /// Received message didn't pass pre-validation
+    #[error("received message didn't pass pre-validation: {0}")]
+    HandleMessage(#[source] StoreErr),
+    /// Received message which we didn't expect to receive now (e.g. message from previous round)
+    #[error(
+        "didn't expect to receive message from round {msg_round} (being at round {current_round})"
+    )]
+    ReceivedOutOfOrderMessage { current_round: u16, msg_round: u16 },
+    /// [Reshare::pick_output] called twice
+    #[error("pick_output called twice")]
+    DoublePickOutput,
+
+    /// Some internal assertions were failed, which is a bug
+    #[doc(hidden)]
+    #[error("internal error: {0:?}")]
+    InternalError(InternalError),
+}
+
+impl IsCritical for Error {
+    fn is_critical(&self) -> bool {
+        match self {
+            Error::ProceedRound(e) => e.is_critical(),
+            Error::HandleMessage(e) => !matches!(
+                e,
+                StoreErr::MsgOverwrite | StoreErr::NotForMe | StoreErr::WantsMoreMessages
+            ),
+            Error::ReceivedOutOfOrderMessage { .. } => false,
+            Error::DoublePickOutput
+            | Error::TooFewParties
+            | Error::TooFewOldParties
+            | Error::InvalidThreshold
+            | Error::InvalidPartyIndex
+            | Error::InvalidCommittee
+            | Error::LocalKeyMismatch
+            | Error::InternalError(_) => true,
+        }
+    }
+}
+
+impl From<InternalError> for Error {
+    fn from(err: InternalError) -> Self {
+        Self::InternalError(err)
+    }
+}
+
+mod private {
+    #[derive(Debug)]
+    #[non_exhaustive]
+    pub enum InternalError {
+        /// [Messages store](super::MessageStore) reported that it received all messages it wanted to receive,
+        /// but refused to return message container
+        RetrieveRoundMessages(super::StoreErr),
+        #[doc(hidden)]
+        StoreGone,
+        /// Member of new committee didn't output a key
+        MissingNewKey,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use round_based::dev::Simulation;
+
+    use crate::protocols::multi_party_ecdsa::gg_2020::state_machine::keygen::test::simulate_keygen;
+    use crate::protocols::multi_party_ecdsa::gg_2020::state_machine::sign::test::{
+        simulate_offline_stage, simulate_signing,
+    };
+
+    use super::*;
+
+    fn committee(
+        key: &LocalKey<Secp256k1>,
+        new_t: u16,
+        roles: &[(Option<u16>, Option<u16>)],
+    ) -> ReshareCommittee {
+        ReshareCommittee {
+            old_t: key.t,
+            new_t,
+            public_key: key.y_sum_s.clone(),
+            old_pk_vec: key.pk_vec.clone(),
+            roles: roles
+                .iter()
+                .map(|&(old_index, new_index)| PartyRole {
+                    old_index,
+                    new_index,
+                })
+                .collect(),
+            new_party_identities: vec![],
+        }
+    }
+
+    #[test]
+    fn reshare_to_overlapping_committee() {
+        let keys = simulate_keygen(1, 3);
+        // Party 1 leaves, party 2 stays, parties 3 and 4 join. Old party 3 doesn't participate.
+        let committee = committee(
+            &keys[0],
+            2,
+            &[
+                (Some(1), None),
+                (Some(2), Some(1)),
+                (None, Some(2)),
+                (None, Some(3)),
+            ],
+        );
+        let old_keys = vec![Some(keys[0].clone()), Some(keys[1].clone()), None, None];
+
+        let mut simulation = Simulation::new();
+        for (i, old_key) in (1..).zip(old_keys) {
+            simulation.add_party(Reshare::new(i, committee.clone(), old_key).unwrap());
+        }
+        let outputs = simulation.run().unwrap();
+        assert!(outputs[0].is_none());
+
+        let new_keys: Vec<_> = outputs.into_iter().flatten().collect();
+        assert_eq!(new_keys.len(), 3);
+        for (new_i, key) in (1..).zip(&new_keys) {
+            assert_eq!((key.i, key.t, key.n), (new_i, 2, 3));
+            assert_eq!(key.public_key(), keys[0].public_key());
+            assert_eq!(key.vss_scheme_vec.len(), 3);
+            key.verify_keys_linear_consistency().unwrap();
+            key.verify_contribution_sum().unwrap();
+        }
+        simulate_signing(simulate_offline_stage(new_keys, &[1, 2, 3]), b"reshared");
+    }
+
+    #[test]
+    fn dealer_resharing_foreign_secret_is_blamed() {
+        use crate::protocols::multi_party_ecdsa::gg_2018::VerifiableSS;
+        use curv::elliptic::curves::Scalar;
+
+        let keys = simulate_keygen(1, 3);
+        let committee = ReshareCommittee::change_threshold(&keys[0], 1, &[1, 2]);
+        let mut parties: Vec<Reshare> = (1..=3)
+            .map(|i| {
+                let old_key = keys.iter().find(|k| k.i == i && i <= 2).cloned();
+                Reshare::new(i, committee.clone(), old_key).unwrap()
+            })
+            .collect();
+
+        let mut blamed = false;
+        while !blamed {
+            for party in &mut parties {
+                if party.wants_to_proceed() {
+                    match party.proceed() {
+                        Ok(()) => (),
+                        Err(Error::ProceedRound(ProceedError::ResharingMismatch { sender: 2 })) => {
+                            blamed = true
+                        }
+                        Err(err) => panic!("unexpected error: {}", err),
+                    }
+                }
+            }
+            let outgoing: Vec<_> = parties
+                .iter_mut()
+                .flat_map(|p| p.message_queue().drain(..).collect::<Vec<_>>())
+                .collect();
+            for mut msg in outgoing {
+                // Party 2 broadcasts commitments to a secret unrelated to its share
+                if let ProtocolMessage(M::Round2(Some(vss))) = &mut msg.body {
+                    if msg.sender == 2 {
+                        *vss = VerifiableSS::share(1, 3, &Scalar::random()).0;
+                    }
+                }
+                for party in &mut parties {
+                    let i = party.party_ind();
+                    if msg.sender != i && msg.receiver.map_or(true, |j| j == i) {
+                        party.handle_incoming(msg.clone()).unwrap();
+                    }
+                }
+            }
+            assert!(!parties.iter().all(|p| p.is_finished()));
+        }
+    }
+
+    #[test]
+    fn reshare_requires_t_plus_one_old_parties() {
+        let keys = simulate_keygen(1, 3);
+        let committee = committee(&keys[0], 1, &[(Some(1), Some(1)), (None, Some(2))]);
+        assert!(matches!(
+            Reshare::new(1, committee, Some(keys[0].clone())),
+            Err(Error::TooFewOldParties)
+        ));
+    }
+
+    #[test]
+    fn threshold_is_changed_for_the_same_parties() {
+        use crate::protocols::multi_party_ecdsa::gg_2020::state_machine::sign::{
+            Error as SignError, OfflineStage,
+        };
+
+        let keys = simulate_keygen(1, 4);
+        let new_keys = reshare_threshold(&keys[1..3], 2).unwrap();
+        for (i, key) in (1..).zip(&new_keys) {
+            assert_eq!((key.i, key.t, key.n), (i, 2, 4));
+            assert_eq!(key.public_key(), keys[0].public_key());
+        }
+        simulate_signing(
+            simulate_offline_stage(new_keys.clone(), &[1, 2, 4]),
+            b"rethresholded",
+        );
+        assert!(matches!(
+            OfflineStage::new(1, vec![1, 2], new_keys[0].clone()),
+            Err(SignError::TooFewSigners { .. })
+        ));
+
+        assert!(matches!(
+            reshare_threshold(&keys[..1], 2),
+            Err(Error::TooFewOldParties)
+        ));
+        assert!(matches!(
+            reshare_threshold(&keys, 4),
+            Err(Error::InvalidThreshold)
+        ));
+    }
+}
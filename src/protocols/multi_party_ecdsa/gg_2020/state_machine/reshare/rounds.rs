@@ -0,0 +1,412 @@
+use std::sync::Arc;
+
+use curv::cryptographic_primitives::secret_sharing::feldman_vss::ShamirSecretSharing;
+use curv::elliptic::curves::{secp256_k1::Secp256k1, Point, Scalar};
+use thiserror::Error;
+
+use round_based::containers::push::Push;
+use round_based::containers::{self, BroadcastMsgs, P2PMsgs, Store};
+use round_based::{IsCritical, Msg};
+
+use crate::protocols::multi_party_ecdsa::gg_2018::VerifiableSS;
+use crate::protocols::multi_party_ecdsa::gg_2020::party_i::{
+    KeyGenBroadcastMessage1, KeyGenDecommitMessage1, Keys, PaillierSetup, SharedKeys,
+};
+use crate::protocols::multi_party_ecdsa::gg_2020::state_machine::keygen::{
+    add_vss, check_own_slot, collect_broadcasts, parties_sharing_modulus, validate_dlog_statement,
+    validate_encrypted_share, LocalKey, ProceedError as KeygenError, ShareCipher,
+};
+
+use super::ReshareCommittee;
+
+/// Round 1 message: fresh Paillier key of a new committee member with proofs of its correctness
+///
+/// Parties which are not members of new committee send `None`.
+pub type NewPartyKey = Option<(KeyGenBroadcastMessage1, KeyGenDecommitMessage1)>;
+
+/// Round 2 broadcast message: old party's sharing of its Lagrange-weighted share
+///
+/// Parties which don't hold old key send `None`. Sent via broadcast channel, so every member of
+/// new committee validates its share against the same commitments; the channel must guarantee
+/// that all parties receive the same message, as keygen broadcasts do.
+pub type ResharingCommitments = Option<VerifiableSS<Secp256k1>>;
+
+/// Round 2 p2p message: share of recipient encrypted under recipient's new Paillier key
+///
+/// Parties which don't hold old key send `None`, as well as everyone sends `None` to parties which
+/// are not members of new committee.
+pub type ResharedShare = Option<Vec<u8>>;
+
+pub struct Round0 {
+    pub party_i: u16,
+    pub committee: ReshareCommittee,
+    pub old_key: Option<LocalKey<Secp256k1>>,
+    pub share_cipher: Arc<dyn ShareCipher>,
+}
+
+impl Round0 {
+    pub fn proceed<O>(self, mut output: O) -> Result<Round1>
+    where
+        O: Push<Msg<NewPartyKey>>,
+    {
+        let role = self.committee.roles[usize::from(self.party_i - 1)];
+        let (keys, own_msg) = match role.new_index {
+            Some(new_i) => {
+                let setup = PaillierSetup::generate();
+                let keys = Keys::create_from_paillier_setup(usize::from(new_i), &setup);
+                let (bc1, decom1) = keys.phase1_broadcast_with_paillier_setup(&setup);
+                (Some(keys), Some((bc1, decom1)))
+            }
+            None => (None, None),
+        };
+
+        output.push(Msg {
+            round: 1,
+            sender: self.party_i,
+            receiver: None,
+            body: own_msg.clone(),
+        });
+        Ok(Round1 {
+            party_i: self.party_i,
+            committee: self.committee,
+            old_key: self.old_key,
+            share_cipher: self.share_cipher,
+            keys,
+            own_msg,
+        })
+    }
+    pub fn is_expensive(&self) -> bool {
+        self.committee.roles[usize::from(self.party_i - 1)]
+            .new_index
+            .is_some()
+    }
+}
+
+pub struct Round1 {
+    party_i: u16,
+    committee: ReshareCommittee,
+    old_key: Option<LocalKey<Secp256k1>>,
+    share_cipher: Arc<dyn ShareCipher>,
+    keys: Option<Keys>,
+    own_msg: NewPartyKey,
+}
+
+impl Round1 {
+    pub fn proceed<B, P>(
+        self,
+        input: BroadcastMsgs<NewPartyKey>,
+        mut broadcast: B,
+        mut output: P,
+    ) -> Result<Round2>
+    where
+        B: Push<Msg<ResharingCommitments>>,
+        P: Push<Msg<ResharedShare>>,
+    {
+        let committee = &self.committee;
+        let (n, new_n) = (committee.n(), committee.new_n());
+
+        let mut new_keys: Vec<Option<KeyGenBroadcastMessage1>> = vec![None; usize::from(new_n)];
+        let received = collect_broadcasts(1, input, self.party_i, self.own_msg, n)?;
+        let own_n = self.keys.as_ref().map(|keys| &keys.ek.n);
+        check_own_slot(1, &received, self.party_i, |msg| {
+            msg.as_ref().map(|(bc1, _)| &bc1.e.n) == own_n
+        })?;
+        for ((party, role), msg) in (1..).zip(&committee.roles).zip(received) {
+            match (role.new_index, msg) {
+                (Some(new_i), Some((bc1, decom1)))
+                    if bc1.verify_with_decommit(&decom1)
+                        && validate_dlog_statement(&bc1.dlog_statement).is_ok() =>
+                {
+                    new_keys[usize::from(new_i - 1)] = Some(bc1)
+                }
+                (None, None) => (),
+                _ => return Err(ProceedError::InvalidNewPartyKey { party }),
+            }
+        }
+        let new_keys: Vec<KeyGenBroadcastMessage1> = new_keys
+            .into_iter()
+            .map(|bc1| bc1.expect("every new index is assigned by committee"))
+            .collect();
+        let duplicated = parties_sharing_modulus(new_keys.iter().map(|bc1| &bc1.e.n));
+        if !duplicated.is_empty() {
+            return Err(ProceedError::DuplicatePaillierKey {
+                parties: committee.parties_of_new_indexes(duplicated),
+            });
+        }
+        let duplicated = parties_sharing_modulus(new_keys.iter().map(|bc1| &bc1.dlog_statement.N));
+        if !duplicated.is_empty() {
+            return Err(ProceedError::DuplicateDLogStatement {
+                parties: committee.parties_of_new_indexes(duplicated),
+            });
+        }
+
+        let mut own_resharing = None;
+        let resharing = self.old_key.as_ref().map(|old_key| {
+            let w_i = lagrange_weight(committee, old_key.i) * &old_key.keys_linear.x_i;
+            VerifiableSS::share(committee.new_t, new_n, &w_i)
+        });
+
+        broadcast.push(Msg {
+            round: 2,
+            sender: self.party_i,
+            receiver: None,
+            body: resharing.as_ref().map(|(vss, _)| vss.clone()),
+        });
+        for (party, role) in (1..).zip(&committee.roles) {
+            let body = match (&resharing, role.new_index) {
+                (Some((vss, shares)), Some(new_i)) if party == self.party_i => {
+                    own_resharing = Some((vss.clone(), shares[usize::from(new_i - 1)].clone()));
+                    continue;
+                }
+                (Some((_, shares)), Some(new_i)) => {
+                    let ek = &new_keys[usize::from(new_i - 1)].e;
+                    let share = &shares[usize::from(new_i - 1)];
+                    Some(self.share_cipher.encrypt(ek, share))
+                }
+                _ if party == self.party_i => continue,
+                _ => None,
+            };
+            output.push(Msg {
+                round: 2,
+                sender: self.party_i,
+                receiver: Some(party),
+                body,
+            })
+        }
+
+        Ok(Round2 {
+            party_i: self.party_i,
+            committee: self.committee,
+            share_cipher: self.share_cipher,
+            keys: self.keys,
+            new_keys,
+            own_resharing,
+        })
+    }
+    pub fn is_expensive(&self) -> bool {
+        true
+    }
+    pub fn expects_messages(i: u16, n: u16) -> Store<BroadcastMsgs<NewPartyKey>> {
+        containers::BroadcastMsgsStore::new(i, n)
+    }
+}
+
+pub struct Round2 {
+    party_i: u16,
+    committee: ReshareCommittee,
+    share_cipher: Arc<dyn ShareCipher>,
+    keys: Option<Keys>,
+    new_keys: Vec<KeyGenBroadcastMessage1>,
+    own_resharing: Option<(VerifiableSS<Secp256k1>, Scalar<Secp256k1>)>,
+}
+
+impl Round2 {
+    pub fn proceed(
+        self,
+        commitments: BroadcastMsgs<ResharingCommitments>,
+        shares: P2PMsgs<ResharedShare>,
+    ) -> Result<Option<LocalKey<Secp256k1>>> {
+        let committee = &self.committee;
+        let (new_i, keys) = match (
+            committee.roles[usize::from(self.party_i - 1)].new_index,
+            &self.keys,
+        ) {
+            (Some(new_i), Some(keys)) => (new_i, keys),
+            // Party is leaving: it doesn't get a share of new committee
+            _ => return Ok(None),
+        };
+        let (new_t, new_n) = (committee.new_t, committee.new_n());
+
+        let mut resharings: Vec<_> = self
+            .own_resharing
+            .into_iter()
+            .map(|(vss, share)| (self.party_i, vss, share))
+            .collect();
+        let mut received_vss = vec![None; committee.roles.len()];
+        for (sender, vss) in commitments.into_iter_indexed() {
+            let old_i = committee.roles[usize::from(sender - 1)].old_index;
+            let (old_i, vss) = match (old_i, vss) {
+                (Some(old_i), Some(vss)) => (old_i, vss),
+                (None, None) => continue,
+                _ => return Err(ProceedError::InvalidResharing { sender }),
+            };
+            if vss.parameters.threshold != new_t
+                || vss.parameters.share_count != new_n
+                || vss.commitments.len() != usize::from(new_t) + 1
+            {
+                return Err(ProceedError::InvalidResharing { sender });
+            }
+            // Dealer must reshare exactly its Lagrange-weighted share of the old key
+            let expected =
+                &committee.old_pk_vec[usize::from(old_i - 1)] * &lagrange_weight(committee, old_i);
+            if vss.commitments[0] != expected {
+                return Err(ProceedError::ResharingMismatch { sender });
+            }
+            received_vss[usize::from(sender - 1)] = Some(vss);
+        }
+        for (sender, msg) in shares.into_iter_indexed() {
+            let vss = received_vss[usize::from(sender - 1)].take();
+            let (vss, encrypted_share) = match (vss, msg) {
+                (Some(vss), Some(encrypted_share)) => (vss, encrypted_share),
+                (None, None) => continue,
+                _ => return Err(ProceedError::InvalidResharing { sender }),
+            };
+            validate_encrypted_share(&encrypted_share, &keys.ek)
+                .map_err(|_| ProceedError::InvalidCiphertext { sender })?;
+            let share = self
+                .share_cipher
+                .decrypt(&keys.dk, &encrypted_share)
+                .ok_or(ProceedError::DecryptShare { sender })?;
+            if vss.validate_share(&share, new_i).is_err() {
+                return Err(ProceedError::InvalidShare { sender });
+            }
+            resharings.push((sender, vss, share));
+        }
+
+        let y_sum_s: Point<Secp256k1> = resharings
+            .iter()
+            .map(|(_, vss, _)| vss.commitments[0].clone())
+            .sum();
+        if y_sum_s != committee.public_key {
+            return Err(ProceedError::PublicKeyMismatch);
+        }
+
+        // New party is attributed resharings of dealers taking its place in new committee,
+        // resharings of leaving dealers are attributed to party 1. Party attributed none of
+        // them gets commitments to zero polynomial.
+        let mut zero_vss = resharings[0].1.clone();
+        zero_vss.commitments = vec![Point::zero(); usize::from(new_t) + 1];
+        let mut vss_scheme_vec = vec![zero_vss; usize::from(new_n)];
+        for (dealer, vss, _) in &resharings {
+            let k = committee.roles[usize::from(dealer - 1)]
+                .new_index
+                .unwrap_or(1);
+            let attributed = &mut vss_scheme_vec[usize::from(k - 1)];
+            *attributed = add_vss(attributed, vss);
+        }
+        let aggregate = vss_scheme_vec[1..]
+            .iter()
+            .fold(vss_scheme_vec[0].clone(), |acc, vss| add_vss(&acc, vss));
+        let pk_vec = (1..=new_n)
+            .map(|k| aggregate.get_point_commitment(k))
+            .collect();
+        let x_i = resharings
+            .iter()
+            .fold(Scalar::zero(), |acc, (_, _, share)| acc + share);
+
+        Ok(Some(LocalKey {
+            paillier_dk: keys.dk.clone(),
+            pk_vec,
+            keys_linear: SharedKeys {
+                y: y_sum_s.clone(),
+                x_i,
+            },
+            paillier_key_vec: self.new_keys.iter().map(|bc1| bc1.e.clone()).collect(),
+            y_sum_s,
+            h1_h2_n_tilde_vec: self
+                .new_keys
+                .iter()
+                .map(|bc1| bc1.dlog_statement.clone())
+                .collect(),
+            vss_scheme: vss_scheme_vec[usize::from(new_i - 1)].clone(),
+            vss_scheme_vec,
+            party_identities: committee.new_party_identities.clone(),
+            recovery_backups: vec![],
+
+            i: new_i,
+            t: new_t,
+            n: new_n,
+        }))
+    }
+    pub fn is_expensive(&self) -> bool {
+        true
+    }
+    pub fn expects_messages(
+        i: u16,
+        n: u16,
+    ) -> (
+        Store<BroadcastMsgs<ResharingCommitments>>,
+        Store<P2PMsgs<ResharedShare>>,
+    ) {
+        (
+            containers::BroadcastMsgsStore::new(i, n),
+            containers::P2PMsgsStore::new(i, n),
+        )
+    }
+}
+
+/// Lagrange coefficient of old party `old_i` for the set of old parties participating in resharing
+fn lagrange_weight(committee: &ReshareCommittee, old_i: u16) -> Scalar<Secp256k1> {
+    let old_indexes: Vec<u16> = committee
+        .roles
+        .iter()
+        .filter_map(|role| role.old_index)
+        .map(|old_i| old_i - 1)
+        .collect();
+    let params = ShamirSecretSharing {
+        threshold: committee.old_t,
+        share_count: committee.old_pk_vec.len() as u16,
+    };
+    VerifiableSS::<Secp256k1>::map_share_to_new_params(&params, old_i - 1, &old_indexes)
+}
+
+// Errors
+
+type Result<T> = std::result::Result<T, ProceedError>;
+
+/// Proceeding protocol error
+///
+/// Subset of [resharing errors](enum@super::Error) that can occur at protocol proceeding (i.e. after
+/// every message was received and pre-validated).
+#[derive(Debug, Error)]
+pub enum ProceedError {
+    #[error("round 1: party {party} sent invalid Paillier key or it doesn't match party's role")]
+    InvalidNewPartyKey { party: u16 },
+    #[error("round 2: party {sender} sent malformed resharing or it doesn't match party's role")]
+    InvalidResharing { sender: u16 },
+    #[error("round 2: party {sender} reshares a secret which is not its share of the old key")]
+    ResharingMismatch { sender: u16 },
+    #[error("round 1: parties {parties:?} use the same Paillier key")]
+    DuplicatePaillierKey { parties: Vec<u16> },
+    #[error("round 1: parties {parties:?} have the same dlog statement")]
+    DuplicateDLogStatement { parties: Vec<u16> },
+    #[error("round 2: party {sender} sent invalid share ciphertext")]
+    InvalidCiphertext { sender: u16 },
+    #[error("round 2: share sent by party {sender} can't be decrypted")]
+    DecryptShare { sender: u16 },
+    #[error("round 2: party {sender} sent share which doesn't match its resharing")]
+    InvalidShare { sender: u16 },
+    #[error("round 2: resharings don't add up to the public key")]
+    PublicKeyMismatch,
+    /// Messages didn't pass a check shared with keygen, e.g. some party didn't send a message
+    #[error(transparent)]
+    Keygen(#[from] KeygenError),
+}
+
+impl ProceedError {
+    /// Indexes of resharing participants (in range `[1; committee.roles.len()]`) responsible
+    /// for the error
+    ///
+    /// Returns `None` if error can't be attributed to specific parties, see
+    /// [keygen blame](KeygenError::blame).
+    pub fn blame(&self) -> Option<Vec<u16>> {
+        match self {
+            ProceedError::InvalidNewPartyKey { party } => Some(vec![*party]),
+            ProceedError::InvalidResharing { sender }
+            | ProceedError::ResharingMismatch { sender }
+            | ProceedError::InvalidCiphertext { sender }
+            | ProceedError::DecryptShare { sender }
+            | ProceedError::InvalidShare { sender } => Some(vec![*sender]),
+            ProceedError::DuplicatePaillierKey { parties }
+            | ProceedError::DuplicateDLogStatement { parties } => Some(parties.clone()),
+            ProceedError::PublicKeyMismatch => None,
+            ProceedError::Keygen(err) => err.blame(),
+        }
+    }
+}
+
+impl IsCritical for ProceedError {
+    fn is_critical(&self) -> bool {
+        true
+    }
+}
@@ -0,0 +1,79 @@
+//! Binding of protocol messages to a session
+
+use serde::de::{Deserialize, Deserializer};
+use serde::ser::{Serialize, SerializeTupleStruct, Serializer};
+use sha2::{Digest, Sha256};
+
+/// Tag binding message to a session, derived from session id, round and sender of the message
+pub(crate) type SessionTag = [u8; 32];
+
+/// Computes tag of message sent by `sender` at `round` of session `session_id`
+///
+/// `domain` separates tags of different protocols, so message of one protocol can't be passed
+/// off as message of another one sharing the same session id.
+pub(crate) fn session_tag(
+    domain: &[u8],
+    session_id: &[u8; 32],
+    round: u16,
+    sender: u16,
+) -> SessionTag {
+    let mut tag = [0u8; 32];
+    tag.copy_from_slice(
+        &Sha256::new()
+            .chain(domain)
+            .chain(session_id)
+            .chain(round.to_be_bytes())
+            .chain(sender.to_be_bytes())
+            .finalize(),
+    );
+    tag
+}
+
+/// Serializes protocol message `body` along with its session `tag`
+///
+/// Message without a tag is serialized as newtype struct `name`, the same as messages of versions
+/// which didn't support session ids, so parties not using session ids keep the wire format.
+/// Tagged message is serialized as 2-element tuple struct.
+pub(crate) fn serialize_tagged<S, T>(
+    name: &'static str,
+    body: &T,
+    tag: &Option<SessionTag>,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+    T: Serialize,
+{
+    match tag {
+        None => serializer.serialize_newtype_struct(name, body),
+        Some(tag) => {
+            let mut s = serializer.serialize_tuple_struct(name, 2)?;
+            s.serialize_field(body)?;
+            s.serialize_field(tag)?;
+            s.end()
+        }
+    }
+}
+
+/// Deserializes message produced by [serialize_tagged]
+///
+/// Both encodings are accepted, so the format must be self-describing (e.g. JSON or CBOR).
+pub(crate) fn deserialize_tagged<'de, D, T>(
+    deserializer: D,
+) -> Result<(T, Option<SessionTag>), D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    #[derive(serde::Deserialize)]
+    #[serde(untagged)]
+    enum Wire<T> {
+        Tagged(T, SessionTag),
+        Untagged(T),
+    }
+
+    Ok(match Wire::deserialize(deserializer)? {
+        Wire::Tagged(body, tag) => (body, Some(tag)),
+        Wire::Untagged(body) => (body, None),
+    })
+}
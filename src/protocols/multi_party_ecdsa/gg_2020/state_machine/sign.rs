@@ -13,20 +13,21 @@
 //! way as [Keygen]. `OfflineStage` outputs a [CompletedOfflineStage]. [SignManual] takes a
 //! `CompletedOfflineStage` and allows you to perform one-round signing. It doesn't implement
 //! `StateMachine`, but rather provides methods to construct messages and final signature manually
-//! (refer to [SignManual] documentation to see how to use it).
+//! (refer to [SignManual] documentation to see how to use it). Offline stage can be carried out
+//! ahead of time by [Presigning], which outputs a single-use [Presignature] to be kept in a
+//! [PresignatureStore].
 //!
 //! [keygen module]: super::keygen
 //! [Keygen]: super::keygen::Keygen
 //! [LocalKey]: super::keygen::LocalKey
 //! [StateMachine]: round_based::StateMachine
 
-use std::convert::TryFrom;
 use std::mem::replace;
 use std::time::Duration;
 
 use round_based::containers::{push::Push, BroadcastMsgs, MessageStore, P2PMsgs, Store, StoreErr};
 use round_based::{IsCritical, Msg, StateMachine};
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use thiserror::Error;
 
 use crate::utilities::mta::MessageA;
@@ -35,14 +36,30 @@ use crate::protocols::multi_party_ecdsa::gg_2020 as gg20;
 use curv::elliptic::curves::secp256_k1::Secp256k1;
 use gg20::party_i::{SignBroadcastPhase1, SignDecommitPhase1, SignatureRecid};
 use gg20::state_machine::keygen::LocalKey;
+use gg20::state_machine::session::{self, SessionTag};
 
+mod credential;
 mod fmt;
+mod presignature;
 pub mod rounds;
+mod subset;
 
 use crate::utilities::zk_pdl_with_slack::PDLwSlackProof;
+pub use credential::{
+    issue_pubkey_credential, Credential, CredentialError, CredentialProof, CredentialSubject,
+};
 use curv::BigInt;
+pub use presignature::{Presignature, PresignatureError, PresignatureStore, Presigning};
 use rounds::*;
-pub use rounds::{CompletedOfflineStage, Error as ProceedError, PartialSignature};
+pub use rounds::{
+    CompletedOfflineStage, Error as ProceedError, PartialSignature, PartialSignatures,
+};
+pub use subset::{SigningSubset, SigningSubsetError};
+
+/// Number of rounds of [OfflineStage]
+pub const OFFLINE_ROUNDS: u16 = 6;
+/// Number of rounds of one-round online signing ([SignManual])
+pub const ONLINE_ROUNDS: u16 = 1;
 
 /// Offline Stage of GG20 signing
 ///
@@ -59,6 +76,7 @@ pub struct OfflineStage {
     msgs6: Option<Store<BroadcastMsgs<(SI, HEGProof)>>>,
 
     msgs_queue: MsgQueue,
+    session_id: Option<[u8; 32]>,
 
     party_i: u16,
     party_n: u16,
@@ -74,7 +92,10 @@ impl OfflineStage {
     /// (`s_l[i]` must be an index of party `i` that was used by this party in keygen protocol), and
     /// party local secret share `local_key`.
     ///
-    /// Returns error if given arguments are contradicting.
+    /// Same as [from_subset](Self::from_subset) with committee `s_l` validated by
+    /// [SigningSubset::new], which is the preferred way to construct offline stage. Returns error
+    /// if given arguments are contradicting, including [Error::InvalidPartyIndex] if `s_l[i]` is
+    /// not the index of `local_key`.
     pub fn new(i: u16, s_l: Vec<u16>, local_key: LocalKey<Secp256k1>) -> Result<Self> {
         if s_l.len() < 2 {
             return Err(Error::TooFewParties);
@@ -82,27 +103,43 @@ impl OfflineStage {
         if i == 0 || usize::from(i) > s_l.len() {
             return Err(Error::InvalidPartyIndex);
         }
-
-        let keygen_n = local_key.n;
-        if s_l.iter().any(|&i| i == 0 || i > keygen_n) {
-            return Err(Error::InvalidSl);
-        }
-        {
-            // Check if s_l has duplicates
-            let mut s_l_sorted = s_l.clone();
-            s_l_sorted.sort_unstable();
-            let mut s_l_sorted_deduped = s_l_sorted.clone();
-            s_l_sorted_deduped.dedup();
-
-            if s_l_sorted != s_l_sorted_deduped {
-                return Err(Error::InvalidSl);
+        let subset = SigningSubset::new(local_key, s_l).map_err(|err| match err {
+            SigningSubsetError::TooFewSigners { required, signers } => {
+                Error::TooFewSigners { required, signers }
             }
+            SigningSubsetError::NotMember { .. }
+            | SigningSubsetError::Duplicate { .. }
+            | SigningSubsetError::MissingSlot { .. } => Error::InvalidSl,
+            SigningSubsetError::MissingOwner { .. } => Error::InvalidPartyIndex,
+        })?;
+        if subset.party_index() != i {
+            return Err(Error::InvalidPartyIndex);
         }
+        Ok(Self::from_subset(subset))
+    }
 
-        let n = u16::try_from(s_l.len()).map_err(|_| Error::TooManyParties { n: s_l.len() })?;
+    /// Construct a party of offline stage signing with validated committee `subset`
+    ///
+    /// Party index and list of signers are taken from the subset, along with precomputed
+    /// Lagrange coefficients, so unlike [new](Self::new) it can't fail.
+    pub fn from_subset(subset: SigningSubset) -> Self {
+        // Committee consists of distinct u16 values, so its size fits into u16
+        let n = subset.committee.len() as u16;
+        Self::from_round0(
+            Round0 {
+                i: subset.party_i,
+                s_l: subset.committee,
+                local_key: subset.local_key,
+                lagrange_coefficients: subset.lagrange_coefficients,
+            },
+            n,
+        )
+    }
 
-        Ok(Self {
-            round: OfflineR::R0(Round0 { i, s_l, local_key }),
+    fn from_round0(round0: Round0, n: u16) -> Self {
+        let i = round0.i;
+        Self {
+            round: OfflineR::R0(round0),
 
             msgs1: Some(Round1::expects_messages(i, n)),
             msgs2: Some(Round2::expects_messages(i, n)),
@@ -111,11 +148,42 @@ impl OfflineStage {
             msgs5: Some(Round5::expects_messages(i, n)),
             msgs6: Some(Round6::expects_messages(i, n)),
 
-            msgs_queue: MsgQueue(vec![]),
+            msgs_queue: MsgQueue(vec![], None),
+            session_id: None,
 
             party_i: i,
             party_n: n,
-        })
+        }
+    }
+
+    /// Constructs a party of offline stage bound to signing session `session_id`
+    ///
+    /// Same as [Keygen::with_session_id](super::keygen::Keygen::with_session_id): every sent
+    /// message is tagged with hash of session id, round and sender, and received messages with a
+    /// different tag are rejected with [Error::SessionMismatch]. It keeps messages of concurrent
+    /// signings from being consumed by each other when they share a relay. Session id must be
+    /// unique per signing and the same for every signer.
+    pub fn with_session_id(
+        i: u16,
+        s_l: Vec<u16>,
+        local_key: LocalKey<Secp256k1>,
+        session_id: [u8; 32],
+    ) -> Result<Self> {
+        Ok(Self::new(i, s_l, local_key)?.bind_to_session(session_id))
+    }
+
+    /// Constructs a party of offline stage signing with validated committee `subset`, bound to
+    /// signing session `session_id`
+    ///
+    /// Combines [from_subset](Self::from_subset) and [with_session_id](Self::with_session_id).
+    pub fn from_subset_with_session_id(subset: SigningSubset, session_id: [u8; 32]) -> Self {
+        Self::from_subset(subset).bind_to_session(session_id)
+    }
+
+    fn bind_to_session(mut self, session_id: [u8; 32]) -> Self {
+        self.session_id = Some(session_id);
+        self.msgs_queue.1 = Some(session_id);
+        self
     }
 
     // fn proceed_state(&mut self, may_block: bool) -> Result<()> {
@@ -257,8 +325,19 @@ impl StateMachine for OfflineStage {
     fn handle_incoming(&mut self, msg: Msg<Self::MessageBody>) -> Result<(), Self::Err> {
         let current_round = self.current_round();
 
+        let msg_round = crate::MessageRoundID::round_id(&msg.body);
+        let expected_tag = self
+            .session_id
+            .map(|id| session_tag(&id, msg_round, msg.sender));
+        if msg.body.1 != expected_tag {
+            return Err(Error::SessionMismatch {
+                sender: msg.sender,
+                msg_round,
+            });
+        }
+
         match msg.body {
-            OfflineProtocolMessage(OfflineM::M1(m)) => {
+            OfflineProtocolMessage(OfflineM::M1(m), _) => {
                 let store = self
                     .msgs1
                     .as_mut()
@@ -275,7 +354,7 @@ impl StateMachine for OfflineStage {
                     })
                     .map_err(Error::HandleMessage)?;
             }
-            OfflineProtocolMessage(OfflineM::M2(m)) => {
+            OfflineProtocolMessage(OfflineM::M2(m), _) => {
                 let store = self
                     .msgs2
                     .as_mut()
@@ -292,7 +371,7 @@ impl StateMachine for OfflineStage {
                     })
                     .map_err(Error::HandleMessage)?;
             }
-            OfflineProtocolMessage(OfflineM::M3(m)) => {
+            OfflineProtocolMessage(OfflineM::M3(m), _) => {
                 let store = self
                     .msgs3
                     .as_mut()
@@ -309,7 +388,7 @@ impl StateMachine for OfflineStage {
                     })
                     .map_err(Error::HandleMessage)?;
             }
-            OfflineProtocolMessage(OfflineM::M4(m)) => {
+            OfflineProtocolMessage(OfflineM::M4(m), _) => {
                 let store = self
                     .msgs4
                     .as_mut()
@@ -326,7 +405,7 @@ impl StateMachine for OfflineStage {
                     })
                     .map_err(Error::HandleMessage)?;
             }
-            OfflineProtocolMessage(OfflineM::M5(m)) => {
+            OfflineProtocolMessage(OfflineM::M5(m), _) => {
                 let store = self
                     .msgs5
                     .as_mut()
@@ -343,7 +422,7 @@ impl StateMachine for OfflineStage {
                     })
                     .map_err(Error::HandleMessage)?;
             }
-            OfflineProtocolMessage(OfflineM::M6(m)) => {
+            OfflineProtocolMessage(OfflineM::M6(m), _) => {
                 let store = self
                     .msgs6
                     .as_mut()
@@ -431,7 +510,7 @@ impl StateMachine for OfflineStage {
     }
 
     fn total_rounds(&self) -> Option<u16> {
-        Some(6)
+        Some(OFFLINE_ROUNDS)
     }
 
     fn party_ind(&self) -> u16 {
@@ -481,8 +560,29 @@ enum OfflineR {
     Gone,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
-pub struct OfflineProtocolMessage(OfflineM);
+/// Message of offline stage
+///
+/// Message of offline stage without [session id](OfflineStage::with_session_id) is serialized the
+/// same as by versions which didn't support session ids.
+#[derive(Debug, Clone)]
+pub struct OfflineProtocolMessage(OfflineM, Option<SessionTag>);
+
+impl Serialize for OfflineProtocolMessage {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        session::serialize_tagged("OfflineProtocolMessage", &self.0, &self.1, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for OfflineProtocolMessage {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let (body, tag) = session::deserialize_tagged(deserializer)?;
+        Ok(OfflineProtocolMessage(body, tag))
+    }
+}
+
+fn session_tag(session_id: &[u8; 32], round: u16, sender: u16) -> SessionTag {
+    session::session_tag(b"gg20-signing-session-tag", session_id, round, sender)
+}
 
 impl crate::MessageRoundID for OfflineProtocolMessage {
     fn round_id(&self) -> u16 {
@@ -508,18 +608,20 @@ enum OfflineM {
     M6((SI, HEGProof)),
 }
 
-struct MsgQueue(Vec<Msg<OfflineProtocolMessage>>);
+/// Outgoing messages, tagged with session id if it's set
+struct MsgQueue(Vec<Msg<OfflineProtocolMessage>>, Option<[u8; 32]>);
 
 macro_rules! make_pushable {
     ($($constructor:ident $t:ty),*$(,)?) => {
         $(
         impl Push<Msg<$t>> for MsgQueue {
             fn push(&mut self, m: Msg<$t>) {
+                let tag = self.1.map(|id| session_tag(&id, m.round, m.sender));
                 Vec::push(&mut self.0, Msg{
                     round: m.round,
                     sender: m.sender,
                     receiver: m.receiver,
-                    body: OfflineProtocolMessage(OfflineM::$constructor(m.body))
+                    body: OfflineProtocolMessage(OfflineM::$constructor(m.body), tag)
                 })
             }
         }
@@ -554,6 +656,10 @@ pub enum Error {
     /// participated in DKG (`exist i. s_l[i] = 0 || s_l[i] > keygen_n`).
     #[error("invalid s_l")]
     InvalidSl,
+    /// Fewer than `t+1` signers are listed in `s_l`. For
+    /// [full consensus](LocalKey::is_full_consensus) keys every party of keygen must sign.
+    #[error("at least {required} signers are required, got {signers}")]
+    TooFewSigners { required: u16, signers: usize },
 
     /// Round proceeding resulted in protocol error
     #[error("proceeding round: {0}")]
@@ -567,6 +673,9 @@ pub enum Error {
     /// Received message didn't pass pre-validation
     #[error("received message didn't pass pre-validation: {0}")]
     HandleMessage(#[source] StoreErr),
+    /// Received message is not bound to this [signing session](OfflineStage::with_session_id)
+    #[error("message of party {sender} at round {msg_round} belongs to different signing session")]
+    SessionMismatch { sender: u16, msg_round: u16 },
 
     /// [OfflineStage::pick_output] called twice
     #[error("pick_output called twice")]
@@ -600,9 +709,11 @@ impl IsCritical for Error {
             Error::TooManyParties { .. } => true,
             Error::InvalidPartyIndex => true,
             Error::InvalidSl => true,
+            Error::TooFewSigners { .. } => true,
             Error::ProceedRound(_) => true,
             Error::ReceivedOutOfOrderMessage { .. } => false,
             Error::HandleMessage(_) => false,
+            Error::SessionMismatch { .. } => false,
             Error::DoublePickOutput => true,
             Error::Bug(_) => true,
         }
@@ -663,6 +774,22 @@ impl SignManual {
             .proceed_manual(sigs)
             .map_err(SignError::CompleteSigning)
     }
+
+    /// Completes signing, additionally returning partial signatures of every signer
+    ///
+    /// `sigs` are partial signatures of other parties tagged with their index (the same index
+    /// parties have in offline stage). Returned [PartialSignatures] can be logged and
+    /// [re-combined](PartialSignatures::combine) independently. Note that a single partial
+    /// signature can't be verified on its own: if combined signature is invalid, error is
+    /// returned as in [complete](Self::complete).
+    pub fn complete_with_partials(
+        self,
+        sigs: &[(u16, PartialSignature)],
+    ) -> Result<(SignatureRecid, PartialSignatures), SignError> {
+        self.state
+            .proceed_with_partials(sigs)
+            .map_err(SignError::CompleteSigning)
+    }
 }
 
 #[derive(Debug, Error)]
@@ -674,17 +801,20 @@ pub enum SignError {
 }
 
 #[cfg(test)]
-mod test {
+pub mod test {
     use curv::arithmetic::Converter;
     use curv::cryptographic_primitives::hashing::{Digest, DigestExt};
     use round_based::dev::Simulation;
     use sha2::Sha256;
 
+    use crate::testing::run_in_place;
+
     use super::*;
     use gg20::party_i::verify;
     use gg20::state_machine::keygen::test::simulate_keygen;
+    use gg20::state_machine::keygen::{Error as KeygenError, Keygen};
 
-    fn simulate_offline_stage(
+    pub fn simulate_offline_stage(
         local_keys: Vec<LocalKey<Secp256k1>>,
         s_l: &[u16],
     ) -> Vec<CompletedOfflineStage> {
@@ -710,7 +840,7 @@ mod test {
         stages
     }
 
-    fn simulate_signing(offline: Vec<CompletedOfflineStage>, message: &[u8]) {
+    pub fn simulate_signing(offline: Vec<CompletedOfflineStage>, message: &[u8]) {
         let message = Sha256::new()
             .chain_bigint(&BigInt::from_bytes(message))
             .result_bigint();
@@ -736,7 +866,108 @@ mod test {
             .into_iter()
             .enumerate()
             .map(|(i, p)| p.complete(&local_sigs_except(i)).unwrap())
-            .all(|signature| verify(&signature, &pk, &message).is_ok()));
+            .all(|signature| verify(&signature, &pk, &message).is_ok()
+                && signature.recover_public_key(&message) == Some(pk.clone())));
+    }
+
+    #[test]
+    fn signing_round_count_matches_state_machine() {
+        let local_keys = simulate_keygen(1, 2);
+        let offline_stage = OfflineStage::new(1, vec![1, 2], local_keys[0].clone()).unwrap();
+        let online_rounds = 1;
+        assert_eq!(
+            local_keys[0].signing_round_count(&[1, 2]),
+            usize::from(offline_stage.total_rounds().unwrap()) + online_rounds
+        );
+    }
+
+    #[test]
+    fn partial_signatures_recombine_into_signature() {
+        let local_keys = simulate_keygen(1, 3);
+        let offline = simulate_offline_stage(local_keys, &[1, 3]);
+        let message = BigInt::from_bytes(b"partials");
+
+        let (mut parties, partial_sigs): (Vec<_>, Vec<_>) = offline
+            .into_iter()
+            .map(|o| SignManual::new(message.clone(), o).unwrap())
+            .unzip();
+        let (signature, partials) = parties
+            .remove(1)
+            .complete_with_partials(&[(1, partial_sigs[0].clone())])
+            .unwrap();
+
+        let indexes: Vec<u16> = partials.partials.iter().map(|(j, _)| *j).collect();
+        assert_eq!(indexes, [1, 2]);
+        assert_eq!(partials.partials[1].1.s_i(), partial_sigs[1].s_i());
+        assert!(partials.matches(&signature));
+
+        let mut tampered = partials.clone();
+        tampered.partials[0].1 = partial_sigs[1].clone();
+        assert!(!tampered.matches(&signature));
+    }
+
+    #[test]
+    fn local_key_verifies_signature() {
+        let local_keys = simulate_keygen(1, 3);
+        let offline = simulate_offline_stage(local_keys.clone(), &[1, 3]);
+        let message_hash: [u8; 32] = Sha256::digest(b"transfer 1 BTC").into();
+
+        let (parties, partial_sigs): (Vec<_>, Vec<_>) = offline
+            .into_iter()
+            .map(|o| SignManual::new(BigInt::from_bytes(&message_hash), o).unwrap())
+            .unzip();
+        let signature = parties
+            .into_iter()
+            .next()
+            .unwrap()
+            .complete(&partial_sigs[1..])
+            .unwrap();
+
+        assert!(local_keys[1].verify_signature(&message_hash, &signature));
+        let wrong_hash: [u8; 32] = Sha256::digest(b"transfer 2 BTC").into();
+        assert!(!local_keys[1].verify_signature(&wrong_hash, &signature));
+    }
+
+    #[test]
+    fn high_s_signature_is_normalized() {
+        use curv::elliptic::curves::Scalar;
+
+        let local_keys = simulate_keygen(1, 2);
+        let offline = simulate_offline_stage(local_keys.clone(), &[1, 2]);
+        let message = BigInt::from_bytes(b"low s");
+
+        let (parties, partial_sigs): (Vec<_>, Vec<_>) = offline
+            .into_iter()
+            .map(|o| SignManual::new(message.clone(), o).unwrap())
+            .unzip();
+        let signature = parties
+            .into_iter()
+            .next()
+            .unwrap()
+            .complete(&partial_sigs[1..])
+            .unwrap();
+        let half_order = Scalar::<Secp256k1>::group_order().clone() / BigInt::from(2);
+        assert!(signature.s.to_bigint() <= half_order);
+
+        let high_s = SignatureRecid {
+            r: signature.r.clone(),
+            s: -signature.s.clone(),
+            recid: signature.recid ^ 1,
+        };
+        assert!(high_s.s.to_bigint() > half_order);
+        let normalized = high_s.normalize_s();
+        assert_eq!(normalized.s, signature.s);
+        assert_eq!(normalized.recid, signature.recid);
+        assert_eq!(
+            normalized.recover_public_key(&message),
+            Some(local_keys[0].public_key())
+        );
+
+        let untouched = signature.clone().normalize_s();
+        assert_eq!(
+            (untouched.s, untouched.recid),
+            (signature.s, signature.recid)
+        );
     }
 
     #[test]
@@ -781,4 +1012,170 @@ mod test {
         let offline_stage = simulate_offline_stage(local_keys, &[1, 2, 3]);
         simulate_signing(offline_stage, b"ZenGo")
     }
+
+    #[test]
+    fn every_threshold_subset_signs_under_the_same_public_key() {
+        let (t, n) = (2, 5);
+        let local_keys = simulate_keygen(t, n);
+        let public_key = local_keys[0].public_key();
+
+        let mut subsets = vec![];
+        for a in 1..=n {
+            for b in a + 1..=n {
+                for c in b + 1..=n {
+                    subsets.push(vec![a, b, c]);
+                }
+            }
+        }
+        assert_eq!(subsets.len(), 10);
+
+        for s_l in subsets {
+            let offline_stage = simulate_offline_stage(local_keys.clone(), &s_l);
+            assert_eq!(
+                *offline_stage[0].public_key(),
+                public_key,
+                "s_l = {:?}",
+                s_l
+            );
+            simulate_signing(offline_stage, b"every subset");
+        }
+    }
+
+    #[test]
+    fn full_consensus_key_requires_every_signer() {
+        let local_keys = simulate_keygen(2, 3);
+        assert!(local_keys.iter().all(|key| key.is_full_consensus()));
+        assert!(!simulate_keygen(1, 3)[0].is_full_consensus());
+
+        let offline_stage = simulate_offline_stage(local_keys.clone(), &[3, 1, 2]);
+        simulate_signing(offline_stage, b"ZenGo");
+
+        let result = OfflineStage::new(1, vec![1, 2], local_keys[0].clone());
+        assert!(matches!(
+            result,
+            Err(Error::TooFewSigners {
+                required: 3,
+                signers: 2
+            })
+        ));
+    }
+
+    #[test]
+    fn offline_stage_rejects_key_of_other_signer() {
+        let local_keys = simulate_keygen(1, 3);
+        // Party 1 of signing must hold key of party 3 of keygen
+        let result = OfflineStage::new(1, vec![3, 1], local_keys[0].clone());
+        assert!(matches!(result, Err(Error::InvalidPartyIndex)));
+        let result = OfflineStage::new(1, vec![3, 2], local_keys[0].clone());
+        assert!(matches!(result, Err(Error::InvalidPartyIndex)));
+        assert!(OfflineStage::new(2, vec![3, 1], local_keys[0].clone()).is_ok());
+    }
+
+    #[test]
+    fn two_party_keygen_and_signing() {
+        let mut simulation = Simulation::new();
+        for i in 1..=2 {
+            simulation.add_party(Keygen::two_party(i).unwrap());
+        }
+        let local_keys = simulation.run().unwrap();
+        assert!(local_keys.iter().all(|key| key.is_two_party()));
+        assert!(!simulate_keygen(1, 3)[0].is_two_party());
+        assert!(matches!(
+            Keygen::two_party(3),
+            Err(KeygenError::InvalidPartyIndex)
+        ));
+
+        // Constant coefficients recover the public key from public shares
+        let key = &local_keys[0];
+        let recovered = &key.pk_vec[0] * &LocalKey::<Secp256k1>::two_party_lagrange_coefficient(1)
+            + &key.pk_vec[1] * &LocalKey::<Secp256k1>::two_party_lagrange_coefficient(2);
+        assert_eq!(recovered, key.public_key());
+
+        let offline_stage = simulate_offline_stage(local_keys.clone(), &[1, 2]);
+        simulate_signing(offline_stage, b"ZenGo");
+        let offline_stage = simulate_offline_stage(local_keys, &[2, 1]);
+        simulate_signing(offline_stage, b"ZenGo");
+    }
+
+    #[test]
+    fn offline_stage_from_subset_signs() {
+        let local_keys = simulate_keygen(2, 4);
+        let committee = vec![4, 1, 3];
+        let mut simulation = Simulation::new();
+        for &j in &committee {
+            let key = local_keys[usize::from(j - 1)].clone();
+            let subset = SigningSubset::new(key, committee.clone()).unwrap();
+            simulation.add_party(OfflineStage::from_subset(subset));
+        }
+        let offline_stage = simulation.run().unwrap();
+        simulate_signing(offline_stage, b"ZenGo");
+    }
+
+    #[test]
+    fn concurrent_signing_sessions_dont_interfere() {
+        let local_keys = simulate_keygen(1, 3);
+        let s_l = vec![1, 2];
+        let mut parties: Vec<OfflineStage> = [[1u8; 32], [2; 32]]
+            .iter()
+            .flat_map(|&session_id| {
+                let (local_keys, s_l) = (&local_keys, &s_l);
+                (1..=2).map(move |i| {
+                    let key = local_keys[usize::from(i - 1)].clone();
+                    let subset = SigningSubset::new(key, s_l.clone()).unwrap();
+                    OfflineStage::from_subset_with_session_id(subset, session_id)
+                })
+            })
+            .collect();
+
+        // Relay delivers every message to every party of both sessions
+        let mut rejected = 0;
+        run_in_place(&mut parties, |parties, msg| {
+            for party in parties {
+                let i = party.party_ind();
+                if msg.sender == i || msg.receiver.map_or(false, |j| j != i) {
+                    continue;
+                }
+                match party.handle_incoming(msg.clone()) {
+                    Ok(()) => (),
+                    Err(err @ Error::SessionMismatch { .. }) => {
+                        assert!(!err.is_critical());
+                        rejected += 1;
+                    }
+                    Err(err) => panic!("unexpected error: {}", err),
+                }
+            }
+        });
+        assert!(rejected > 0);
+
+        let completed: Vec<_> = parties
+            .iter_mut()
+            .map(|p| p.pick_output().unwrap().unwrap())
+            .collect();
+        assert_ne!(completed[0].R, completed[2].R);
+        for session in completed.chunks(2) {
+            simulate_signing(session.to_vec(), b"~~ MESSAGE ~~");
+        }
+    }
+
+    #[test]
+    fn offline_message_without_session_id_keeps_wire_format() {
+        let local_keys = simulate_keygen(1, 2);
+        let mut party = OfflineStage::new(1, vec![1, 2], local_keys[0].clone()).unwrap();
+        party.proceed().unwrap();
+        let msg = party.message_queue().pop().unwrap().body;
+        assert!(msg.1.is_none());
+        let json = serde_json::to_value(&msg).unwrap();
+        assert_eq!(json, serde_json::to_value(&msg.0).unwrap());
+        let decoded: OfflineProtocolMessage = serde_json::from_value(json).unwrap();
+        assert!(decoded.1.is_none());
+
+        let mut party =
+            OfflineStage::with_session_id(1, vec![1, 2], local_keys[0].clone(), [1; 32]).unwrap();
+        party.proceed().unwrap();
+        let msg = party.message_queue().pop().unwrap().body;
+        let decoded: OfflineProtocolMessage =
+            serde_json::from_slice(&serde_json::to_vec(&msg).unwrap()).unwrap();
+        assert_eq!(decoded.1, msg.1);
+        assert!(decoded.1.is_some());
+    }
 }
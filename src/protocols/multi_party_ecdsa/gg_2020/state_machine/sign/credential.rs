@@ -0,0 +1,215 @@
+//! Self-attested credential for the group public key
+
+use curv::arithmetic::Converter;
+use curv::elliptic::curves::{secp256_k1::Secp256k1, Point};
+use curv::BigInt;
+use round_based::dev::Simulation;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+use super::{Error, OfflineStage, SignError, SignManual};
+use crate::protocols::multi_party_ecdsa::gg_2020::party_i::{verify, SignatureRecid};
+use crate::protocols::multi_party_ecdsa::gg_2020::state_machine::keygen::LocalKey;
+
+const CREDENTIALS_CONTEXT: &str = "https://www.w3.org/2018/credentials/v1";
+const CREDENTIAL_TYPE: &str = "VerifiableCredential";
+const PUBKEY_CREDENTIAL_TYPE: &str = "ThresholdPublicKeyCredential";
+const PROOF_TYPE: &str = "EcdsaSecp256k1Signature2019";
+
+/// W3C-style verifiable credential binding `subject` to the group public key
+///
+/// Credential is issued and signed by the threshold key itself, see [issue_pubkey_credential].
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Credential {
+    #[serde(rename = "@context")]
+    pub context: Vec<String>,
+    #[serde(rename = "type")]
+    pub types: Vec<String>,
+    pub issuer: String,
+    #[serde(rename = "credentialSubject")]
+    pub credential_subject: CredentialSubject,
+    pub proof: CredentialProof,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CredentialSubject {
+    pub id: String,
+    /// Compressed group public key, hex-encoded
+    #[serde(rename = "publicKeyHex")]
+    pub public_key_hex: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CredentialProof {
+    #[serde(rename = "type")]
+    pub proof_type: String,
+    pub signature: SignatureRecid,
+}
+
+/// Part of the credential covered by the signature
+#[derive(Serialize)]
+struct CredentialPayload<'a> {
+    #[serde(rename = "@context")]
+    context: &'a [String],
+    #[serde(rename = "type")]
+    types: &'a [String],
+    issuer: &'a str,
+    #[serde(rename = "credentialSubject")]
+    credential_subject: &'a CredentialSubject,
+}
+
+impl Credential {
+    /// Canonical serialization of the credential excluding the proof
+    pub fn payload(&self) -> Vec<u8> {
+        payload(
+            &self.context,
+            &self.types,
+            &self.issuer,
+            &self.credential_subject,
+        )
+    }
+
+    /// Checks that credential is issued for `public_key` and signed by it
+    pub fn verify(&self, public_key: &Point<Secp256k1>) -> bool {
+        self.proof.proof_type == PROOF_TYPE
+            && self.credential_subject.public_key_hex == to_hex(&public_key.to_bytes(true))
+            && self.issuer == issuer(public_key)
+            && verify(
+                &self.proof.signature,
+                public_key,
+                &message_hash(&self.payload()),
+            )
+            .is_ok()
+    }
+}
+
+/// Issues a [Credential] for the group public key, signed by the quorum holding `keys`
+///
+/// Every key must belong to the same group, and there must be at least `t+1` of them. Signing is
+/// carried out locally, so all the quorum keys must be available in this process.
+pub fn issue_pubkey_credential(
+    keys: &[LocalKey<Secp256k1>],
+    subject: &str,
+) -> Result<Credential, CredentialError> {
+    let first = keys.first().ok_or(CredentialError::NotEnoughKeys)?;
+    if keys.iter().any(|k| k.y_sum_s != first.y_sum_s) {
+        return Err(CredentialError::KeysMismatch);
+    }
+    if keys.len() <= usize::from(first.t) {
+        return Err(CredentialError::NotEnoughKeys);
+    }
+    let s_l: Vec<u16> = keys.iter().map(|k| k.i).collect();
+
+    let public_key = first.public_key();
+    let context = vec![CREDENTIALS_CONTEXT.to_string()];
+    let types = vec![
+        CREDENTIAL_TYPE.to_string(),
+        PUBKEY_CREDENTIAL_TYPE.to_string(),
+    ];
+    let issuer = issuer(&public_key);
+    let credential_subject = CredentialSubject {
+        id: subject.to_string(),
+        public_key_hex: to_hex(&public_key.to_bytes(true)),
+    };
+    let message = message_hash(&payload(&context, &types, &issuer, &credential_subject));
+
+    let mut simulation = Simulation::new();
+    for (i, key) in (1..).zip(keys) {
+        simulation.add_party(
+            OfflineStage::new(i, s_l.clone(), key.clone())
+                .map_err(CredentialError::OfflineStage)?,
+        );
+    }
+    let offline = simulation.run().map_err(CredentialError::OfflineStage)?;
+
+    let (parties, partial_sigs): (Vec<_>, Vec<_>) = offline
+        .into_iter()
+        .map(|o| SignManual::new(message.clone(), o))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(CredentialError::Signing)?
+        .into_iter()
+        .unzip();
+    let signature = parties
+        .into_iter()
+        .next()
+        .ok_or(CredentialError::NotEnoughKeys)?
+        .complete(&partial_sigs[1..])
+        .map_err(CredentialError::Signing)?;
+
+    Ok(Credential {
+        context,
+        types,
+        issuer,
+        credential_subject,
+        proof: CredentialProof {
+            proof_type: PROOF_TYPE.to_string(),
+            signature,
+        },
+    })
+}
+
+fn payload(
+    context: &[String],
+    types: &[String],
+    issuer: &str,
+    credential_subject: &CredentialSubject,
+) -> Vec<u8> {
+    serde_json::to_vec(&CredentialPayload {
+        context,
+        types,
+        issuer,
+        credential_subject,
+    })
+    .expect("serialization of credential payload never fails")
+}
+
+fn message_hash(payload: &[u8]) -> BigInt {
+    BigInt::from_bytes(&Sha256::digest(payload))
+}
+
+fn issuer(public_key: &Point<Secp256k1>) -> String {
+    format!("urn:secp256k1:{}", to_hex(&public_key.to_bytes(true)))
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[derive(Debug, Error)]
+pub enum CredentialError {
+    #[error("at least t+1 keys are required to issue a credential")]
+    NotEnoughKeys,
+    #[error("keys belong to different groups")]
+    KeysMismatch,
+    #[error("offline stage: {0}")]
+    OfflineStage(Error),
+    #[error("signing: {0}")]
+    Signing(SignError),
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::protocols::multi_party_ecdsa::gg_2020::state_machine::keygen::test::simulate_keygen;
+
+    #[test]
+    fn credential_is_signed_by_group_key() {
+        let keys = simulate_keygen(1, 3);
+        let credential = issue_pubkey_credential(&keys[1..], "did:example:custody").unwrap();
+        assert!(credential.verify(&keys[0].public_key()));
+
+        let mut forged = credential.clone();
+        forged.credential_subject.id = "did:example:attacker".to_string();
+        assert!(!forged.verify(&keys[0].public_key()));
+    }
+
+    #[test]
+    fn credential_requires_quorum() {
+        let keys = simulate_keygen(1, 3);
+        assert!(matches!(
+            issue_pubkey_credential(&keys[..1], "did:example:custody"),
+            Err(CredentialError::NotEnoughKeys)
+        ));
+    }
+}
@@ -0,0 +1,306 @@
+//! Storable single-use result of offline stage
+
+use std::collections::BTreeSet;
+use std::time::Duration;
+
+use curv::elliptic::curves::{secp256_k1::Secp256k1, Point};
+use curv::BigInt;
+use round_based::{Msg, StateMachine};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+use super::{
+    CompletedOfflineStage, Error, OfflineProtocolMessage, OfflineStage, PartialSignature,
+    SignError, SignManual,
+};
+
+/// Message-independent part of signing (nonce `k_i`, `R` and `σ_i`), computed ahead of time
+///
+/// Run [Presigning] in idle time as many times as presignatures are needed, store them, and
+/// consume one per message. Unlike [CompletedOfflineStage], presignature is not `Clone` and
+/// [sign](Self::sign) consumes it: signing two messages with the same presignature reveals the
+/// secret share. Presignature is serializable, so it's up to the storage to make sure it's
+/// loaded at most once, see [PresignatureStore].
+///
+/// Signing with presignature still takes one round: every signer of offline stage signs the
+/// message hash with their presignature and broadcasts [PartialSignature], signature is
+/// [completed](SignManual::complete) once partial signatures of all other signers are received.
+#[derive(Serialize, Deserialize)]
+pub struct Presignature(CompletedOfflineStage);
+
+impl Presignature {
+    /// Joint public key the signature will be verified against
+    pub fn public_key(&self) -> &Point<Secp256k1> {
+        self.0.public_key()
+    }
+
+    /// Identifier of offline stage the presignature is obtained from
+    ///
+    /// Derived from `R`, so presignatures of all signers of the offline stage (and copies of
+    /// them) have the same id.
+    pub fn id(&self) -> [u8; 32] {
+        Sha256::new()
+            .chain(&self.0.R.to_bytes(true)[..])
+            .finalize()
+            .into()
+    }
+
+    /// Signs `message_hash` locally, consuming presignature
+    ///
+    /// Returned [PartialSignature] must be broadcast to other signers, see [SignManual].
+    pub fn sign(self, message_hash: BigInt) -> Result<(SignManual, PartialSignature), SignError> {
+        SignManual::new(message_hash, self.0)
+    }
+}
+
+/// [OfflineStage] outputting [Presignature]
+///
+/// Presignature can only be obtained by running offline stage this way: its output never leaves
+/// the state machine as cloneable [CompletedOfflineStage], so every run produces exactly one
+/// presignature per signer.
+#[derive(Debug)]
+pub struct Presigning(OfflineStage);
+
+impl Presigning {
+    /// Wraps offline stage that isn't started yet
+    pub fn new(offline_stage: OfflineStage) -> Self {
+        Self(offline_stage)
+    }
+}
+
+impl StateMachine for Presigning {
+    type MessageBody = OfflineProtocolMessage;
+    type Err = Error;
+    type Output = Presignature;
+
+    fn handle_incoming(&mut self, msg: Msg<Self::MessageBody>) -> Result<(), Self::Err> {
+        self.0.handle_incoming(msg)
+    }
+
+    fn message_queue(&mut self) -> &mut Vec<Msg<Self::MessageBody>> {
+        self.0.message_queue()
+    }
+
+    fn wants_to_proceed(&self) -> bool {
+        self.0.wants_to_proceed()
+    }
+
+    fn proceed(&mut self) -> Result<(), Self::Err> {
+        self.0.proceed()
+    }
+
+    fn round_timeout(&self) -> Option<Duration> {
+        self.0.round_timeout()
+    }
+
+    fn round_timeout_reached(&mut self) -> Self::Err {
+        self.0.round_timeout_reached()
+    }
+
+    fn is_finished(&self) -> bool {
+        self.0.is_finished()
+    }
+
+    fn pick_output(&mut self) -> Option<Result<Self::Output, Self::Err>> {
+        self.0.pick_output().map(|output| output.map(Presignature))
+    }
+
+    fn current_round(&self) -> u16 {
+        self.0.current_round()
+    }
+
+    fn total_rounds(&self) -> Option<u16> {
+        self.0.total_rounds()
+    }
+
+    fn party_ind(&self) -> u16 {
+        self.0.party_ind()
+    }
+
+    fn parties(&self) -> u16 {
+        self.0.parties()
+    }
+}
+
+/// Pool of presignatures handing out every presignature at most once
+///
+/// Store remembers [ids](Presignature::id) of presignatures it handed out and refuses to accept
+/// them again, so a copy of consumed presignature (e.g. restored from backup) can't be used to
+/// sign another message. Store is serializable: persist it after every [take](Self::take)
+/// before signing with taken presignature, otherwise consumption is lost on restart.
+///
+/// Store holds presignatures of a single signer, participant holding several
+/// [share indices](crate::protocols::multi_party_ecdsa::gg_2020::state_machine::keygen::WeightedKeygen)
+/// needs a store per index.
+#[derive(Serialize, Deserialize, Default)]
+pub struct PresignatureStore {
+    available: Vec<Presignature>,
+    consumed: BTreeSet<[u8; 32]>,
+}
+
+/// Error of adding presignature to [PresignatureStore]
+#[derive(Debug, Error)]
+pub enum PresignatureError {
+    #[error("presignature was already consumed")]
+    AlreadyConsumed { id: [u8; 32] },
+    #[error("presignature is already in the store")]
+    Duplicate { id: [u8; 32] },
+}
+
+impl PresignatureStore {
+    /// Constructs empty store
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds presignature to the store
+    ///
+    /// Returns [PresignatureError::AlreadyConsumed] if presignature was handed out by the store
+    /// before, and [PresignatureError::Duplicate] if the store already has it.
+    pub fn insert(&mut self, presignature: Presignature) -> Result<(), PresignatureError> {
+        let id = presignature.id();
+        if self.consumed.contains(&id) {
+            return Err(PresignatureError::AlreadyConsumed { id });
+        }
+        if self.available.iter().any(|p| p.id() == id) {
+            return Err(PresignatureError::Duplicate { id });
+        }
+        self.available.push(presignature);
+        Ok(())
+    }
+
+    /// Hands out the oldest presignature and marks it as consumed
+    ///
+    /// Returns `None` if the store is empty.
+    pub fn take(&mut self) -> Option<Presignature> {
+        if self.available.is_empty() {
+            return None;
+        }
+        let presignature = self.available.remove(0);
+        self.consumed.insert(presignature.id());
+        Some(presignature)
+    }
+
+    /// Hands out presignature with given `id` and marks it as consumed
+    ///
+    /// Signers of a message must use presignatures of the same offline stage: one signer takes
+    /// the oldest and tells its id to others, others take theirs by that id. Returns `None` if the store
+    /// doesn't have such presignature.
+    pub fn take_by_id(&mut self, id: &[u8; 32]) -> Option<Presignature> {
+        let index = self.available.iter().position(|p| &p.id() == id)?;
+        let presignature = self.available.remove(index);
+        self.consumed.insert(*id);
+        Some(presignature)
+    }
+
+    /// Checks whether presignature with given `id` was handed out by the store
+    pub fn is_consumed(&self, id: &[u8; 32]) -> bool {
+        self.consumed.contains(id)
+    }
+
+    /// Number of presignatures left in the store
+    pub fn len(&self) -> usize {
+        self.available.len()
+    }
+
+    /// Indicates whether the store has no presignatures left
+    pub fn is_empty(&self) -> bool {
+        self.available.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use curv::arithmetic::Converter;
+    use round_based::dev::Simulation;
+
+    use super::*;
+    use crate::protocols::multi_party_ecdsa::gg_2020::party_i::verify;
+    use crate::protocols::multi_party_ecdsa::gg_2020::state_machine::keygen::test::simulate_keygen;
+    use crate::protocols::multi_party_ecdsa::gg_2020::state_machine::keygen::LocalKey;
+
+    fn simulate_presigning(local_keys: &[LocalKey<Secp256k1>], s_l: &[u16]) -> Vec<Presignature> {
+        let mut simulation = Simulation::new();
+        for (i, &keygen_i) in (1..).zip(s_l) {
+            let key = local_keys[usize::from(keygen_i - 1)].clone();
+            let offline_stage = OfflineStage::new(i, s_l.to_vec(), key).unwrap();
+            simulation.add_party(Presigning::new(offline_stage));
+        }
+        simulation.run().unwrap()
+    }
+
+    #[test]
+    fn stored_presignatures_sign_one_message_each() {
+        let local_keys = simulate_keygen(1, 3);
+        // Two presignatures for every signer, stored serialized
+        let stored: Vec<Vec<Vec<u8>>> = (0..2)
+            .map(|_| {
+                simulate_presigning(&local_keys, &[1, 2])
+                    .iter()
+                    .map(|p| serde_json::to_vec(p).unwrap())
+                    .collect()
+            })
+            .collect();
+
+        for (presignatures, message) in stored.iter().zip(&[&b"first"[..], b"second"]) {
+            let message_hash = BigInt::from_bytes(&Sha256::digest(message));
+            let (parties, partial_sigs): (Vec<_>, Vec<_>) = presignatures
+                .iter()
+                .map(|bytes| serde_json::from_slice::<Presignature>(bytes).unwrap())
+                .map(|p| p.sign(message_hash.clone()).unwrap())
+                .unzip();
+            let pk = local_keys[0].public_key();
+            let signature = parties
+                .into_iter()
+                .next()
+                .unwrap()
+                .complete(&partial_sigs[1..])
+                .unwrap();
+            assert!(verify(&signature, &pk, &message_hash).is_ok());
+        }
+    }
+
+    #[test]
+    fn store_hands_out_presignature_once() {
+        let local_keys = simulate_keygen(1, 2);
+        let mut stores = vec![PresignatureStore::new(), PresignatureStore::new()];
+        for _ in 0..2 {
+            let presignatures = simulate_presigning(&local_keys, &[1, 2]);
+            for (store, p) in stores.iter_mut().zip(presignatures) {
+                // Copy of presignature, e.g. restored from backup
+                let copy: Presignature =
+                    serde_json::from_slice(&serde_json::to_vec(&p).unwrap()).unwrap();
+                store.insert(p).unwrap();
+                assert!(matches!(
+                    store.insert(copy),
+                    Err(PresignatureError::Duplicate { .. })
+                ));
+            }
+        }
+
+        let first = stores[0].take().unwrap();
+        let id = first.id();
+        let other = stores[1].take_by_id(&id).unwrap();
+        assert_eq!(stores.iter().map(|s| s.len()).collect::<Vec<_>>(), [1, 1]);
+        assert!(stores[1].take_by_id(&id).is_none());
+
+        // Consumption survives saving the store, so restored copy of presignature is rejected
+        let copy: Presignature =
+            serde_json::from_slice(&serde_json::to_vec(&first).unwrap()).unwrap();
+        let saved = serde_json::to_vec(&stores[0]).unwrap();
+        let mut restored: PresignatureStore = serde_json::from_slice(&saved).unwrap();
+        assert!(restored.is_consumed(&id));
+        assert!(matches!(
+            restored.insert(copy),
+            Err(PresignatureError::AlreadyConsumed { .. })
+        ));
+        assert_eq!(restored.len(), 1);
+
+        let message_hash = BigInt::from_bytes(b"once");
+        let (party1, _) = first.sign(message_hash.clone()).unwrap();
+        let (_, partial2) = other.sign(message_hash.clone()).unwrap();
+        let signature = party1.complete(&[partial2]).unwrap();
+        assert!(verify(&signature, &local_keys[0].public_key(), &message_hash).is_ok());
+    }
+}
@@ -6,6 +6,7 @@ use std::iter;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
+use curv::arithmetic::traits::*;
 use curv::elliptic::curves::{secp256_k1::Secp256k1, Point, Scalar};
 use curv::BigInt;
 use sha2::Sha256;
@@ -61,7 +62,13 @@ pub struct Round0 {
     pub s_l: Vec<u16>,
 
     /// Party local secret share
+    ///
+    /// Public data of the key must be restricted to parties listed in `s_l`, in the same order
+    /// (see [SigningSubset::new](super::SigningSubset::new)).
     pub local_key: LocalKey<Secp256k1>,
+
+    /// Lagrange coefficients of parties listed in `s_l`, in the same order
+    pub lagrange_coefficients: Vec<Scalar<Secp256k1>>,
 }
 
 
@@ -71,19 +78,13 @@ impl Round0 {
     where
         O: Push<Msg<(MessageA, SignBroadcastPhase1)>>,
     {
-        let sign_keys = SignKeys::create(
+        let sign_keys = SignKeys::create_with_lagrange_coefficient(
             &self.local_key.keys_linear.x_i,
-            &self.local_key.vss_scheme.clone(),
-            usize::from(self.s_l[usize::from(self.i - 1)]) - 1,
-            &self
-                .s_l
-                .iter()
-                .map(|&i| usize::from(i) - 1)
-                .collect::<Vec<_>>(),
+            &self.lagrange_coefficients[usize::from(self.i - 1)],
         );
         let (bc1, decom1) = sign_keys.phase1_broadcast();
 
-        let party_ek = self.local_key.paillier_key_vec[usize::from(self.local_key.i - 1)].clone();
+        let party_ek = self.local_key.paillier_key_vec[usize::from(self.i - 1)].clone();
         let m_a = MessageA::a(&sign_keys.k_i, &party_ek, &self.local_key.h1_h2_n_tilde_vec);
 
         output.push(Msg {
@@ -97,6 +98,7 @@ impl Round0 {
             i: self.i,
             s_l: self.s_l.clone(),
             local_key: self.local_key,
+            lagrange_coefficients: self.lagrange_coefficients,
             m_a,
             sign_keys,
             phase1_com: bc1,
@@ -116,6 +118,7 @@ pub struct Round1 {
     i: u16,
     s_l: Vec<u16>,
     local_key: LocalKey<Secp256k1>,
+    lagrange_coefficients: Vec<Scalar<Secp256k1>>,
     m_a: (MessageA, BigInt),
     sign_keys: SignKeys,
     phase1_com: SignBroadcastPhase1,
@@ -142,19 +145,13 @@ impl Round1 {
         let mut ni_vec = Vec::new();
 
         let ttag = self.s_l.len();
-        let l_s: Vec<_> = self
-            .s_l
-            .iter()
-            .cloned()
-            .map(|i| usize::from(i) - 1)
-            .collect();
         let i = usize::from(self.i - 1);
         for j in 0..ttag - 1 {
             let ind = if j < i { j } else { j + 1 };
 
             let (m_b_gamma, beta_gamma, _beta_randomness, _beta_tag) = MessageB::b(
                 &self.sign_keys.gamma_i,
-                &self.local_key.paillier_key_vec[l_s[ind]],
+                &self.local_key.paillier_key_vec[ind],
                 m_a_vec[ind].clone(),
                 &self.local_key.h1_h2_n_tilde_vec,
             )
@@ -168,7 +165,7 @@ impl Round1 {
 
             let (m_b_w, beta_wi, _, _) = MessageB::b(
                 &self.sign_keys.w_i,
-                &self.local_key.paillier_key_vec[l_s[ind]],
+                &self.local_key.paillier_key_vec[ind],
                 m_a_vec[ind].clone(),
                 &self.local_key.h1_h2_n_tilde_vec,
             )
@@ -202,6 +199,7 @@ impl Round1 {
             i: self.i,
             s_l: self.s_l,
             local_key: self.local_key,
+            lagrange_coefficients: self.lagrange_coefficients,
             sign_keys: self.sign_keys,
             m_a: self.m_a,
             beta_vec,
@@ -228,6 +226,7 @@ pub struct Round2 {
     i: u16,
     s_l: Vec<u16>,
     local_key: LocalKey<Secp256k1>,
+    lagrange_coefficients: Vec<Scalar<Secp256k1>>,
     sign_keys: SignKeys,
     m_a: (MessageA, BigInt),
     beta_vec: Vec<Scalar<Secp256k1>>,
@@ -254,17 +253,13 @@ impl Round2 {
 
         let ttag = self.s_l.len();
         let index = usize::from(self.i) - 1;
-        let l_s: Vec<_> = self
-            .s_l
+        let g_w_vec: Vec<_> = self
+            .local_key
+            .pk_vec
             .iter()
-            .cloned()
-            .map(|i| usize::from(i) - 1)
+            .zip(&self.lagrange_coefficients)
+            .map(|(pk, li)| pk * li)
             .collect();
-        let g_w_vec = SignKeys::g_w_vec(
-            &self.local_key.pk_vec[..],
-            &l_s[..],
-            &self.local_key.vss_scheme,
-        );
         for j in 0..ttag - 1 {
             let ind = if j < index { j } else { j + 1 };
             let m_b = m_b_gamma_s[j].clone();
@@ -468,12 +463,6 @@ impl Round4 {
 
         // each party sends first message to all other parties
         let mut phase5_proofs_vec = Vec::new();
-        let l_s: Vec<_> = self
-            .s_l
-            .iter()
-            .cloned()
-            .map(|i| usize::from(i) - 1)
-            .collect();
         let index = usize::from(self.i - 1);
         for j in 0..ttag - 1 {
             let ind = if j < index { j } else { j + 1 };
@@ -481,10 +470,10 @@ impl Round4 {
                 &R_dash,
                 &R,
                 &self.m_a.0.c,
-                &self.local_key.paillier_key_vec[l_s[index]],
+                &self.local_key.paillier_key_vec[index],
                 &self.sign_keys.k_i,
                 &self.m_a.1,
-                &self.local_key.h1_h2_n_tilde_vec[l_s[ind]],
+                &self.local_key.h1_h2_n_tilde_vec[ind],
             );
 
             phase5_proofs_vec.push(proof);
@@ -553,12 +542,8 @@ impl Round5 {
             .map(|(r_dash, pdl_proof)| (r_dash.0, pdl_proof))
             .unzip();
 
-        let l_s: Vec<_> = self
-            .s_l
-            .iter()
-            .cloned()
-            .map(|i| usize::from(i) - 1)
-            .collect();
+        // Public data of the key is restricted to the committee, see `SigningSubset::new`
+        let l_s: Vec<_> = (0..self.s_l.len()).collect();
         let ttag = self.s_l.len();
         for i in 0..ttag {
             LocalSignature::phase5_verify_pdl(
@@ -566,7 +551,7 @@ impl Round5 {
                 &r_dash_vec[i],
                 &self.R,
                 &self.m_a_vec[i].c,
-                &self.local_key.paillier_key_vec[l_s[i]],
+                &self.local_key.paillier_key_vec[i],
                 &self.local_key.h1_h2_n_tilde_vec,
                 &l_s,
                 i,
@@ -665,13 +650,13 @@ impl Round6 {
     }
 }
 
-#[derive(Clone)]
+#[derive(Serialize, Deserialize, Clone)]
 pub struct CompletedOfflineStage {
-    i: u16,
+    pub(super) i: u16,
     local_key: LocalKey<Secp256k1>,
     sign_keys: SignKeys,
     t_vec: Vec<Point<Secp256k1>>,
-    R: Point<Secp256k1>,
+    pub(super) R: Point<Secp256k1>,
     sigma_i: Scalar<Secp256k1>,
 }
 
@@ -684,8 +669,52 @@ impl CompletedOfflineStage {
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct PartialSignature(Scalar<Secp256k1>);
 
+impl PartialSignature {
+    /// Party's contribution `s_i` to `s` component of the signature
+    pub fn s_i(&self) -> &Scalar<Secp256k1> {
+        &self.0
+    }
+}
+
+/// Partial signatures of every signer which were combined into signature
+///
+/// Obtained via [SignManual::complete_with_partials](super::SignManual::complete_with_partials).
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PartialSignatures {
+    /// Point which x-coordinate is `r` component of the signature
+    pub R: Point<Secp256k1>,
+    /// Partial signatures tagged with index of signer (in range `[1; |s_l|]`), including
+    /// partial signature of local party
+    pub partials: Vec<(u16, PartialSignature)>,
+}
+
+impl PartialSignatures {
+    /// Sums up partial signatures `s = Σ s_i`
+    pub fn combine(&self) -> Scalar<Secp256k1> {
+        self.partials
+            .iter()
+            .fold(Scalar::zero(), |acc, (_, partial)| acc + &partial.0)
+    }
+
+    /// Checks that partial signatures add up to the `signature`
+    ///
+    /// Takes into account that `s` of the signature is normalized to low-S form.
+    pub fn matches(&self, signature: &SignatureRecid) -> bool {
+        let s = self.combine();
+        let r = Scalar::<Secp256k1>::from(
+            &self
+                .R
+                .x_coord()
+                .unwrap()
+                .mod_floor(Scalar::<Secp256k1>::group_order()),
+        );
+        signature.r == r && (signature.s == s || signature.s == Scalar::zero() - &s)
+    }
+}
+
 #[derive(Clone)]
 pub struct Round7 {
+    i: u16,
     local_signature: LocalSignature,
 }
 
@@ -702,7 +731,13 @@ impl Round7 {
             &completed_offline_stage.local_key.y_sum_s,
         );
         let partial = PartialSignature(local_signature.s_i.clone());
-        Ok((Self { local_signature }, partial))
+        Ok((
+            Self {
+                i: completed_offline_stage.i,
+                local_signature,
+            },
+            partial,
+        ))
     }
 
     pub fn proceed_manual(self, sigs: &[PartialSignature]) -> Result<SignatureRecid> {
@@ -711,6 +746,26 @@ impl Round7 {
             .output_signature(&sigs)
             .map_err(Error::Round7)
     }
+
+    pub fn proceed_with_partials(
+        self,
+        sigs: &[(u16, PartialSignature)],
+    ) -> Result<(SignatureRecid, PartialSignatures)> {
+        let mut partials = sigs.to_vec();
+        partials.push((self.i, PartialSignature(self.local_signature.s_i.clone())));
+        partials.sort_by_key(|(j, _)| *j);
+        let partials = PartialSignatures {
+            R: self.local_signature.R.clone(),
+            partials,
+        };
+
+        let sigs = sigs.iter().map(|(_, s_i)| s_i.0.clone()).collect::<Vec<_>>();
+        let signature = self
+            .local_signature
+            .output_signature(&sigs)
+            .map_err(Error::Round7)?;
+        Ok((signature, partials))
+    }
 }
 
 #[derive(Debug, Error)]
@@ -0,0 +1,239 @@
+//! Validated signing committee
+
+use std::collections::BTreeSet;
+
+use curv::elliptic::curves::{secp256_k1::Secp256k1, Scalar};
+use thiserror::Error;
+
+use crate::protocols::multi_party_ecdsa::gg_2018::VerifiableSS;
+use crate::protocols::multi_party_ecdsa::gg_2020::state_machine::keygen::LocalKey;
+
+/// Committee of parties signing with `local_key`, validated once and carrying Lagrange
+/// coefficients of its members
+///
+/// Constructed via [SigningSubset::new] and consumed by
+/// [OfflineStage::from_subset](super::OfflineStage::from_subset), so offline stage can't be
+/// started with an invalid committee and doesn't interpolate coefficients again.
+#[derive(Clone, Debug)]
+pub struct SigningSubset {
+    pub(super) local_key: LocalKey<Secp256k1>,
+    pub(super) committee: Vec<u16>,
+    pub(super) party_i: u16,
+    pub(super) lagrange_coefficients: Vec<Scalar<Secp256k1>>,
+}
+
+impl SigningSubset {
+    /// Validates `committee` of keygen indexes (in range `[1; n]`) of signing parties
+    ///
+    /// Committee must list at least `t+1` distinct parties of keygen including the owner of
+    /// `local_key`. Order of the committee determines parties' indexes in signing, so every
+    /// signer must construct the subset with the same committee.
+    ///
+    /// Public data of parties outside of the committee is dropped from the key, offline stage
+    /// never reads it. Returns [SigningSubsetError::MissingSlot] if the key doesn't hold public
+    /// data of a committee member.
+    pub fn new(
+        mut local_key: LocalKey<Secp256k1>,
+        committee: Vec<u16>,
+    ) -> Result<Self, SigningSubsetError> {
+        let party_i = validate_committee(&local_key, &committee)?;
+        let slots = local_key
+            .pk_vec
+            .len()
+            .min(local_key.paillier_key_vec.len())
+            .min(local_key.h1_h2_n_tilde_vec.len());
+        if let Some(&index) = committee.iter().find(|&&j| usize::from(j) > slots) {
+            return Err(SigningSubsetError::MissingSlot { index });
+        }
+        let pick = |j: &u16| usize::from(*j - 1);
+        local_key.pk_vec = committee
+            .iter()
+            .map(|j| local_key.pk_vec[pick(j)].clone())
+            .collect();
+        local_key.paillier_key_vec = committee
+            .iter()
+            .map(|j| local_key.paillier_key_vec[pick(j)].clone())
+            .collect();
+        local_key.h1_h2_n_tilde_vec = committee
+            .iter()
+            .map(|j| local_key.h1_h2_n_tilde_vec[pick(j)].clone())
+            .collect();
+        Ok(Self::from_validated(local_key, committee, party_i))
+    }
+
+    /// Same as [new](Self::new), but takes `local_key` that already holds public data of
+    /// `committee` members only, in the committee order
+    pub(crate) fn from_committee_key(
+        local_key: LocalKey<Secp256k1>,
+        committee: Vec<u16>,
+    ) -> Result<Self, SigningSubsetError> {
+        let party_i = validate_committee(&local_key, &committee)?;
+        let slots = local_key
+            .pk_vec
+            .len()
+            .min(local_key.paillier_key_vec.len())
+            .min(local_key.h1_h2_n_tilde_vec.len());
+        if slots < committee.len() {
+            return Err(SigningSubsetError::MissingSlot {
+                index: committee[slots],
+            });
+        }
+        Ok(Self::from_validated(local_key, committee, party_i))
+    }
+
+    fn from_validated(local_key: LocalKey<Secp256k1>, committee: Vec<u16>, party_i: u16) -> Self {
+        let lagrange_coefficients = lagrange_coefficients(&local_key, &committee);
+        Self {
+            local_key,
+            committee,
+            party_i,
+            lagrange_coefficients,
+        }
+    }
+
+    /// Keygen indexes of signing parties, in the order of their indexes in signing
+    pub fn committee(&self) -> &[u16] {
+        &self.committee
+    }
+
+    /// Index of this party in signing (in range `[1; committee.len()]`)
+    pub fn party_index(&self) -> u16 {
+        self.party_i
+    }
+
+    /// Lagrange coefficient of this party for the committee
+    ///
+    /// Same as [LocalKey::lagrange_coefficient] called with the committee.
+    pub fn lagrange_coefficient(&self) -> &Scalar<Secp256k1> {
+        &self.lagrange_coefficients[usize::from(self.party_i - 1)]
+    }
+
+    /// Key of this party
+    ///
+    /// Key holds public data of committee members only, in the committee order.
+    pub fn local_key(&self) -> &LocalKey<Secp256k1> {
+        &self.local_key
+    }
+}
+
+/// Validates `committee` of `local_key`, returns index of the key owner in signing
+fn validate_committee(
+    local_key: &LocalKey<Secp256k1>,
+    committee: &[u16],
+) -> Result<u16, SigningSubsetError> {
+    let mut seen = BTreeSet::new();
+    for &j in committee {
+        if j == 0 || j > local_key.n {
+            return Err(SigningSubsetError::NotMember { index: j });
+        }
+        if !seen.insert(j) {
+            return Err(SigningSubsetError::Duplicate { index: j });
+        }
+    }
+    if committee.len() <= usize::from(local_key.t) {
+        return Err(SigningSubsetError::TooFewSigners {
+            required: local_key.t + 1,
+            signers: committee.len(),
+        });
+    }
+    match committee.iter().position(|&j| j == local_key.i) {
+        // Committee consists of distinct u16 values, so position fits into u16
+        Some(position) => Ok(position as u16 + 1),
+        None => Err(SigningSubsetError::MissingOwner { index: local_key.i }),
+    }
+}
+
+/// Lagrange coefficients of every member of validated `committee`, in the committee order
+pub(super) fn lagrange_coefficients(
+    local_key: &LocalKey<Secp256k1>,
+    committee: &[u16],
+) -> Vec<Scalar<Secp256k1>> {
+    if local_key.is_two_party() {
+        return committee
+            .iter()
+            .map(|&j| LocalKey::<Secp256k1>::two_party_lagrange_coefficient(j))
+            .collect();
+    }
+    let zero_based: Vec<u16> = committee.iter().map(|j| j - 1).collect();
+    zero_based
+        .iter()
+        .map(|&j| {
+            VerifiableSS::<Secp256k1>::map_share_to_new_params(
+                &local_key.vss_scheme.parameters,
+                j,
+                &zero_based,
+            )
+        })
+        .collect()
+}
+
+/// Committee passed to [SigningSubset::new] is invalid
+#[derive(Debug, Error)]
+pub enum SigningSubsetError {
+    #[error("at least {required} signers are required, got {signers}")]
+    TooFewSigners { required: u16, signers: usize },
+    #[error("party {index} is not a party of keygen")]
+    NotMember { index: u16 },
+    #[error("party {index} is listed in the committee more than once")]
+    Duplicate { index: u16 },
+    #[error("committee doesn't contain party {index} owning the key")]
+    MissingOwner { index: u16 },
+    #[error("key doesn't hold public data of party {index}")]
+    MissingSlot { index: u16 },
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::protocols::multi_party_ecdsa::gg_2020::state_machine::keygen::test::simulate_keygen;
+
+    #[test]
+    fn invalid_committee_is_rejected() {
+        let keys = simulate_keygen(1, 3);
+        let key = &keys[1];
+
+        let subset = SigningSubset::new(key.clone(), vec![3, 2]).unwrap();
+        assert_eq!(subset.committee(), &[3, 2]);
+        assert_eq!(subset.party_index(), 2);
+        assert_eq!(
+            *subset.lagrange_coefficient(),
+            key.lagrange_coefficient(&[3, 2])
+        );
+
+        assert!(matches!(
+            SigningSubset::new(key.clone(), vec![2]),
+            Err(SigningSubsetError::TooFewSigners {
+                required: 2,
+                signers: 1
+            })
+        ));
+        assert!(matches!(
+            SigningSubset::new(key.clone(), vec![2, 4]),
+            Err(SigningSubsetError::NotMember { index: 4 })
+        ));
+        assert!(matches!(
+            SigningSubset::new(key.clone(), vec![0, 2]),
+            Err(SigningSubsetError::NotMember { index: 0 })
+        ));
+        assert!(matches!(
+            SigningSubset::new(key.clone(), vec![2, 2]),
+            Err(SigningSubsetError::Duplicate { index: 2 })
+        ));
+        assert!(matches!(
+            SigningSubset::new(key.clone(), vec![1, 3]),
+            Err(SigningSubsetError::MissingOwner { index: 2 })
+        ));
+
+        // Only public data of the committee is kept
+        assert_eq!(
+            subset.local_key().pk_vec,
+            vec![key.pk_vec[2].clone(), key.pk_vec[1].clone()]
+        );
+        let mut truncated = key.clone();
+        truncated.paillier_key_vec.truncate(2);
+        assert!(matches!(
+            SigningSubset::new(truncated, vec![3, 2]),
+            Err(SigningSubsetError::MissingSlot { index: 3 })
+        ));
+    }
+}
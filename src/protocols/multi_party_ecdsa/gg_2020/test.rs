@@ -21,8 +21,8 @@ use crate::protocols::multi_party_ecdsa::gg_2020::blame::{
 };
 use crate::protocols::multi_party_ecdsa::gg_2020::party_i::SignatureRecid;
 use crate::protocols::multi_party_ecdsa::gg_2020::party_i::{
-    KeyGenBroadcastMessage1, KeyGenDecommitMessage1, Keys, LocalSignature, Parameters, SharedKeys,
-    SignKeys,
+    recommended_paillier_bits_for_curve, KeyGenBroadcastMessage1, KeyGenDecommitMessage1, Keys,
+    LocalSignature, Parameters, SharedKeys, SignKeys,
 };
 use crate::utilities::mta::{MessageA, MessageB};
 use curv::arithmetic::traits::Converter;
@@ -761,6 +761,69 @@ fn test_serialize_deserialize() {
     let decoded: KeyGenDecommitMessage1 = serde_json::from_str(&encoded).unwrap();
     assert_eq!(decommit.y_i, decoded.y_i);
 }
+#[test]
+fn test_keys_from_sealed_secret_material() {
+    use crate::protocols::multi_party_ecdsa::gg_2020::party_i::SecretMaterial;
+
+    let params = Parameters {
+        threshold: 1,
+        share_count: 2,
+    };
+    let keys = Keys::create(0);
+    let sealed = serde_json::to_vec(&keys.secret_material()).unwrap();
+    let public = keys.public_material();
+    drop(keys);
+
+    let secret: SecretMaterial = serde_json::from_slice(&sealed).unwrap();
+    let party_keys_vec = vec![Keys::from_parts(secret, public), Keys::create(1)];
+
+    let (bc1_vec, decom_vec): (Vec<_>, Vec<_>) = party_keys_vec
+        .iter()
+        .map(|k| k.phase1_broadcast_phase3_proof_of_correct_key_proof_of_correct_h1h2())
+        .unzip();
+    for k in &party_keys_vec {
+        k.phase1_verify_com_phase3_verify_correct_key_verify_dlog_phase2_distribute(
+            &params, &decom_vec, &bc1_vec,
+        )
+        .unwrap();
+    }
+}
+
+#[test]
+fn test_keys_from_prime_pool() {
+    use crate::protocols::multi_party_ecdsa::gg_2020::party_i::SafePrimePool;
+
+    let mut pool = SafePrimePool::new();
+    pool.fill(SafePrimePool::PRIMES_PER_KEYS);
+    assert_eq!(pool.len(), SafePrimePool::PRIMES_PER_KEYS);
+
+    let party_keys_vec = vec![
+        Keys::create_from_prime_pool(0, &mut pool).unwrap(),
+        Keys::create(1),
+    ];
+    assert!(pool.is_empty());
+    assert!(Keys::create_from_prime_pool(0, &mut pool).is_none());
+
+    let (bc1_vec, decom_vec): (Vec<_>, Vec<_>) = party_keys_vec
+        .iter()
+        .map(|k| k.phase1_broadcast_phase3_proof_of_correct_key_proof_of_correct_h1h2())
+        .unzip();
+    assert!(bc1_vec[0]
+        .correct_key_proof
+        .verify(&bc1_vec[0].e, zk_paillier::zkproofs::SALT_STRING)
+        .is_ok());
+    party_keys_vec[1]
+        .phase1_verify_com_phase3_verify_correct_key_verify_dlog_phase2_distribute(
+            &Parameters {
+                threshold: 1,
+                share_count: 2,
+            },
+            &decom_vec,
+            &bc1_vec,
+        )
+        .unwrap();
+}
+
 #[test]
 fn test_small_paillier() {
     // parties shouldn't be able to choose small Paillier modulus
@@ -781,3 +844,150 @@ fn test_small_paillier() {
         )
         .is_err());
 }
+
+#[test]
+fn test_recommended_paillier_bits() {
+    use curv::elliptic::curves::Curve;
+
+    assert!(recommended_paillier_bits_for_curve(Secp256k1::CURVE_NAME) >= 2048);
+    assert!(recommended_paillier_bits_for_curve("unknown-curve") >= 2048);
+}
+
+#[test]
+fn test_batch_dlog_verification_blames_tampered_proof() {
+    let (t, n) = (2, 5);
+    let params = Parameters {
+        threshold: t,
+        share_count: n,
+    };
+    let u_vec = (0..n)
+        .map(|_| Scalar::<Secp256k1>::random())
+        .collect::<Vec<_>>();
+    let y_vec = u_vec
+        .iter()
+        .map(|u| Point::generator() * u)
+        .collect::<Vec<Point<Secp256k1>>>();
+    let (vss_scheme_vec, shares_vec): (Vec<_>, Vec<_>) =
+        u_vec.iter().map(|u| VerifiableSS::share(t, n, u)).unzip();
+    let mut dlog_proof_vec = (0..usize::from(n))
+        .map(|i| {
+            let x_i = shares_vec
+                .iter()
+                .fold(Scalar::<Secp256k1>::zero(), |acc, shares| acc + &shares[i]);
+            DLogProof::<Secp256k1, Sha256>::prove(&x_i)
+        })
+        .collect::<Vec<_>>();
+
+    Keys::verify_dlog_proofs_batch(&params, &dlog_proof_vec, &y_vec, &vss_scheme_vec).unwrap();
+
+    dlog_proof_vec[3].challenge_response =
+        &dlog_proof_vec[3].challenge_response + Scalar::<Secp256k1>::from_bigint(&1.into());
+    let err = Keys::verify_dlog_proofs_batch(&params, &dlog_proof_vec, &y_vec, &vss_scheme_vec)
+        .unwrap_err();
+    assert_eq!(err.bad_actors, vec![3]);
+}
+
+#[test]
+fn test_keys_from_seed_are_deterministic() {
+    use crate::protocols::multi_party_ecdsa::gg_2020::party_i::DEFAULT_PAILLIER_BITS;
+
+    let seed = [42u8; 32];
+    let keys = Keys::create_from_seed(1, &seed);
+    assert_eq!(
+        serde_json::to_string(&keys).unwrap(),
+        serde_json::to_string(&Keys::create_from_seed(1, &seed)).unwrap()
+    );
+    assert_eq!(keys.ek.n.bit_length(), DEFAULT_PAILLIER_BITS);
+    assert_eq!(&keys.dk.p * &keys.dk.q, keys.ek.n);
+
+    let other = Keys::create_from_seed(2, &seed);
+    assert_ne!(keys.u_i, other.u_i);
+    assert_ne!(keys.ek.n, other.ek.n);
+
+    let (bc1, decom) = keys.phase1_broadcast_phase3_proof_of_correct_key_proof_of_correct_h1h2();
+    assert!(bc1.verify_with_decommit(&decom));
+}
+
+#[test]
+fn test_keys_from_components_reuse_supplied_paillier_key() {
+    let u = Scalar::<Secp256k1>::random();
+    let (ek, dk) = Paillier::keypair_with_modulus_size(2048).keys();
+
+    let (keys, setup) = Keys::from_components(1, u.clone(), (ek.clone(), dk.clone())).unwrap();
+    assert_eq!(keys.ek.n, ek.n);
+    assert_eq!(keys.y_i, Point::generator() * &u);
+    assert_ne!(keys.N_tilde, ek.n);
+    let (bc1, decom) = keys.phase1_broadcast_with_paillier_setup(&setup);
+    assert!(bc1.verify_with_decommit(&decom));
+
+    let (_, other_dk) = Paillier::keypair_with_modulus_size(2048).keys();
+    assert!(matches!(
+        Keys::from_components(1, u, (ek, other_dk)),
+        Err(crate::Error::InvalidKey)
+    ));
+}
+
+#[test]
+fn test_signature_der_and_compact_encodings() {
+    use secp256k1::Signature;
+
+    let q_minus_one =
+        Scalar::<Secp256k1>::from_bigint(&(Scalar::<Secp256k1>::group_order() - BigInt::from(1)));
+    let signatures = vec![
+        // r has the most significant bit set, s is encoded in a single byte
+        SignatureRecid {
+            r: q_minus_one,
+            s: Scalar::from_bigint(&BigInt::from(1)),
+            recid: 1,
+        },
+        SignatureRecid {
+            r: Scalar::random(),
+            s: Scalar::random(),
+            recid: 0,
+        },
+    ];
+    for sig in signatures {
+        let compact = sig.to_compact();
+        let der = sig.to_der();
+        let reference = Signature::from_compact(&compact).unwrap();
+        assert_eq!(&reference.serialize_der()[..], &der[..]);
+        assert_eq!(Signature::from_der(&der).unwrap(), reference);
+
+        let parsed = SignatureRecid::from_der(&der, sig.recid).unwrap();
+        assert_eq!((&parsed.r, &parsed.s), (&sig.r, &sig.s));
+        let parsed = SignatureRecid::from_compact(&compact, sig.recid).unwrap();
+        assert_eq!(
+            (&parsed.r, &parsed.s, parsed.recid),
+            (&sig.r, &sig.s, sig.recid)
+        );
+    }
+
+    let rejected: [&[u8]; 6] = [
+        // r = 1 padded with unnecessary zero byte
+        &[0x30, 0x07, 0x02, 0x02, 0x00, 0x01, 0x02, 0x01, 0x01],
+        // negative r
+        &[0x30, 0x06, 0x02, 0x01, 0x81, 0x02, 0x01, 0x01],
+        // trailing byte
+        &[0x30, 0x07, 0x02, 0x01, 0x01, 0x02, 0x01, 0x01, 0x00],
+        // sequence length doesn't match
+        &[0x30, 0x07, 0x02, 0x01, 0x01, 0x02, 0x01, 0x01],
+        // s = 0
+        &[0x30, 0x06, 0x02, 0x01, 0x01, 0x02, 0x01, 0x00],
+        // integer is not tagged as such
+        &[0x30, 0x06, 0x03, 0x01, 0x01, 0x02, 0x01, 0x01],
+    ];
+    for der in &rejected {
+        assert!(matches!(
+            SignatureRecid::from_der(der, 0),
+            Err(crate::Error::InvalidSig)
+        ));
+    }
+    assert!(SignatureRecid::from_der(&[0x30, 0x06, 0x02, 0x01, 0x01, 0x02, 0x01, 0x01], 0).is_ok());
+    let mut order = [0u8; 64];
+    order[..32].copy_from_slice(&Scalar::<Secp256k1>::group_order().to_bytes());
+    order[63] = 1;
+    assert!(matches!(
+        SignatureRecid::from_compact(&order, 0),
+        Err(crate::Error::InvalidSig)
+    ));
+}
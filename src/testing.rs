@@ -0,0 +1,271 @@
+/*
+    Multi-party ECDSA
+
+    Copyright 2018 by Kzen Networks
+
+    This file is part of Multi-party ECDSA library
+    (https://github.com/KZen-networks/multi-party-ecdsa)
+
+    Multi-party ECDSA is free software: you can redistribute
+    it and/or modify it under the terms of the GNU General Public
+    License as published by the Free Software Foundation, either
+    version 3 of the License, or (at your option) any later version.
+
+    @license GPL-3.0+ <https://github.com/KZen-networks/multi-party-ecdsa/blob/master/LICENSE>
+*/
+
+//! Utilities for running protocols locally, e.g. in tests
+//!
+//! Functions of this module run every party within a single process, so they're only suitable
+//! for testing and as a template for wiring parties together.
+
+use std::fmt::Debug;
+
+use curv::elliptic::curves::secp256_k1::Secp256k1;
+use round_based::containers::{MessageStore, P2PMsgs, P2PMsgsStore};
+use round_based::dev::Simulation;
+use round_based::{Msg, StateMachine};
+#[cfg(feature = "tokio")]
+use tokio::sync::mpsc;
+
+#[cfg(feature = "tokio")]
+use crate::protocols::multi_party_ecdsa::gg_2020::state_machine::keygen::ProtocolMessage;
+use crate::protocols::multi_party_ecdsa::gg_2020::state_machine::keygen::{Keygen, LocalKey};
+
+/// Runs `t`-out-of-`n` keygen, all parties within the current thread
+///
+/// Returns keys ordered by party index.
+///
+/// ## Panics
+/// Panics if arguments are invalid or any party fails to complete keygen, panic message
+/// describes the error
+pub fn simulate_keygen(t: u16, n: u16) -> Vec<LocalKey<Secp256k1>> {
+    let mut simulation = Simulation::new();
+    for i in 1..=n {
+        let party = Keygen::new(i, t, n)
+            .unwrap_or_else(|e| panic!("invalid keygen parameters t={} n={}: {}", t, n, e));
+        simulation.add_party(party);
+    }
+    simulation
+        .run()
+        .unwrap_or_else(|e| panic!("keygen failed: {}", e))
+}
+
+/// Runs `parties` within the current thread until every one of them is finished
+///
+/// Parties ready to proceed are proceeded, then every message they sent is passed to `deliver`
+/// along with all the parties, in order of sending. Unlike [Simulation], `deliver` decides how
+/// the message reaches parties, so it can drop, duplicate or tamper messages, or record them on
+/// the way. [deliver_to_receivers] delivers message as addressed.
+///
+/// ## Panics
+/// Panics if any party fails to proceed, or if parties are stuck: nobody is ready to proceed and
+/// no message is sent, while not every party is finished
+pub fn run_in_place<P>(parties: &mut [P], mut deliver: impl FnMut(&mut [P], Msg<P::MessageBody>))
+where
+    P: StateMachine,
+    P::Err: Debug,
+{
+    while !parties.iter().all(|p| p.is_finished()) {
+        let mut progressed = false;
+        for party in parties.iter_mut() {
+            if party.wants_to_proceed() {
+                party.proceed().unwrap_or_else(|e| {
+                    panic!("party {} failed to proceed: {:?}", party.party_ind(), e)
+                });
+                progressed = true;
+            }
+        }
+        let outgoing: Vec<_> = parties
+            .iter_mut()
+            .flat_map(|p| p.message_queue().drain(..).collect::<Vec<_>>())
+            .collect();
+        assert!(
+            progressed || !outgoing.is_empty(),
+            "parties are stuck: nobody proceeds or sends messages"
+        );
+        for msg in outgoing {
+            deliver(parties, msg);
+        }
+    }
+}
+
+/// Hands `msg` to every party it's addressed to, except its sender
+///
+/// ## Panics
+/// Panics if any receiver fails to handle the message
+pub fn deliver_to_receivers<P>(parties: &mut [P], msg: &Msg<P::MessageBody>)
+where
+    P: StateMachine,
+    P::MessageBody: Clone,
+    P::Err: Debug,
+{
+    for party in parties {
+        let i = party.party_ind();
+        if msg.sender != i && msg.receiver.map_or(true, |j| j == i) {
+            party
+                .handle_incoming(msg.clone())
+                .unwrap_or_else(|e| panic!("party {} failed to handle message: {:?}", i, e));
+        }
+    }
+}
+
+/// Delivers p2p messages addressed to party `i` into [P2PMsgsStore] in a shuffled order
+///
+/// Order is a pseudo-random permutation determined by `seed`, so different seeds simulate
+/// network delivering messages in different order, and the same seed always reproduces the
+/// same order.
+///
+/// ## Panics
+/// Panics if `msgs` don't contain exactly one valid message from every party to party `i`
+pub fn shuffle_delivery<T: Clone>(i: u16, n: u16, msgs: &[Msg<T>], seed: u64) -> P2PMsgs<T> {
+    let mut incoming: Vec<_> = msgs.iter().filter(|m| m.receiver == Some(i)).collect();
+
+    // Fisher-Yates shuffle driven by xorshift64, seed must be non-zero for xorshift
+    let mut state = seed | 1;
+    for j in (1..incoming.len()).rev() {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        let k = (state % (j as u64 + 1)) as usize;
+        incoming.swap(j, k);
+    }
+
+    let mut store = P2PMsgsStore::new(i, n);
+    for msg in incoming {
+        store
+            .push_msg(msg.clone())
+            .expect("message is not acceptable");
+    }
+    store.finish().expect("not every message was received")
+}
+
+/// Runs `t`-out-of-`n` keygen, every party in a separate tokio task
+///
+/// Parties exchange messages via tokio mpsc channels. Must be called within tokio runtime.
+/// Requires `tokio` feature along with `testing`.
+///
+/// ## Panics
+/// Panics if arguments are invalid or any party fails to complete keygen
+#[cfg(feature = "tokio")]
+pub async fn run_keygen_async(t: u16, n: u16) -> Vec<LocalKey<Secp256k1>> {
+    let (senders, receivers): (Vec<_>, Vec<_>) = (0..n).map(|_| mpsc::unbounded_channel()).unzip();
+
+    let parties = (1..=n)
+        .zip(receivers)
+        .map(|(i, incoming)| {
+            let party = Keygen::new(i, t, n).expect("invalid keygen parameters");
+            tokio::spawn(run_party(party, incoming, senders.clone()))
+        })
+        .collect::<Vec<_>>();
+
+    let mut keys = Vec::with_capacity(usize::from(n));
+    for party in parties {
+        keys.push(party.await.expect("party task panicked"));
+    }
+    keys
+}
+
+#[cfg(feature = "tokio")]
+async fn run_party(
+    mut party: Keygen,
+    mut incoming: mpsc::UnboundedReceiver<Msg<ProtocolMessage>>,
+    outgoing: Vec<mpsc::UnboundedSender<Msg<ProtocolMessage>>>,
+) -> LocalKey<Secp256k1> {
+    let i = party.party_ind();
+    loop {
+        if party.wants_to_proceed() {
+            party.proceed().expect("proceed keygen");
+        }
+
+        for msg in party.message_queue().drain(..) {
+            match msg.receiver {
+                Some(j) => {
+                    // Receiver might have already finished the protocol and dropped its channel
+                    let _ = outgoing[usize::from(j - 1)].send(msg);
+                }
+                None => {
+                    for (j, tx) in (1..).zip(&outgoing) {
+                        if j != i {
+                            let _ = tx.send(msg.clone());
+                        }
+                    }
+                }
+            }
+        }
+
+        if party.is_finished() {
+            return party
+                .pick_output()
+                .expect("finished party must have output")
+                .expect("keygen failed");
+        }
+
+        if !party.wants_to_proceed() {
+            let msg = incoming
+                .recv()
+                .await
+                .expect("all the parties dropped their channels");
+            party.handle_incoming(msg).expect("handle incoming message");
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn run_keygen_async_t1_n3() {
+        let keys = run_keygen_async(1, 3).await;
+        assert_eq!(keys.len(), 3);
+        assert!(keys.iter().all(|k| k.public_key() == keys[0].public_key()));
+    }
+
+    #[test]
+    fn simulate_keygen_t1_n2() {
+        let keys = simulate_keygen(1, 2);
+        assert_eq!(keys.len(), 2);
+        assert_eq!(keys[0].public_key(), keys[1].public_key());
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid keygen parameters t=2 n=2")]
+    fn simulate_keygen_panics_on_invalid_threshold() {
+        simulate_keygen(2, 2);
+    }
+
+    #[test]
+    fn run_in_place_delivers_as_addressed() {
+        let mut parties: Vec<_> = (1..=3).map(|i| Keygen::new(i, 1, 3).unwrap()).collect();
+        let mut delivered = 0;
+        run_in_place(&mut parties, |parties, msg| {
+            delivered += 1;
+            deliver_to_receivers(parties, &msg);
+        });
+        // Round 1, 2 and 4 broadcasts and round 3 shares of every party
+        assert_eq!(delivered, 3 * (3 + 2));
+        let keys: Vec<_> = parties
+            .iter_mut()
+            .map(|p| p.pick_output().unwrap().unwrap())
+            .collect();
+        assert!(keys.iter().all(|k| k.public_key() == keys[0].public_key()));
+    }
+
+    #[test]
+    fn shuffle_delivery_is_reproducible() {
+        let msgs: Vec<_> = (2..=5)
+            .map(|j| Msg {
+                round: 3,
+                sender: j,
+                receiver: Some(1),
+                body: j,
+            })
+            .collect();
+        let deliver = |seed| shuffle_delivery(1, 5, &msgs, seed).into_vec();
+
+        assert_eq!(deliver(1), vec![2, 3, 4, 5]);
+        assert_eq!(deliver(42), deliver(42));
+    }
+}
@@ -0,0 +1,137 @@
+/*
+    Multi-party ECDSA
+
+    Copyright 2018 by Kzen Networks
+
+    This file is part of Multi-party ECDSA library
+    (https://github.com/KZen-networks/multi-party-ecdsa)
+
+    Multi-party ECDSA is free software: you can redistribute
+    it and/or modify it under the terms of the GNU General Public
+    License as published by the Free Software Foundation, either
+    version 3 of the License, or (at your option) any later version.
+
+    @license GPL-3.0+ <https://github.com/KZen-networks/multi-party-ecdsa/blob/master/LICENSE>
+*/
+
+//! Minimal binary Merkle tree over SHA-256
+//!
+//! Leaves are hashed as `H(0x00 || leaf)`, inner nodes as `H(0x01 || left || right)`. A node
+//! without a sibling is promoted to the next level as is.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+const LEAF_TAG: u8 = 0x00;
+const NODE_TAG: u8 = 0x01;
+
+/// Proof that a leaf is included in a Merkle tree
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MerkleProof {
+    /// Index of the leaf in the tree
+    pub leaf_index: usize,
+    /// Sibling hashes from the leaf level up to the root. Boolean flag is `true` if sibling is
+    /// the left node.
+    pub path: Vec<([u8; 32], bool)>,
+}
+
+pub fn hash_leaf(leaf: &[u8]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(&[LEAF_TAG]);
+    hasher.update(leaf);
+    to_array(hasher)
+}
+
+fn hash_node(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(&[NODE_TAG]);
+    hasher.update(left);
+    hasher.update(right);
+    to_array(hasher)
+}
+
+fn to_array(hasher: Sha256) -> [u8; 32] {
+    let mut out = [0u8; 32];
+    out.copy_from_slice(&hasher.finalize());
+    out
+}
+
+fn next_level(level: &[[u8; 32]]) -> Vec<[u8; 32]> {
+    level
+        .chunks(2)
+        .map(|pair| match pair {
+            [left, right] => hash_node(left, right),
+            [single] => *single,
+            _ => unreachable!("chunks(2) yields one or two elements"),
+        })
+        .collect()
+}
+
+/// Computes Merkle root of given leaves
+///
+/// Root of an empty tree is defined as `H(0x00)`.
+pub fn merkle_root<L: AsRef<[u8]>>(leaves: &[L]) -> [u8; 32] {
+    let mut level: Vec<_> = leaves.iter().map(|l| hash_leaf(l.as_ref())).collect();
+    if level.is_empty() {
+        return hash_leaf(&[]);
+    }
+    while level.len() > 1 {
+        level = next_level(&level);
+    }
+    level[0]
+}
+
+/// Constructs inclusion proof for leaf at `leaf_index`
+///
+/// Returns `None` if `leaf_index` is out of range.
+pub fn merkle_proof<L: AsRef<[u8]>>(leaves: &[L], leaf_index: usize) -> Option<MerkleProof> {
+    if leaf_index >= leaves.len() {
+        return None;
+    }
+    let mut level: Vec<_> = leaves.iter().map(|l| hash_leaf(l.as_ref())).collect();
+    let mut index = leaf_index;
+    let mut path = vec![];
+    while level.len() > 1 {
+        let sibling = index ^ 1;
+        if sibling < level.len() {
+            path.push((level[sibling], sibling < index));
+        }
+        level = next_level(&level);
+        index /= 2;
+    }
+    Some(MerkleProof { leaf_index, path })
+}
+
+/// Verifies that `leaf` is included in a tree with given `root`
+pub fn verify_merkle_proof(root: &[u8; 32], leaf: &[u8], proof: &MerkleProof) -> bool {
+    let computed = proof
+        .path
+        .iter()
+        .fold(hash_leaf(leaf), |acc, (sibling, sibling_is_left)| {
+            if *sibling_is_left {
+                hash_node(sibling, &acc)
+            } else {
+                hash_node(&acc, sibling)
+            }
+        });
+    &computed == root
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn every_leaf_is_provably_included() {
+        for n in 1..=7 {
+            let leaves: Vec<_> = (0..n).map(|i: u8| vec![i; 3]).collect();
+            let root = merkle_root(&leaves);
+            for (i, leaf) in leaves.iter().enumerate() {
+                let proof = merkle_proof(&leaves, i).unwrap();
+                assert!(verify_merkle_proof(&root, leaf, &proof));
+                assert!(!verify_merkle_proof(&root, b"other leaf", &proof));
+            }
+            assert!(merkle_proof(&leaves, n as usize).is_none());
+        }
+    }
+}
@@ -1,3 +1,5 @@
+pub mod merkle;
 pub mod mta;
+pub mod transport;
 pub mod zk_pdl;
 pub mod zk_pdl_with_slack;
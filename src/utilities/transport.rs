@@ -0,0 +1,74 @@
+/*
+    Multi-party ECDSA
+
+    Copyright 2018 by Kzen Networks
+
+    This file is part of Multi-party ECDSA library
+    (https://github.com/KZen-networks/multi-party-ecdsa)
+
+    Multi-party ECDSA is free software: you can redistribute
+    it and/or modify it under the terms of the GNU General Public
+    License as published by the Free Software Foundation, either
+    version 3 of the License, or (at your option) any later version.
+
+    @license GPL-3.0+ <https://github.com/KZen-networks/multi-party-ecdsa/blob/master/LICENSE>
+*/
+
+//! Key derivation for transporting shares between two parties out-of-band
+
+use hkdf::Hkdf;
+use sha2::Sha256;
+
+const TRANSPORT_KEY_INFO: &[u8] = b"multi-party-ecdsa/share-transport/v1";
+
+/// Derives a 32 bytes key for sealing shares sent between two parties with AES-GCM
+///
+/// Key is derived via HKDF-SHA256 with `session_nonce` as salt. Identities are put in
+/// canonical order, so both parties derive the same key regardless of which one is "me".
+/// Use a fresh nonce for every session, otherwise the same key is derived again.
+///
+/// Note that the key is only as secret as its inputs: if both identities and the nonce are
+/// public, so is the key.
+pub fn derive_transport_key(my_id: &[u8], their_id: &[u8], session_nonce: &[u8]) -> [u8; 32] {
+    let (first, second) = if my_id <= their_id {
+        (my_id, their_id)
+    } else {
+        (their_id, my_id)
+    };
+
+    // Identities are length-prefixed so that distinct pairs never map to the same input
+    let mut ikm = Vec::with_capacity(16 + first.len() + second.len());
+    for id in [first, second].iter() {
+        ikm.extend_from_slice(&(id.len() as u64).to_be_bytes());
+        ikm.extend_from_slice(id);
+    }
+
+    let mut key = [0u8; 32];
+    Hkdf::<Sha256>::new(Some(session_nonce), &ikm)
+        .expand(TRANSPORT_KEY_INFO, &mut key)
+        .expect("32 bytes is a valid HKDF-SHA256 output length");
+    key
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn transport_key_is_deterministic() {
+        let key = derive_transport_key(b"alice", b"bob", b"nonce-1");
+        assert_eq!(key, derive_transport_key(b"alice", b"bob", b"nonce-1"));
+        assert_eq!(key, derive_transport_key(b"bob", b"alice", b"nonce-1"));
+    }
+
+    #[test]
+    fn transport_key_depends_on_nonce_and_identities() {
+        let key = derive_transport_key(b"alice", b"bob", b"nonce-1");
+        assert_ne!(key, derive_transport_key(b"alice", b"bob", b"nonce-2"));
+        assert_ne!(key, derive_transport_key(b"alice", b"carol", b"nonce-1"));
+        assert_ne!(
+            derive_transport_key(b"ab", b"c", b"nonce-1"),
+            derive_transport_key(b"a", b"bc", b"nonce-1")
+        );
+    }
+}